@@ -52,10 +52,11 @@ pub(crate) fn generate_endpoint_internal(input: TokenStream) -> TokenStream1 {
         "post" => quote! { #[actix_web::post(#path)] },
         "put" => quote! { #[actix_web::put(#path)] },
         "delete" => quote! { #[actix_web::delete(#path)] },
+        "patch" => quote! { #[actix_web::patch(#path)] },
         _ => {
             return syn::Error::new_spanned(
                 method,
-                "Unsupported method. Expected one of: get, post, put, delete.",
+                "Unsupported method. Expected one of: get, post, put, delete, patch.",
             )
             .to_compile_error()
             .into();
@@ -51,10 +51,6 @@ define_oauth_client!(
     GithubScope,
     GithubUserInfo,
     {
-        client_id_env: "GITHUB_CLIENT_ID",
-        client_secret_env: "GITHUB_CLIENT_SECRET",
-        base_url_env: "BASE_URL",
-        default_base_url: "http://localhost:9999",
         user_info_mapping: |github_user_info| {
             Ok::<UserInfo, OauthError>(UserInfo {
                 id: None,
@@ -43,10 +43,6 @@ define_oauth_client! {
     GoogleScope,
     GoogleUserInfo,
     {
-        client_id_env: "GOOGLE_CLIENT_ID",
-        client_secret_env: "GOOGLE_CLIENT_SECRET",
-        base_url_env: "BASE_URL",
-        default_base_url: "http://localhost:9999",
         user_info_mapping: |google_user_info| {
             Ok::<UserInfo, OauthError>(UserInfo::from(google_user_info))
         },
@@ -7,6 +7,7 @@ pub(crate) mod scopes;
 
 use crate::auth::oauth::github::GithubOauth;
 use crate::auth::oauth::google::GoogleOauth;
+use crate::config::settings::OauthConfig;
 use anyhow::Result;
 
 #[derive(Debug, Clone)]
@@ -16,10 +17,10 @@ pub struct Oauth {
 }
 
 impl Oauth {
-    pub async fn new() -> Result<Self> {
+    pub async fn new(config: &OauthConfig, base_url: &str) -> Result<Self> {
         Ok(Self {
-            google: GoogleOauth::new().await?,
-            github: GithubOauth::new().await?,
+            google: GoogleOauth::new(&config.google, base_url).await?,
+            github: GithubOauth::new(&config.github, base_url).await?,
         })
     }
 }
@@ -1,6 +1,7 @@
 pub(crate) mod github;
 pub mod google;
 
+use crate::metrics::ObserveQuery;
 use crate::server::db::INTERNAL_DB;
 use anyhow::{bail, Result};
 use serde::{Deserialize, Serialize};
@@ -112,6 +113,7 @@ impl OauthProvider {
         let mut res = INTERNAL_DB
             .query("SELECT *, config.* from provider WHERE name = $name;")
             .bind(("name", name.clone()))
+            .observed()
             .await?;
 
         let provider: Option<Self> = res.take(0)?;
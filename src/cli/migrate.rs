@@ -0,0 +1,130 @@
+use crate::server::db::INTERNAL_DB;
+use crate::server::migrations_runner::{
+    down_scripts, migration_history, migration_scripts, AppliedMigration,
+};
+use crate::server_error::ServerError;
+
+/// The migration scripts on disk and the migrations applied to the database
+pub(crate) struct Migrations {
+    pub scripts: Vec<String>,
+    pub down: Vec<String>,
+    pub applied: Vec<AppliedMigration>,
+}
+
+impl Migrations {
+    async fn load() -> Result<Self, ServerError> {
+        Ok(Self {
+            scripts: migration_scripts().await?,
+            down: down_scripts().await?,
+            applied: migration_history(&INTERNAL_DB).await?,
+        })
+    }
+
+    fn applied(&self, name: &str) -> Option<&AppliedMigration> {
+        self.applied
+            .iter()
+            .find(|migration| migration.script_name == name)
+    }
+
+    /// Everything that keeps the migrations from being applied or reverted cleanly
+    pub(crate) fn problems(&self) -> Vec<String> {
+        let mut problems = Vec::new();
+        let newest = self
+            .applied
+            .iter()
+            .map(|migration| migration.script_name.as_str())
+            .max();
+
+        for migration in &self.applied {
+            if !self.scripts.contains(&migration.script_name) {
+                problems.push(format!(
+                    "{} was applied but its script is missing",
+                    migration.script_name
+                ));
+            }
+        }
+        for script in &self.scripts {
+            if !self.down.contains(script) {
+                problems.push(format!("{script} has no down script"));
+            }
+            if let Some(newest) = newest {
+                if self.applied(script).is_none() && script.as_str() < newest {
+                    problems.push(format!(
+                        "{script} is pending but older than the applied {newest}"
+                    ));
+                }
+            }
+        }
+        for script in &self.down {
+            if !self.scripts.contains(script) {
+                problems.push(format!("the down script {script} belongs to no migration"));
+            }
+        }
+
+        problems
+    }
+}
+
+pub(crate) async fn is_applied(name: &str) -> Result<bool, ServerError> {
+    Ok(Migrations::load().await?.applied(name).is_some())
+}
+
+/// Prints every migration with when it was applied
+pub(crate) async fn status() -> Result<(), ServerError> {
+    let migrations = Migrations::load().await?;
+
+    let mut names: Vec<&str> = migrations.scripts.iter().map(String::as_str).collect();
+    for migration in &migrations.applied {
+        if !names.contains(&migration.script_name.as_str()) {
+            names.push(&migration.script_name);
+        }
+    }
+    names.sort();
+
+    for name in names {
+        let (state, executed_at) = match migrations.applied(name) {
+            Some(migration) => (
+                "applied",
+                migration
+                    .executed_at
+                    .0
+                    .format("%Y-%m-%d %H:%M:%S")
+                    .to_string(),
+            ),
+            None => ("pending", String::new()),
+        };
+        let state = if migrations.scripts.iter().any(|script| script == name) {
+            state
+        } else {
+            "missing"
+        };
+        let down = if migrations.down.iter().any(|script| script == name) {
+            ""
+        } else {
+            " (no down script)"
+        };
+
+        println!("{state:<8} {executed_at:<19} {name}{down}");
+    }
+
+    Ok(())
+}
+
+/// Prints the [problems](Migrations::problems), fails if there are any
+pub(crate) async fn verify() -> Result<(), ServerError> {
+    let problems = Migrations::load().await?.problems();
+
+    if problems.is_empty() {
+        println!("Migrations are consistent");
+        return Ok(());
+    }
+
+    for problem in &problems {
+        println!("{problem}");
+    }
+
+    Err(ServerError::Error(format!(
+        "{} problems with the migrations",
+        problems.len()
+    )))
+}
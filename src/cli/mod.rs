@@ -0,0 +1,92 @@
+//! Subcommands of the server binary. Without one the server starts, the flags after a
+//! subcommand configure it like they configure the server, see [`Config`](crate::config::Config).
+//!
+//! - `migrate up` applies the schemas, events and pending migrations
+//! - `migrate down --to <migration>` reverts the migrations after `<migration>`, `0` reverts all
+//! - `migrate status` lists the migrations and whether they were applied
+//! - `migrate verify` checks that the applied migrations match the scripts and that every
+//!   migration can be reverted
+//! - `schema diff` compares the schema files with the database
+
+pub mod migrate;
+pub mod schema;
+mod test;
+
+use crate::server::{connect, migrations_runner, revert_migrations};
+use crate::server_error::ServerError;
+
+pub const USAGE: &str = "usage: backend [migrate up | migrate down --to <migration> | \
+                         migrate status | migrate verify | schema diff] [--<flag> <value>...]";
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Command {
+    Serve,
+    MigrateUp,
+    MigrateDown { to: String },
+    MigrateStatus,
+    MigrateVerify,
+    SchemaDiff,
+}
+
+impl Command {
+    /// Splits the subcommand off `args`, the remaining arguments are configuration flags
+    pub fn parse(args: Vec<String>) -> Result<(Self, Vec<String>), String> {
+        let words = args.iter().take_while(|arg| !arg.starts_with("--")).count();
+        let mut flags = args[words..].to_vec();
+        let words: Vec<&str> = args[..words].iter().map(String::as_str).collect();
+
+        let command = match words.as_slice() {
+            [] => Self::Serve,
+            ["migrate", "up"] => Self::MigrateUp,
+            ["migrate", "down"] => Self::MigrateDown {
+                to: take_flag(&mut flags, "to")
+                    .ok_or_else(|| format!("migrate down requires --to <migration>\n{USAGE}"))?,
+            },
+            ["migrate", "status"] => Self::MigrateStatus,
+            ["migrate", "verify"] => Self::MigrateVerify,
+            ["schema", "diff"] => Self::SchemaDiff,
+            _ => return Err(format!("unknown command {:?}\n{USAGE}", words.join(" "))),
+        };
+
+        Ok((command, flags))
+    }
+
+    /// Runs the subcommand, [`Command::Serve`] is left to `main`
+    pub async fn run(self, flags: Vec<String>) -> Result<(), ServerError> {
+        connect(flags).await?;
+
+        match self {
+            Self::Serve => Ok(()),
+            Self::MigrateUp => migrations_runner(false).await,
+            Self::MigrateDown { to } => {
+                // A typo would otherwise revert everything
+                if to != "0" && !migrate::is_applied(&to).await? {
+                    return Err(ServerError::Error(format!(
+                        "{to} is not an applied migration, see migrate status"
+                    )));
+                }
+                revert_migrations(&to).await
+            }
+            Self::MigrateStatus => migrate::status().await,
+            Self::MigrateVerify => migrate::verify().await,
+            Self::SchemaDiff => schema::diff().await,
+        }
+    }
+}
+
+/// Removes `--name value` or `--name=value` from `args` and returns the value
+fn take_flag(args: &mut Vec<String>, name: &str) -> Option<String> {
+    let flag = format!("--{name}");
+    let prefix = format!("--{name}=");
+
+    let index = args
+        .iter()
+        .position(|arg| *arg == flag || arg.starts_with(&prefix))?;
+    let arg = args.remove(index);
+
+    match arg.strip_prefix(&prefix) {
+        Some(value) => Some(value.to_string()),
+        None if index < args.len() && !args[index].starts_with("--") => Some(args.remove(index)),
+        None => None,
+    }
+}
@@ -0,0 +1,235 @@
+use crate::metrics::ObserveQuery;
+use crate::server::db::INTERNAL_DB;
+use crate::server_error::ServerError;
+use serde::Deserialize;
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt;
+
+/// Directories the schema definitions are read from, relative to the working directory
+pub const SCHEMA_DIRS: [&str; 2] = ["schemas", "events"];
+
+/// Something the schema defines. Only names are compared, definitions that exist on both
+/// sides with different clauses are not reported.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Definition {
+    Analyzer(String),
+    /// Without the `fn::` prefix
+    Function(String),
+    Table(String),
+    Field {
+        table: String,
+        name: String,
+    },
+    Index {
+        table: String,
+        name: String,
+    },
+    Event {
+        table: String,
+        name: String,
+    },
+}
+
+impl fmt::Display for Definition {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Definition::Analyzer(name) => write!(f, "analyzer {name}"),
+            Definition::Function(name) => write!(f, "function fn::{name}"),
+            Definition::Table(name) => write!(f, "table {name}"),
+            Definition::Field { table, name } => write!(f, "field {table}.{name}"),
+            Definition::Index { table, name } => write!(f, "index {name} on {table}"),
+            Definition::Event { table, name } => write!(f, "event {name} on {table}"),
+        }
+    }
+}
+
+fn identifier(token: &str) -> String {
+    token
+        .trim_matches(|c| c == '`' || c == '⟨' || c == '⟩')
+        .to_string()
+}
+
+/// The `DEFINE` statements in `sql` that the diff compares, other statements are skipped
+pub fn parse_definitions(sql: &str) -> Vec<Definition> {
+    let sql: Vec<&str> = sql
+        .lines()
+        .map(|line| line.split("--").next().unwrap_or_default())
+        .collect();
+    let sql = sql.join("\n");
+    let mut definitions = Vec::new();
+
+    for statement in sql.split(';') {
+        let mut tokens = statement.split_whitespace().peekable();
+        if !tokens
+            .next()
+            .is_some_and(|token| token.eq_ignore_ascii_case("DEFINE"))
+        {
+            continue;
+        }
+        let Some(kind) = tokens.next().map(str::to_uppercase) else {
+            continue;
+        };
+
+        if tokens
+            .next_if(|token| token.eq_ignore_ascii_case("OVERWRITE"))
+            .is_none()
+            && tokens
+                .next_if(|token| token.eq_ignore_ascii_case("IF"))
+                .is_some()
+        {
+            // NOT EXISTS
+            tokens.next();
+            tokens.next();
+        }
+        let Some(name) = tokens.next() else {
+            continue;
+        };
+
+        let mut on_table = || {
+            tokens.next_if(|token| token.eq_ignore_ascii_case("ON"))?;
+            tokens.next_if(|token| token.eq_ignore_ascii_case("TABLE"));
+            tokens.next().map(identifier)
+        };
+
+        let definition = match kind.as_str() {
+            "ANALYZER" => Definition::Analyzer(identifier(name)),
+            "FUNCTION" => {
+                let name = name.split('(').next().unwrap_or_default();
+                Definition::Function(identifier(name.trim_start_matches("fn::")))
+            }
+            "TABLE" => Definition::Table(identifier(name)),
+            "FIELD" | "INDEX" | "EVENT" => {
+                let Some(table) = on_table() else {
+                    continue;
+                };
+                let name = identifier(name);
+                match kind.as_str() {
+                    "FIELD" => Definition::Field { table, name },
+                    "INDEX" => Definition::Index { table, name },
+                    _ => Definition::Event { table, name },
+                }
+            }
+            _ => continue,
+        };
+        definitions.push(definition);
+    }
+
+    definitions
+}
+
+#[derive(Deserialize, Debug, Default)]
+struct DbInfo {
+    #[serde(default)]
+    analyzers: BTreeMap<String, String>,
+    #[serde(default)]
+    functions: BTreeMap<String, String>,
+    #[serde(default)]
+    tables: BTreeMap<String, String>,
+}
+
+#[derive(Deserialize, Debug, Default)]
+struct TableInfo {
+    #[serde(default)]
+    fields: BTreeMap<String, String>,
+    #[serde(default)]
+    indexes: BTreeMap<String, String>,
+    #[serde(default)]
+    events: BTreeMap<String, String>,
+}
+
+async fn file_definitions() -> Result<BTreeSet<Definition>, ServerError> {
+    let mut definitions = BTreeSet::new();
+
+    for dir in SCHEMA_DIRS {
+        let mut entries = tokio::fs::read_dir(dir).await?;
+        while let Some(entry) = entries.next_entry().await? {
+            if entry.file_type().await?.is_file() {
+                let sql = tokio::fs::read_to_string(entry.path()).await?;
+                definitions.extend(parse_definitions(&sql));
+            }
+        }
+    }
+
+    Ok(definitions)
+}
+
+async fn database_definitions() -> Result<BTreeSet<Definition>, ServerError> {
+    let info: Option<DbInfo> = INTERNAL_DB
+        .query("INFO FOR DB;")
+        .observed()
+        .await?
+        .take(0)?;
+    let info = info.unwrap_or_default();
+
+    let mut definitions: BTreeSet<Definition> = info
+        .analyzers
+        .into_keys()
+        .map(Definition::Analyzer)
+        .chain(info.functions.into_keys().map(Definition::Function))
+        .chain(info.tables.keys().cloned().map(Definition::Table))
+        .collect();
+    if info.tables.is_empty() {
+        return Ok(definitions);
+    }
+
+    let sql: String = info
+        .tables
+        .keys()
+        .map(|table| format!("INFO FOR TABLE `{table}`;"))
+        .collect();
+    let mut response = INTERNAL_DB.query(sql).observed().await?;
+
+    for (index, table) in info.tables.into_keys().enumerate() {
+        let info: Option<TableInfo> = response.take(index)?;
+        let info = info.unwrap_or_default();
+
+        // SurrealDB defines these itself, e.g. on relation tables
+        definitions.extend(
+            info.fields
+                .into_keys()
+                .filter(|name| !matches!(name.as_str(), "id" | "in" | "out"))
+                .map(|name| Definition::Field {
+                    table: table.clone(),
+                    name,
+                }),
+        );
+        definitions.extend(info.indexes.into_keys().map(|name| Definition::Index {
+            table: table.clone(),
+            name,
+        }));
+        definitions.extend(info.events.into_keys().map(|name| Definition::Event {
+            table: table.clone(),
+            name,
+        }));
+    }
+
+    Ok(definitions)
+}
+
+/// Prints what the schema files define but the database lacks (`+`) and the other way round
+/// (`-`), fails if they differ
+pub async fn diff() -> Result<(), ServerError> {
+    let files = file_definitions().await?;
+    let database = database_definitions().await?;
+
+    let missing: Vec<_> = files.difference(&database).collect();
+    let unknown: Vec<_> = database.difference(&files).collect();
+
+    if missing.is_empty() && unknown.is_empty() {
+        println!("The database matches the schema files");
+        return Ok(());
+    }
+
+    for definition in &missing {
+        println!("+ {definition}");
+    }
+    for definition in &unknown {
+        println!("- {definition}");
+    }
+
+    Err(ServerError::Error(format!(
+        "{} definitions are missing in the database, {} are not in the schema files",
+        missing.len(),
+        unknown.len()
+    )))
+}
@@ -0,0 +1,86 @@
+#![cfg(test)]
+
+use super::migrate::Migrations;
+use super::schema::{parse_definitions, Definition};
+use super::Command;
+use crate::server::migrations_runner::AppliedMigration;
+
+fn args(args: &[&str]) -> Vec<String> {
+    args.iter().map(|arg| arg.to_string()).collect()
+}
+
+#[test]
+fn subcommands_are_split_off_the_flags() {
+    assert_eq!(
+        Command::parse(args(&["--port", "8080"])).unwrap(),
+        (Command::Serve, args(&["--port", "8080"]))
+    );
+    assert_eq!(
+        Command::parse(args(&["migrate", "down", "--port=1", "--to", "20241023"])).unwrap(),
+        (
+            Command::MigrateDown {
+                to: "20241023".to_string()
+            },
+            args(&["--port=1"])
+        )
+    );
+    assert!(Command::parse(args(&["migrate", "down"])).is_err());
+    assert!(Command::parse(args(&["migrate", "sideways"])).is_err());
+}
+
+#[test]
+fn definitions_are_parsed() {
+    let sql = "
+        -- DEFINE TABLE commented ON nothing;
+        DEFINE TABLE IF NOT EXISTS user SCHEMALESS;
+        DEFINE FIELD IF NOT EXISTS email ON user TYPE string;
+        DEFINE INDEX OVERWRITE email_index ON TABLE user FIELDS email UNIQUE;
+        DEFINE EVENT user_deleted ON TABLE user WHEN $after == NONE THEN {
+            DELETE session WHERE user_id = $before.id;
+        };
+        DEFINE FUNCTION OVERWRITE fn::hash($value: string) -> string { RETURN $value; };
+        UPDATE user SET email = 'a@b.c';
+    ";
+    assert_eq!(
+        parse_definitions(sql),
+        vec![
+            Definition::Table("user".to_string()),
+            Definition::Field {
+                table: "user".to_string(),
+                name: "email".to_string()
+            },
+            Definition::Index {
+                table: "user".to_string(),
+                name: "email_index".to_string()
+            },
+            Definition::Event {
+                table: "user".to_string(),
+                name: "user_deleted".to_string()
+            },
+            Definition::Function("hash".to_string()),
+        ]
+    );
+}
+
+#[test]
+fn migration_problems_are_found() {
+    let applied = |name: &str| AppliedMigration {
+        script_name: name.to_string(),
+        executed_at: Default::default(),
+    };
+    let migrations = Migrations {
+        scripts: args(&["1_first", "2_second", "3_third"]),
+        down: args(&["1_first", "3_third", "4_fourth"]),
+        applied: vec![applied("1_first"), applied("3_third"), applied("0_gone")],
+    };
+
+    assert_eq!(
+        migrations.problems(),
+        args(&[
+            "0_gone was applied but its script is missing",
+            "2_second has no down script",
+            "2_second is pending but older than the applied 3_third",
+            "the down script 4_fourth belongs to no migration",
+        ])
+    );
+}
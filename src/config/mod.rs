@@ -1,3 +1,10 @@
+pub mod session;
+pub mod settings;
+mod test;
+
+use crate::dto::problem::{FieldError, ProblemDetails};
+use crate::error::ServerResponseError;
+use crate::metrics::METRICS;
 use actix_cors::Cors;
 use actix_extensible_rate_limit::backend::memory::InMemoryBackend;
 use actix_extensible_rate_limit::backend::{
@@ -5,9 +12,14 @@ use actix_extensible_rate_limit::backend::{
 };
 use actix_extensible_rate_limit::{HeaderCompatibleOutput, RateLimiter};
 use actix_web::dev::ServiceRequest;
-use actix_web::HttpResponse;
+use actix_web::error::{JsonPayloadError, PathError, QueryPayloadError};
+use actix_web::http::header::{HeaderName, HeaderValue};
+use actix_web::http::StatusCode;
+use actix_web::web::{JsonConfig, PathConfig, QueryConfig};
 use std::time::Duration;
 
+pub use settings::{Config, Secret};
+
 pub fn rate_limiter(
     rate_limit_backend: InMemoryBackend,
     max_requests: u64,
@@ -21,15 +33,88 @@ pub fn rate_limiter(
     RateLimiter::builder(rate_limit_backend.clone(), input)
         .add_headers()
         .request_denied_response(|res| {
-            HttpResponse::TooManyRequests()
-                .insert_header(("x-ratelimit-reset", res.seconds_until_reset().to_string()))
-                .insert_header(("x-ratelimit-limit", res.limit().to_string()))
-                .insert_header(("x-ratelimit-remaining", res.remaining().to_string()))
-                .body("Too many requests, please try again later")
+            METRICS.rate_limited.inc();
+            let mut response = ProblemDetails::new(
+                StatusCode::TOO_MANY_REQUESTS,
+                "rate_limited",
+                "Too many requests, please try again later",
+            )
+            .with_retry_after(res.seconds_until_reset())
+            .response();
+
+            let headers = response.headers_mut();
+            for (name, value) in [
+                ("x-ratelimit-reset", res.seconds_until_reset()),
+                ("x-ratelimit-limit", res.limit()),
+                ("x-ratelimit-remaining", res.remaining()),
+            ] {
+                headers.insert(HeaderName::from_static(name), HeaderValue::from(value));
+            }
+
+            response
         })
         .build()
 }
 
-pub fn cors() -> Cors {
-    Cors::permissive().supports_credentials().allow_any_header()
+/// Allows credentialed requests from the origin of `frontend_url` only
+pub fn cors(frontend_url: &str) -> Cors {
+    Cors::default()
+        .allowed_origin(origin(frontend_url))
+        .allow_any_method()
+        .allow_any_header()
+        .expose_any_header()
+        .supports_credentials()
+        .max_age(3600)
+}
+
+/// Scheme, host and port of `url`, e.g. `https://example.com` for `https://example.com/app/`
+fn origin(url: &str) -> &str {
+    let host = url.find("://").map(|scheme| scheme + 3).unwrap_or(0);
+
+    match url[host..].find('/') {
+        Some(path) => &url[..host + path],
+        None => url,
+    }
+}
+
+/// Turns a deserialization error into a validation error of the field it names, e.g.
+/// ``missing field `name` at line 1 column 2``, or of `location` if it names none
+fn deserialize_error(location: &str, message: String) -> ServerResponseError {
+    let field = message
+        .split('`')
+        .nth(1)
+        .filter(|_| message.contains("field `"))
+        .unwrap_or(location)
+        .to_string();
+
+    ServerResponseError::Validation(vec![FieldError::new(field, message)])
+}
+
+/// Rejects JSON bodies larger than `limit` bytes and reports invalid ones as problem details
+pub fn json_config(limit: usize) -> JsonConfig {
+    JsonConfig::default()
+        .limit(limit)
+        .error_handler(|err, _| match err {
+            JsonPayloadError::Deserialize(err) => deserialize_error("body", err.to_string()).into(),
+            JsonPayloadError::OverflowKnownLength { .. } | JsonPayloadError::Overflow { .. } => {
+                ServerResponseError::PayloadTooLarge(err.to_string()).into()
+            }
+            err => ServerResponseError::BadRequest(err.to_string()).into(),
+        })
+}
+
+/// Reports invalid query strings as problem details
+pub fn query_config() -> QueryConfig {
+    QueryConfig::default().error_handler(|err, _| match err {
+        QueryPayloadError::Deserialize(err) => deserialize_error("query", err.to_string()).into(),
+        err => ServerResponseError::BadRequest(err.to_string()).into(),
+    })
+}
+
+/// Reports invalid path parameters as problem details
+pub fn path_config() -> PathConfig {
+    PathConfig::default().error_handler(|err, _| match err {
+        PathError::Deserialize(err) => deserialize_error("path", err.to_string()).into(),
+        err => ServerResponseError::BadRequest(err.to_string()).into(),
+    })
 }
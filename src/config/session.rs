@@ -0,0 +1,175 @@
+//! Cookie settings of the session that `actix-identity` keeps the login in. The session lives in
+//! an encrypted cookie, so the key has to be the same for every worker and survive restarts.
+
+use crate::services::audit::REDACTED;
+use actix_identity::IdentityMiddleware;
+use actix_session::config::PersistentSession;
+use actix_session::storage::CookieSessionStore;
+use actix_session::SessionMiddleware;
+use actix_web::cookie::{Cookie, CookieJar, Key, SameSite};
+use std::fmt;
+use std::sync::Arc;
+use std::time::Duration;
+use tracing::warn;
+
+/// Name of the session cookie
+pub const SESSION_COOKIE: &str = "id";
+
+/// Shortest accepted cookie key, the key is split into a signing and an encryption key
+const MIN_KEY_LENGTH: usize = 64;
+
+/// The key new session cookies are encrypted with and the keys that are still accepted
+#[derive(Clone)]
+pub struct SessionKeys {
+    pub current: Key,
+    /// Keys that were rotated out. Cookies encrypted with them are still accepted, they are
+    /// only re-encrypted for the session middleware of the current request and the client keeps
+    /// the old cookie until it expires, so a key has to stay here for at least the session TTL.
+    pub old: Vec<Key>,
+}
+
+impl fmt::Debug for SessionKeys {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("SessionKeys")
+            .field("current", &REDACTED)
+            .field("old", &self.old.len())
+            .finish()
+    }
+}
+
+impl SessionKeys {
+    /// The value of a session cookie encrypted with one of the old keys, encrypted with the
+    /// current key instead. `None` if the current key already decrypts it or no key does.
+    pub fn reseal(&self, value: &str) -> Option<String> {
+        let mut jar = CookieJar::new();
+        jar.add_original(Cookie::new(SESSION_COOKIE, value.to_string()));
+
+        if jar.private(&self.current).get(SESSION_COOKIE).is_some() {
+            return None;
+        }
+
+        let cookie = self
+            .old
+            .iter()
+            .find_map(|key| jar.private(key).get(SESSION_COOKIE))?;
+
+        let mut resealed = CookieJar::new();
+        resealed.private_mut(&self.current).add(cookie);
+        resealed
+            .get(SESSION_COOKIE)
+            .map(|cookie| cookie.value().to_string())
+    }
+}
+
+/// How the session cookie is sent
+///
+/// - `COOKIE_KEY` - at least 64 characters, e.g. `openssl rand -base64 64`. Without it a random
+///   key is generated and every restart logs everyone out.
+/// - `COOKIE_OLD_KEYS` - comma separated keys that were replaced by `COOKIE_KEY`
+/// - `COOKIE_SECURE` - only send the cookie over HTTPS, `true` by default
+/// - `COOKIE_SAME_SITE` - `strict`, `lax` (the default) or `none`, which requires
+///   `COOKIE_SECURE`
+/// - `SESSION_TTL` - seconds a login lasts, `3600` by default
+#[derive(Debug, Clone)]
+pub struct SessionConfig {
+    pub keys: Arc<SessionKeys>,
+    pub secure: bool,
+    pub same_site: SameSite,
+    pub ttl: Duration,
+}
+
+impl SessionConfig {
+    pub(crate) fn from_vars(var: impl Fn(&str) -> Option<String>) -> Result<Self, Vec<String>> {
+        let mut errors = Vec::new();
+        let mut key = |name: &str, value: &str| match Key::try_from(value.trim().as_bytes()) {
+            Ok(key) => Some(key),
+            Err(_) => {
+                errors.push(format!(
+                    "{name} has to be at least {MIN_KEY_LENGTH} characters long"
+                ));
+                None
+            }
+        };
+
+        let current = match var("COOKIE_KEY") {
+            Some(value) => key("COOKIE_KEY", &value),
+            None => {
+                warn!("COOKIE_KEY is not set, sessions will not survive a restart");
+                Some(Key::generate())
+            }
+        };
+        let old: Vec<Key> = var("COOKIE_OLD_KEYS")
+            .unwrap_or_default()
+            .split(',')
+            .filter(|value| !value.trim().is_empty())
+            .filter_map(|value| key("COOKIE_OLD_KEYS", value))
+            .collect();
+
+        let secure = match var("COOKIE_SECURE").map(|value| value.to_lowercase()) {
+            None => true,
+            Some(value) if value == "true" || value == "1" => true,
+            Some(value) if value == "false" || value == "0" => false,
+            Some(value) => {
+                errors.push(format!("COOKIE_SECURE is not a boolean: {value:?}"));
+                true
+            }
+        };
+
+        let same_site = match var("COOKIE_SAME_SITE").map(|value| value.to_lowercase()) {
+            None => SameSite::Lax,
+            Some(value) if value == "strict" => SameSite::Strict,
+            Some(value) if value == "lax" => SameSite::Lax,
+            Some(value) if value == "none" => SameSite::None,
+            Some(value) => {
+                errors.push(format!(
+                    "unknown COOKIE_SAME_SITE {value:?}, expected strict, lax or none"
+                ));
+                SameSite::Lax
+            }
+        };
+        if same_site == SameSite::None && !secure {
+            errors.push("COOKIE_SAME_SITE=none requires COOKIE_SECURE=true".to_string());
+        }
+
+        let ttl = match var("SESSION_TTL") {
+            None => Duration::from_secs(60 * 60),
+            Some(value) => match value.trim().parse::<u64>() {
+                Ok(seconds) if seconds > 0 => Duration::from_secs(seconds),
+                _ => {
+                    errors.push(format!(
+                        "SESSION_TTL has to be a number greater than 0: {value:?}"
+                    ));
+                    Duration::from_secs(60 * 60)
+                }
+            },
+        };
+
+        match current {
+            Some(current) if errors.is_empty() => Ok(Self {
+                keys: Arc::new(SessionKeys { current, old }),
+                secure,
+                same_site,
+                ttl,
+            }),
+            _ => Err(errors),
+        }
+    }
+
+    pub fn middleware(&self) -> SessionMiddleware<CookieSessionStore> {
+        let ttl = actix_web::cookie::time::Duration::seconds(self.ttl.as_secs() as i64);
+
+        SessionMiddleware::builder(CookieSessionStore::default(), self.keys.current.clone())
+            .cookie_name(SESSION_COOKIE.to_string())
+            .cookie_secure(self.secure)
+            .cookie_same_site(self.same_site)
+            .session_lifecycle(PersistentSession::default().session_ttl(ttl))
+            .build()
+    }
+
+    /// Logins expire together with the session
+    pub fn identity(&self) -> IdentityMiddleware {
+        IdentityMiddleware::builder()
+            .login_deadline(Some(self.ttl))
+            .build()
+    }
+}
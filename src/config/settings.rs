@@ -0,0 +1,421 @@
+//! The configuration of the server, loaded and validated once on startup.
+//!
+//! Every value is looked up in layers, a later layer wins: the defaults, the file given with
+//! `--config` or `CONFIG_FILE` in the `.env` format, the environment including the `.env` files
+//! and command line flags, where `--limit-duration 60` or `--limit-duration=60` sets
+//! `LIMIT_DURATION`. All invalid values are reported together before the server starts.
+
+use crate::config::session::SessionConfig;
+use crate::dto::config::{ConfigLayer, ConfigValue, ConfigView};
+use crate::metrics::MetricsAccess;
+use crate::server::background_logger::LoggerConfig;
+use crate::server_error::ServerError;
+use crate::services::audit::REDACTED;
+use crate::services::embeddings::reembed::EmbeddingConfig;
+use crate::services::files::policy::UploadPolicy;
+use crate::services::files::scan::ScannerConfig;
+use crate::services::files::store::FileStoreConfig;
+use crate::services::jobs::JobsConfig;
+use crate::services::llm::LlmConfig;
+use once_cell::sync::OnceCell;
+use std::cell::RefCell;
+use std::collections::{BTreeMap, HashMap};
+use std::fmt;
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::Duration;
+
+static CONFIG: OnceCell<Arc<Config>> = OnceCell::new();
+
+/// Parts of a name that mark its value as secret, e.g. `SURREALDB_PASS` or `COOKIE_KEY`
+const SECRET_PARTS: [&str; 5] = ["PASS", "PASSWORD", "SECRET", "KEY", "KEYS"];
+
+/// A value that is never logged or shown, use [`Secret::expose`] where it is needed
+#[derive(Clone, PartialEq, Eq)]
+pub struct Secret(String);
+
+impl Secret {
+    pub fn new(value: impl Into<String>) -> Self {
+        Self(value.into())
+    }
+
+    pub fn expose(&self) -> &str {
+        &self.0
+    }
+}
+
+impl fmt::Debug for Secret {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(REDACTED)
+    }
+}
+
+fn is_secret(name: &str) -> bool {
+    name.split('_').any(|part| SECRET_PARTS.contains(&part))
+}
+
+/// The layers values are read from, see the [module documentation](self)
+pub struct ConfigSource {
+    /// Path of the configuration file, if one was given
+    file_path: Option<PathBuf>,
+    file: HashMap<String, String>,
+    env: Box<dyn Fn(&str) -> Option<String>>,
+    flags: HashMap<String, String>,
+    /// Every value that was looked up and the layer it came from
+    read: RefCell<BTreeMap<String, (ConfigLayer, Option<String>)>>,
+}
+
+impl ConfigSource {
+    /// Reads the flags in `args`, the process arguments without the program name, the
+    /// configuration file and the environment
+    pub fn new(args: impl IntoIterator<Item = String>) -> Result<Self, String> {
+        let env = |name: &str| std::env::var(name).ok().filter(|value| !value.is_empty());
+        let flags = parse_flags(args)?;
+
+        let file_path = flags
+            .get("CONFIG")
+            .cloned()
+            .or_else(|| env("CONFIG_FILE"))
+            .map(PathBuf::from);
+        let file = match &file_path {
+            Some(path) => dotenv::from_filename_iter(path)
+                .and_then(|values| values.collect::<Result<HashMap<_, _>, _>>())
+                .map_err(|err| format!("cannot read the configuration file {path:?}: {err}"))?,
+            None => HashMap::new(),
+        };
+
+        Ok(Self::from_layers(file, env, flags).with_file_path(file_path))
+    }
+
+    pub(crate) fn from_layers(
+        file: HashMap<String, String>,
+        env: impl Fn(&str) -> Option<String> + 'static,
+        flags: HashMap<String, String>,
+    ) -> Self {
+        Self {
+            file_path: None,
+            file,
+            env: Box::new(env),
+            flags,
+            read: RefCell::new(BTreeMap::new()),
+        }
+    }
+
+    fn with_file_path(mut self, file_path: Option<PathBuf>) -> Self {
+        self.file_path = file_path;
+        self
+    }
+
+    /// The value of `name` from the highest layer that sets it, empty values count as unset
+    pub fn get(&self, name: &str) -> Option<String> {
+        let non_empty = |value: Option<&String>| value.filter(|value| !value.is_empty()).cloned();
+
+        let (layer, value) = if let Some(value) = non_empty(self.flags.get(name)) {
+            (ConfigLayer::Flag, Some(value))
+        } else if let Some(value) = (self.env)(name).filter(|value| !value.is_empty()) {
+            (ConfigLayer::Environment, Some(value))
+        } else if let Some(value) = non_empty(self.file.get(name)) {
+            (ConfigLayer::File, Some(value))
+        } else {
+            (ConfigLayer::Default, None)
+        };
+
+        self.read
+            .borrow_mut()
+            .insert(name.to_string(), (layer, value.clone()));
+
+        value
+    }
+
+    /// Every value that was looked up, secrets replaced with [`REDACTED`]
+    fn view(&self) -> ConfigView {
+        let values = self
+            .read
+            .borrow()
+            .iter()
+            .map(|(name, (source, value))| ConfigValue {
+                name: name.clone(),
+                value: value.as_ref().map(|value| {
+                    if is_secret(name) {
+                        REDACTED.to_string()
+                    } else {
+                        value.clone()
+                    }
+                }),
+                source: *source,
+            })
+            .collect();
+
+        ConfigView {
+            config_file: self
+                .file_path
+                .as_ref()
+                .map(|path| path.display().to_string()),
+            values,
+        }
+    }
+}
+
+/// Parses `--name value`, `--name=value` and `--name` (meaning `true`) into `NAME`
+pub(crate) fn parse_flags(
+    args: impl IntoIterator<Item = String>,
+) -> Result<HashMap<String, String>, String> {
+    let mut args = args.into_iter().peekable();
+    let mut flags = HashMap::new();
+
+    while let Some(arg) = args.next() {
+        let Some(flag) = arg.strip_prefix("--") else {
+            return Err(format!(
+                "unexpected argument {arg:?}, expected --name value"
+            ));
+        };
+
+        let (name, value) = match flag.split_once('=') {
+            Some((name, value)) => (name, value.to_string()),
+            None => match args.next_if(|next| !next.starts_with("--")) {
+                Some(value) => (flag, value),
+                None => (flag, "true".to_string()),
+            },
+        };
+
+        flags.insert(name.replace('-', "_").to_uppercase(), value);
+    }
+
+    Ok(flags)
+}
+
+/// Connection to SurrealDB, `SURREALDB_*`
+#[derive(Debug, Clone)]
+pub struct DatabaseConfig {
+    /// Address of the server, e.g. `localhost:8000`
+    pub url: String,
+    pub user: String,
+    pub password: Secret,
+    pub namespace: String,
+    pub database: String,
+}
+
+/// Requests per client and window, `LIMIT` and `LIMIT_DURATION`
+#[derive(Debug, Clone, Copy)]
+pub struct RateLimitConfig {
+    pub max_requests: u64,
+    pub duration: Duration,
+}
+
+/// Credentials of an OAuth application
+#[derive(Debug, Clone)]
+pub struct OauthCredentials {
+    pub client_id: String,
+    pub client_secret: Secret,
+}
+
+#[derive(Debug, Clone)]
+pub struct OauthConfig {
+    /// `GOOGLE_CLIENT_ID` and `GOOGLE_CLIENT_SECRET`
+    pub google: OauthCredentials,
+    /// `GITHUB_CLIENT_ID` and `GITHUB_CLIENT_SECRET`
+    pub github: OauthCredentials,
+}
+
+#[derive(Debug)]
+pub struct Config {
+    /// `PORT`, defaults to `9999`
+    pub port: u16,
+    /// Where the server is reachable, used for redirects, `BASE_URL`
+    pub base_url: String,
+    /// `FRONTEND_URL`
+    pub frontend_url: String,
+    pub database: DatabaseConfig,
+    pub rate_limit: RateLimitConfig,
+    /// The session cookie, see [`SessionConfig`]
+    pub session: SessionConfig,
+    pub oauth: OauthConfig,
+    pub llm: LlmConfig,
+    pub file_store: FileStoreConfig,
+    /// Local directory for uploads that are still being received, `FILE_SCRATCH_PATH`
+    pub scratch_path: PathBuf,
+    /// Key of share link signatures, `SHARE_LINK_SECRET`
+    pub share_link_secret: Option<Secret>,
+    pub upload_policy: UploadPolicy,
+    pub scanner: ScannerConfig,
+    pub logger: LoggerConfig,
+    pub metrics_access: MetricsAccess,
+    pub jobs: JobsConfig,
+    /// Embedding API of the `reembed` job, `None` without `EMBEDDING_URL`
+    pub embedding: Option<EmbeddingConfig>,
+    /// Seconds in-flight requests, job runs and the audit log get to finish on shutdown,
+    /// `SHUTDOWN_TIMEOUT`
+    pub shutdown_timeout: Duration,
+    /// Start even when migrations fail, `ALLOW_FAILED_MIGRATIONS`
+    pub allow_failed_migrations: bool,
+    /// What was read from where, for `/admin/config`
+    view: ConfigView,
+}
+
+impl Config {
+    /// Loads the configuration on first use, `args` are the command line flags
+    pub fn init(args: impl IntoIterator<Item = String>) -> Result<Arc<Self>, ServerError> {
+        CONFIG
+            .get_or_try_init(|| {
+                let source = ConfigSource::new(args).map_err(ServerError::Error)?;
+                Self::load(&source).map(Arc::new).map_err(|errors| {
+                    ServerError::Error(format!("Invalid configuration: {}", errors.join("; ")))
+                })
+            })
+            .cloned()
+    }
+
+    /// The configuration loaded by [`Config::init`]
+    pub fn global() -> Arc<Self> {
+        CONFIG
+            .get()
+            .cloned()
+            .expect("the configuration is loaded during setup")
+    }
+
+    /// Reads and validates every value, returns all problems that were found
+    pub(crate) fn load(source: &ConfigSource) -> Result<Self, Vec<String>> {
+        let mut errors = Vec::new();
+        let var = |name: &str| source.get(name);
+
+        let mut required = |name: &str| {
+            var(name).unwrap_or_else(|| {
+                errors.push(format!("{name} is required"));
+                String::new()
+            })
+        };
+        let database = DatabaseConfig {
+            url: required("SURREALDB_URL"),
+            user: required("SURREALDB_USER"),
+            password: Secret::new(required("SURREALDB_PASS")),
+            namespace: var("SURREALDB_NAMESPACE").unwrap_or_else(|| "default".to_string()),
+            database: var("SURREALDB_DATABASE").unwrap_or_else(|| "default".to_string()),
+        };
+        let mut credentials = |provider: &str| OauthCredentials {
+            client_id: required(&format!("{provider}_CLIENT_ID")),
+            client_secret: Secret::new(required(&format!("{provider}_CLIENT_SECRET"))),
+        };
+        let oauth = OauthConfig {
+            google: credentials("GOOGLE"),
+            github: credentials("GITHUB"),
+        };
+
+        let mut number = |name: &str, default: u64| match var(name) {
+            Some(value) => match value.trim().parse::<u64>() {
+                Ok(number) if number > 0 => number,
+                _ => {
+                    errors.push(format!(
+                        "{name} has to be a number greater than 0: {value:?}"
+                    ));
+                    default
+                }
+            },
+            None => default,
+        };
+        let port = number("PORT", 9999);
+        let rate_limit = RateLimitConfig {
+            max_requests: number("LIMIT", 100),
+            duration: Duration::from_secs(number("LIMIT_DURATION", 30)),
+        };
+        let shutdown_timeout = Duration::from_secs(number("SHUTDOWN_TIMEOUT", 30));
+
+        let port = u16::try_from(port).unwrap_or_else(|_| {
+            errors.push(format!("PORT has to be at most {}: {port}", u16::MAX));
+            9999
+        });
+
+        let mut url = |name: &str, default: &str| {
+            let url = var(name).unwrap_or_else(|| default.to_string());
+            if !url.starts_with("http://") && !url.starts_with("https://") {
+                errors.push(format!(
+                    "{name} has to start with http:// or https://: {url:?}"
+                ));
+            }
+            url
+        };
+        // Redirect endpoints start with a `/`
+        let base_url = url("BASE_URL", "http://localhost:9999")
+            .trim_end_matches('/')
+            .to_string();
+        let frontend_url = url("FRONTEND_URL", "http://localhost:5173");
+
+        let scratch_path = var("FILE_SCRATCH_PATH")
+            .map(PathBuf::from)
+            .unwrap_or_else(|| std::env::temp_dir().join("threatmapper_uploads"));
+        let share_link_secret = var("SHARE_LINK_SECRET").map(Secret::new);
+        let allow_failed_migrations =
+            match var("ALLOW_FAILED_MIGRATIONS").map(|value| value.to_lowercase()) {
+                None => false,
+                Some(value) if value == "true" || value == "1" => true,
+                Some(value) if value == "false" || value == "0" => false,
+                Some(value) => {
+                    errors.push(format!(
+                        "ALLOW_FAILED_MIGRATIONS is not a boolean: {value:?}"
+                    ));
+                    false
+                }
+            };
+
+        let llm = LlmConfig::from_vars(var).map_err(|err| errors.push(err.to_string()));
+        let file_store =
+            FileStoreConfig::from_vars(var).map_err(|err| errors.push(err.to_string()));
+        let upload_policy = UploadPolicy::from_vars(var).map_err(|err| errors.push(err));
+        let scanner = ScannerConfig::from_vars(var).map_err(|err| errors.push(err.to_string()));
+        let logger = LoggerConfig::from_vars(var).map_err(|err| errors.push(err));
+        let metrics_access = MetricsAccess::from_vars(var).map_err(|err| errors.push(err));
+        let session = SessionConfig::from_vars(var).map_err(|errs| errors.extend(errs));
+        let jobs = JobsConfig::from_vars(var).map_err(|err| errors.push(err));
+        let embedding = EmbeddingConfig::from_vars(var).map_err(|err| errors.push(err));
+
+        match (
+            llm,
+            file_store,
+            upload_policy,
+            scanner,
+            logger,
+            metrics_access,
+            session,
+            jobs,
+            embedding,
+        ) {
+            (
+                Ok(llm),
+                Ok(file_store),
+                Ok(upload_policy),
+                Ok(scanner),
+                Ok(logger),
+                Ok(metrics_access),
+                Ok(session),
+                Ok(jobs),
+                Ok(embedding),
+            ) if errors.is_empty() => Ok(Self {
+                port,
+                base_url,
+                frontend_url,
+                database,
+                rate_limit,
+                session,
+                oauth,
+                llm,
+                file_store,
+                scratch_path,
+                share_link_secret,
+                upload_policy,
+                scanner,
+                logger,
+                metrics_access,
+                jobs,
+                embedding,
+                shutdown_timeout,
+                allow_failed_migrations,
+                view: source.view(),
+            }),
+            _ => Err(errors),
+        }
+    }
+
+    /// The resolved values and where they came from, secrets are redacted
+    pub fn view(&self) -> &ConfigView {
+        &self.view
+    }
+}
@@ -0,0 +1,189 @@
+#![cfg(test)]
+
+use super::settings::{parse_flags, ConfigSource};
+use super::Config;
+use crate::dto::config::ConfigLayer;
+use crate::models::job::JobName;
+use crate::services::jobs::JobSchedule;
+use std::collections::HashMap;
+use std::time::Duration;
+
+fn layer(values: &[(&str, &str)]) -> HashMap<String, String> {
+    values
+        .iter()
+        .map(|(name, value)| (name.to_string(), value.to_string()))
+        .collect()
+}
+
+fn required() -> HashMap<String, String> {
+    layer(&[
+        ("SURREALDB_URL", "localhost:8000"),
+        ("SURREALDB_USER", "root"),
+        ("SURREALDB_PASS", "root"),
+        ("GOOGLE_CLIENT_ID", "google"),
+        ("GOOGLE_CLIENT_SECRET", "google-secret"),
+        ("GITHUB_CLIENT_ID", "github"),
+        ("GITHUB_CLIENT_SECRET", "github-secret"),
+    ])
+}
+
+#[test]
+fn flags_are_parsed() {
+    let args = ["--limit-duration", "60", "--port=8080", "--dry-run"].map(String::from);
+    let flags = parse_flags(args).unwrap();
+
+    assert_eq!(flags["LIMIT_DURATION"], "60");
+    assert_eq!(flags["PORT"], "8080");
+    assert_eq!(flags["DRY_RUN"], "true");
+    assert!(parse_flags(["60".to_string()]).is_err());
+}
+
+#[test]
+fn later_layers_win() {
+    let mut file = required();
+    file.extend(layer(&[
+        ("LIMIT", "5"),
+        ("LIMIT_DURATION", "10"),
+        ("PORT", "1000"),
+    ]));
+    let env = |name: &str| match name {
+        "LIMIT_DURATION" => Some("20".to_string()),
+        "PORT" => Some("2000".to_string()),
+        _ => None,
+    };
+    let source = ConfigSource::from_layers(file, env, layer(&[("PORT", "3000")]));
+
+    let config = Config::load(&source).unwrap();
+    assert_eq!(config.rate_limit.max_requests, 5);
+    assert_eq!(config.rate_limit.duration, Duration::from_secs(20));
+    assert_eq!(config.port, 3000);
+
+    let value = |name: &str| {
+        config
+            .view()
+            .values
+            .iter()
+            .find(|value| value.name == name)
+            .cloned()
+            .unwrap()
+    };
+    assert_eq!(value("LIMIT").source, ConfigLayer::File);
+    assert_eq!(value("LIMIT_DURATION").source, ConfigLayer::Environment);
+    assert_eq!(value("PORT").source, ConfigLayer::Flag);
+    assert_eq!(value("BASE_URL").source, ConfigLayer::Default);
+    assert_eq!(value("SURREALDB_PASS").value.as_deref(), Some("[REDACTED]"));
+}
+
+#[test]
+fn every_invalid_value_is_reported() {
+    let flags = layer(&[
+        ("PORT", "70000"),
+        ("LIMIT", "none"),
+        ("BASE_URL", "localhost"),
+    ]);
+    let source = ConfigSource::from_layers(HashMap::new(), |_| None, flags);
+
+    let errors = Config::load(&source).unwrap_err();
+    for name in [
+        "SURREALDB_URL",
+        "GITHUB_CLIENT_SECRET",
+        "PORT",
+        "LIMIT",
+        "BASE_URL",
+    ] {
+        assert!(
+            errors.iter().any(|error| error.starts_with(name)),
+            "{name} is not reported in {errors:?}"
+        );
+    }
+}
+
+#[test]
+fn session_cookies_of_old_keys_are_resealed() {
+    use super::session::{SessionConfig, SESSION_COOKIE};
+    use actix_web::cookie::{Cookie, CookieJar, Key};
+
+    let (old, current) = (Key::generate(), Key::generate());
+    let seal = |key: &Key| {
+        let mut jar = CookieJar::new();
+        jar.private_mut(key)
+            .add(Cookie::new(SESSION_COOKIE, "session"));
+        jar.get(SESSION_COOKIE).unwrap().value().to_string()
+    };
+    let keys = super::session::SessionKeys {
+        current: current.clone(),
+        old: vec![old.clone()],
+    };
+
+    assert_eq!(keys.reseal(&seal(&current)), None);
+    assert_eq!(keys.reseal(&seal(&Key::generate())), None);
+
+    let resealed = keys.reseal(&seal(&old)).unwrap();
+    let mut jar = CookieJar::new();
+    jar.add_original(Cookie::new(SESSION_COOKIE, resealed));
+    assert_eq!(
+        jar.private(&current).get(SESSION_COOKIE).unwrap().value(),
+        "session"
+    );
+
+    let vars = |values: &'static [(&'static str, &'static str)]| {
+        move |name: &str| {
+            values
+                .iter()
+                .find(|(var, _)| *var == name)
+                .map(|(_, value)| value.to_string())
+        }
+    };
+    assert!(SessionConfig::from_vars(vars(&[("COOKIE_KEY", "short")])).is_err());
+    assert!(SessionConfig::from_vars(vars(&[
+        ("COOKIE_SAME_SITE", "none"),
+        ("COOKIE_SECURE", "false")
+    ]))
+    .is_err());
+    let errors = SessionConfig::from_vars(vars(&[("COOKIE_KEY", "short"), ("SESSION_TTL", "0")]))
+        .err()
+        .unwrap();
+    assert_eq!(errors.len(), 2);
+}
+
+#[test]
+fn cors_allows_the_frontend_origin() {
+    assert_eq!(
+        super::origin("https://example.com/app/"),
+        "https://example.com"
+    );
+    assert_eq!(
+        super::origin("http://localhost:5173"),
+        "http://localhost:5173"
+    );
+}
+
+#[test]
+fn job_schedules_are_parsed() {
+    let mut flags = required();
+    flags.extend(layer(&[
+        ("FILE_RECONCILE_INTERVAL", "600"),
+        ("JOB_PURGE_SESSIONS_SCHEDULE", "0 */5 * * * *"),
+        ("JOB_REEMBED_SCHEDULE", "manual"),
+    ]));
+    let source = ConfigSource::from_layers(HashMap::new(), |_| None, flags);
+
+    let config = Config::load(&source).unwrap();
+    let schedule = |job| config.jobs.schedule(job).clone();
+    assert_eq!(
+        schedule(JobName::PurgeSessions),
+        JobSchedule::Cron("0 */5 * * * *".to_string())
+    );
+    assert_eq!(
+        schedule(JobName::FileCleanup),
+        JobSchedule::Every(Duration::from_secs(600))
+    );
+    assert_eq!(schedule(JobName::Reembed), JobSchedule::Manual);
+
+    let flags = layer(&[("JOB_LOG_RETENTION_SCHEDULE", "*/5 * * *")]);
+    let source = ConfigSource::from_layers(required(), |_| None, flags);
+    let errors = Config::load(&source).unwrap_err();
+    assert!(errors
+        .iter()
+        .any(|error| error.starts_with("JOB_LOG_RETENTION_SCHEDULE")));
+}
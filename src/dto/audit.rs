@@ -0,0 +1,62 @@
+use crate::models::audit::Outcome;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use utoipa::{IntoParams, ToResponse, ToSchema};
+
+/// Filters the audit trail, all given filters have to match
+#[derive(Serialize, Deserialize, IntoParams, Debug, Clone, Default)]
+#[into_params(parameter_in = Query)]
+pub struct AuditQuery {
+    /// ID of the user, with or without the `user:` prefix
+    pub user: Option<String>,
+    /// Method and route, e.g. `DELETE /api/v1/files/{file_id}`
+    pub action: Option<String>,
+    /// Record the request acted on, e.g. `file:1b6k0a4r5dn7s0ex9a3f`
+    pub target: Option<String>,
+    #[param(inline)]
+    pub outcome: Option<Outcome>,
+    pub status: Option<u16>,
+    /// Only entries at or after this time
+    #[param(value_type = Option<String>, format = DateTime)]
+    pub from: Option<DateTime<Utc>>,
+    /// Only entries before this time
+    #[param(value_type = Option<String>, format = DateTime)]
+    pub to: Option<DateTime<Utc>>,
+    /// Entries per page, at most 500. Defaults to 50.
+    pub limit: Option<u64>,
+    /// Entries to skip
+    pub offset: Option<u64>,
+}
+
+/// Format of an audit trail export
+#[derive(Serialize, Deserialize, ToSchema, Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum AuditExportFormat {
+    /// One JSON object per line
+    #[default]
+    Jsonl,
+    Csv,
+}
+
+#[derive(Serialize, Deserialize, IntoParams, Debug, Clone, Default)]
+#[into_params(parameter_in = Query)]
+pub struct AuditExportQuery {
+    #[param(inline)]
+    #[serde(default)]
+    pub format: AuditExportFormat,
+}
+
+/// Counters of the background logger writing the audit trail since startup
+#[derive(Serialize, Deserialize, ToSchema, ToResponse, Debug, Clone, Copy, Default)]
+pub struct LoggerStatus {
+    /// Entries waiting to be written
+    pub queued: u64,
+    pub written: u64,
+    /// Entries lost because the queue was full or they could not be written
+    pub dropped: u64,
+    /// Entries kept aside while the queue was full, see `LOG_OVERFLOW`
+    pub spilled: u64,
+    pub failed_writes: u64,
+    /// Times the logger reconnected to the database
+    pub reconnects: u64,
+}
@@ -0,0 +1,52 @@
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+/// Token counts for a single chat completion.
+#[derive(Serialize, Deserialize, ToSchema, Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct TokenUsage {
+    pub prompt_tokens: u64,
+    pub completion_tokens: u64,
+}
+
+/// A single piece of a streamed chat completion.
+///
+/// Every LLM backend is translated into this format, the client receives one chunk per
+/// server-sent event followed by a final `data: [DONE]` event.
+#[derive(Serialize, Deserialize, ToSchema, Debug, Clone, Default, PartialEq)]
+pub struct ChatChunk {
+    /// The backend that produced this chunk
+    #[schema(example = "openai")]
+    pub backend: String,
+    /// The model that produced this chunk, if the backend reports one
+    #[schema(example = "llama3.1:8b")]
+    pub model: Option<String>,
+    /// Text generated since the previous chunk
+    #[schema(example = "SQL injection is ")]
+    pub delta: String,
+    /// Set on the last chunk of a completion
+    #[schema(example = "stop")]
+    pub finish_reason: Option<String>,
+    /// Token counts, only present when the backend reports them
+    pub usage: Option<TokenUsage>,
+}
+
+impl ChatChunk {
+    pub fn delta(backend: &str, model: Option<&str>, delta: impl Into<String>) -> Self {
+        Self {
+            backend: backend.to_string(),
+            model: model.map(str::to_string),
+            delta: delta.into(),
+            ..Default::default()
+        }
+    }
+
+    pub fn finish(backend: &str, model: Option<&str>, usage: Option<TokenUsage>) -> Self {
+        Self {
+            backend: backend.to_string(),
+            model: model.map(str::to_string),
+            finish_reason: Some("stop".to_string()),
+            usage,
+            ..Default::default()
+        }
+    }
+}
@@ -1,7 +1,7 @@
 use serde::{Deserialize, Serialize};
-use utoipa::ToSchema;
+use utoipa::{IntoParams, ToSchema};
 
-#[derive(Serialize, Deserialize, ToSchema, Debug)]
+#[derive(Serialize, Deserialize, ToSchema, Debug, Clone)]
 pub enum Keyword {
     Website,
     Web,
@@ -15,7 +15,7 @@ pub enum Keyword {
     Encryption
 }
 
-#[derive(Serialize, Deserialize, ToSchema, Debug)]
+#[derive(Serialize, Deserialize, ToSchema, Debug, Clone)]
 #[serde(tag = "type", rename_all = "snake_case")]
 pub enum ChatRequest {
     Structured {
@@ -25,3 +25,50 @@ pub enum ChatRequest {
     },
     Chat { prompt: String },
 }
+
+impl ChatRequest {
+    pub fn prompt(&self) -> &str {
+        match self {
+            ChatRequest::Structured { prompt, .. } => prompt,
+            ChatRequest::Chat { prompt } => prompt,
+        }
+    }
+
+    pub fn keywords(&self) -> &[Keyword] {
+        match self {
+            ChatRequest::Structured { keywords, .. } => keywords,
+            ChatRequest::Chat { .. } => &[],
+        }
+    }
+}
+
+/// Selects which LLM backend and model should answer a chat request.
+///
+/// Anything left out falls back to the user's stored preference and then to the server default.
+#[derive(Serialize, Deserialize, ToSchema, IntoParams, Debug, Clone, Default)]
+#[into_params(parameter_in = Query)]
+pub struct LlmSelection {
+    /// Name of the backend, see `GET /api/v1/chat/backends`
+    #[param(example = "openai")]
+    pub backend: Option<String>,
+    /// Model to use, must be in the backend's allow-list
+    #[param(example = "llama3.1:8b")]
+    pub model: Option<String>,
+}
+
+impl LlmSelection {
+    /// Fills in every unset field from `fallback`. The fallback's model is only inherited when
+    /// it belongs to the selected backend, i.e. the backend is unset or names the same backend.
+    pub fn or(self, fallback: LlmSelection) -> Self {
+        match self.backend {
+            Some(backend) if fallback.backend.as_deref() != Some(backend.as_str()) => Self {
+                backend: Some(backend),
+                model: self.model,
+            },
+            backend => Self {
+                backend: backend.or(fallback.backend),
+                model: self.model.or(fallback.model),
+            },
+        }
+    }
+}
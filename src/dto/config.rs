@@ -0,0 +1,35 @@
+use serde::{Deserialize, Serialize};
+use utoipa::{ToResponse, ToSchema};
+
+/// Where a configuration value came from, later layers win
+#[derive(Serialize, Deserialize, ToSchema, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum ConfigLayer {
+    /// Not set, the default is used
+    Default,
+    /// The file given with `--config` or `CONFIG_FILE`
+    File,
+    /// The environment or a `.env` file
+    Environment,
+    /// A command line flag
+    Flag,
+}
+
+/// A configuration value as the server resolved it
+#[derive(Serialize, Deserialize, ToSchema, Debug, Clone)]
+pub struct ConfigValue {
+    #[schema(example = "LIMIT_DURATION")]
+    pub name: String,
+    /// `null` if the default is used, secrets are replaced with `[REDACTED]`
+    #[schema(example = "30")]
+    pub value: Option<String>,
+    pub source: ConfigLayer,
+}
+
+/// Every configuration value the server read on startup
+#[derive(Serialize, Deserialize, ToSchema, ToResponse, Debug, Clone)]
+pub struct ConfigView {
+    /// Path of the configuration file, if one was given
+    pub config_file: Option<String>,
+    pub values: Vec<ConfigValue>,
+}
@@ -0,0 +1,49 @@
+use crate::models::file_share::FileShare;
+use serde::{Deserialize, Serialize};
+use utoipa::{IntoParams, ToResponse, ToSchema};
+
+fn default_expires_in() -> u64 {
+    24 * 60 * 60
+}
+
+#[derive(Serialize, Deserialize, ToSchema, Debug, Clone)]
+pub struct ShareLinkRequest {
+    /// Seconds until the link expires, at most 30 days
+    #[serde(default = "default_expires_in")]
+    #[schema(example = 86400)]
+    pub expires_in: u64,
+    /// Password the recipient has to enter before downloading
+    pub password: Option<String>,
+    /// Number of downloads after which the link stops working
+    #[schema(example = 3)]
+    pub max_downloads: Option<u64>,
+}
+
+/// A share link together with its signed URL, relative to the server root
+#[derive(Serialize, Deserialize, ToSchema, ToResponse, Debug, Clone)]
+pub struct ShareLink {
+    #[serde(flatten)]
+    pub share: FileShare,
+    #[schema(example = "/api/v1/shared/8qk2mzp0tq1b?expires=1767225600&signature=3f2a...")]
+    pub url: String,
+}
+
+#[allow(dead_code)]
+#[derive(ToResponse)]
+pub struct ShareLinks(pub Vec<ShareLink>);
+
+/// Signature part of a share link URL
+#[derive(Serialize, Deserialize, ToSchema, IntoParams, Debug, Clone)]
+#[into_params(parameter_in = Query)]
+pub struct SharedFileQuery {
+    /// Unix timestamp at which the link expires
+    pub expires: i64,
+    /// Hex encoded HMAC-SHA256 of the share ID and expiry
+    pub signature: String,
+}
+
+/// Form posted to download a password protected share
+#[derive(Serialize, Deserialize, ToSchema, Debug, Clone)]
+pub struct SharePasswordForm {
+    pub password: String,
+}
@@ -0,0 +1,62 @@
+use crate::dto::llm_backend::LlmBackendHealth;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use utoipa::{ToResponse, ToSchema};
+
+#[derive(Serialize, Deserialize, ToSchema, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum HealthStatus {
+    Up,
+    /// Usable with reduced functionality, does not fail the readiness check
+    Degraded,
+    /// Not usable, the server is not ready
+    Down,
+}
+
+/// Status of the server as reported to unauthenticated probes, the details of a
+/// [`HealthReport`] are only shown to admins
+#[derive(Serialize, Deserialize, ToSchema, ToResponse, Debug, Clone)]
+pub struct HealthSummary {
+    pub status: HealthStatus,
+}
+
+/// Migration scripts compared with the ones applied to the database
+#[derive(Serialize, Deserialize, ToSchema, Debug, Clone)]
+pub struct MigrationStatus {
+    pub up_to_date: bool,
+    pub applied: usize,
+    /// Scripts that have not been applied, oldest first
+    pub pending: Vec<String>,
+}
+
+/// Result of checking a single dependency
+#[derive(Serialize, Deserialize, ToSchema, Debug, Clone)]
+pub struct DependencyHealth {
+    #[schema(example = "database")]
+    pub name: String,
+    pub status: HealthStatus,
+    /// Time the check took in milliseconds, not set for dependencies that are checked without
+    /// a request
+    pub latency_ms: Option<f64>,
+    #[schema(example = "surrealdb-2.0.4")]
+    pub version: Option<String>,
+    /// Why the dependency is not up, or what was found
+    pub detail: Option<String>,
+    /// Only reported for the database
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub migrations: Option<MigrationStatus>,
+}
+
+#[derive(Serialize, Deserialize, ToSchema, ToResponse, Debug, Clone)]
+pub struct HealthReport {
+    /// `down` if any dependency is down, `degraded` if any is degraded
+    pub status: HealthStatus,
+    /// The database, the audit log database, the LLM backends, the file store and the OAuth
+    /// providers
+    pub dependencies: Vec<DependencyHealth>,
+    /// Circuit breaker state of every LLM backend, open breakers only degrade the server
+    pub llm: Vec<LlmBackendHealth>,
+    /// When the dependencies were checked, reports are reused for a few seconds
+    #[schema(value_type = String, format = DateTime)]
+    pub checked_at: DateTime<Utc>,
+}
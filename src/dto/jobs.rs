@@ -0,0 +1,30 @@
+use crate::models::job::{JobName, JobRun};
+use serde::{Deserialize, Serialize};
+use utoipa::{IntoParams, ToResponse, ToSchema};
+
+/// A background job and its last run
+#[derive(Serialize, Deserialize, ToSchema, ToResponse, Debug, Clone)]
+pub struct JobInfo {
+    pub name: JobName,
+    /// A cron expression, `every <n>s` or `manual`
+    #[schema(example = "0 */15 * * * *")]
+    pub schedule: String,
+    /// Paused jobs are only run by admins
+    pub paused: bool,
+    pub running: bool,
+    pub last_run: Option<JobRun>,
+}
+
+#[allow(dead_code)]
+#[derive(ToResponse)]
+pub struct JobInfos(pub Vec<JobInfo>);
+
+/// Filters job runs, the newest runs come first
+#[derive(Serialize, Deserialize, IntoParams, Debug, Clone, Default)]
+#[into_params(parameter_in = Query)]
+pub struct JobRunQuery {
+    #[param(inline)]
+    pub job: Option<JobName>,
+    /// At most 500, defaults to 50
+    pub limit: Option<u64>,
+}
@@ -0,0 +1,37 @@
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+/// A configured LLM backend and the models it may serve.
+#[derive(Serialize, Deserialize, ToSchema, Debug, Clone)]
+pub struct LlmBackendInfo {
+    #[schema(example = "openai")]
+    pub name: String,
+    /// Allowed models, empty if any model may be requested
+    #[schema(example = json!(["llama3.1:8b"]))]
+    pub models: Vec<String>,
+    /// Whether this backend is used when none is selected
+    pub default: bool,
+}
+
+#[derive(Serialize, Deserialize, ToSchema, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum BreakerState {
+    /// Requests are sent normally
+    Closed,
+    /// The backend failed repeatedly and requests are rejected with 503
+    Open,
+    /// The cooldown has passed and the next request is a trial
+    HalfOpen,
+}
+
+/// Health of a single LLM backend as seen by its circuit breaker.
+#[derive(Serialize, Deserialize, ToSchema, Debug, Clone)]
+pub struct LlmBackendHealth {
+    #[schema(example = "threatmapper")]
+    pub name: String,
+    pub state: BreakerState,
+    pub consecutive_failures: u32,
+    pub last_error: Option<String>,
+    /// Seconds until the breaker lets a trial request through, only set while open
+    pub retry_after_secs: Option<u64>,
+}
@@ -4,10 +4,23 @@
 //! the server and the client. A DTO is not meant to be used as an internal model and therefore is separate from the models module
 
 pub(crate) mod access_token_request;
+pub(crate) mod audit;
+pub(crate) mod chat_chunk;
+pub(crate) mod config;
 pub(crate) mod embeddings;
+pub(crate) mod file_share;
 pub(crate) mod file_upload_form;
+pub(crate) mod health;
+pub(crate) mod jobs;
+pub(crate) mod llm_backend;
 pub(crate) mod oauth_callback;
+pub(crate) mod problem;
+pub(crate) mod team;
+pub(crate) mod threat_analysis;
+pub(crate) mod threat_model;
 pub(crate) mod token;
+pub(crate) mod upload_session;
+pub(crate) mod usage;
 pub(crate) mod user_info;
 pub(crate) mod user_registration_request;
 pub(crate) mod user_update_request;
@@ -0,0 +1,104 @@
+use crate::middlewares::request_id::RequestId;
+use actix_web::http::header::RETRY_AFTER;
+use actix_web::http::StatusCode;
+use actix_web::HttpResponse;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use utoipa::{ToResponse, ToSchema};
+
+/// Media type of [`ProblemDetails`] bodies
+pub const PROBLEM_JSON: &str = "application/problem+json";
+
+/// A request field that failed validation
+#[derive(Serialize, Deserialize, ToSchema, Debug, Clone, PartialEq, Eq)]
+pub struct FieldError {
+    /// Name of the field, e.g. `name` or `threats`
+    pub field: String,
+    pub message: String,
+}
+
+impl FieldError {
+    pub fn new(field: impl Into<String>, message: impl Into<String>) -> Self {
+        Self {
+            field: field.into(),
+            message: message.into(),
+        }
+    }
+}
+
+/// Body of every error response, an RFC 7807 problem detail
+#[derive(Serialize, Deserialize, ToSchema, ToResponse, Debug, Clone)]
+#[response(
+    description = "The request failed",
+    content_type = "application/problem+json"
+)]
+pub struct ProblemDetails {
+    /// Always `about:blank`, the problem is identified by `code`
+    #[serde(rename = "type")]
+    pub problem_type: String,
+    /// Reason phrase of the status, e.g. `Not Found`
+    pub title: String,
+    pub status: u16,
+    /// Human readable explanation, not meant to be parsed
+    pub detail: String,
+    /// Stable machine readable name of the error, e.g. `validation_failed`
+    pub code: String,
+    /// ID of the request, also sent in the `x-request-id` header
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub request_id: Option<String>,
+    /// Fields that failed validation
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub errors: Vec<FieldError>,
+    /// Additional details that depend on `code`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[schema(value_type = Option<Object>)]
+    pub details: Option<Value>,
+    /// Seconds to wait before sending the request again, also sent in `Retry-After`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub retry_after: Option<u64>,
+}
+
+impl ProblemDetails {
+    pub fn new(status: StatusCode, code: &str, detail: impl Into<String>) -> Self {
+        Self {
+            problem_type: "about:blank".to_string(),
+            title: status.canonical_reason().unwrap_or("Error").to_string(),
+            status: status.as_u16(),
+            detail: detail.into(),
+            code: code.to_string(),
+            request_id: RequestId::current().map(|id| id.0),
+            errors: Vec::new(),
+            details: None,
+            retry_after: None,
+        }
+    }
+
+    pub fn with_errors(mut self, errors: Vec<FieldError>) -> Self {
+        self.errors = errors;
+        self
+    }
+
+    pub fn with_details(mut self, details: Value) -> Self {
+        self.details = Some(details);
+        self
+    }
+
+    pub fn with_retry_after(mut self, seconds: u64) -> Self {
+        self.retry_after = Some(seconds);
+        self
+    }
+
+    /// Serializes the problem with the `application/problem+json` content type
+    pub fn response(&self) -> HttpResponse {
+        let status = StatusCode::from_u16(self.status).unwrap_or(StatusCode::INTERNAL_SERVER_ERROR);
+        let mut response = HttpResponse::build(status);
+
+        if let Some(seconds) = self.retry_after {
+            response.insert_header((RETRY_AFTER, seconds.to_string()));
+        }
+
+        response
+            .content_type(PROBLEM_JSON)
+            .body(serde_json::to_string(self).unwrap_or_default())
+    }
+}
@@ -0,0 +1,45 @@
+use crate::models::team::{Team, TeamInvitation, TeamMember, TeamRole};
+use serde::{Deserialize, Serialize};
+use utoipa::{IntoParams, ToResponse, ToSchema};
+
+#[derive(Serialize, Deserialize, ToSchema, Debug, Clone)]
+pub struct TeamRequest {
+    #[schema(example = "Platform security")]
+    pub name: String,
+}
+
+#[derive(Serialize, Deserialize, ToSchema, Debug, Clone)]
+pub struct InvitationRequest {
+    #[schema(example = "janedoe@example.com")]
+    pub email: String,
+    #[serde(default = "default_invitation_role")]
+    pub role: TeamRole,
+}
+
+fn default_invitation_role() -> TeamRole {
+    TeamRole::Viewer
+}
+
+#[derive(Serialize, Deserialize, ToSchema, Debug, Clone)]
+pub struct MemberRoleRequest {
+    pub role: TeamRole,
+}
+
+/// Selects the team that owns a resource, leave it out for resources owned by the user alone
+#[derive(Serialize, Deserialize, ToSchema, IntoParams, Debug, Clone, Default)]
+#[into_params(parameter_in = Query)]
+pub struct TeamQuery {
+    /// ID of the team, without the `team:` prefix
+    pub team: Option<String>,
+}
+
+/// A team with its members, pending invitations are only included for team owners
+#[derive(Serialize, Deserialize, ToSchema, ToResponse, Debug, Clone)]
+pub struct TeamDetails {
+    #[serde(flatten)]
+    pub team: Team,
+    pub role: TeamRole,
+    pub members: Vec<TeamMember>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub invitations: Option<Vec<TeamInvitation>>,
+}
@@ -0,0 +1,52 @@
+use crate::dto::chat_chunk::TokenUsage;
+use crate::models::MITREEntry;
+use serde::{Deserialize, Serialize};
+use utoipa::{ToResponse, ToSchema};
+
+/// A threat or mitigation proposed by the LLM, before it was checked against the database.
+#[derive(Serialize, Deserialize, ToSchema, Debug, Clone, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct ProposedEntry {
+    /// MITRE ATT&CK ID, e.g. `T1190` or `M1050`
+    #[schema(example = "T1190")]
+    pub mitre_id: String,
+    /// Why the LLM considers this entry relevant to the system
+    #[schema(example = "The login form is exposed to the internet.")]
+    pub rationale: String,
+}
+
+/// The JSON document the LLM is asked to produce for a structured request.
+#[derive(Serialize, Deserialize, ToSchema, Debug, Clone, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct ThreatModelOutput {
+    pub threats: Vec<ProposedEntry>,
+    pub mitigations: Vec<ProposedEntry>,
+}
+
+/// A proposed entry after its `mitre_id` was looked up.
+#[derive(Serialize, Deserialize, ToSchema, Debug, Clone)]
+pub struct AnalysisEntry {
+    #[schema(example = "T1190")]
+    pub mitre_id: String,
+    #[schema(example = "The login form is exposed to the internet.")]
+    pub rationale: String,
+    /// The matching entry from the `threat` or `mitigation` table
+    pub entry: Option<MITREEntry>,
+    /// Set when no entry with this ID exists, i.e. the LLM made it up
+    pub hallucinated: bool,
+}
+
+/// Validated result of a structured chat request.
+#[derive(Serialize, Deserialize, ToSchema, ToResponse, Debug, Clone)]
+pub struct ThreatAnalysis {
+    #[schema(example = "openai")]
+    pub backend: String,
+    #[schema(example = "llama3.1:8b")]
+    pub model: Option<String>,
+    pub threats: Vec<AnalysisEntry>,
+    pub mitigations: Vec<AnalysisEntry>,
+    /// Every ID that could not be resolved, in the order they were proposed
+    #[schema(example = json!(["T9999"]))]
+    pub hallucinated_ids: Vec<String>,
+    pub usage: Option<TokenUsage>,
+}
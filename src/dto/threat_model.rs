@@ -0,0 +1,50 @@
+use crate::models::threat_model::{MitigationItem, ThreatItem, ThreatModel};
+use crate::models::MITREEntry;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use utoipa::{ToResponse, ToSchema};
+
+/// Content of a threat model, used both to create and to replace one.
+#[derive(Serialize, Deserialize, ToSchema, Debug, Clone)]
+pub struct ThreatModelRequest {
+    #[schema(example = "Customer portal")]
+    pub title: String,
+    #[serde(default)]
+    #[schema(example = "Public web application backed by a PostgreSQL database.")]
+    pub description: String,
+    /// IDs of uploaded files to attach, see `GET /api/v1/files`
+    #[serde(default)]
+    pub files: Vec<String>,
+    #[serde(default)]
+    pub threats: Vec<ThreatItem>,
+    #[serde(default)]
+    pub mitigations: Vec<MitigationItem>,
+    /// ID of the team that owns the model, only used when the model is created
+    #[serde(default)]
+    pub team: Option<String>,
+}
+
+/// How well the threats of a model are covered by mitigations.
+#[derive(Serialize, Deserialize, ToSchema, Debug, Clone, Default, PartialEq)]
+pub struct CoverageSummary {
+    /// Threats that are not marked as not applicable
+    pub applicable_threats: usize,
+    /// Applicable threats that are mitigated or addressed by an applicable mitigation
+    pub covered_threats: usize,
+    /// Share of applicable threats that are covered, `1.0` when there are none
+    #[schema(example = 0.75)]
+    pub coverage: f64,
+    /// MITRE IDs of applicable threats without any mitigation
+    #[schema(example = json!(["T1110"]))]
+    pub uncovered: Vec<String>,
+}
+
+/// A threat model together with the MITRE entries it references and its coverage.
+#[derive(Serialize, Deserialize, ToSchema, ToResponse, Debug, Clone)]
+pub struct ThreatModelDetails {
+    #[serde(flatten)]
+    pub model: ThreatModel,
+    /// The `threat` and `mitigation` entries referenced by the model, by MITRE ID
+    pub entries: BTreeMap<String, MITREEntry>,
+    pub coverage: CoverageSummary,
+}
@@ -0,0 +1,18 @@
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+#[derive(Serialize, Deserialize, ToSchema, Debug, Clone)]
+pub struct UploadSessionRequest {
+    #[schema(example = "capture.pcap")]
+    pub filename: String,
+    /// Size of the whole file in bytes
+    #[schema(example = 73400320)]
+    pub size: u64,
+    /// Hex encoded SHA-256 of the whole file
+    #[schema(example = "9f86d081884c7d659a2feaa0c55ad015a3bf4f1b2b0b822cd15d6c15b0f00a08")]
+    pub sha256: String,
+    /// Content type of the file as known to the client
+    #[serde(default)]
+    #[schema(example = "application/vnd.tcpdump.pcap")]
+    pub content_type: Option<String>,
+}
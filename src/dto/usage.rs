@@ -0,0 +1,69 @@
+use crate::models::thing::Thing;
+use chrono::NaiveDate;
+use serde::{Deserialize, Serialize};
+use utoipa::{IntoParams, ToResponse, ToSchema};
+
+/// Date range for usage queries, both ends inclusive. Defaults to the last 30 days.
+#[derive(Serialize, Deserialize, IntoParams, Debug, Clone, Default)]
+#[into_params(parameter_in = Query)]
+pub struct UsageQuery {
+    #[param(example = "2024-11-01", value_type = Option<String>, format = Date)]
+    pub from: Option<NaiveDate>,
+    #[param(example = "2024-11-30", value_type = Option<String>, format = Date)]
+    pub to: Option<NaiveDate>,
+}
+
+#[derive(Serialize, Deserialize, ToSchema, Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct UsageTotals {
+    #[serde(default)]
+    pub prompt_tokens: u64,
+    #[serde(default)]
+    pub completion_tokens: u64,
+    #[serde(default)]
+    pub requests: u64,
+}
+
+impl UsageTotals {
+    pub fn tokens(&self) -> u64 {
+        self.prompt_tokens + self.completion_tokens
+    }
+}
+
+#[derive(Serialize, Deserialize, ToSchema, Debug, Clone)]
+pub struct DailyUsage {
+    #[schema(example = "2024-11-15")]
+    pub day: String,
+    #[serde(flatten)]
+    pub totals: UsageTotals,
+}
+
+/// A quota and how much of it has been used
+#[derive(Serialize, Deserialize, ToSchema, Debug, Clone, Copy)]
+pub struct QuotaStatus {
+    /// Max tokens for the period, `None` if unlimited
+    pub limit: Option<u64>,
+    pub used: u64,
+}
+
+/// LLM usage of the current user.
+#[derive(Serialize, Deserialize, ToSchema, ToResponse, Debug, Clone)]
+pub struct UserUsageResponse {
+    /// Usage per day in the requested range, most recent first
+    pub days: Vec<DailyUsage>,
+    pub daily_quota: QuotaStatus,
+    pub monthly_quota: QuotaStatus,
+}
+
+/// LLM usage of a single user in the requested range.
+#[derive(Serialize, Deserialize, ToSchema, Debug, Clone)]
+pub struct UserUsageSummary {
+    pub user: Thing,
+    #[schema(example = "johndoe@example.com")]
+    pub email: Option<String>,
+    #[serde(flatten)]
+    pub totals: UsageTotals,
+}
+
+#[allow(dead_code)]
+#[derive(ToResponse)]
+pub struct UserUsageSummaries(pub Vec<UserUsageSummary>);
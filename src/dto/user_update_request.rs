@@ -7,4 +7,10 @@ pub struct UserUpdateRequest {
     pub first_name: String,
     pub last_name: String,
     pub password: Option<String>,
+    /// Default LLM backend for chat requests that do not select one
+    #[serde(default)]
+    pub llm_backend: Option<String>,
+    /// Default model for chat requests that do not select one
+    #[serde(default)]
+    pub llm_model: Option<String>,
 }
@@ -0,0 +1,115 @@
+use crate::dto::audit::{AuditExportFormat, AuditExportQuery, AuditQuery, LoggerStatus};
+use crate::error::ServerResponseError;
+use crate::extractors::AdminSession;
+use crate::models::audit::AuditLogPage;
+use crate::server::background_logger::LOGGER_STATS;
+use crate::services::audit::export::{render_csv, render_jsonl};
+use crate::services::audit::query::{export_audit_log, query_audit_log};
+use crate::state::AppState;
+use actix_web::http::header::{ContentDisposition, DispositionParam, DispositionType};
+use actix_web::{web, HttpResponse};
+use chrono::Utc;
+use helper_macros::generate_endpoint;
+
+generate_endpoint! {
+    fn get_audit_log;
+    method: get;
+    path: "/audit";
+    docs: {
+        params: (AuditQuery),
+        tag: "admin",
+        responses: {
+            (status = 200, response = AuditLogPage),
+            (status = 401, description = "Not logged in"),
+            (status = 403, description = "Not an admin"),
+            (status = 500, description = "An error occurred when reading the audit trail"),
+        },
+        security: [
+            ("bearer_token" = []),
+            ("cookie_session" = []),
+        ]
+    }
+    params: {
+        query: web::Query<AuditQuery>,
+        _admin: AdminSession,
+        state: web::Data<AppState>,
+    };
+    {
+        let page = query_audit_log(&state.log_db, &query).await?;
+        Ok(HttpResponse::Ok().json(page))
+    }
+}
+
+generate_endpoint! {
+    fn export_audit_log_endpoint;
+    method: get;
+    path: "/audit/export";
+    docs: {
+        params: (AuditQuery, AuditExportQuery),
+        tag: "admin",
+        responses: {
+            (status = 200, description = "The matching entries as JSON Lines or CSV, the newest first"),
+            (status = 400, description = "Too many entries match, narrow the time range"),
+            (status = 401, description = "Not logged in"),
+            (status = 403, description = "Not an admin"),
+            (status = 500, description = "An error occurred when reading the audit trail"),
+        },
+        security: [
+            ("bearer_token" = []),
+            ("cookie_session" = []),
+        ]
+    }
+    params: {
+        query: web::Query<AuditQuery>,
+        export: web::Query<AuditExportQuery>,
+        _admin: AdminSession,
+        state: web::Data<AppState>,
+    };
+    {
+        let entries = export_audit_log(&state.log_db, &query).await?;
+
+        let (content_type, extension, body) = match export.format {
+            AuditExportFormat::Jsonl => (
+                "application/jsonl",
+                "jsonl",
+                render_jsonl(&entries).map_err(|err| ServerResponseError::InternalError(err.to_string()))?,
+            ),
+            AuditExportFormat::Csv => ("text/csv; charset=utf-8", "csv", render_csv(&entries)),
+        };
+
+        let filename = format!("audit-{}.{extension}", Utc::now().format("%Y%m%dT%H%M%SZ"));
+
+        Ok(HttpResponse::Ok()
+            .content_type(content_type)
+            .insert_header(ContentDisposition {
+                disposition: DispositionType::Attachment,
+                parameters: vec![DispositionParam::Filename(filename)],
+            })
+            .body(body))
+    }
+}
+
+generate_endpoint! {
+    fn get_logger_status;
+    method: get;
+    path: "/audit/logger";
+    docs: {
+        params: (),
+        tag: "admin",
+        responses: {
+            (status = 200, response = LoggerStatus),
+            (status = 401, description = "Not logged in"),
+            (status = 403, description = "Not an admin"),
+        },
+        security: [
+            ("bearer_token" = []),
+            ("cookie_session" = []),
+        ]
+    }
+    params: {
+        _admin: AdminSession,
+    };
+    {
+        Ok(HttpResponse::Ok().json(LOGGER_STATS.status()))
+    }
+}
@@ -0,0 +1,31 @@
+use crate::dto::config::ConfigView;
+use crate::extractors::AdminSession;
+use crate::state::AppState;
+use actix_web::{web, HttpResponse};
+use helper_macros::generate_endpoint;
+
+generate_endpoint! {
+    fn get_config;
+    method: get;
+    path: "/config";
+    docs: {
+        params: (),
+        tag: "admin",
+        responses: {
+            (status = 200, response = ConfigView),
+            (status = 401, description = "Not logged in"),
+            (status = 403, description = "Not an admin"),
+        },
+        security: [
+            ("bearer_token" = []),
+            ("cookie_session" = []),
+        ]
+    }
+    params: {
+        _admin: AdminSession,
+        state: web::Data<AppState>,
+    };
+    {
+        Ok(HttpResponse::Ok().json(state.config.view()))
+    }
+}
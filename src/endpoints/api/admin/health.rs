@@ -0,0 +1,32 @@
+use crate::dto::health::HealthReport;
+use crate::extractors::AdminSession;
+use crate::services::health::readiness;
+use crate::state::AppState;
+use actix_web::{web, HttpResponse};
+use helper_macros::generate_endpoint;
+
+generate_endpoint! {
+    fn get_health_report;
+    method: get;
+    path: "/health";
+    docs: {
+        params: (),
+        tag: "admin",
+        responses: {
+            (status = 200, response = HealthReport),
+            (status = 401, description = "Not logged in"),
+            (status = 403, description = "Not an admin"),
+        },
+        security: [
+            ("bearer_token" = []),
+            ("cookie_session" = []),
+        ]
+    }
+    params: {
+        _admin: AdminSession,
+        state: web::Data<AppState>,
+    };
+    {
+        Ok(HttpResponse::Ok().json(readiness(&state).await))
+    }
+}
@@ -0,0 +1,167 @@
+use crate::dto::jobs::{JobInfo, JobInfos, JobRunQuery};
+use crate::extractors::AdminSession;
+use crate::models::job::{JobName, JobRun, JobRuns, JobTrigger};
+use crate::services::jobs::runs::list_runs;
+use crate::services::jobs::JOBS;
+use crate::state::AppState;
+use actix_web::{web, HttpResponse};
+use helper_macros::generate_endpoint;
+
+/// Runs returned by `/jobs/runs` without a limit
+const DEFAULT_RUN_LIMIT: u64 = 50;
+
+generate_endpoint! {
+    fn list_jobs;
+    method: get;
+    path: "/jobs";
+    docs: {
+        params: (),
+        tag: "admin",
+        responses: {
+            (status = 200, response = JobInfos),
+            (status = 401, description = "Not logged in"),
+            (status = 403, description = "Not an admin"),
+            (status = 500, description = "An error occurred when reading the job runs"),
+        },
+        security: [
+            ("bearer_token" = []),
+            ("cookie_session" = []),
+        ]
+    }
+    params: {
+        _admin: AdminSession,
+        state: web::Data<AppState>,
+    };
+    {
+        let mut jobs = Vec::with_capacity(JobName::ALL.len());
+        for job in JobName::ALL {
+            jobs.push(JOBS.info(job, &state).await?);
+        }
+
+        Ok(HttpResponse::Ok().json(jobs))
+    }
+}
+
+generate_endpoint! {
+    fn list_job_runs;
+    method: get;
+    path: "/jobs/runs";
+    docs: {
+        params: (JobRunQuery),
+        tag: "admin",
+        responses: {
+            (status = 200, response = JobRuns),
+            (status = 401, description = "Not logged in"),
+            (status = 403, description = "Not an admin"),
+            (status = 500, description = "An error occurred when reading the job runs"),
+        },
+        security: [
+            ("bearer_token" = []),
+            ("cookie_session" = []),
+        ]
+    }
+    params: {
+        query: web::Query<JobRunQuery>,
+        _admin: AdminSession,
+        state: web::Data<AppState>,
+    };
+    {
+        let runs = list_runs(&state.db, query.job, query.limit.unwrap_or(DEFAULT_RUN_LIMIT)).await?;
+        Ok(HttpResponse::Ok().json(runs))
+    }
+}
+
+generate_endpoint! {
+    fn trigger_job;
+    method: post;
+    path: "/jobs/{job}/run";
+    docs: {
+        params: (),
+        tag: "admin",
+        responses: {
+            (status = 202, response = JobRun),
+            (status = 401, description = "Not logged in"),
+            (status = 403, description = "Not an admin"),
+            (status = 404, description = "Job not found"),
+            (status = 409, description = "The job is already running or the server is shutting down"),
+            (status = 500, description = "An error occurred when recording the run"),
+        },
+        security: [
+            ("bearer_token" = []),
+            ("cookie_session" = []),
+        ]
+    }
+    params: {
+        job: web::Path<JobName>,
+        admin: AdminSession,
+        state: web::Data<AppState>,
+    };
+    {
+        let run = JOBS
+            .start(job.into_inner(), JobTrigger::Manual, Some(admin.0.user_id), state)
+            .await?;
+        Ok(HttpResponse::Accepted().json(run))
+    }
+}
+
+generate_endpoint! {
+    fn pause_job;
+    method: post;
+    path: "/jobs/{job}/pause";
+    docs: {
+        params: (),
+        tag: "admin",
+        responses: {
+            (status = 200, response = JobInfo),
+            (status = 401, description = "Not logged in"),
+            (status = 403, description = "Not an admin"),
+            (status = 404, description = "Job not found"),
+            (status = 500, description = "An error occurred when pausing the job"),
+        },
+        security: [
+            ("bearer_token" = []),
+            ("cookie_session" = []),
+        ]
+    }
+    params: {
+        job: web::Path<JobName>,
+        _admin: AdminSession,
+        state: web::Data<AppState>,
+    };
+    {
+        let job = job.into_inner();
+        JOBS.set_paused(job, true, &state).await?;
+        Ok(HttpResponse::Ok().json(JOBS.info(job, &state).await?))
+    }
+}
+
+generate_endpoint! {
+    fn resume_job;
+    method: post;
+    path: "/jobs/{job}/resume";
+    docs: {
+        params: (),
+        tag: "admin",
+        responses: {
+            (status = 200, response = JobInfo),
+            (status = 401, description = "Not logged in"),
+            (status = 403, description = "Not an admin"),
+            (status = 404, description = "Job not found"),
+            (status = 500, description = "An error occurred when resuming the job"),
+        },
+        security: [
+            ("bearer_token" = []),
+            ("cookie_session" = []),
+        ]
+    }
+    params: {
+        job: web::Path<JobName>,
+        _admin: AdminSession,
+        state: web::Data<AppState>,
+    };
+    {
+        let job = job.into_inner();
+        JOBS.set_paused(job, false, &state).await?;
+        Ok(HttpResponse::Ok().json(JOBS.info(job, &state).await?))
+    }
+}
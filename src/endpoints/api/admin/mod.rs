@@ -0,0 +1,84 @@
+pub mod audit;
+pub mod config;
+pub mod health;
+pub mod jobs;
+pub mod usage;
+
+use crate::dto::audit::{AuditExportFormat, LoggerStatus};
+use crate::dto::config::{ConfigLayer, ConfigValue, ConfigView};
+use crate::dto::jobs::{JobInfo, JobInfos};
+use crate::dto::usage::{UsageTotals, UserUsageSummaries, UserUsageSummary};
+use crate::models::audit::{AuditLogEntry, AuditLogPage, Outcome};
+use crate::models::job::{JobName, JobRun, JobRunStatus, JobRuns, JobTrigger};
+use actix_web::guard::Acceptable;
+use actix_web::web;
+use audit::*;
+use config::*;
+use health::*;
+use jobs::*;
+use usage::*;
+use utoipa::OpenApi;
+
+/// Endpoints only available to users with the `Admin` or `Owner` role
+pub fn admin_service() -> impl actix_web::dev::HttpServiceFactory {
+    web::scope("/admin")
+        .guard(Acceptable::new(mime::APPLICATION_JSON).match_star_star())
+        .service(get_usage_summary_endpoint)
+        .service(get_audit_log)
+        .service(export_audit_log_endpoint)
+        .service(get_logger_status)
+        .service(get_config)
+        .service(get_health_report)
+        .service(list_jobs)
+        .service(list_job_runs)
+        .service(trigger_job)
+        .service(pause_job)
+        .service(resume_job)
+}
+
+#[derive(OpenApi)]
+#[openapi(
+    paths(
+        get_usage_summary_endpoint,
+        get_audit_log,
+        export_audit_log_endpoint,
+        get_logger_status,
+        get_config,
+        get_health_report,
+        list_jobs,
+        list_job_runs,
+        trigger_job,
+        pause_job,
+        resume_job
+    ),
+    components(
+        schemas(
+            UserUsageSummary,
+            UsageTotals,
+            AuditLogEntry,
+            AuditLogPage,
+            Outcome,
+            AuditExportFormat,
+            LoggerStatus,
+            ConfigLayer,
+            ConfigValue,
+            ConfigView,
+            JobName,
+            JobTrigger,
+            JobRunStatus,
+            JobRun,
+            JobInfo
+        ),
+        responses(
+            UserUsageSummaries,
+            AuditLogPage,
+            LoggerStatus,
+            ConfigView,
+            JobInfos,
+            JobRun,
+            JobRuns,
+            JobInfo
+        )
+    )
+)]
+pub(crate) struct AdminApi;
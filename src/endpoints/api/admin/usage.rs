@@ -0,0 +1,36 @@
+use crate::dto::usage::{UsageQuery, UserUsageSummaries};
+use crate::extractors::AdminSession;
+use crate::services::llm::usage::get_usage_summary;
+use crate::state::AppState;
+use actix_web::{web, HttpResponse};
+use helper_macros::generate_endpoint;
+
+generate_endpoint! {
+    fn get_usage_summary_endpoint;
+    method: get;
+    path: "/usage";
+    docs: {
+        params: (UsageQuery),
+        tag: "admin",
+        responses: {
+            (status = 200, response = UserUsageSummaries),
+            (status = 401, description = "Not logged in"),
+            (status = 403, description = "Not an admin"),
+            (status = 500, description = "An error occurred when reading usage from the database"),
+        },
+        security: [
+            ("bearer_token" = []),
+            ("cookie_session" = []),
+        ]
+    }
+    params: {
+        query: web::Query<UsageQuery>,
+        _admin: AdminSession,
+        state: web::Data<AppState>,
+    };
+    {
+        let query = query.into_inner();
+        let usage = get_usage_summary(&state.db, query.from, query.to).await?;
+        Ok(HttpResponse::Ok().json(usage))
+    }
+}
@@ -1,75 +1,182 @@
-use std::time::Duration;
-use actix_web::{web, HttpMessage, HttpRequest, HttpResponse, Responder};
-use actix_web::web::Bytes;
-use awc::Client;
-use awc::error::SendRequestError;
-use awc::http::Method;
-use tracing::debug;
-use helper_macros::generate_endpoint;
-use crate::dto::chat_request::ChatRequest;
+use crate::dto::chat_request::{ChatRequest, LlmSelection};
+use crate::dto::threat_analysis::ThreatAnalysis;
 use crate::error::ServerResponseError;
 use crate::extractors::Authenticated;
+use crate::models::UserSession;
+use crate::services::llm::preference::get_llm_preference;
+use crate::services::llm::structured::{collect, parse_output, resolve_output};
+use crate::services::llm::usage::{check_quota, track_usage};
+use crate::services::llm::LlmResponse;
+use crate::state::AppState;
+use actix_web::http::header::{CacheControl, CacheDirective};
+use actix_web::web::Bytes;
+use actix_web::{web, HttpResponse};
+use futures::{stream, StreamExt};
+use helper_macros::generate_endpoint;
+use std::convert::Infallible;
+use tracing::{debug, error};
 
-async fn proxy(path: &str, req: HttpRequest, bytes: Bytes) -> impl Responder {
-    let client = Client::default();
-    let url = format!("{}{}", req.url_for_static("llm").expect("LLM URL not set"), path);
+/// Encodes a chat stream as server-sent events. Errors after the response has started are sent
+/// as an `error` event since the status code can no longer be changed. The event only names the
+/// kind of error, the details can contain the upstream response and are logged instead.
+fn event_stream(response: LlmResponse) -> HttpResponse {
+    let events = response
+        .chunks
+        .map(|chunk| {
+            let event = match chunk {
+                Ok(chunk) => format!(
+                    "data: {}\n\n",
+                    serde_json::to_string(&chunk).unwrap_or_default()
+                ),
+                Err(err) => {
+                    error!("LLM stream failed: {}", err);
+                    format!(
+                        "event: error\ndata: {}\n\n",
+                        serde_json::json!({
+                            "error": err.kind(),
+                            "message": "the response could not be completed",
+                        })
+                    )
+                }
+            };
 
-    let req = client.request_from(url, req.head()).timeout(Duration::from_mins(20));
+            Ok::<_, Infallible>(Bytes::from(event))
+        })
+        .chain(stream::once(async {
+            Ok(Bytes::from_static(b"data: [DONE]\n\n"))
+        }));
 
-    let resp;
+    let mut builder = HttpResponse::Ok();
 
-    if !bytes.is_empty() {
-        resp = req.send_body(bytes);
-    } else {
-        resp = req.send();
+    for header in response.headers {
+        builder.append_header(header);
     }
 
-    match resp.await {
-        Ok(res) => {
-            let content_type = res.content_type().to_string();
-            debug!("Got response, content type: {}", content_type);
+    builder
+        .content_type(mime::TEXT_EVENT_STREAM)
+        .insert_header(CacheControl(vec![CacheDirective::NoCache]))
+        .streaming(events)
+}
 
-            let mut client_res = HttpResponse::build(res.status());
+generate_endpoint! {
+    fn chat;
+    method: post;
+    path: "/chat/completions";
+    docs: {
+        params: (LlmSelection),
+        tag: "llm",
+        responses: {
+            (status = 200, description = "Server-sent events, one `ChatChunk` per event followed by `[DONE]`"),
+            (status = 400, description = "Unknown backend or model not allowed"),
+            (status = 401, description = "Unauthorized"),
+            (status = 429, description = "The token quota is exhausted, see the `Retry-After` header"),
+            (status = 502, description = "The LLM backend failed"),
+            (status = 503, description = "The LLM backend is unavailable, see the `Retry-After` header"),
+            (status = 504, description = "The LLM backend timed out"),
+        },
+        security: [
+            ("bearer_token" = []),
+            ("cookie_session" = []),
+        ]
+    }
+    params: {
+        body: web::Json<ChatRequest>,
+        selection: web::Query<LlmSelection>,
+        session: UserSession,
+        state: web::Data<AppState>,
+    };
+    {
+        check_quota(&state.db, state.llm.quota(), &session.user_id).await?;
 
-            let response;
+        let preference = get_llm_preference(&state.db, &state.llm, session.user_id.clone()).await?;
+        let (backend, model) = state.llm.select(&selection.into_inner().or(preference))?;
 
-            response = client_res.content_type(content_type);
+        debug!("Received chat request for backend {} and model {:?}", backend.name(), model);
 
-            for (key, value) in res.headers().iter() {
-                response.append_header((key.clone(), value.clone()));
-            }
+        let request = body.into_inner();
+        let prompt = request.prompt().to_string();
+        let response = state.llm.chat(backend.name(), request, model).await?;
 
-            response.streaming(res)
-        },
-        Err(err) => match err {
-            SendRequestError::Http(http_err) => {
-                HttpResponse::BadGateway().body(format!("HTTP error: {}", http_err))
-            }
-            _ => HttpResponse::InternalServerError().body(format!("Request failed: {}", err)),
-        },
+        Ok(event_stream(track_usage(response, state.db.clone(), session.user_id, prompt)))
     }
 }
 
 generate_endpoint! {
-    fn chat;
+    fn structured_chat;
     method: post;
-    path: "/chat/completions";
+    path: "/chat/structured";
     docs: {
+        params: (LlmSelection),
         tag: "llm",
-        context_path: "/",
         responses: {
-            (status = 200, description = "Everything works just fine!")
-        }
+            (status = 200, response = ThreatAnalysis),
+            (status = 400, description = "Not a structured request, unknown backend or model not allowed"),
+            (status = 401, description = "Unauthorized"),
+            (status = 429, description = "The token quota is exhausted, see the `Retry-After` header"),
+            (status = 502, description = "The LLM backend failed or its output does not match the schema"),
+            (status = 503, description = "The LLM backend is unavailable, see the `Retry-After` header"),
+            (status = 504, description = "The LLM backend timed out"),
+        },
+        security: [
+            ("bearer_token" = []),
+            ("cookie_session" = []),
+        ]
     }
     params: {
-        req: HttpRequest,
         body: web::Json<ChatRequest>,
-        _auth: Authenticated,
+        selection: web::Query<LlmSelection>,
+        session: UserSession,
+        state: web::Data<AppState>,
     };
     {
-        let body = serde_json::to_vec(&body.into_inner()).expect("Failed to serialize ChatRequest");
+        let request = body.into_inner();
+
+        if !matches!(request, ChatRequest::Structured { .. }) {
+            return Err(ServerResponseError::BadRequest("expected a structured request".to_string()));
+        }
+
+        check_quota(&state.db, state.llm.quota(), &session.user_id).await?;
+
+        let preference = get_llm_preference(&state.db, &state.llm, session.user_id.clone()).await?;
+        let (backend, model) = state.llm.select(&selection.into_inner().or(preference))?;
+
+        debug!("Received structured request for backend {} and model {:?}", backend.name(), model);
+
+        let prompt = request.prompt().to_string();
+        let response = state.llm.chat(backend.name(), request, model).await?;
+        let completion = collect(track_usage(response, state.db.clone(), session.user_id, prompt)).await?;
+
+        let output = parse_output(&completion.text)?;
+        let analysis = resolve_output(&state.db, completion, output).await?;
+
+        if !analysis.hallucinated_ids.is_empty() {
+            debug!("LLM proposed unknown MITRE IDs: {:?}", analysis.hallucinated_ids);
+        }
+
+        Ok(HttpResponse::Ok().json(analysis))
+    }
+}
 
-        debug!("Received chat request");
-        Ok(proxy("chat/completions", req, Bytes::from(body)).await)
+generate_endpoint! {
+    fn list_llm_backends;
+    method: get;
+    path: "/chat/backends";
+    docs: {
+        tag: "llm",
+        responses: {
+            (status = 200, description = "All configured LLM backends"),
+            (status = 401, description = "Unauthorized"),
+        },
+        security: [
+            ("bearer_token" = []),
+            ("cookie_session" = []),
+        ]
     }
-}
\ No newline at end of file
+    params: {
+        _auth: Authenticated,
+        state: web::Data<AppState>,
+    };
+    {
+        Ok(HttpResponse::Ok().json(state.llm.describe()))
+    }
+}
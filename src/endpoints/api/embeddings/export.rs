@@ -0,0 +1,44 @@
+use actix_web::{web, HttpRequest};
+use helper_macros::generate_endpoint;
+
+use crate::{
+    dto::embeddings::SearchEmbeddingsRequest,
+    services::{
+        embeddings::search::search_embeddings_,
+        export::{export_response, ExportDocument, ExportFormat},
+    },
+    state::AppState,
+};
+
+generate_endpoint! {
+    fn export_search_embeddings;
+    method: post;
+    path: "/search/export";
+    docs: {
+        params: (),
+        tag: "embeddings",
+        responses: {
+            (status = 200, description = "The search results as `text/markdown`, `text/html`, `text/csv` or `application/stix+json;version=2.1` depending on the `Accept` header"),
+            (status = 406, description = "None of the accepted content types is supported"),
+            (status = 500, description = "Internal server error"),
+        },
+    }
+    params: {
+        req: HttpRequest,
+        data: web::Json<SearchEmbeddingsRequest>,
+        state: web::Data<AppState>,
+    };
+    {
+        let format = ExportFormat::negotiate(&req)?;
+        let data = data.into_inner();
+        let entries = search_embeddings_(
+            &state.db,
+            data.embedding,
+            data.entry_type,
+            data.num_neighbors,
+        )
+        .await?;
+        let document = ExportDocument::from_entries(data.entry_type, entries);
+        Ok(export_response(&document, format, &format!("{}-search", data.entry_type)))
+    }
+}
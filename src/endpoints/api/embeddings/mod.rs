@@ -4,9 +4,11 @@ use utoipa::{openapi, OpenApi};
 
 use crate::models::{Entry, EntryType, MITREEntry};
 
+mod export;
 mod post;
 mod search;
 
+use export::*;
 use post::*;
 use search::*;
 
@@ -14,11 +16,12 @@ pub fn embeddings_service() -> impl actix_web::dev::HttpServiceFactory {
     web::scope("/embeddings")
         .service(add_embeddings)
         .service(search_embeddings)
+        .service(export_search_embeddings)
 }
 
 #[derive(OpenApi)]
 #[openapi(
-    paths(add_embeddings, search_embeddings),
+    paths(add_embeddings, search_embeddings, export_search_embeddings),
     components(schemas(Entry, EntryType, MITREEntry),)
 )]
 pub(crate) struct EmbeddingsApi;
@@ -1,11 +1,12 @@
-use std::fs;
-
 use actix_web::{delete, get, web, HttpResponse, Responder};
 use helper_macros::generate_endpoint;
 
 use crate::{
-    endpoints::files, error::ServerResponseError, models::UserSession,
-    services::files::delete::delete_file_metadata, state::AppState,
+    endpoints::files,
+    error::ServerResponseError,
+    models::UserSession,
+    services::files::delete::{delete_file_metadata, remove_stored_content},
+    state::AppState,
 };
 
 generate_endpoint! {
@@ -18,7 +19,7 @@ generate_endpoint! {
         responses: {
             (status = 200, description = "File deleted successfully"),
             (status = 401, description = "Unauthorized"),
-            (status = 404, description = "File not found"),
+            (status = 404, description = "File not found or not editable by the user"),
             (status = 500, description = "Internal server error"),
         },
         security: [
@@ -32,11 +33,10 @@ generate_endpoint! {
         state: web::Data<AppState>,
     };
     {
-        let file_id = file_id.into_inner();
-        delete_file_metadata(&state.db, file_id.clone(), session.user_id).await?;
-        let file_path = state.files.get_path_for(&file_id);
-        if let Err(err) = fs::remove_file(&file_path) {
-            return Err(ServerResponseError::InternalError(err.to_string()));
+        let keys = delete_file_metadata(&state.db, file_id.into_inner(), session.user_id).await?;
+        // The record is gone, content that cannot be removed now is left to the reconciler
+        for key in &keys {
+            remove_stored_content(&state.db, state.files.store.as_ref(), key).await;
         }
         Ok(HttpResponse::Ok().finish())
     }
@@ -1,13 +1,180 @@
-use std::fs;
-
-use actix_web::{delete, get, web, HttpResponse, Responder};
+use actix_web::http::header::{
+    Charset, ContentDisposition, ContentRange, ContentRangeSpec, DispositionParam, DispositionType,
+    ETag, EntityTag, ExtendedValue, Header, IfNoneMatch, IfRange, Range, ACCEPT_RANGES,
+    X_CONTENT_TYPE_OPTIONS,
+};
+use actix_web::http::StatusCode;
+use actix_web::{delete, get, web, HttpRequest, HttpResponse, Responder};
 use helper_macros::generate_endpoint;
 
 use crate::{
-    endpoints::files, error::ServerResponseError, models::UserSession,
-    services::files::get::get_file_metadata, state::AppState,
+    endpoints::files,
+    error::ServerResponseError,
+    models::{FileMetadata, FileRevision, ScanStatus, UserSession},
+    services::files::{get::get_file_metadata, store::FileStore},
+    state::AppState,
 };
 
+fn content_disposition(filename: String) -> ContentDisposition {
+    // Clients that do not understand `filename*` fall back to an ASCII name
+    let mut parameters = vec![DispositionParam::Filename(
+        filename
+            .chars()
+            .map(|c| if c.is_ascii() { c } else { '_' })
+            .collect(),
+    )];
+
+    if !filename.is_ascii() {
+        parameters.push(DispositionParam::FilenameExt(ExtendedValue {
+            charset: Charset::Ext("UTF-8".to_string()),
+            language_tag: None,
+            value: filename.into_bytes(),
+        }));
+    }
+
+    ContentDisposition {
+        disposition: DispositionType::Attachment,
+        parameters,
+    }
+}
+
+/// A content in the file store and what is known about it, either the current content of a file
+/// or one of its revisions
+pub(crate) struct StoredContent {
+    pub key: String,
+    pub filename: String,
+    pub size: Option<u64>,
+    pub mime_type: Option<String>,
+    pub sha256: Option<String>,
+    pub scan_status: Option<ScanStatus>,
+}
+
+impl From<FileMetadata> for StoredContent {
+    fn from(metadata: FileMetadata) -> Self {
+        Self {
+            key: metadata.content_key(),
+            filename: metadata.filename,
+            size: metadata.size,
+            mime_type: metadata.mime_type,
+            sha256: metadata.sha256,
+            scan_status: metadata.scan_status,
+        }
+    }
+}
+
+impl From<FileRevision> for StoredContent {
+    fn from(revision: FileRevision) -> Self {
+        Self {
+            key: revision.key,
+            filename: revision.filename,
+            size: revision.size,
+            mime_type: revision.mime_type,
+            sha256: revision.sha256,
+            scan_status: revision.scan_status,
+        }
+    }
+}
+
+/// Serves `content` as an attachment. Contents that have not been scanned yet or are infected
+/// are refused.
+///
+/// The response carries the detected MIME type and the checksum as `ETag`. `If-None-Match`
+/// is answered with `304 Not Modified` and a single byte range is served as `206 Partial
+/// Content`, honouring `If-Range`. Contents uploaded before their size and checksum were
+/// recorded are always served in full.
+pub(crate) async fn serve_stored_file(
+    store: &dyn FileStore,
+    content: impl Into<StoredContent>,
+    req: &HttpRequest,
+) -> Result<HttpResponse, ServerResponseError> {
+    let content: StoredContent = content.into();
+
+    match content.scan_status {
+        Some(ScanStatus::Clean) => {}
+        Some(ScanStatus::Infected) => {
+            return Err(ServerResponseError::ForbiddenWithMessage(
+                "the file contains malware and is quarantined".to_string(),
+            ))
+        }
+        Some(ScanStatus::Pending) | None => {
+            return Err(ServerResponseError::Conflict(
+                "the file has not been scanned for malware yet, try again later".to_string(),
+            ))
+        }
+    }
+
+    let key = content.key;
+    let etag = content.sha256.map(EntityTag::new_strong);
+
+    if let (Some(etag), Ok(IfNoneMatch::Items(tags))) = (&etag, IfNoneMatch::parse(req)) {
+        if tags.iter().any(|tag| tag.weak_eq(etag)) {
+            return Ok(HttpResponse::NotModified()
+                .insert_header(ETag(etag.clone()))
+                .finish());
+        }
+    }
+
+    let content_type = content
+        .mime_type
+        .and_then(|mime| mime.parse::<mime::Mime>().ok())
+        .unwrap_or(mime::APPLICATION_OCTET_STREAM);
+
+    let mut response = HttpResponse::Ok();
+    response
+        .content_type(content_type)
+        .insert_header(content_disposition(content.filename))
+        .insert_header((X_CONTENT_TYPE_OPTIONS, "nosniff"));
+
+    if let Some(etag) = &etag {
+        response.insert_header(ETag(etag.clone()));
+    }
+
+    let Some(size) = content.size else {
+        let object = store.get(&key).await?;
+        return Ok(response.no_chunking(object.size).streaming(object.body));
+    };
+    response.insert_header((ACCEPT_RANGES, "bytes"));
+
+    // A range is only served if the client's copy is still current
+    let range_applies = match IfRange::parse(req) {
+        Ok(IfRange::EntityTag(tag)) => etag.as_ref().is_some_and(|etag| tag.strong_eq(etag)),
+        Ok(IfRange::Date(_)) => false,
+        Err(_) => true,
+    };
+
+    // Several ranges would need a multipart response, the whole file is sent instead
+    let range = match Range::parse(req) {
+        Ok(Range::Bytes(specs)) if range_applies && specs.len() == 1 => {
+            Some(specs[0].to_satisfiable_range(size))
+        }
+        _ => None,
+    };
+
+    match range {
+        None => {
+            let object = store.get(&key).await?;
+            Ok(response.no_chunking(object.size).streaming(object.body))
+        }
+        Some(None) => Ok(HttpResponse::RangeNotSatisfiable()
+            .insert_header(ContentRange(ContentRangeSpec::Bytes {
+                range: None,
+                instance_length: Some(size),
+            }))
+            .finish()),
+        Some(Some((start, end))) => {
+            let object = store.get_range(&key, start..end + 1).await?;
+            Ok(response
+                .status(StatusCode::PARTIAL_CONTENT)
+                .insert_header(ContentRange(ContentRangeSpec::Bytes {
+                    range: Some((start, end)),
+                    instance_length: Some(size),
+                }))
+                .no_chunking(object.size)
+                .streaming(object.body))
+        }
+    }
+}
+
 generate_endpoint! {
     fn download_file;
     method: get;
@@ -17,8 +184,13 @@ generate_endpoint! {
         tag: "files",
         responses: {
             (status = 200, description = "File found successfully"),
+            (status = 206, description = "The requested byte range of the file"),
+            (status = 304, description = "The file matches the `If-None-Match` ETag"),
             (status = 401, description = "Unauthorized"),
+            (status = 403, description = "The file contains malware and is quarantined"),
             (status = 404, description = "File not found"),
+            (status = 409, description = "The file has not been scanned for malware yet"),
+            (status = 416, description = "The requested range lies outside of the file"),
             (status = 500, description = "Internal server error"),
         },
         security: [
@@ -28,15 +200,12 @@ generate_endpoint! {
     }
     params: {
         file_id: web::Path<String>,
+        req: HttpRequest,
         session: UserSession,
         state: web::Data<AppState>,
     };
     {
         let metadata = get_file_metadata(&state.db, file_id.into_inner(), session.user_id).await?;
-        let file_path = state.files.get_path_for(&metadata.id.id.to_string());
-        let Ok(file) = actix_files::NamedFile::open_async(file_path).await else {
-            return Err(ServerResponseError::NotFound);
-        };
-        Ok(file)
+        serve_stored_file(state.files.store.as_ref(), metadata, &req).await
     }
 }
@@ -2,10 +2,14 @@ use actix_web::{get, web, HttpResponse, Responder};
 use helper_macros::generate_endpoint;
 
 use crate::{
+    dto::team::TeamQuery,
     endpoints::files,
     error::ServerResponseError,
-    models::{FileMetadata, FileMetadataMultiple, UserSession},
-    services::files::get::{get_file_metadata, get_file_metadata_by_token},
+    models::{FileMetadata, FileMetadataMultiple, TeamRole, UserSession},
+    services::{
+        files::get::{get_file_metadata, get_file_metadata_by_token, get_team_file_metadata},
+        team::access::require_team_role,
+    },
     state::AppState,
 };
 
@@ -44,11 +48,12 @@ generate_endpoint! {
     method: get;
     path: "";
     docs: {
-        params: (),
+        params: (TeamQuery),
         tag: "files",
         responses: {
             (status = 200 , response = FileMetadataMultiple),
             (status = 401, description = "Unauthorized"),
+            (status = 404, description = "Team not found"),
             (status = 500, description = "Internal server error"),
         },
         security: [
@@ -57,11 +62,18 @@ generate_endpoint! {
         ]
     }
     params: {
+        query: web::Query<TeamQuery>,
         session: UserSession,
         state: web::Data<AppState>,
     };
     {
-        let files = get_file_metadata_by_token(&state.db, session.user_id).await?;
+        let files = match &query.team {
+            Some(team_id) => {
+                let (team, _) = require_team_role(&state.db, team_id, &session.user_id, TeamRole::Viewer).await?;
+                get_team_file_metadata(&state.db, team).await?
+            }
+            None => get_file_metadata_by_token(&state.db, session.user_id).await?,
+        };
         Ok(HttpResponse::Ok().json(files))
     }
 }
@@ -1,15 +1,27 @@
 pub mod delete;
 pub mod download;
 pub mod get;
+pub mod resumable;
+pub mod revision;
+pub mod share;
 pub mod upload;
 
-use crate::models::{FileMetadata, FileMetadataMultiple};
+use crate::dto::file_share::{ShareLink, ShareLinkRequest, ShareLinks};
+use crate::dto::team::TeamQuery;
+use crate::dto::upload_session::UploadSessionRequest;
+use crate::models::{
+    FileMetadata, FileMetadataMultiple, FileRevision, FileRevisions, FileShare, ScanStatus,
+    UploadSession,
+};
 use actix_web::{dev::HttpServiceFactory, web};
 use utoipa::{openapi, path, OpenApi};
 
 use delete::*;
 use download::*;
 use get::*;
+use resumable::*;
+use revision::*;
+use share::*;
 use upload::*;
 
 /// A simple file storage service.
@@ -19,18 +31,73 @@ use upload::*;
 /// * Get metadata
 /// * Get all metadata for user
 /// * Download file
+/// * Create, list and revoke share links
+/// * Resumable uploads in chunks
+/// * Upload new revisions, list, download and restore them
 pub fn files_service() -> impl HttpServiceFactory {
+    // Uploads are registered first so `uploads` is not taken for a file ID
     web::scope("/files")
+        .service(create_upload_session)
+        .service(get_upload_session)
+        .service(upload_chunk)
+        .service(finalize_upload_session)
+        .service(abort_upload_session)
         .service(upload_files)
         .service(get_file)
         .service(delete_file)
         .service(list_files)
         .service(download_file)
+        .service(upload_file_revision)
+        .service(list_file_revisions)
+        .service(download_file_revision)
+        .service(restore_file_revision)
+        .service(create_share_link)
+        .service(list_share_links)
+        .service(revoke_share_link)
 }
 
 #[derive(OpenApi)]
 #[openapi(
-    paths(upload_files, get_file, delete_file, list_files, download_file),
-    components(schemas(FileMetadata), responses(FileMetadata, FileMetadataMultiple))
+    paths(
+        upload_files,
+        get_file,
+        delete_file,
+        list_files,
+        download_file,
+        upload_file_revision,
+        list_file_revisions,
+        download_file_revision,
+        restore_file_revision,
+        create_share_link,
+        list_share_links,
+        revoke_share_link,
+        create_upload_session,
+        get_upload_session,
+        upload_chunk,
+        finalize_upload_session,
+        abort_upload_session
+    ),
+    components(
+        schemas(
+            FileMetadata,
+            FileRevision,
+            ScanStatus,
+            TeamQuery,
+            FileShare,
+            ShareLink,
+            ShareLinkRequest,
+            UploadSession,
+            UploadSessionRequest
+        ),
+        responses(
+            FileMetadata,
+            FileMetadataMultiple,
+            FileRevision,
+            FileRevisions,
+            ShareLink,
+            ShareLinks,
+            UploadSession
+        )
+    )
 )]
 pub(crate) struct FilesApi;
@@ -0,0 +1,216 @@
+use actix_web::{web, HttpRequest, HttpResponse};
+use helper_macros::generate_endpoint;
+
+use crate::{
+    dto::{team::TeamQuery, upload_session::UploadSessionRequest},
+    error::ServerResponseError,
+    metrics::METRICS,
+    models::{FileMetadata, TeamRole, UploadSession, UserSession},
+    services::{
+        files::{
+            scan::spawn_scans,
+            upload::{abort_upload, append_chunk, create_upload, finalize_upload, get_upload},
+        },
+        team::access::require_team_role,
+    },
+    state::AppState,
+};
+
+/// Header carrying the offset of a chunk in requests and the offset of the upload in responses
+pub const UPLOAD_OFFSET: &str = "Upload-Offset";
+
+fn upload_offset(req: &HttpRequest) -> Result<u64, ServerResponseError> {
+    req.headers()
+        .get(UPLOAD_OFFSET)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.parse().ok())
+        .ok_or_else(|| {
+            ServerResponseError::BadRequest(format!("missing or invalid {UPLOAD_OFFSET} header"))
+        })
+}
+
+generate_endpoint! {
+    fn create_upload_session;
+    method: post;
+    path: "/uploads";
+    docs: {
+        params: (TeamQuery),
+        tag: "files",
+        responses: {
+            (status = 201, response = UploadSession),
+            (status = 400, description = "Empty filename, size or invalid checksum"),
+            (status = 401, description = "Unauthorized"),
+            (status = 403, description = "Only editors and owners may upload files to a team"),
+            (status = 404, description = "Team not found"),
+            (status = 413, description = "The file is too large or exceeds the storage quota"),
+            (status = 415, description = "The declared content type is not allowed"),
+            (status = 500, description = "Internal server error"),
+        },
+        security: [
+            ("bearer_token" = []),
+            ("cookie_session" = []),
+        ]
+    }
+    params: {
+        body: web::Json<UploadSessionRequest>,
+        query: web::Query<TeamQuery>,
+        session: UserSession,
+        state: web::Data<AppState>,
+    };
+    {
+        let team = match &query.team {
+            Some(team_id) => {
+                let (team, _) = require_team_role(&state.db, team_id, &session.user_id, TeamRole::Editor).await?;
+                Some(team)
+            }
+            None => None,
+        };
+
+        let upload = create_upload(&state.db, &state.files.policy, session.user_id, team, body.into_inner()).await?;
+
+        Ok(HttpResponse::Created()
+            .insert_header(("Location", format!("/api/v1/files/uploads/{}", upload.id.id.to_raw())))
+            .insert_header((UPLOAD_OFFSET, upload.offset.to_string()))
+            .json(upload))
+    }
+}
+
+generate_endpoint! {
+    fn get_upload_session;
+    method: get;
+    path: "/uploads/{upload_id}";
+    docs: {
+        params: (),
+        tag: "files",
+        responses: {
+            (status = 200, response = UploadSession),
+            (status = 401, description = "Unauthorized"),
+            (status = 404, description = "Upload not found or expired"),
+            (status = 500, description = "Internal server error"),
+        },
+        security: [
+            ("bearer_token" = []),
+            ("cookie_session" = []),
+        ]
+    }
+    params: {
+        upload_id: web::Path<String>,
+        session: UserSession,
+        state: web::Data<AppState>,
+    };
+    {
+        let upload = get_upload(&state.db, upload_id.into_inner(), session.user_id).await?;
+
+        Ok(HttpResponse::Ok()
+            .insert_header((UPLOAD_OFFSET, upload.offset.to_string()))
+            .json(upload))
+    }
+}
+
+generate_endpoint! {
+    fn upload_chunk;
+    method: patch;
+    path: "/uploads/{upload_id}";
+    docs: {
+        params: (),
+        tag: "files",
+        responses: {
+            (status = 204, description = "Chunk stored, the `Upload-Offset` header holds the new offset"),
+            (status = 400, description = "Missing `Upload-Offset` header or the chunk was interrupted"),
+            (status = 401, description = "Unauthorized"),
+            (status = 404, description = "Upload not found or expired"),
+            (status = 409, description = "The offset does not match the upload, read it and resume from there"),
+            (status = 413, description = "The chunk exceeds the declared size"),
+            (status = 500, description = "Internal server error"),
+        },
+        security: [
+            ("bearer_token" = []),
+            ("cookie_session" = []),
+        ]
+    }
+    params: {
+        upload_id: web::Path<String>,
+        payload: web::Payload,
+        req: HttpRequest,
+        session: UserSession,
+        state: web::Data<AppState>,
+    };
+    {
+        let offset = upload_offset(&req)?;
+        let new_offset = append_chunk(&state.db, &state.files, upload_id.into_inner(), session.user_id, offset, payload).await?;
+        METRICS.upload_bytes.inc_by(new_offset.saturating_sub(offset));
+
+        Ok(HttpResponse::NoContent()
+            .insert_header((UPLOAD_OFFSET, new_offset.to_string()))
+            .finish())
+    }
+}
+
+generate_endpoint! {
+    fn finalize_upload_session;
+    method: post;
+    path: "/uploads/{upload_id}/finalize";
+    docs: {
+        params: (),
+        tag: "files",
+        responses: {
+            (status = 201, response = FileMetadata),
+            (status = 400, description = "Checksum mismatch, the upload was discarded"),
+            (status = 401, description = "Unauthorized"),
+            (status = 404, description = "Upload not found or expired"),
+            (status = 409, description = "Not all bytes have been received"),
+            (status = 413, description = "The file exceeds the storage quota, the upload was discarded"),
+            (status = 415, description = "The file type is not allowed, the upload was discarded"),
+            (status = 500, description = "Internal server error"),
+        },
+        security: [
+            ("bearer_token" = []),
+            ("cookie_session" = []),
+        ]
+    }
+    params: {
+        upload_id: web::Path<String>,
+        session: UserSession,
+        state: web::Data<AppState>,
+    };
+    {
+        let metadata = finalize_upload(&state.db, &state.files, upload_id.into_inner(), session.user_id).await?;
+
+        spawn_scans(
+            state.db.clone(),
+            state.files.store.clone(),
+            state.files.scanner.clone(),
+            vec![metadata.content_key()],
+        );
+        Ok(HttpResponse::Created().json(metadata))
+    }
+}
+
+generate_endpoint! {
+    fn abort_upload_session;
+    method: delete;
+    path: "/uploads/{upload_id}";
+    docs: {
+        params: (),
+        tag: "files",
+        responses: {
+            (status = 204, description = "Upload discarded"),
+            (status = 401, description = "Unauthorized"),
+            (status = 404, description = "Upload not found or expired"),
+            (status = 500, description = "Internal server error"),
+        },
+        security: [
+            ("bearer_token" = []),
+            ("cookie_session" = []),
+        ]
+    }
+    params: {
+        upload_id: web::Path<String>,
+        session: UserSession,
+        state: web::Data<AppState>,
+    };
+    {
+        abort_upload(&state.db, &state.files, upload_id.into_inner(), session.user_id).await?;
+        Ok(HttpResponse::NoContent().finish())
+    }
+}
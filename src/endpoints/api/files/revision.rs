@@ -0,0 +1,194 @@
+use actix_multipart::form::MultipartForm;
+use actix_web::{get, post, put, web, HttpRequest, HttpResponse, Responder};
+use helper_macros::generate_endpoint;
+
+use crate::{
+    dto::file_upload_form::UploadForm,
+    endpoints::api::files::download::serve_stored_file,
+    endpoints::files,
+    error::ServerResponseError,
+    metrics::METRICS,
+    models::{FileRevision, FileRevisions, TeamRole, UserSession},
+    services::files::{
+        insert::NewFile,
+        inspect::{inspect_file, normalize_declared_type},
+        revision::{add_revision, get_revision, list_revisions, restore_revision},
+        scan::spawn_scans,
+    },
+    state::AppState,
+};
+
+generate_endpoint! {
+    fn upload_file_revision;
+    method: put;
+    path: "/{file_id}/content";
+    docs: {
+        params: (),
+        tag: "files",
+        responses: {
+            (status = 201, response = FileRevision),
+            (status = 400, description = "The request does not contain exactly one file"),
+            (status = 401, description = "Unauthorized"),
+            (status = 404, description = "File not found or not editable by the user"),
+            (status = 413, description = "The file is too large or the storage quota is exceeded"),
+            (status = 415, description = "The type of the file is not allowed"),
+            (status = 500, description = "Internal server error"),
+        },
+        security: [
+            ("bearer_token" = []),
+            ("cookie_session" = []),
+        ]
+    };
+    params: {
+        file_id: web::Path<String>,
+        form: MultipartForm<UploadForm>,
+        session: UserSession,
+        state: web::Data<AppState>,
+    };
+    {
+        let form = form.into_inner();
+        let [temp_file] = form.files.as_slice() else {
+            return Err(ServerResponseError::BadRequest(
+                "exactly one file has to be sent as `file`".to_string(),
+            ));
+        };
+
+        let properties = inspect_file(temp_file.file.path())
+            .await
+            .map_err(|err| ServerResponseError::InternalError(err.to_string()))?;
+        let filename = temp_file
+            .file_name
+            .clone()
+            .unwrap_or_else(|| "unnamed_file".to_string());
+
+        let policy = &state.files.policy;
+        policy.check_size(&filename, properties.size)?;
+        policy.check_type(&filename, &properties.mime_type)?;
+        policy.check_quota(&state.db, session.user_id.clone(), properties.size, true).await?;
+
+        let size = properties.size;
+        let new = NewFile {
+            filename,
+            declared_type: temp_file
+                .content_type
+                .as_ref()
+                .and_then(|mime| normalize_declared_type(Some(mime.as_ref()))),
+            properties,
+        };
+        let revision = add_revision(
+            &state.db,
+            state.files.store.as_ref(),
+            file_id.into_inner(),
+            session.user_id,
+            new,
+            temp_file.file.path(),
+        )
+        .await?;
+        METRICS.upload_bytes.inc_by(size);
+
+        spawn_scans(
+            state.db.clone(),
+            state.files.store.clone(),
+            state.files.scanner.clone(),
+            vec![revision.key.clone()],
+        );
+
+        Ok(HttpResponse::Created().json(revision))
+    }
+}
+
+generate_endpoint! {
+    fn list_file_revisions;
+    method: get;
+    path: "/{file_id}/revisions";
+    docs: {
+        params: (),
+        tag: "files",
+        responses: {
+            (status = 200, response = FileRevisions),
+            (status = 401, description = "Unauthorized"),
+            (status = 404, description = "File not found"),
+            (status = 500, description = "Internal server error"),
+        },
+        security: [
+            ("bearer_token" = []),
+            ("cookie_session" = []),
+        ]
+    }
+    params: {
+        file_id: web::Path<String>,
+        session: UserSession,
+        state: web::Data<AppState>,
+    };
+    {
+        let revisions = list_revisions(&state.db, file_id.into_inner(), session.user_id).await?;
+        Ok(HttpResponse::Ok().json(revisions))
+    }
+}
+
+generate_endpoint! {
+    fn download_file_revision;
+    method: get;
+    path: "/{file_id}/revisions/{revision}/content";
+    docs: {
+        params: (),
+        tag: "files",
+        responses: {
+            (status = 200, description = "Content of the revision"),
+            (status = 206, description = "The requested byte range of the revision"),
+            (status = 304, description = "The revision matches the `If-None-Match` ETag"),
+            (status = 401, description = "Unauthorized"),
+            (status = 403, description = "The revision contains malware and is quarantined"),
+            (status = 404, description = "File or revision not found"),
+            (status = 409, description = "The revision has not been scanned for malware yet"),
+            (status = 416, description = "The requested range lies outside of the revision"),
+            (status = 500, description = "Internal server error"),
+        },
+        security: [
+            ("bearer_token" = []),
+            ("cookie_session" = []),
+        ]
+    }
+    params: {
+        path: web::Path<(String, u32)>,
+        req: HttpRequest,
+        session: UserSession,
+        state: web::Data<AppState>,
+    };
+    {
+        let (file_id, revision) = path.into_inner();
+        let revision = get_revision(&state.db, file_id, revision, session.user_id, TeamRole::Viewer).await?;
+        serve_stored_file(state.files.store.as_ref(), revision, &req).await
+    }
+}
+
+generate_endpoint! {
+    fn restore_file_revision;
+    method: post;
+    path: "/{file_id}/revisions/{revision}/restore";
+    docs: {
+        params: (),
+        tag: "files",
+        responses: {
+            (status = 201, response = FileRevision),
+            (status = 401, description = "Unauthorized"),
+            (status = 403, description = "The revision contains malware"),
+            (status = 404, description = "File or revision not found or not editable by the user"),
+            (status = 500, description = "Internal server error"),
+        },
+        security: [
+            ("bearer_token" = []),
+            ("cookie_session" = []),
+        ]
+    }
+    params: {
+        path: web::Path<(String, u32)>,
+        session: UserSession,
+        state: web::Data<AppState>,
+    };
+    {
+        let (file_id, revision) = path.into_inner();
+        let restored = restore_revision(&state.db, file_id, revision, session.user_id).await?;
+        Ok(HttpResponse::Created().json(restored))
+    }
+}
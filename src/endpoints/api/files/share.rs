@@ -0,0 +1,99 @@
+use actix_web::{web, HttpResponse};
+use helper_macros::generate_endpoint;
+
+use crate::{
+    dto::file_share::{ShareLink, ShareLinkRequest, ShareLinks},
+    models::UserSession,
+    services::files::share::{create_share, list_shares, revoke_share},
+    state::AppState,
+};
+
+generate_endpoint! {
+    fn create_share_link;
+    method: post;
+    path: "/{file_id}/shares";
+    docs: {
+        params: (),
+        tag: "files",
+        responses: {
+            (status = 201, response = ShareLink),
+            (status = 400, description = "Invalid expiry, download limit or password"),
+            (status = 401, description = "Unauthorized"),
+            (status = 404, description = "File not found"),
+            (status = 500, description = "Internal server error"),
+        },
+        security: [
+            ("bearer_token" = []),
+            ("cookie_session" = []),
+        ]
+    }
+    params: {
+        file_id: web::Path<String>,
+        body: web::Json<ShareLinkRequest>,
+        session: UserSession,
+        state: web::Data<AppState>,
+    };
+    {
+        let link = create_share(&state.db, &state.files.share_links, file_id.into_inner(), session.user_id, body.into_inner()).await?;
+        Ok(HttpResponse::Created().json(link))
+    }
+}
+
+generate_endpoint! {
+    fn list_share_links;
+    method: get;
+    path: "/{file_id}/shares";
+    docs: {
+        params: (),
+        tag: "files",
+        responses: {
+            (status = 200, response = ShareLinks),
+            (status = 401, description = "Unauthorized"),
+            (status = 404, description = "File not found"),
+            (status = 500, description = "Internal server error"),
+        },
+        security: [
+            ("bearer_token" = []),
+            ("cookie_session" = []),
+        ]
+    }
+    params: {
+        file_id: web::Path<String>,
+        session: UserSession,
+        state: web::Data<AppState>,
+    };
+    {
+        let links = list_shares(&state.db, &state.files.share_links, file_id.into_inner(), session.user_id).await?;
+        Ok(HttpResponse::Ok().json(links))
+    }
+}
+
+generate_endpoint! {
+    fn revoke_share_link;
+    method: delete;
+    path: "/{file_id}/shares/{share_id}";
+    docs: {
+        params: (),
+        tag: "files",
+        responses: {
+            (status = 204, description = "Share link revoked"),
+            (status = 401, description = "Unauthorized"),
+            (status = 404, description = "File or share link not found"),
+            (status = 500, description = "Internal server error"),
+        },
+        security: [
+            ("bearer_token" = []),
+            ("cookie_session" = []),
+        ]
+    }
+    params: {
+        path: web::Path<(String, String)>,
+        session: UserSession,
+        state: web::Data<AppState>,
+    };
+    {
+        let (file_id, share_id) = path.into_inner();
+        revoke_share(&state.db, file_id, share_id, session.user_id).await?;
+        Ok(HttpResponse::NoContent().finish())
+    }
+}
@@ -1,14 +1,23 @@
 use actix_multipart::form::MultipartForm;
 use actix_web::{get, post, web, HttpRequest, HttpResponse, Responder};
 use helper_macros::generate_endpoint;
+use std::path::Path;
 
 use crate::{
-    dto::file_upload_form::UploadForm,
+    dto::{file_upload_form::UploadForm, team::TeamQuery},
     endpoints::files,
     error::ServerResponseError,
-    extractors::token_from_request,
-    models::{FileMetadataMultiple, UserSession},
-    services::files::insert::insert_file_metadata,
+    extractors::user_from_request,
+    metrics::METRICS,
+    models::{FileMetadataMultiple, TeamRole},
+    services::{
+        files::{
+            insert::{insert_file_metadata_for, store_file_contents, NewFile},
+            inspect::{inspect_file, normalize_declared_type},
+            scan::spawn_scans,
+        },
+        team::access::require_team_role,
+    },
     state::AppState,
 };
 
@@ -17,11 +26,15 @@ generate_endpoint! {
     method: post;
     path: "";
     docs: {
-        params: (),
+        params: (TeamQuery),
         tag: "files",
         responses: {
             (status = 201, response = FileMetadataMultiple),
             (status = 401, description = "Unauthorized"),
+            (status = 403, description = "Only editors and owners may upload files to a team"),
+            (status = 404, description = "Team not found"),
+            (status = 413, description = "Too many files, a file is too large or the storage quota is exceeded"),
+            (status = 415, description = "The type of a file is not allowed"),
             (status = 500, description = "Internal server error"),
         },
         security: [
@@ -31,39 +44,61 @@ generate_endpoint! {
     };
     params: {
         form: MultipartForm<UploadForm>,
+        query: web::Query<TeamQuery>,
         req: HttpRequest,
         state: web::Data<AppState>,
     };
     {
-        let token = token_from_request(&state.db, &req).await?;
+        let user_id = user_from_request(&state.db, &req).await?;
+        let team = match &query.team {
+            Some(team_id) => {
+                Some(require_team_role(&state.db, team_id, &user_id, TeamRole::Editor).await?.0)
+            }
+            None => None,
+        };
         let form = form.into_inner();
+        let policy = &state.files.policy;
+        policy.check_count(form.files.len())?;
+
+        let mut files = Vec::with_capacity(form.files.len());
+        for temp_file in &form.files {
+            let properties = inspect_file(temp_file.file.path())
+                .await
+                .map_err(|err| ServerResponseError::InternalError(err.to_string()))?;
+            let filename = temp_file
+                .file_name
+                .clone()
+                .unwrap_or_else(|| "unnamed_file".to_string());
 
-        let filenames: Vec<&str> = form
-            .files
-            .iter()
-            .map(|temp_file| {
-                temp_file
-                    .file_name
+            policy.check_size(&filename, properties.size)?;
+            policy.check_type(&filename, &properties.mime_type)?;
+
+            files.push(NewFile {
+                filename,
+                declared_type: temp_file
+                    .content_type
                     .as_ref()
-                    .map_or("unnamed_file", |f| f.as_str())
-            })
-            .collect();
+                    .and_then(|mime| normalize_declared_type(Some(mime.as_ref()))),
+                properties,
+            });
+        }
 
-        let metadata = insert_file_metadata(&state.db, filenames.clone(), token).await?;
+        let total = files.iter().map(|file| file.properties.size).sum();
+        policy.check_quota(&state.db, user_id.clone(), total, true).await?;
 
-        let persisted: Result<Vec<_>, _> = form
-            .files
-            .into_iter()
-            .zip(&metadata)
-            .map(|(f, m)| {
-                let path = state.files.get_path_for(&m.id.id.to_string());
-                f.file.persist(path)
-            })
-            .collect();
+        let metadata = insert_file_metadata_for(&state.db, files, user_id, team).await?;
+
+        let sources: Vec<&Path> = form.files.iter().map(|f| f.file.path()).collect();
+        store_file_contents(&state.db, state.files.store.as_ref(), &metadata, &sources).await?;
+        METRICS.upload_bytes.inc_by(total);
+
+        spawn_scans(
+            state.db.clone(),
+            state.files.store.clone(),
+            state.files.scanner.clone(),
+            metadata.iter().map(|m| m.content_key()).collect(),
+        );
 
-        if let Err(err) = persisted {
-            return Err(ServerResponseError::InternalError(err.to_string()));
-        }
         Ok(HttpResponse::Created().json(metadata))
     }
 }
@@ -6,9 +6,12 @@ use actix_extensible_rate_limit::RateLimiter;
 use actix_web::dev::ServiceRequest;
 use actix_web::middleware::NormalizePath;
 use actix_web::web;
+use admin::admin_service;
 use embeddings::embeddings_service;
 use oauth::oauth_service;
-use tracing_actix_web::TracingLogger;
+use shared::shared_service;
+use team::team_service;
+use threat_model::threat_model_service;
 use user::user_service;
 use utoipa::OpenApi;
 use utoipa_rapidoc::RapiDoc;
@@ -18,9 +21,13 @@ use utoipa_swagger_ui::{Config, SwaggerUi};
 
 use files::files_service;
 
+pub(crate) mod admin;
 pub(crate) mod embeddings;
 pub(crate) mod files;
 pub(crate) mod oauth;
+pub(crate) mod shared;
+pub(crate) mod team;
+pub(crate) mod threat_model;
 pub(crate) mod user;
 pub(crate) mod chat;
 
@@ -39,12 +46,17 @@ fn v1_endpoints(
     logger: LoggingMiddleware,
 ) -> impl actix_web::dev::HttpServiceFactory {
     web::scope("/v1")
-        .wrap(TracingLogger::default()) // this is logging using tracing
         .service(user_service())
         .service(embeddings_service())
         .service(oauth_service())
         .service(files_service())
+        .service(shared_service())
+        .service(threat_model_service())
+        .service(team_service())
+        .service(admin_service())
         .service(chat::chat)
+        .service(chat::structured_chat)
+        .service(chat::list_llm_backends)
         .wrap(limiter)
         .wrap(logger) // this is database logging
         .wrap(NormalizePath::default())
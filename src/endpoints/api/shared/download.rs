@@ -0,0 +1,116 @@
+use actix_web::http::header::{HeaderValue, CACHE_CONTROL};
+use actix_web::http::StatusCode;
+use actix_web::{web, HttpRequest, HttpResponse};
+use chrono::Utc;
+use helper_macros::generate_endpoint;
+
+use crate::{
+    dto::file_share::{SharePasswordForm, SharedFileQuery},
+    endpoints::api::files::download::serve_stored_file,
+    error::ServerResponseError,
+    services::files::share::{count_share_download, redeem_share},
+    state::AppState,
+};
+
+/// Whether serving `response` uses up a download of the link. Every response that serves
+/// bytes counts, including ranges, so that a limit cannot be bypassed by fetching a file in
+/// pieces. Revalidations do not count.
+fn counts_as_download(response: &HttpResponse) -> bool {
+    matches!(
+        response.status(),
+        StatusCode::OK | StatusCode::PARTIAL_CONTENT
+    )
+}
+
+/// Checks the signature of the link, counts the download and serves the file as an attachment.
+/// Invalid signatures are reported as not found so that share IDs cannot be probed.
+async fn serve_shared_file(
+    state: &AppState,
+    req: &HttpRequest,
+    share_id: String,
+    query: SharedFileQuery,
+    password: Option<String>,
+) -> Result<HttpResponse, ServerResponseError> {
+    if !state
+        .files
+        .share_links
+        .verify(&share_id, query.expires, &query.signature)
+    {
+        return Err(ServerResponseError::NotFound);
+    }
+
+    if query.expires <= Utc::now().timestamp() {
+        return Err(ServerResponseError::Gone);
+    }
+
+    let metadata = redeem_share(&state.db, share_id.clone(), password).await?;
+    let mut response = serve_stored_file(state.files.store.as_ref(), metadata, req).await?;
+
+    if counts_as_download(&response) {
+        count_share_download(&state.db, share_id).await?;
+    }
+
+    response
+        .headers_mut()
+        .insert(CACHE_CONTROL, HeaderValue::from_static("no-store"));
+
+    Ok(response)
+}
+
+generate_endpoint! {
+    fn download_shared_file;
+    method: get;
+    path: "/{share_id}";
+    docs: {
+        params: (SharedFileQuery),
+        tag: "files",
+        responses: {
+            (status = 200, description = "The shared file"),
+            (status = 206, description = "The requested byte range of the shared file"),
+            (status = 401, description = "The link is password protected, use the `POST` variant"),
+            (status = 404, description = "Invalid or revoked link"),
+            (status = 409, description = "The file has not been scanned for malware yet"),
+            (status = 410, description = "The link expired or its download limit is reached"),
+            (status = 500, description = "Internal server error"),
+        }
+    }
+    params: {
+        share_id: web::Path<String>,
+        query: web::Query<SharedFileQuery>,
+        req: HttpRequest,
+        state: web::Data<AppState>,
+    };
+    {
+        serve_shared_file(&state, &req, share_id.into_inner(), query.into_inner(), None).await
+    }
+}
+
+generate_endpoint! {
+    fn download_protected_shared_file;
+    method: post;
+    path: "/{share_id}";
+    docs: {
+        params: (SharedFileQuery),
+        tag: "files",
+        responses: {
+            (status = 200, description = "The shared file"),
+            (status = 206, description = "The requested byte range of the shared file"),
+            (status = 401, description = "Wrong password"),
+            (status = 404, description = "Invalid or revoked link"),
+            (status = 409, description = "The file has not been scanned for malware yet"),
+            (status = 410, description = "The link expired or its download limit is reached"),
+            (status = 500, description = "Internal server error"),
+        }
+    }
+    params: {
+        share_id: web::Path<String>,
+        query: web::Query<SharedFileQuery>,
+        req: HttpRequest,
+        form: web::Form<SharePasswordForm>,
+        state: web::Data<AppState>,
+    };
+    {
+        let password = Some(form.into_inner().password);
+        serve_shared_file(&state, &req, share_id.into_inner(), query.into_inner(), password).await
+    }
+}
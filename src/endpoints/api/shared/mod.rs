@@ -0,0 +1,24 @@
+pub mod download;
+
+use crate::dto::file_share::{SharePasswordForm, SharedFileQuery};
+use actix_web::{dev::HttpServiceFactory, web};
+use utoipa::OpenApi;
+
+use download::*;
+
+/// Unauthenticated downloads of shared files.
+/// Operations:
+/// * Download a file through a signed share link
+/// * Download a password protected file through a signed share link
+pub fn shared_service() -> impl HttpServiceFactory {
+    web::scope("/shared")
+        .service(download_shared_file)
+        .service(download_protected_shared_file)
+}
+
+#[derive(OpenApi)]
+#[openapi(
+    paths(download_shared_file, download_protected_shared_file),
+    components(schemas(SharedFileQuery, SharePasswordForm))
+)]
+pub(crate) struct SharedApi;
@@ -0,0 +1,38 @@
+use actix_web::{web, HttpResponse};
+use helper_macros::generate_endpoint;
+
+use crate::{
+    dto::team::TeamRequest,
+    models::{Team, UserSession},
+    services::team::create::create_team,
+    state::AppState,
+};
+
+generate_endpoint! {
+    fn create_team_endpoint;
+    method: post;
+    path: "";
+    docs: {
+        params: (),
+        tag: "team",
+        responses: {
+            (status = 201, response = Team),
+            (status = 400, description = "The team name is empty"),
+            (status = 401, description = "Unauthorized"),
+            (status = 500, description = "Internal server error"),
+        },
+        security: [
+            ("bearer_token" = []),
+            ("cookie_session" = []),
+        ]
+    }
+    params: {
+        body: web::Json<TeamRequest>,
+        session: UserSession,
+        state: web::Data<AppState>,
+    };
+    {
+        let team = create_team(&state.db, session.user_id, body.into_inner()).await?;
+        Ok(HttpResponse::Created().json(team))
+    }
+}
@@ -0,0 +1,34 @@
+use actix_web::{web, HttpResponse};
+use helper_macros::generate_endpoint;
+
+use crate::{models::UserSession, services::team::delete::delete_team, state::AppState};
+
+generate_endpoint! {
+    fn delete_team_endpoint;
+    method: delete;
+    path: "/{team_id}";
+    docs: {
+        params: (),
+        tag: "team",
+        responses: {
+            (status = 204, description = "Team, its memberships, invitations and threat models deleted"),
+            (status = 401, description = "Unauthorized"),
+            (status = 403, description = "Only owners may delete the team"),
+            (status = 404, description = "Team not found"),
+            (status = 500, description = "Internal server error"),
+        },
+        security: [
+            ("bearer_token" = []),
+            ("cookie_session" = []),
+        ]
+    }
+    params: {
+        team_id: web::Path<String>,
+        session: UserSession,
+        state: web::Data<AppState>,
+    };
+    {
+        delete_team(&state.db, team_id.into_inner(), session.user_id).await?;
+        Ok(HttpResponse::NoContent().finish())
+    }
+}
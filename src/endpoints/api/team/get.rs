@@ -0,0 +1,65 @@
+use actix_web::{web, HttpResponse};
+use helper_macros::generate_endpoint;
+
+use crate::{
+    dto::team::TeamDetails,
+    models::{TeamMemberships, UserSession},
+    services::team::get::{get_team_details, list_teams},
+    state::AppState,
+};
+
+generate_endpoint! {
+    fn list_teams_endpoint;
+    method: get;
+    path: "";
+    docs: {
+        params: (),
+        tag: "team",
+        responses: {
+            (status = 200, response = TeamMemberships),
+            (status = 401, description = "Unauthorized"),
+            (status = 500, description = "Internal server error"),
+        },
+        security: [
+            ("bearer_token" = []),
+            ("cookie_session" = []),
+        ]
+    }
+    params: {
+        session: UserSession,
+        state: web::Data<AppState>,
+    };
+    {
+        let teams = list_teams(&state.db, session.user_id).await?;
+        Ok(HttpResponse::Ok().json(teams))
+    }
+}
+
+generate_endpoint! {
+    fn get_team_endpoint;
+    method: get;
+    path: "/{team_id}";
+    docs: {
+        params: (),
+        tag: "team",
+        responses: {
+            (status = 200, response = TeamDetails),
+            (status = 401, description = "Unauthorized"),
+            (status = 404, description = "Team not found"),
+            (status = 500, description = "Internal server error"),
+        },
+        security: [
+            ("bearer_token" = []),
+            ("cookie_session" = []),
+        ]
+    }
+    params: {
+        team_id: web::Path<String>,
+        session: UserSession,
+        state: web::Data<AppState>,
+    };
+    {
+        let details = get_team_details(&state.db, team_id.into_inner(), session.user_id).await?;
+        Ok(HttpResponse::Ok().json(details))
+    }
+}
@@ -0,0 +1,162 @@
+use actix_web::{web, HttpResponse};
+use helper_macros::generate_endpoint;
+
+use crate::{
+    dto::team::InvitationRequest,
+    models::{TeamInvitation, TeamInvitations, UserSession},
+    services::team::invitations::{
+        accept_invitation, create_invitation, decline_invitation, list_invitations_for,
+        revoke_invitation,
+    },
+    state::AppState,
+};
+
+generate_endpoint! {
+    fn create_invitation_endpoint;
+    method: post;
+    path: "/{team_id}/invitations";
+    docs: {
+        params: (),
+        tag: "team",
+        responses: {
+            (status = 201, response = TeamInvitation),
+            (status = 400, description = "Invalid email address"),
+            (status = 401, description = "Unauthorized"),
+            (status = 403, description = "Only owners may invite users"),
+            (status = 404, description = "Team not found"),
+            (status = 409, description = "The user is already a member"),
+            (status = 500, description = "Internal server error"),
+        },
+        security: [
+            ("bearer_token" = []),
+            ("cookie_session" = []),
+        ]
+    }
+    params: {
+        team_id: web::Path<String>,
+        body: web::Json<InvitationRequest>,
+        session: UserSession,
+        state: web::Data<AppState>,
+    };
+    {
+        let invitation = create_invitation(&state.db, team_id.into_inner(), session.user_id, body.into_inner()).await?;
+        Ok(HttpResponse::Created().json(invitation))
+    }
+}
+
+generate_endpoint! {
+    fn revoke_invitation_endpoint;
+    method: delete;
+    path: "/{team_id}/invitations/{invitation_id}";
+    docs: {
+        params: (),
+        tag: "team",
+        responses: {
+            (status = 204, description = "Invitation revoked"),
+            (status = 401, description = "Unauthorized"),
+            (status = 403, description = "Only owners may revoke invitations"),
+            (status = 404, description = "Team or invitation not found"),
+            (status = 500, description = "Internal server error"),
+        },
+        security: [
+            ("bearer_token" = []),
+            ("cookie_session" = []),
+        ]
+    }
+    params: {
+        path: web::Path<(String, String)>,
+        session: UserSession,
+        state: web::Data<AppState>,
+    };
+    {
+        let (team_id, invitation_id) = path.into_inner();
+        revoke_invitation(&state.db, team_id, invitation_id, session.user_id).await?;
+        Ok(HttpResponse::NoContent().finish())
+    }
+}
+
+generate_endpoint! {
+    fn list_my_invitations_endpoint;
+    method: get;
+    path: "/invitations";
+    docs: {
+        params: (),
+        tag: "team",
+        responses: {
+            (status = 200, response = TeamInvitations),
+            (status = 401, description = "Unauthorized"),
+            (status = 500, description = "Internal server error"),
+        },
+        security: [
+            ("bearer_token" = []),
+            ("cookie_session" = []),
+        ]
+    }
+    params: {
+        session: UserSession,
+        state: web::Data<AppState>,
+    };
+    {
+        let invitations = list_invitations_for(&state.db, &session.email).await?;
+        Ok(HttpResponse::Ok().json(invitations))
+    }
+}
+
+generate_endpoint! {
+    fn accept_invitation_endpoint;
+    method: post;
+    path: "/invitations/{invitation_id}/accept";
+    docs: {
+        params: (),
+        tag: "team",
+        responses: {
+            (status = 204, description = "Joined the team"),
+            (status = 401, description = "Unauthorized"),
+            (status = 404, description = "Invitation not found or expired"),
+            (status = 409, description = "Already a member of the team"),
+            (status = 500, description = "Internal server error"),
+        },
+        security: [
+            ("bearer_token" = []),
+            ("cookie_session" = []),
+        ]
+    }
+    params: {
+        invitation_id: web::Path<String>,
+        session: UserSession,
+        state: web::Data<AppState>,
+    };
+    {
+        accept_invitation(&state.db, invitation_id.into_inner(), session.user_id, &session.email).await?;
+        Ok(HttpResponse::NoContent().finish())
+    }
+}
+
+generate_endpoint! {
+    fn decline_invitation_endpoint;
+    method: delete;
+    path: "/invitations/{invitation_id}";
+    docs: {
+        params: (),
+        tag: "team",
+        responses: {
+            (status = 204, description = "Invitation declined"),
+            (status = 401, description = "Unauthorized"),
+            (status = 404, description = "Invitation not found or expired"),
+            (status = 500, description = "Internal server error"),
+        },
+        security: [
+            ("bearer_token" = []),
+            ("cookie_session" = []),
+        ]
+    }
+    params: {
+        invitation_id: web::Path<String>,
+        session: UserSession,
+        state: web::Data<AppState>,
+    };
+    {
+        decline_invitation(&state.db, invitation_id.into_inner(), &session.email).await?;
+        Ok(HttpResponse::NoContent().finish())
+    }
+}
@@ -0,0 +1,74 @@
+use actix_web::{web, HttpResponse};
+use helper_macros::generate_endpoint;
+
+use crate::{
+    dto::team::MemberRoleRequest,
+    models::UserSession,
+    services::team::members::{remove_member, update_member_role},
+    state::AppState,
+};
+
+generate_endpoint! {
+    fn update_member_role_endpoint;
+    method: put;
+    path: "/{team_id}/members/{user_id}";
+    docs: {
+        params: (),
+        tag: "team",
+        responses: {
+            (status = 204, description = "Role changed"),
+            (status = 401, description = "Unauthorized"),
+            (status = 403, description = "Only owners may change roles"),
+            (status = 404, description = "Team or member not found"),
+            (status = 409, description = "The team would be left without an owner"),
+            (status = 500, description = "Internal server error"),
+        },
+        security: [
+            ("bearer_token" = []),
+            ("cookie_session" = []),
+        ]
+    }
+    params: {
+        path: web::Path<(String, String)>,
+        body: web::Json<MemberRoleRequest>,
+        session: UserSession,
+        state: web::Data<AppState>,
+    };
+    {
+        let (team_id, member_id) = path.into_inner();
+        update_member_role(&state.db, team_id, member_id, session.user_id, body.into_inner().role).await?;
+        Ok(HttpResponse::NoContent().finish())
+    }
+}
+
+generate_endpoint! {
+    fn remove_member_endpoint;
+    method: delete;
+    path: "/{team_id}/members/{user_id}";
+    docs: {
+        params: (),
+        tag: "team",
+        responses: {
+            (status = 204, description = "Member removed, members may always remove themselves"),
+            (status = 401, description = "Unauthorized"),
+            (status = 403, description = "Only owners may remove other members"),
+            (status = 404, description = "Team or member not found"),
+            (status = 409, description = "The team would be left without an owner"),
+            (status = 500, description = "Internal server error"),
+        },
+        security: [
+            ("bearer_token" = []),
+            ("cookie_session" = []),
+        ]
+    }
+    params: {
+        path: web::Path<(String, String)>,
+        session: UserSession,
+        state: web::Data<AppState>,
+    };
+    {
+        let (team_id, member_id) = path.into_inner();
+        remove_member(&state.db, team_id, member_id, session.user_id).await?;
+        Ok(HttpResponse::NoContent().finish())
+    }
+}
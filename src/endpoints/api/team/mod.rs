@@ -0,0 +1,75 @@
+pub mod create;
+pub mod delete;
+pub mod get;
+pub mod invitations;
+pub mod members;
+pub mod update;
+
+use crate::dto::team::{InvitationRequest, MemberRoleRequest, TeamDetails, TeamRequest};
+use crate::models::{
+    Team, TeamInvitation, TeamInvitations, TeamMember, TeamMembership, TeamMemberships, TeamRole,
+};
+use actix_web::{dev::HttpServiceFactory, web};
+use utoipa::OpenApi;
+
+use create::*;
+use delete::*;
+use get::*;
+use invitations::*;
+use members::*;
+use update::*;
+
+/// Teams that share files and threat models.
+/// Operations:
+/// * Create, list, get, rename and delete teams
+/// * Change the role of members and remove them
+/// * Invite users by email, list, accept, decline and revoke invitations
+pub fn team_service() -> impl HttpServiceFactory {
+    // `/invitations` has to be registered before `/{team_id}` so it is not taken for a team ID
+    web::scope("/teams")
+        .service(list_my_invitations_endpoint)
+        .service(accept_invitation_endpoint)
+        .service(decline_invitation_endpoint)
+        .service(create_team_endpoint)
+        .service(list_teams_endpoint)
+        .service(get_team_endpoint)
+        .service(update_team_endpoint)
+        .service(delete_team_endpoint)
+        .service(update_member_role_endpoint)
+        .service(remove_member_endpoint)
+        .service(create_invitation_endpoint)
+        .service(revoke_invitation_endpoint)
+}
+
+#[derive(OpenApi)]
+#[openapi(
+    paths(
+        create_team_endpoint,
+        list_teams_endpoint,
+        get_team_endpoint,
+        update_team_endpoint,
+        delete_team_endpoint,
+        update_member_role_endpoint,
+        remove_member_endpoint,
+        create_invitation_endpoint,
+        revoke_invitation_endpoint,
+        list_my_invitations_endpoint,
+        accept_invitation_endpoint,
+        decline_invitation_endpoint
+    ),
+    components(
+        schemas(
+            Team,
+            TeamRole,
+            TeamMembership,
+            TeamMember,
+            TeamInvitation,
+            TeamDetails,
+            TeamRequest,
+            InvitationRequest,
+            MemberRoleRequest
+        ),
+        responses(Team, TeamMemberships, TeamDetails, TeamInvitation, TeamInvitations)
+    )
+)]
+pub(crate) struct TeamApi;
@@ -0,0 +1,41 @@
+use actix_web::{web, HttpResponse};
+use helper_macros::generate_endpoint;
+
+use crate::{
+    dto::team::TeamRequest,
+    models::{Team, UserSession},
+    services::team::update::update_team,
+    state::AppState,
+};
+
+generate_endpoint! {
+    fn update_team_endpoint;
+    method: put;
+    path: "/{team_id}";
+    docs: {
+        params: (),
+        tag: "team",
+        responses: {
+            (status = 200, response = Team),
+            (status = 400, description = "The team name is empty"),
+            (status = 401, description = "Unauthorized"),
+            (status = 403, description = "Only owners may rename the team"),
+            (status = 404, description = "Team not found"),
+            (status = 500, description = "Internal server error"),
+        },
+        security: [
+            ("bearer_token" = []),
+            ("cookie_session" = []),
+        ]
+    }
+    params: {
+        team_id: web::Path<String>,
+        body: web::Json<TeamRequest>,
+        session: UserSession,
+        state: web::Data<AppState>,
+    };
+    {
+        let team = update_team(&state.db, team_id.into_inner(), session.user_id, body.into_inner()).await?;
+        Ok(HttpResponse::Ok().json(team))
+    }
+}
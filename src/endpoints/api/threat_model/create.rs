@@ -0,0 +1,40 @@
+use actix_web::{web, HttpResponse};
+use helper_macros::generate_endpoint;
+
+use crate::{
+    dto::threat_model::ThreatModelRequest,
+    models::{ThreatModel, UserSession},
+    services::threat_model::create::create_threat_model,
+    state::AppState,
+};
+
+generate_endpoint! {
+    fn create_threat_model_endpoint;
+    method: post;
+    path: "";
+    docs: {
+        params: (),
+        tag: "threat_model",
+        responses: {
+            (status = 201, response = ThreatModel),
+            (status = 400, description = "Unknown MITRE IDs, files or invalid content"),
+            (status = 401, description = "Unauthorized"),
+            (status = 403, description = "Only editors may create threat models for the team"),
+            (status = 404, description = "Team not found"),
+            (status = 500, description = "Internal server error"),
+        },
+        security: [
+            ("bearer_token" = []),
+            ("cookie_session" = []),
+        ]
+    }
+    params: {
+        body: web::Json<ThreatModelRequest>,
+        session: UserSession,
+        state: web::Data<AppState>,
+    };
+    {
+        let model = create_threat_model(&state.db, session.user_id, body.into_inner()).await?;
+        Ok(HttpResponse::Created().json(model))
+    }
+}
@@ -0,0 +1,35 @@
+use actix_web::{web, HttpResponse};
+use helper_macros::generate_endpoint;
+
+use crate::{
+    models::UserSession, services::threat_model::delete::delete_threat_model, state::AppState,
+};
+
+generate_endpoint! {
+    fn delete_threat_model_endpoint;
+    method: delete;
+    path: "/{model_id}";
+    docs: {
+        params: (),
+        tag: "threat_model",
+        responses: {
+            (status = 204, description = "Threat model and its history deleted"),
+            (status = 401, description = "Unauthorized"),
+            (status = 404, description = "Threat model not found"),
+            (status = 500, description = "Internal server error"),
+        },
+        security: [
+            ("bearer_token" = []),
+            ("cookie_session" = []),
+        ]
+    }
+    params: {
+        model_id: web::Path<String>,
+        session: UserSession,
+        state: web::Data<AppState>,
+    };
+    {
+        delete_threat_model(&state.db, model_id.into_inner(), session.user_id).await?;
+        Ok(HttpResponse::NoContent().finish())
+    }
+}
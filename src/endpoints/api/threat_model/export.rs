@@ -0,0 +1,46 @@
+use actix_web::{web, HttpRequest};
+use helper_macros::generate_endpoint;
+
+use crate::{
+    models::UserSession,
+    services::{
+        export::{export_response, ExportDocument, ExportFormat},
+        threat_model::get::{get_threat_model, get_threat_model_details},
+    },
+    state::AppState,
+};
+
+generate_endpoint! {
+    fn export_threat_model_endpoint;
+    method: get;
+    path: "/{model_id}/export";
+    docs: {
+        params: (),
+        tag: "threat_model",
+        responses: {
+            (status = 200, description = "The threat model as `text/markdown`, `text/html`, `text/csv` or `application/stix+json;version=2.1` depending on the `Accept` header"),
+            (status = 401, description = "Unauthorized"),
+            (status = 404, description = "Threat model not found"),
+            (status = 406, description = "None of the accepted content types is supported"),
+            (status = 500, description = "Internal server error"),
+        },
+        security: [
+            ("bearer_token" = []),
+            ("cookie_session" = []),
+        ]
+    }
+    params: {
+        req: HttpRequest,
+        model_id: web::Path<String>,
+        session: UserSession,
+        state: web::Data<AppState>,
+    };
+    {
+        let format = ExportFormat::negotiate(&req)?;
+        let model_id = model_id.into_inner();
+        let model = get_threat_model(&state.db, model_id.clone(), session.user_id).await?;
+        let details = get_threat_model_details(&state.db, model).await?;
+        let document = ExportDocument::from_threat_model(details);
+        Ok(export_response(&document, format, &format!("threat-model-{model_id}")))
+    }
+}
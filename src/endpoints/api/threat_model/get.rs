@@ -0,0 +1,66 @@
+use actix_web::{web, HttpResponse};
+use helper_macros::generate_endpoint;
+
+use crate::{
+    dto::threat_model::ThreatModelDetails,
+    models::{ThreatModels, UserSession},
+    services::threat_model::get::{get_threat_model, get_threat_model_details, list_threat_models},
+    state::AppState,
+};
+
+generate_endpoint! {
+    fn get_threat_model_endpoint;
+    method: get;
+    path: "/{model_id}";
+    docs: {
+        params: (),
+        tag: "threat_model",
+        responses: {
+            (status = 200, response = ThreatModelDetails),
+            (status = 401, description = "Unauthorized"),
+            (status = 404, description = "Threat model not found"),
+            (status = 500, description = "Internal server error"),
+        },
+        security: [
+            ("bearer_token" = []),
+            ("cookie_session" = []),
+        ]
+    }
+    params: {
+        model_id: web::Path<String>,
+        session: UserSession,
+        state: web::Data<AppState>,
+    };
+    {
+        let model = get_threat_model(&state.db, model_id.into_inner(), session.user_id).await?;
+        let details = get_threat_model_details(&state.db, model).await?;
+        Ok(HttpResponse::Ok().json(details))
+    }
+}
+
+generate_endpoint! {
+    fn list_threat_models_endpoint;
+    method: get;
+    path: "";
+    docs: {
+        params: (),
+        tag: "threat_model",
+        responses: {
+            (status = 200, response = ThreatModels),
+            (status = 401, description = "Unauthorized"),
+            (status = 500, description = "Internal server error"),
+        },
+        security: [
+            ("bearer_token" = []),
+            ("cookie_session" = []),
+        ]
+    }
+    params: {
+        session: UserSession,
+        state: web::Data<AppState>,
+    };
+    {
+        let models = list_threat_models(&state.db, session.user_id).await?;
+        Ok(HttpResponse::Ok().json(models))
+    }
+}
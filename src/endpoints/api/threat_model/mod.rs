@@ -0,0 +1,73 @@
+pub mod create;
+pub mod delete;
+pub mod export;
+pub mod get;
+pub mod update;
+pub mod versions;
+
+use crate::dto::threat_model::{CoverageSummary, ThreatModelDetails, ThreatModelRequest};
+use crate::models::{
+    ItemStatus, MITREEntry, MitigationItem, ThreatItem, ThreatModel, ThreatModelVersion,
+    ThreatModelVersions, ThreatModels,
+};
+use actix_web::{dev::HttpServiceFactory, web};
+use utoipa::OpenApi;
+
+use create::*;
+use delete::*;
+use export::*;
+use get::*;
+use update::*;
+use versions::*;
+
+/// Saved threat models.
+/// Operations:
+/// * Create, list, get, replace and delete threat models
+/// * List and get previous versions of a threat model
+/// * Export a threat model as Markdown, HTML, CSV or STIX
+pub fn threat_model_service() -> impl HttpServiceFactory {
+    web::scope("/threat-models")
+        .service(create_threat_model_endpoint)
+        .service(list_threat_models_endpoint)
+        .service(get_threat_model_endpoint)
+        .service(update_threat_model_endpoint)
+        .service(delete_threat_model_endpoint)
+        .service(list_threat_model_versions_endpoint)
+        .service(get_threat_model_version_endpoint)
+        .service(export_threat_model_endpoint)
+}
+
+#[derive(OpenApi)]
+#[openapi(
+    paths(
+        create_threat_model_endpoint,
+        list_threat_models_endpoint,
+        get_threat_model_endpoint,
+        update_threat_model_endpoint,
+        delete_threat_model_endpoint,
+        list_threat_model_versions_endpoint,
+        get_threat_model_version_endpoint,
+        export_threat_model_endpoint
+    ),
+    components(
+        schemas(
+            ThreatModel,
+            ThreatModelRequest,
+            ThreatModelDetails,
+            ThreatModelVersion,
+            ThreatItem,
+            MitigationItem,
+            ItemStatus,
+            CoverageSummary,
+            MITREEntry
+        ),
+        responses(
+            ThreatModel,
+            ThreatModels,
+            ThreatModelDetails,
+            ThreatModelVersion,
+            ThreatModelVersions
+        )
+    )
+)]
+pub(crate) struct ThreatModelApi;
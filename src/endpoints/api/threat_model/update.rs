@@ -0,0 +1,40 @@
+use actix_web::{web, HttpResponse};
+use helper_macros::generate_endpoint;
+
+use crate::{
+    dto::threat_model::ThreatModelRequest,
+    models::{ThreatModel, UserSession},
+    services::threat_model::update::update_threat_model,
+    state::AppState,
+};
+
+generate_endpoint! {
+    fn update_threat_model_endpoint;
+    method: put;
+    path: "/{model_id}";
+    docs: {
+        params: (),
+        tag: "threat_model",
+        responses: {
+            (status = 200, response = ThreatModel),
+            (status = 400, description = "Unknown MITRE IDs, files or invalid content"),
+            (status = 401, description = "Unauthorized"),
+            (status = 404, description = "Threat model not found"),
+            (status = 500, description = "Internal server error"),
+        },
+        security: [
+            ("bearer_token" = []),
+            ("cookie_session" = []),
+        ]
+    }
+    params: {
+        model_id: web::Path<String>,
+        body: web::Json<ThreatModelRequest>,
+        session: UserSession,
+        state: web::Data<AppState>,
+    };
+    {
+        let model = update_threat_model(&state.db, model_id.into_inner(), session.user_id, body.into_inner()).await?;
+        Ok(HttpResponse::Ok().json(model))
+    }
+}
@@ -0,0 +1,67 @@
+use actix_web::{web, HttpResponse};
+use helper_macros::generate_endpoint;
+
+use crate::{
+    models::{ThreatModelVersion, ThreatModelVersions, UserSession},
+    services::threat_model::versions::{get_threat_model_version, list_threat_model_versions},
+    state::AppState,
+};
+
+generate_endpoint! {
+    fn list_threat_model_versions_endpoint;
+    method: get;
+    path: "/{model_id}/versions";
+    docs: {
+        params: (),
+        tag: "threat_model",
+        responses: {
+            (status = 200, response = ThreatModelVersions),
+            (status = 401, description = "Unauthorized"),
+            (status = 404, description = "Threat model not found"),
+            (status = 500, description = "Internal server error"),
+        },
+        security: [
+            ("bearer_token" = []),
+            ("cookie_session" = []),
+        ]
+    }
+    params: {
+        model_id: web::Path<String>,
+        session: UserSession,
+        state: web::Data<AppState>,
+    };
+    {
+        let versions = list_threat_model_versions(&state.db, model_id.into_inner(), session.user_id).await?;
+        Ok(HttpResponse::Ok().json(versions))
+    }
+}
+
+generate_endpoint! {
+    fn get_threat_model_version_endpoint;
+    method: get;
+    path: "/{model_id}/versions/{version}";
+    docs: {
+        params: (),
+        tag: "threat_model",
+        responses: {
+            (status = 200, response = ThreatModelVersion),
+            (status = 401, description = "Unauthorized"),
+            (status = 404, description = "Threat model or version not found"),
+            (status = 500, description = "Internal server error"),
+        },
+        security: [
+            ("bearer_token" = []),
+            ("cookie_session" = []),
+        ]
+    }
+    params: {
+        path: web::Path<(String, u64)>,
+        session: UserSession,
+        state: web::Data<AppState>,
+    };
+    {
+        let (model_id, version) = path.into_inner();
+        let version = get_threat_model_version(&state.db, model_id, version, session.user_id).await?;
+        Ok(HttpResponse::Ok().json(version))
+    }
+}
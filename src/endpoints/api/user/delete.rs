@@ -3,10 +3,12 @@ use crate::extractors::AuthenticatedToken;
 use crate::extractors::IntoSession;
 use crate::generate_endpoint;
 use crate::models::UserInfo;
+use crate::services::files::delete::{purge_deleted_contents, PURGE_BATCH_SIZE};
 use crate::services::user::delete::delete_user;
 use crate::services::user::get::get_user_by_token;
 use crate::state::AppState;
 use actix_web::{web, HttpResponse};
+use tracing::warn;
 use utoipa::ToSchema;
 
 generate_endpoint! {
@@ -19,6 +21,7 @@ generate_endpoint! {
             (status = 200, description = "User deleted successfully"),
             (status = 401, description = "Not logged in"),
             (status = 404, description = "User not found or invalid credentials"),
+            (status = 409, description = "The user is the last owner of a team with other members"),
             (status = 500, description = "An error occurred when deleting user in the database"),
         },
         security: [
@@ -37,6 +40,12 @@ generate_endpoint! {
             return Err(ServerResponseError::NotFound);
         };
         delete_user(&state.db , user_id).await?;
+
+        // Whatever cannot be removed now is left to the reconciler
+        if let Err(err) = purge_deleted_contents(&state.db, state.files.store.as_ref(), PURGE_BATCH_SIZE).await {
+            warn!("Failed to remove the files of a deleted user: {}", err);
+        }
+
         Ok(HttpResponse::Ok().finish())
     }
 }
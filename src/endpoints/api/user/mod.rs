@@ -1,10 +1,13 @@
 pub mod delete;
 pub mod get;
 pub mod update;
+pub mod usage;
 
 use crate::endpoints::user::delete::*;
 use crate::endpoints::user::get::*;
 use crate::endpoints::user::update::*;
+use crate::endpoints::user::usage::*;
+use crate::dto::usage::{DailyUsage, QuotaStatus, UsageTotals, UserUsageResponse};
 use crate::extractors::Authenticated;
 use crate::models::user_info::UserInfo;
 use crate::models::user_info::UserInfoExampleResponses;
@@ -19,10 +22,10 @@ use utoipa::OpenApi;
 
 #[derive(OpenApi)]
 #[openapi(
-    paths(get_user_by, update_user, delete_user_endpoint),
+    paths(get_user_by, update_user, delete_user_endpoint, get_user_usage_endpoint),
     components(
-        schemas(Role, UserInfo, GetUserBy),
-        responses(UserInfoExampleResponses)
+        schemas(Role, UserInfo, GetUserBy, UserUsageResponse, DailyUsage, QuotaStatus, UsageTotals),
+        responses(UserInfoExampleResponses, UserUsageResponse)
     )
 )]
 pub(crate) struct UserApi;
@@ -33,4 +36,5 @@ pub fn user_service() -> impl actix_web::dev::HttpServiceFactory {
         .service(get_user_by)
         .service(update_user)
         .service(delete_user_endpoint)
+        .service(get_user_usage_endpoint)
 }
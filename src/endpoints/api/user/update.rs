@@ -17,6 +17,7 @@ generate_endpoint! {
         tag: "user",
         responses: {
             (status = 200, description = "User updated successfully"),
+            (status = 400, description = "Unknown LLM backend or model not allowed"),
             (status = 401, description = "Not logged in"),
             (status = 404, description = "User not found or invalid credentials"),
             (status = 500, description = "An error occurred when updating user information in the database"),
@@ -37,9 +38,8 @@ generate_endpoint! {
         let Some(user_id) = user.id else {
             return Err(ServerResponseError::NotFound);
         };
-        let update_data = data.into_inner();
 
-        update_user_data(&state.db, user_id, update_data).await?;
+        update_user_data(&state.db, &state.llm, user_id, data.into_inner()).await?;
         Ok(HttpResponse::Ok().finish())
     }
 }
@@ -0,0 +1,35 @@
+use crate::dto::usage::{UsageQuery, UserUsageResponse};
+use crate::models::UserSession;
+use crate::services::llm::usage::get_user_usage;
+use crate::state::AppState;
+use actix_web::{web, HttpResponse};
+use helper_macros::generate_endpoint;
+
+generate_endpoint! {
+    fn get_user_usage_endpoint;
+    method: get;
+    path: "/usage";
+    docs: {
+        params: (UsageQuery),
+        tag: "user",
+        responses: {
+            (status = 200, response = UserUsageResponse),
+            (status = 401, description = "Not logged in"),
+            (status = 500, description = "An error occurred when reading usage from the database"),
+        },
+        security: [
+            ("bearer_token" = []),
+            ("cookie_session" = []),
+        ]
+    }
+    params: {
+        query: web::Query<UsageQuery>,
+        session: UserSession,
+        state: web::Data<AppState>,
+    };
+    {
+        let query = query.into_inner();
+        let usage = get_user_usage(&state.db, state.llm.quota(), session.user_id, query.from, query.to).await?;
+        Ok(HttpResponse::Ok().json(usage))
+    }
+}
@@ -1,6 +1,56 @@
-use crate::services::health::check_health;
+use crate::dto::health::{HealthReport, HealthStatus, HealthSummary};
+use crate::services::health::{liveness, readiness};
+use crate::state::AppState;
+use actix_web::{web, HttpResponse};
 use helper_macros::generate_endpoint;
 
+/// 503 if a dependency is down, so that the server is taken out of rotation. Only the status is
+/// reported, the details are at `GET /api/v1/admin/health`.
+fn report_response(report: HealthReport) -> HttpResponse {
+    let summary = HealthSummary {
+        status: report.status,
+    };
+
+    match report.status {
+        HealthStatus::Down => HttpResponse::ServiceUnavailable().json(summary),
+        _ => HttpResponse::Ok().json(summary),
+    }
+}
+
+generate_endpoint! {
+    fn live;
+    method: get;
+    path: "/health/live";
+    docs: {
+        tag: "health",
+        responses: {
+            (status = 200, response = HealthSummary),
+        }
+    }
+    {
+        Ok(HttpResponse::Ok().json(liveness()))
+    }
+}
+
+generate_endpoint! {
+    fn ready;
+    method: get;
+    path: "/health/ready";
+    docs: {
+        tag: "health",
+        responses: {
+            (status = 200, response = HealthSummary),
+            (status = 503, response = HealthSummary),
+        }
+    }
+    params: {
+        state: web::Data<AppState>,
+    };
+    {
+        Ok(report_response(readiness(&state).await))
+    }
+}
+
 generate_endpoint! {
     fn health;
     method: get;
@@ -8,11 +58,14 @@ generate_endpoint! {
     docs: {
         tag: "health",
         responses: {
-            (status = 200, description = "Everything works just fine!"),
-            (status = 424, description = "Database not responding"),
+            (status = 200, response = HealthSummary),
+            (status = 503, response = HealthSummary),
         }
     }
+    params: {
+        state: web::Data<AppState>,
+    };
     {
-        check_health().await
+        Ok(report_response(readiness(&state).await))
     }
 }
@@ -0,0 +1,59 @@
+use crate::error::ServerResponseError;
+use crate::extractors::{AdminSession, Authenticated};
+use crate::metrics::{MetricsAccess, METRICS};
+use crate::models::UserSession;
+use crate::state::AppState;
+use actix_web::http::StatusCode;
+use actix_web::{web, FromRequest, HttpRequest, HttpResponse};
+use helper_macros::generate_endpoint;
+use tracing::warn;
+
+generate_endpoint! {
+    fn metrics;
+    method: get;
+    path: "/metrics";
+    docs: {
+        tag: "health",
+        responses: {
+            (status = 200, description = "Metrics in the Prometheus text format"),
+            (status = 401, description = "Not logged in, if `METRICS_ACCESS` requires it"),
+            (status = 403, description = "Not an admin, if `METRICS_ACCESS=admin`"),
+        }
+    }
+    params: {
+        req: HttpRequest,
+        state: web::Data<AppState>,
+    };
+    {
+        match state.config.metrics_access {
+            MetricsAccess::Public => {}
+            MetricsAccess::Authenticated => {
+                Authenticated::extract(&req)
+                    .await
+                    .map_err(|_| ServerResponseError::Unauthorized)?;
+            }
+            MetricsAccess::Admin => {
+                AdminSession::extract(&req).await.map_err(|err| {
+                    match err.as_response_error().status_code() {
+                        StatusCode::FORBIDDEN => ServerResponseError::Forbidden,
+                        _ => ServerResponseError::Unauthorized,
+                    }
+                })?;
+            }
+        }
+
+        // The last value is kept if counting fails so that the scrape still succeeds
+        match UserSession::count_active().await {
+            Ok(sessions) => METRICS.active_sessions.set(sessions as i64),
+            Err(err) => warn!("Failed to count active sessions: {}", err),
+        }
+
+        let body = METRICS
+            .render()
+            .map_err(|err| ServerResponseError::InternalError(err.to_string()))?;
+
+        Ok(HttpResponse::Ok()
+            .content_type("text/plain; version=0.0.4; charset=utf-8")
+            .body(body))
+    }
+}
@@ -10,12 +10,14 @@ use utoipa::OpenApi;
 
 pub(crate) mod api;
 pub(crate) mod health;
+pub(crate) mod metrics;
 mod not_found;
 mod test;
 
 use crate::middlewares::logger::LoggingMiddleware;
 pub(crate) use api::*;
 pub(crate) use health::*;
+pub(crate) use metrics::*;
 
 pub(crate) fn index_scope(
     limiter: RateLimiter<
@@ -27,6 +29,9 @@ pub(crate) fn index_scope(
 ) -> impl actix_web::dev::HttpServiceFactory {
     web::scope("")
         .service(health::health)
+        .service(health::live)
+        .service(health::ready)
+        .service(metrics::metrics)
         .service(api(limiter, logger))
         .default_service(web::to(not_found::not_found))
 }
@@ -16,3 +16,88 @@ actix_test!(
         Ok(())
     }
 );
+
+actix_test!(
+    fn metrics_are_exposed() {
+        let app = crate::server::test::init_test_app!();
+
+        let req = test::TestRequest::get().uri(INVALID_ENDPOINT).to_request();
+        test::call_service(&app, req).await;
+
+        let req = test::TestRequest::get().uri("/metrics").to_request();
+        let body = test::call_and_read_body(&app, req).await;
+        let body = String::from_utf8_lossy(&body);
+
+        assert!(body.contains("http_requests_total{"));
+        assert!(body.contains(r#"status="404""#));
+        assert!(body.contains("audit_log_queue_depth"));
+
+        Ok(())
+    }
+);
+
+actix_test!(
+    fn request_id_is_echoed() {
+        let app = crate::server::test::init_test_app!();
+
+        let req = test::TestRequest::get()
+            .uri(INVALID_ENDPOINT)
+            .insert_header(("X-Request-Id", "client-request-1"))
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(
+            resp.headers().get("x-request-id").unwrap(),
+            "client-request-1"
+        );
+
+        // IDs that could forge log lines are replaced
+        let req = test::TestRequest::get()
+            .uri(INVALID_ENDPOINT)
+            .insert_header(("X-Request-Id", "a b"))
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        let request_id = resp.headers().get("x-request-id").unwrap();
+        assert_eq!(request_id.len(), 32);
+
+        Ok(())
+    }
+);
+
+actix_test!(
+    fn errors_are_problem_details() {
+        let app = crate::server::test::init_test_app!();
+
+        let req = test::TestRequest::get()
+            .uri("/api/v1/teams")
+            .insert_header(("X-Request-Id", "client-request-2"))
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), actix_web::http::StatusCode::UNAUTHORIZED);
+        assert_eq!(
+            resp.headers().get("content-type").unwrap(),
+            crate::dto::problem::PROBLEM_JSON
+        );
+
+        let body: serde_json::Value = test::read_body_json(resp).await;
+        assert_eq!(body["status"], 401);
+        assert_eq!(body["code"], "unauthorized");
+        assert_eq!(body["request_id"], "client-request-2");
+
+        Ok(())
+    }
+);
+
+actix_test!(
+    fn liveness_is_reported() {
+        let app = crate::server::test::init_test_app!();
+
+        let req = test::TestRequest::get().uri("/health/live").to_request();
+        let resp = test::call_service(&app, req).await;
+        assert!(resp.status().is_success());
+
+        let body: serde_json::Value = test::read_body_json(resp).await;
+        assert_eq!(body["status"], "up");
+
+        Ok(())
+    }
+);
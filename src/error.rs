@@ -1,8 +1,15 @@
 #![allow(dead_code)]
 
+use crate::auth::oauth::error::OauthError;
+use crate::dto::problem::{FieldError, ProblemDetails};
+use crate::services::llm::LlmError;
 use actix_web::http::StatusCode;
 use actix_web::{HttpResponse, ResponseError};
 use actix_web_httpauth::headers::www_authenticate::bearer;
+use tracing::error;
+
+/// Detail of errors whose cause is only logged, outside of debug builds
+const INTERNAL_DETAIL: &str = "An internal error occurred, see the server log for the request ID";
 
 #[derive(Debug, thiserror::Error)]
 pub enum ServerResponseError {
@@ -13,7 +20,11 @@ pub enum ServerResponseError {
     #[error("Error constructing query: {0}")]
     QueryError(#[from] surrealdb_abstraction::error::Error),
     #[error("OAuth error: {0}")]
-    OAuthError(#[from] crate::auth::oauth::error::OauthError),
+    OAuthError(#[from] OauthError),
+    #[error(transparent)]
+    LlmError(#[from] LlmError),
+    #[error(transparent)]
+    UploadPolicyError(#[from] crate::services::files::policy::UploadPolicyError),
     #[error("Serialization error: {0}")]
     FormSerializationError(#[from] serde_urlencoded::ser::Error),
     #[error("Internal error: {0}")]
@@ -22,6 +33,8 @@ pub enum ServerResponseError {
     NotFound,
     #[error("Bad request: {0}")]
     BadRequest(String),
+    #[error("Invalid request: {}", describe_fields(.0))]
+    Validation(Vec<FieldError>),
     #[error("Not implemented")]
     NotImplemented,
     #[error("Not implemented: {0}")]
@@ -30,6 +43,16 @@ pub enum ServerResponseError {
     Unauthorized,
     #[error("Unauthorized: {0}")]
     UnauthorizedWithMessage(String),
+    #[error("Forbidden")]
+    Forbidden,
+    #[error("Forbidden: {0}")]
+    ForbiddenWithMessage(String),
+    #[error("Conflict: {0}")]
+    Conflict(String),
+    #[error("Payload too large: {0}")]
+    PayloadTooLarge(String),
+    #[error("Gone")]
+    Gone,
     #[error("Content type not accepted")]
     NotAcceptable,
     #[error(transparent)]
@@ -42,21 +65,119 @@ pub enum ServerResponseError {
     GenericError(#[from] anyhow::Error),
 }
 
+fn describe_fields(errors: &[FieldError]) -> String {
+    errors
+        .iter()
+        .map(|err| format!("{} {}", err.field, err.message))
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+impl ServerResponseError {
+    /// A [`Validation`](ServerResponseError::Validation) error of a single field
+    pub fn invalid_field(field: impl Into<String>, message: impl Into<String>) -> Self {
+        ServerResponseError::Validation(vec![FieldError::new(field, message)])
+    }
+
+    /// Stable machine readable name of the error, the `code` of its problem details
+    pub fn code(&self) -> &'static str {
+        match self {
+            ServerResponseError::AuthorizationError(_)
+            | ServerResponseError::Unauthorized
+            | ServerResponseError::UnauthorizedWithMessage(_)
+            | ServerResponseError::GetIdentityError(_) => "unauthorized",
+            ServerResponseError::DatabaseError(_) | ServerResponseError::QueryError(_) => {
+                "database_error"
+            }
+            ServerResponseError::OAuthError(_) => "oauth_error",
+            ServerResponseError::LlmError(err) => err.kind(),
+            ServerResponseError::UploadPolicyError(err) => err.kind(),
+            ServerResponseError::FormSerializationError(_)
+            | ServerResponseError::InternalError(_)
+            | ServerResponseError::GenericError(_) => "internal_error",
+            ServerResponseError::NotFound => "not_found",
+            ServerResponseError::BadRequest(_) => "bad_request",
+            ServerResponseError::Validation(_) => "validation_failed",
+            ServerResponseError::NotImplemented
+            | ServerResponseError::NotImplementedWithMessage(_) => "not_implemented",
+            ServerResponseError::Forbidden | ServerResponseError::ForbiddenWithMessage(_) => {
+                "forbidden"
+            }
+            ServerResponseError::Conflict(_) => "conflict",
+            ServerResponseError::PayloadTooLarge(_) => "payload_too_large",
+            ServerResponseError::Gone => "gone",
+            ServerResponseError::NotAcceptable => "not_acceptable",
+            ServerResponseError::FailedDependency
+            | ServerResponseError::FailedDependencyWithMessage(_) => "failed_dependency",
+        }
+    }
+
+    /// Whether the message may reveal internals like queries or configuration and is only sent
+    /// in debug builds
+    fn is_internal(&self) -> bool {
+        match self {
+            ServerResponseError::DatabaseError(_)
+            | ServerResponseError::QueryError(_)
+            | ServerResponseError::FormSerializationError(_)
+            | ServerResponseError::InternalError(_)
+            | ServerResponseError::GenericError(_) => true,
+            ServerResponseError::OAuthError(_) => self.status_code().is_server_error(),
+            ServerResponseError::LlmError(err) => matches!(err, LlmError::Config(_)),
+            _ => false,
+        }
+    }
+
+    pub fn problem_details(&self) -> ProblemDetails {
+        let detail = if self.is_internal() && !cfg!(debug_assertions) {
+            INTERNAL_DETAIL.to_string()
+        } else {
+            self.to_string()
+        };
+        let problem = ProblemDetails::new(self.status_code(), self.code(), detail);
+
+        match self {
+            ServerResponseError::LlmError(err) => err.problem_details(problem),
+            ServerResponseError::UploadPolicyError(err) => err.problem_details(problem),
+            ServerResponseError::Validation(errors) => problem.with_errors(errors.clone()),
+            _ => problem,
+        }
+    }
+}
+
 impl ResponseError for ServerResponseError {
     fn status_code(&self) -> StatusCode {
         match self {
+            ServerResponseError::AuthorizationError(_) => StatusCode::UNAUTHORIZED,
+            ServerResponseError::GetIdentityError(_) => StatusCode::UNAUTHORIZED,
+            ServerResponseError::OAuthError(OauthError::FetchUserInfoError(_)) => {
+                StatusCode::BAD_GATEWAY
+            }
             ServerResponseError::NotFound => StatusCode::NOT_FOUND,
             ServerResponseError::BadRequest(_) => StatusCode::BAD_REQUEST,
+            ServerResponseError::Validation(_) => StatusCode::BAD_REQUEST,
             ServerResponseError::Unauthorized => StatusCode::UNAUTHORIZED,
             ServerResponseError::UnauthorizedWithMessage(_) => StatusCode::UNAUTHORIZED,
+            ServerResponseError::Forbidden => StatusCode::FORBIDDEN,
+            ServerResponseError::ForbiddenWithMessage(_) => StatusCode::FORBIDDEN,
             ServerResponseError::NotImplemented => StatusCode::NOT_IMPLEMENTED,
             ServerResponseError::NotImplementedWithMessage(_) => StatusCode::NOT_IMPLEMENTED,
+            ServerResponseError::Conflict(_) => StatusCode::CONFLICT,
+            ServerResponseError::PayloadTooLarge(_) => StatusCode::PAYLOAD_TOO_LARGE,
+            ServerResponseError::Gone => StatusCode::GONE,
             ServerResponseError::NotAcceptable => StatusCode::NOT_ACCEPTABLE,
+            ServerResponseError::FailedDependency => StatusCode::FAILED_DEPENDENCY,
+            ServerResponseError::FailedDependencyWithMessage(_) => StatusCode::FAILED_DEPENDENCY,
+            ServerResponseError::LlmError(err) => err.status_code(),
+            ServerResponseError::UploadPolicyError(err) => err.status_code(),
             _ => StatusCode::INTERNAL_SERVER_ERROR,
         }
     }
 
     fn error_response(&self) -> HttpResponse {
-        HttpResponse::build(self.status_code()).body(self.to_string())
+        if self.status_code().is_server_error() {
+            error!("{self}");
+        }
+
+        self.problem_details().response()
     }
 }
@@ -1,12 +1,17 @@
 use crate::error::ServerResponseError;
+use crate::metrics::ObserveQuery;
+use crate::middlewares::logger::AuthenticatedUser;
 use crate::models::session::UserSession;
+use crate::models::user_info::Role;
+use crate::server::db::INTERNAL_DB;
 use crate::services::user::get::get_user_by_token;
 use actix_identity::Identity;
-use actix_web::{dev::Payload, error::ErrorUnauthorized, Either, FromRequest, HttpRequest, Result};
+use actix_web::{dev::Payload, Either, FromRequest, HttpMessage, HttpRequest, Result};
 use actix_web_httpauth::extractors::bearer::BearerAuth;
 use std::future::Future;
 use std::pin::Pin;
 use std::sync::Arc;
+use surrealdb::sql::Thing;
 use surrealdb::Surreal;
 
 /// This Extractor is used to get the token from the request, this does not check if the token is valid.
@@ -18,6 +23,9 @@ pub(crate) struct Authenticated;
 /// This Extractor is used to get the token from the request and check if the token is valid.
 pub(crate) struct AuthenticatedToken(pub(crate) String);
 
+/// This Extractor makes sure that the user has a valid session and the `Admin` or `Owner` role
+pub(crate) struct AdminSession(pub(crate) UserSession);
+
 pub(crate) trait IntoSession {
     fn get_token(&self) -> String;
 
@@ -79,7 +87,7 @@ impl FromRequest for AuthenticatedToken {
 
                     Ok(AuthenticatedToken(token))
                 }
-                Err(_) => Err(ErrorUnauthorized("Unauthorized")),
+                Err(_) => Err(ServerResponseError::Unauthorized.into()),
             }
         })
     }
@@ -96,7 +104,7 @@ impl FromRequest for Authenticated {
             let session = session.await;
             match session {
                 Ok(_) => Ok(Authenticated),
-                Err(_) => Err(ErrorUnauthorized("Unauthorized")),
+                Err(_) => Err(ServerResponseError::Unauthorized.into()),
             }
         })
     }
@@ -108,25 +116,57 @@ impl FromRequest for UserSession {
 
     fn from_request(req: &HttpRequest, payload: &mut Payload) -> Self::Future {
         let token = Token::from_request(req, payload);
+        let req = req.clone();
 
-        Box::pin(async {
+        Box::pin(async move {
             let token = token.await;
 
             match token {
                 Ok(token) => match token.get_session().await {
-                    Some(session) => Ok(session),
-                    None => Err(ErrorUnauthorized("Unauthorized")),
+                    Some(session) => {
+                        req.extensions_mut()
+                            .insert(AuthenticatedUser(session.user_id.clone()));
+                        Ok(session)
+                    }
+                    None => Err(ServerResponseError::Unauthorized.into()),
                 },
-                Err(_err) => Err(ErrorUnauthorized("Unauthorized")),
+                Err(_err) => Err(ServerResponseError::Unauthorized.into()),
+            }
+        })
+    }
+}
+
+impl FromRequest for AdminSession {
+    type Error = actix_web::Error;
+    type Future = Pin<Box<dyn Future<Output = Result<Self, Self::Error>>>>;
+
+    fn from_request(req: &HttpRequest, payload: &mut Payload) -> Self::Future {
+        let session = UserSession::from_request(req, payload);
+
+        Box::pin(async move {
+            let session = session.await?;
+
+            let role: Option<Role> = INTERNAL_DB
+                .query("SELECT VALUE role FROM ONLY $USER;")
+                .bind(("USER", session.user_id.clone()))
+                .observed()
+                .await
+                .and_then(|mut res| res.take(0))
+                .map_err(|_| ServerResponseError::Unauthorized)?;
+
+            match role {
+                Some(Role::Admin | Role::Owner) => Ok(AdminSession(session)),
+                _ => Err(ServerResponseError::Forbidden.into()),
             }
         })
     }
 }
 
-pub async fn token_from_request<T>(
+/// Resolves the user holding the bearer token of `req` and returns their ID
+pub async fn user_from_request<T>(
     db: &Arc<Surreal<T>>,
     req: &HttpRequest,
-) -> Result<String, ServerResponseError>
+) -> Result<Thing, ServerResponseError>
 where
     T: surrealdb::Connection,
 {
@@ -159,8 +199,11 @@ where
             "No bearer token in authorization header".to_string(),
         ));
     };
-    if get_user_by_token(db, bearer_token).await.is_err() {
+    let Ok(user) = get_user_by_token(db, bearer_token).await else {
         return Err(ServerResponseError::Unauthorized);
-    }
-    Ok(bearer_token.to_string())
+    };
+    let Some(user_id) = user.id else {
+        return Err(ServerResponseError::Unauthorized);
+    };
+    Ok(Thing::from((user_id.tb.clone(), user_id.id.clone())))
 }
@@ -1,11 +1,52 @@
 use crate::server_error::ServerError;
+use opentelemetry::trace::TracerProvider as _;
+use opentelemetry::{global, KeyValue};
+use opentelemetry_otlp::WithExportConfig;
+use opentelemetry_sdk::propagation::TraceContextPropagator;
+use opentelemetry_sdk::trace::{Config, TracerProvider};
+use opentelemetry_sdk::{runtime, Resource};
 use tracing_subscriber::fmt::format::FmtSpan;
 use tracing_subscriber::fmt::Layer as FmtLayer;
 use tracing_subscriber::layer::SubscriberExt;
 use tracing_subscriber::{filter::LevelFilter, EnvFilter, Layer, Registry};
 
+/// Service name reported to the collector unless `OTEL_SERVICE_NAME` is set
+const DEFAULT_SERVICE_NAME: &str = "threatmapper-backend";
+
+/// Builds the OTLP exporter if `OTEL_EXPORTER_OTLP_ENDPOINT` is set, spans are sent over gRPC
+/// in batches
+fn otlp_tracer_provider() -> Result<Option<TracerProvider>, ServerError> {
+    let Some(endpoint) = std::env::var("OTEL_EXPORTER_OTLP_ENDPOINT")
+        .ok()
+        .filter(|endpoint| !endpoint.is_empty())
+    else {
+        return Ok(None);
+    };
+    let service_name = std::env::var("OTEL_SERVICE_NAME")
+        .ok()
+        .filter(|name| !name.is_empty())
+        .unwrap_or_else(|| DEFAULT_SERVICE_NAME.to_string());
+
+    let provider = opentelemetry_otlp::new_pipeline()
+        .tracing()
+        .with_exporter(
+            opentelemetry_otlp::new_exporter()
+                .tonic()
+                .with_endpoint(endpoint),
+        )
+        .with_trace_config(
+            Config::default()
+                .with_resource(Resource::new([KeyValue::new("service.name", service_name)])),
+        )
+        .install_batch(runtime::TokioCurrentThread)
+        .map_err(|err| ServerError::Error(format!("Failed to set up the OTLP exporter: {err}")))?;
+
+    Ok(Some(provider))
+}
+
 #[inline]
-/// Initialize tracing with default settings
+/// Initialize tracing with default settings, spans are exported with OTLP if
+/// `OTEL_EXPORTER_OTLP_ENDPOINT` is set
 pub fn init_tracing() -> Result<(), ServerError> {
     let filter = EnvFilter::try_from_default_env().unwrap_or_else(|_| {
         #[cfg(not(debug_assertions))]
@@ -27,9 +68,25 @@ pub fn init_tracing() -> Result<(), ServerError> {
         .compact()
         .with_filter(filter);
 
-    let subscriber = Registry::default().with(def_layer);
+    global::set_text_map_propagator(TraceContextPropagator::new());
+
+    let otel_layer = otlp_tracer_provider()?.map(|provider| {
+        let tracer = provider.tracer("backend");
+        global::set_tracer_provider(provider);
+
+        tracing_opentelemetry::layer()
+            .with_tracer(tracer)
+            .with_filter(LevelFilter::INFO)
+    });
+
+    let subscriber = Registry::default().with(def_layer).with(otel_layer);
 
     tracing::subscriber::set_global_default(subscriber)?;
 
     Ok(())
 }
+
+/// Exports the spans that are still buffered
+pub fn shutdown_tracing() {
+    global::shutdown_tracer_provider();
+}
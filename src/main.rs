@@ -1,11 +1,14 @@
 #![feature(async_closure)]
 #![feature(duration_constructors)]
 
-use crate::server::{server, setup};
+use crate::cli::Command;
+use crate::server::{background_logger, server, setup, shutdown_on_signal, stop_scheduler};
 use crate::server_error::ServerError;
+use crate::services::jobs::JOBS;
 use helper_macros::generate_endpoint;
 
 mod auth;
+mod cli;
 mod config;
 mod dto;
 mod endpoints;
@@ -15,6 +18,7 @@ mod init_env;
 
 #[cfg(not(test))]
 mod logging;
+mod metrics;
 mod middlewares;
 mod models;
 mod server;
@@ -26,15 +30,38 @@ mod utils;
 
 #[actix::main]
 async fn main() -> Result<(), ServerError> {
-    setup().await?;
+    let (command, args) =
+        Command::parse(std::env::args().skip(1).collect()).map_err(ServerError::Error)?;
+    if command != Command::Serve {
+        let result = command.run(args).await;
 
-    let port = tosic_utils::prelude::env!("PORT", "9999");
+        #[cfg(not(test))]
+        logging::shutdown_tracing();
 
-    server!()
+        return result;
+    }
+
+    let config = setup(args).await?;
+    let port = config.port;
+
+    let logger = background_logger(&config.logger);
+
+    let server = server!(logger.queue())
         .bind(format!("0.0.0.0:{port}"))?
         .bind(format!("[::1]:{port}"))?
-        .run()
-        .await?;
+        .shutdown_timeout(config.shutdown_timeout.as_secs())
+        .disable_signals()
+        .run();
+    shutdown_on_signal(server.handle());
+    server.await?;
+
+    // The server has stopped, running jobs get to finish and whatever is still queued gets written
+    stop_scheduler().await;
+    JOBS.shutdown(config.shutdown_timeout).await;
+    logger.shutdown().await;
+
+    #[cfg(not(test))]
+    logging::shutdown_tracing();
 
     Ok(())
 }
@@ -0,0 +1,267 @@
+//! Prometheus metrics of the running server, served in the text format at `/metrics`. Request
+//! counts and latencies are recorded by the
+//! [`MetricsMiddleware`](crate::middlewares::metrics::MetricsMiddleware), everything else where
+//! it happens. Database queries are timed by awaiting them through [`ObserveQuery::observed`].
+
+use crate::server::background_logger::LOGGER_STATS;
+use once_cell::sync::Lazy;
+use prometheus::{
+    Encoder, Histogram, HistogramOpts, HistogramVec, IntCounter, IntCounterVec, IntGauge, Opts,
+    Registry, TextEncoder,
+};
+use std::future::{Future, IntoFuture};
+use std::pin::Pin;
+use std::task::{ready, Context, Poll};
+use std::time::{Duration, Instant};
+
+/// Label of requests that matched no route, keeps unknown paths from creating new series
+pub const UNMATCHED_ROUTE: &str = "unmatched";
+
+/// Buckets of the request and query latency histograms, in seconds
+const LATENCY_BUCKETS: [f64; 12] = [
+    0.001, 0.0025, 0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 10.0,
+];
+
+/// Buckets of the LLM upstream latency histogram, in seconds
+const UPSTREAM_BUCKETS: [f64; 10] = [0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0, 30.0, 60.0, 120.0];
+
+/// Buckets of the job run duration histogram, in seconds
+const JOB_BUCKETS: [f64; 8] = [0.1, 1.0, 5.0, 30.0, 60.0, 300.0, 900.0, 3600.0];
+
+pub static METRICS: Lazy<Metrics> = Lazy::new(Metrics::new);
+
+pub struct Metrics {
+    registry: Registry,
+    /// `http_requests_total` by `method`, `route` and `status`
+    pub http_requests: IntCounterVec,
+    /// `http_request_duration_seconds` by `method`, `route` and `status`
+    pub http_request_duration: HistogramVec,
+    /// `http_rate_limited_total`, requests rejected by the rate limiter
+    pub rate_limited: IntCounter,
+    /// `surrealdb_query_duration_seconds`
+    pub db_query_duration: Histogram,
+    /// `surrealdb_query_errors_total`, queries that could not be executed
+    pub db_query_errors: IntCounter,
+    /// `llm_upstream_duration_seconds` until the backend answered, by `backend` and `outcome`
+    pub llm_upstream_duration: HistogramVec,
+    /// `llm_upstream_streamed_bytes_total`, body bytes streamed from LLM backends
+    pub llm_streamed_bytes: IntCounter,
+    /// `upload_bytes_total`, bytes of accepted uploads and upload chunks
+    pub upload_bytes: IntCounter,
+    /// `active_sessions`, unexpired sessions, updated on every scrape and by the
+    /// `purge_sessions` job
+    pub active_sessions: IntGauge,
+    /// `job_run_duration_seconds` of finished job runs, by `job` and `status`
+    pub job_run_duration: HistogramVec,
+    /// `audit_log_queue_depth`, entries waiting for the background logger
+    log_queue_depth: IntGauge,
+    /// `audit_log_dropped_entries`, entries the background logger lost since startup
+    log_dropped: IntGauge,
+}
+
+impl Metrics {
+    fn new() -> Self {
+        let registry = Registry::new();
+
+        let http_requests = IntCounterVec::new(
+            Opts::new("http_requests_total", "HTTP requests handled"),
+            &["method", "route", "status"],
+        )
+        .expect("valid metric");
+        let http_request_duration = HistogramVec::new(
+            HistogramOpts::new(
+                "http_request_duration_seconds",
+                "Time spent handling HTTP requests",
+            )
+            .buckets(LATENCY_BUCKETS.to_vec()),
+            &["method", "route", "status"],
+        )
+        .expect("valid metric");
+        let rate_limited = IntCounter::new(
+            "http_rate_limited_total",
+            "Requests rejected by the rate limiter",
+        )
+        .expect("valid metric");
+        let db_query_duration = Histogram::with_opts(
+            HistogramOpts::new(
+                "surrealdb_query_duration_seconds",
+                "Time until SurrealDB answered a query",
+            )
+            .buckets(LATENCY_BUCKETS.to_vec()),
+        )
+        .expect("valid metric");
+        let db_query_errors = IntCounter::new(
+            "surrealdb_query_errors_total",
+            "SurrealDB queries that could not be executed",
+        )
+        .expect("valid metric");
+        let llm_upstream_duration = HistogramVec::new(
+            HistogramOpts::new(
+                "llm_upstream_duration_seconds",
+                "Time until an LLM backend answered a chat request",
+            )
+            .buckets(UPSTREAM_BUCKETS.to_vec()),
+            &["backend", "outcome"],
+        )
+        .expect("valid metric");
+        let llm_streamed_bytes = IntCounter::new(
+            "llm_upstream_streamed_bytes_total",
+            "Response bytes streamed from LLM backends",
+        )
+        .expect("valid metric");
+        let upload_bytes = IntCounter::new("upload_bytes_total", "Bytes of accepted uploads")
+            .expect("valid metric");
+        let active_sessions = IntGauge::new("active_sessions", "Sessions that have not expired")
+            .expect("valid metric");
+        let job_run_duration = HistogramVec::new(
+            HistogramOpts::new("job_run_duration_seconds", "Time background job runs took")
+                .buckets(JOB_BUCKETS.to_vec()),
+            &["job", "status"],
+        )
+        .expect("valid metric");
+        let log_queue_depth = IntGauge::new(
+            "audit_log_queue_depth",
+            "Audit log entries waiting to be written",
+        )
+        .expect("valid metric");
+        let log_dropped = IntGauge::new(
+            "audit_log_dropped_entries",
+            "Audit log entries dropped since startup",
+        )
+        .expect("valid metric");
+
+        for collector in [
+            Box::new(http_requests.clone()) as Box<dyn prometheus::core::Collector>,
+            Box::new(http_request_duration.clone()),
+            Box::new(rate_limited.clone()),
+            Box::new(db_query_duration.clone()),
+            Box::new(db_query_errors.clone()),
+            Box::new(llm_upstream_duration.clone()),
+            Box::new(llm_streamed_bytes.clone()),
+            Box::new(upload_bytes.clone()),
+            Box::new(active_sessions.clone()),
+            Box::new(job_run_duration.clone()),
+            Box::new(log_queue_depth.clone()),
+            Box::new(log_dropped.clone()),
+        ] {
+            registry.register(collector).expect("unique metric");
+        }
+
+        Self {
+            registry,
+            http_requests,
+            http_request_duration,
+            rate_limited,
+            db_query_duration,
+            db_query_errors,
+            llm_upstream_duration,
+            llm_streamed_bytes,
+            upload_bytes,
+            active_sessions,
+            job_run_duration,
+            log_queue_depth,
+            log_dropped,
+        }
+    }
+
+    pub fn observe_request(&self, method: &str, route: &str, status: u16, elapsed: Duration) {
+        let status = status.to_string();
+        let labels = [method, route, status.as_str()];
+
+        self.http_requests.with_label_values(&labels).inc();
+        self.http_request_duration
+            .with_label_values(&labels)
+            .observe(elapsed.as_secs_f64());
+    }
+
+    pub fn observe_upstream(&self, backend: &str, success: bool, elapsed: Duration) {
+        let outcome = if success { "success" } else { "failure" };
+        self.llm_upstream_duration
+            .with_label_values(&[backend, outcome])
+            .observe(elapsed.as_secs_f64());
+    }
+
+    pub fn observe_job(&self, job: &str, status: &str, elapsed: Duration) {
+        self.job_run_duration
+            .with_label_values(&[job, status])
+            .observe(elapsed.as_secs_f64());
+    }
+
+    /// All metrics in the Prometheus text format
+    pub fn render(&self) -> Result<String, prometheus::Error> {
+        let logger = LOGGER_STATS.status();
+        self.log_queue_depth.set(logger.queued as i64);
+        self.log_dropped.set(logger.dropped as i64);
+
+        let mut buffer = Vec::new();
+        TextEncoder::new().encode(&self.registry.gather(), &mut buffer)?;
+
+        Ok(String::from_utf8_lossy(&buffer).into_owned())
+    }
+}
+
+/// Who may read `/metrics`, `METRICS_ACCESS`
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum MetricsAccess {
+    /// Everyone, restrict access in the proxy in front of the server
+    #[default]
+    Public,
+    /// Any logged in user
+    Authenticated,
+    /// Users with the `Admin` or `Owner` role
+    Admin,
+}
+
+impl MetricsAccess {
+    pub(crate) fn from_vars(var: impl Fn(&str) -> Option<String>) -> Result<Self, String> {
+        match var("METRICS_ACCESS").map(|value| value.to_lowercase()) {
+            None => Ok(Self::default()),
+            Some(value) => match value.as_str() {
+                "public" => Ok(Self::Public),
+                "authenticated" => Ok(Self::Authenticated),
+                "admin" => Ok(Self::Admin),
+                _ => Err(format!(
+                    "unknown METRICS_ACCESS {value:?}, expected public, authenticated or admin"
+                )),
+            },
+        }
+    }
+}
+
+/// Times SurrealDB queries, e.g. `db.query(SQL).bind(..).observed().await?`
+pub trait ObserveQuery: IntoFuture + Sized {
+    fn observed(self) -> ObservedQuery<Self::IntoFuture> {
+        ObservedQuery {
+            query: Box::pin(self.into_future()),
+            started: Instant::now(),
+        }
+    }
+}
+
+impl<Q: IntoFuture> ObserveQuery for Q {}
+
+/// A query that records its latency and failure in [`METRICS`] once it completes
+pub struct ObservedQuery<F> {
+    query: Pin<Box<F>>,
+    started: Instant,
+}
+
+impl<F, T> Future for ObservedQuery<F>
+where
+    F: Future<Output = surrealdb::Result<T>>,
+{
+    type Output = F::Output;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let result = ready!(self.query.as_mut().poll(cx));
+
+        METRICS
+            .db_query_duration
+            .observe(self.started.elapsed().as_secs_f64());
+        if result.is_err() {
+            METRICS.db_query_errors.inc();
+        }
+
+        Poll::Ready(result)
+    }
+}
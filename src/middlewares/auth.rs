@@ -1,3 +1,4 @@
+use crate::error::ServerResponseError;
 use crate::utils::middleware::define_middleware;
 use actix_web::{dev::ServiceRequest, HttpMessage};
 use serde::{Deserialize, Serialize};
@@ -53,7 +54,7 @@ define_middleware! {
                 Ok(res)
             } else {
                 // Return 401 Unauthorized
-                Err(actix_web::error::ErrorUnauthorized("Unauthorized"))
+                Err(ServerResponseError::Unauthorized.into())
             }
         };
 
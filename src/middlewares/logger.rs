@@ -1,54 +1,108 @@
-use crate::middlewares::auth::AuthType;
+use crate::middlewares::request_id::{RequestId, REQUEST_ID_HEADER};
+use crate::models::audit::Outcome;
+use crate::server::background_logger::LogQueue;
+use crate::services::audit::{action_of, redact_query, target_of, MAX_USER_AGENT_LEN};
 use crate::utils::middleware::define_middleware;
+use actix_web::http::header::{AUTHORIZATION, USER_AGENT};
 use actix_web::{dev::ServiceRequest, HttpMessage};
 use serde::{Deserialize, Serialize};
-use surrealdb::sql::Datetime;
-use tokio::sync::mpsc::Sender;
-use tracing::error;
+use std::time::Instant;
+use surrealdb::sql::{Datetime, Thing};
 
+/// The user a request was authenticated as, put into the request extensions
+/// by the session extractors so that the audit trail can record it
+#[derive(Debug, Clone)]
+pub struct AuthenticatedUser(pub Thing);
+
+/// A request in the audit trail, see [`crate::services::audit`]
 #[derive(Debug, Clone, Serialize, Deserialize)]
-#[allow(dead_code)]
 pub struct LogEntry {
+    pub request_id: String,
+    pub date: Datetime,
+    pub user: Option<Thing>,
+    pub auth_method: Option<String>,
     pub method: String,
     pub path: String,
-    pub user: Option<AuthType>,
+    pub query: Option<String>,
+    pub action: String,
+    pub target: Option<String>,
     pub status: u16,
-    pub date: Datetime,
+    pub outcome: Outcome,
+    pub latency_ms: f64,
+    pub ip: Option<String>,
+    pub user_agent: Option<String>,
+}
+
+/// How the request carries its credentials, cookie sessions are only known
+/// once the session has been resolved
+fn credentials_kind(req: &ServiceRequest) -> Option<&'static str> {
+    if req.headers().contains_key(AUTHORIZATION) {
+        Some("bearer")
+    } else if req.headers().contains_key("x-api-key") {
+        Some("api_key")
+    } else {
+        None
+    }
 }
 
 define_middleware! {
-    /// Middleware that logs requests and responses and sends it to a channel so that a standalone logger can consume it with minimal overhead to the request
+    /// Middleware that records every request in the audit trail. Entries are handed to the background logger without waiting so that writing them adds no latency to the request
     pub struct LoggingMiddleware {
-        log_sender: Sender<LogEntry>
+        log_queue: LogQueue
     },
     pub struct LoggingMiddlewareService;
     |this: &LoggingMiddlewareService<S>, req: ServiceRequest| {
-        let method = req.method().clone();
+        let started = Instant::now();
+        let date = Datetime::default();
+        let request_id = req
+            .extensions()
+            .get::<RequestId>()
+            .cloned()
+            .unwrap_or_else(|| RequestId::from_header(req.headers().get(REQUEST_ID_HEADER)));
+
+        let method = req.method().to_string();
         let path = req.path().to_string();
-        let user = req.extensions().get::<AuthType>().cloned();
-        let log_sender = this.log_sender.clone();
+        let query = redact_query(req.query_string());
+        let credentials = credentials_kind(&req);
+        let ip = req.connection_info().realip_remote_addr().map(String::from);
+        let user_agent = req
+            .headers()
+            .get(USER_AGENT)
+            .and_then(|agent| agent.to_str().ok())
+            .map(|agent| agent.chars().take(MAX_USER_AGENT_LEN).collect());
+        let log_queue = this.log_queue.clone();
 
         let fut = this.service.call(req);
 
         Box::pin(async move {
             let res = fut.await?;
+            let status = res.status().as_u16();
 
-            // Access response status code
-            let status = res.status();
+            let request = res.request();
+            let route = request.match_pattern();
+            let user = request.extensions().get::<AuthenticatedUser>().cloned();
 
-            // Create the log entry
             let log_entry = LogEntry {
-                method: method.to_string(),
+                request_id: request_id.0,
+                date,
+                auth_method: credentials
+                    .or(user.as_ref().map(|_| "session"))
+                    .map(String::from),
+                user: user.map(|user| user.0),
+                action: action_of(&method, route.as_deref(), &path),
+                target: target_of(request.match_info().iter()),
+                method,
                 path,
-                user,
-                status: status.as_u16(),
-                date: Datetime::default(),
+                query,
+                status,
+                outcome: Outcome::from_status(status),
+                latency_ms: started.elapsed().as_secs_f64() * 1000.0,
+                ip,
+                user_agent,
             };
 
-            // Send the log entry into the channel
-            if let Err(e) = log_sender.send(log_entry).await {
-                error!("Failed to send log entry: {}", e);
-            }
+            // Never waits, a full queue drops or spills the entry
+            log_queue.submit(log_entry);
 
             Ok(res)
         })
@@ -56,7 +110,7 @@ define_middleware! {
 }
 
 impl LoggingMiddleware {
-    pub fn new(log_sender: Sender<LogEntry>) -> Self {
-        Self { log_sender }
+    pub fn new(log_queue: LogQueue) -> Self {
+        Self { log_queue }
     }
 }
@@ -0,0 +1,31 @@
+use crate::metrics::{METRICS, UNMATCHED_ROUTE};
+use crate::utils::middleware::define_middleware;
+use actix_web::dev::ServiceRequest;
+use std::time::Instant;
+
+define_middleware! {
+    /// Middleware that counts every request and records its latency by method, route pattern and status, see [`METRICS`]
+    pub struct MetricsMiddleware {},
+    pub struct MetricsMiddlewareService;
+    |this: &MetricsMiddlewareService<S>, req: ServiceRequest| {
+        let started = Instant::now();
+        let method = req.method().to_string();
+
+        let fut = this.service.call(req);
+
+        Box::pin(async move {
+            let res = fut.await?;
+
+            // Patterns instead of paths keep the number of series bounded
+            let route = res.request().match_pattern();
+            METRICS.observe_request(
+                &method,
+                route.as_deref().unwrap_or(UNMATCHED_ROUTE),
+                res.status().as_u16(),
+                started.elapsed(),
+            );
+
+            Ok(res)
+        })
+    }
+}
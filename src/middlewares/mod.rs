@@ -1,2 +1,6 @@
 pub mod auth;
 pub mod logger;
+pub mod metrics;
+pub mod request_id;
+pub mod session_keys;
+pub mod trace_context;
@@ -0,0 +1,62 @@
+use crate::utils::middleware::define_middleware;
+use actix_web::dev::ServiceRequest;
+use actix_web::http::header::{HeaderName, HeaderValue};
+use actix_web::HttpMessage;
+
+/// Header carrying the ID of a request
+pub const REQUEST_ID_HEADER: &str = "x-request-id";
+
+tokio::task_local! {
+    static CURRENT_REQUEST_ID: RequestId;
+}
+
+/// ID of the current request, taken from `X-Request-Id` if the client sent
+/// a usable one, kept in the request extensions
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RequestId(pub String);
+
+impl RequestId {
+    pub fn from_header(value: Option<&HeaderValue>) -> Self {
+        let sent = value.and_then(|value| value.to_str().ok()).filter(|id| {
+            !id.is_empty()
+                && id.len() <= 128
+                && id
+                    .bytes()
+                    .all(|b| b.is_ascii_alphanumeric() || matches!(b, b'-' | b'_' | b'.'))
+        });
+
+        match sent {
+            Some(id) => Self(id.to_string()),
+            None => Self(format!("{:032x}", rand::random::<u128>())),
+        }
+    }
+
+    /// ID of the request that is being handled, `None` outside of requests
+    pub fn current() -> Option<Self> {
+        CURRENT_REQUEST_ID.try_with(Clone::clone).ok()
+    }
+}
+
+define_middleware! {
+    /// Middleware that assigns every request a [`RequestId`] and echoes it in the `X-Request-Id` response header. The ID is available through [`RequestId::current`] while the request is handled, so that error bodies and upstream requests can carry it.
+    pub struct RequestIdMiddleware {},
+    pub struct RequestIdMiddlewareService;
+    |this: &RequestIdMiddlewareService<S>, req: ServiceRequest| {
+        let request_id = RequestId::from_header(req.headers().get(REQUEST_ID_HEADER));
+        req.extensions_mut().insert(request_id.clone());
+
+        let fut = CURRENT_REQUEST_ID.sync_scope(request_id.clone(), || this.service.call(req));
+        let header = HeaderValue::from_str(&request_id.0).ok();
+
+        Box::pin(CURRENT_REQUEST_ID.scope(request_id, async move {
+            let mut res = fut.await?;
+
+            if let Some(header) = header {
+                res.headers_mut()
+                    .insert(HeaderName::from_static(REQUEST_ID_HEADER), header);
+            }
+
+            Ok(res)
+        }))
+    }
+}
@@ -0,0 +1,56 @@
+use crate::config::session::{SessionKeys, SESSION_COOKIE};
+use crate::utils::middleware::define_middleware;
+use actix_web::dev::ServiceRequest;
+use actix_web::http::header::{HeaderValue, COOKIE};
+use std::sync::Arc;
+
+/// The `Cookie` header with the session cookie resealed with the current key, `None` if nothing
+/// had to change
+fn rotate_cookies(req: &ServiceRequest, keys: &SessionKeys) -> Option<HeaderValue> {
+    let mut rotated = false;
+    let cookies: Vec<String> = req
+        .headers()
+        .get_all(COOKIE)
+        .filter_map(|value| value.to_str().ok())
+        .flat_map(|value| value.split(';'))
+        .map(|cookie| {
+            let cookie = cookie.trim();
+            match cookie.split_once('=') {
+                Some((SESSION_COOKIE, value)) => match keys.reseal(value) {
+                    Some(value) => {
+                        rotated = true;
+                        format!("{SESSION_COOKIE}={value}")
+                    }
+                    None => cookie.to_string(),
+                },
+                _ => cookie.to_string(),
+            }
+        })
+        .collect();
+
+    if rotated {
+        HeaderValue::from_str(&cookies.join("; ")).ok()
+    } else {
+        None
+    }
+}
+
+define_middleware! {
+    /// Middleware that lets session cookies encrypted with one of the old keys in
+    /// [`SessionKeys`] through, by handing the session middleware the cookie encrypted with the
+    /// current key. Has to wrap the session middleware. The header is rewritten before anything
+    /// parses the cookies, since parsed cookies are cached.
+    pub struct SessionKeyRotation {
+        keys: Arc<SessionKeys>,
+    },
+    pub struct SessionKeyRotationService;
+    |this: &SessionKeyRotationService<S>, mut req: ServiceRequest| {
+        if !this.keys.old.is_empty() {
+            if let Some(cookies) = rotate_cookies(&req, &this.keys) {
+                req.headers_mut().insert(COOKIE, cookies);
+            }
+        }
+
+        Box::pin(this.service.call(req))
+    }
+}
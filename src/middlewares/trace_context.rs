@@ -0,0 +1,64 @@
+//! W3C trace context propagation. The root span of every request continues the trace of an
+//! incoming `traceparent` header, and upstream requests carry the context of the span they are
+//! sent from, so that traces of the frontend, this server and the LLM service can be joined.
+
+use crate::middlewares::request_id::RequestId;
+use actix_web::body::MessageBody;
+use actix_web::dev::{ServiceRequest, ServiceResponse};
+use actix_web::http::header::HeaderMap;
+use actix_web::{Error, HttpMessage};
+use opentelemetry::global;
+use opentelemetry::propagation::Extractor;
+use std::collections::HashMap;
+use tracing::Span;
+use tracing_actix_web::{DefaultRootSpanBuilder, RootSpanBuilder};
+use tracing_opentelemetry::OpenTelemetrySpanExt;
+
+struct HeaderExtractor<'a>(&'a HeaderMap);
+
+impl Extractor for HeaderExtractor<'_> {
+    fn get(&self, key: &str) -> Option<&str> {
+        self.0.get(key).and_then(|value| value.to_str().ok())
+    }
+
+    fn keys(&self) -> Vec<&str> {
+        self.0.keys().map(|name| name.as_str()).collect()
+    }
+}
+
+/// Root span of the [`TracingLogger`](tracing_actix_web::TracingLogger), a child of the
+/// incoming trace context that records the [`RequestId`] as `correlation_id`
+pub struct TraceContextRootSpan;
+
+impl RootSpanBuilder for TraceContextRootSpan {
+    fn on_request_start(request: &ServiceRequest) -> Span {
+        let correlation_id = request
+            .extensions()
+            .get::<RequestId>()
+            .map(|id| id.0.clone())
+            .unwrap_or_default();
+        let span = tracing_actix_web::root_span!(request, correlation_id = %correlation_id);
+
+        let parent = global::get_text_map_propagator(|propagator| {
+            propagator.extract(&HeaderExtractor(request.headers()))
+        });
+        span.set_parent(parent);
+
+        span
+    }
+
+    fn on_request_end<B: MessageBody>(span: Span, outcome: &Result<ServiceResponse<B>, Error>) {
+        DefaultRootSpanBuilder::on_request_end(span, outcome);
+    }
+}
+
+/// Headers that continue the trace of the current span in an upstream request, empty when
+/// traces are not exported
+pub fn trace_headers() -> HashMap<String, String> {
+    let mut headers = HashMap::new();
+    let context = Span::current().context();
+
+    global::get_text_map_propagator(|propagator| propagator.inject_context(&context, &mut headers));
+
+    headers
+}
@@ -0,0 +1,70 @@
+use super::{datetime::Datetime, thing::Thing};
+use serde::{Deserialize, Serialize};
+use utoipa::{ToResponse, ToSchema};
+
+/// How a request ended
+#[derive(Serialize, Deserialize, ToSchema, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum Outcome {
+    /// Answered with a 1xx, 2xx or 3xx status
+    Success,
+    /// Answered with 401 or 403
+    Denied,
+    /// Answered with any other 4xx or 5xx status
+    Failure,
+}
+
+/// A request recorded in the audit trail, stored in the `log` table of the
+/// `default/log` database. Entries written before the audit trail existed
+/// only have the method, path, status and date.
+#[derive(Serialize, Deserialize, ToSchema, Debug, Clone)]
+pub struct AuditLogEntry {
+    pub id: Thing,
+    #[serde(default)]
+    #[schema(example = "4bf92f3577b34da6a3ce929d0e0e4736")]
+    pub request_id: Option<String>,
+    pub date: Datetime,
+    /// The user the request was authenticated as.
+    #[serde(default)]
+    pub user: Option<Thing>,
+    /// How the user authenticated: `bearer`, `api_key` or `session`.
+    #[serde(default)]
+    pub auth_method: Option<String>,
+    #[schema(example = "DELETE")]
+    pub method: String,
+    #[schema(example = "/api/v1/files/1b6k0a4r5dn7s0ex9a3f")]
+    pub path: String,
+    /// The query string with credentials redacted.
+    #[serde(default)]
+    pub query: Option<String>,
+    /// The method and route of the request.
+    #[serde(default)]
+    #[schema(example = "DELETE /api/v1/files/{file_id}")]
+    pub action: Option<String>,
+    /// The record the request acted on.
+    #[serde(default)]
+    #[schema(example = "file:1b6k0a4r5dn7s0ex9a3f")]
+    pub target: Option<String>,
+    pub status: u16,
+    #[serde(default)]
+    pub outcome: Option<Outcome>,
+    /// Time from receiving the request to sending the response head.
+    #[serde(default)]
+    pub latency_ms: Option<f64>,
+    /// The client address, taken from `Forwarded` or `X-Forwarded-For` if
+    /// present, so only trustworthy behind a proxy that sets them.
+    #[serde(default)]
+    pub ip: Option<String>,
+    #[serde(default)]
+    pub user_agent: Option<String>,
+}
+
+/// A page of the audit trail, the newest entries first
+#[derive(Serialize, Deserialize, ToSchema, ToResponse, Debug, Clone)]
+pub struct AuditLogPage {
+    pub entries: Vec<AuditLogEntry>,
+    /// Number of entries matching the filter.
+    pub total: u64,
+    pub limit: u64,
+    pub offset: u64,
+}
@@ -4,7 +4,7 @@ use utoipa::{ToResponse, ToSchema};
 use super::thing::Thing;
 
 /// Represents an entry from the MITRE ATT&CK database
-#[derive(Serialize, Deserialize, ToSchema)]
+#[derive(Serialize, Deserialize, ToSchema, Debug, Clone)]
 pub struct MITREEntry {
     pub mitre_id: String,
     pub mitre_name: String,
@@ -16,7 +16,7 @@ pub struct MITREEntry {
 #[derive(ToResponse)]
 pub struct MITREEntries(pub Vec<MITREEntry>);
 
-#[derive(Serialize, Deserialize, ToSchema)]
+#[derive(Serialize, Deserialize, ToSchema, Debug, Clone, Copy, PartialEq, Eq, Hash)]
 #[serde(rename_all = "lowercase")]
 pub enum EntryType {
     Threat,
@@ -2,6 +2,18 @@ use super::{datetime::Datetime, thing::Thing};
 use serde::{Deserialize, Serialize};
 use utoipa::{ToResponse, ToSchema};
 
+/// Where a file is in the malware scanning pipeline.
+#[derive(Serialize, Deserialize, ToSchema, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum ScanStatus {
+    /// Not scanned yet, the file cannot be downloaded
+    Pending,
+    /// No malware was found
+    Clean,
+    /// Malware was found, the file is quarantined
+    Infected,
+}
+
 /// A type representing the metadata of an uploaded file.
 #[derive(Debug, Clone, Serialize, Deserialize, ToSchema, ToResponse)]
 pub struct FileMetadata {
@@ -13,6 +25,50 @@ pub struct FileMetadata {
     pub filename: String,
     /// The datetime when the file was uploaded.
     pub created_at: Datetime,
+    /// The team owning the file, files without a team
+    /// belong to their uploader.
+    #[serde(default)]
+    pub team: Option<Thing>,
+    /// Size of the file in bytes.
+    #[serde(default)]
+    #[schema(example = 73400320)]
+    pub size: Option<u64>,
+    /// The MIME type detected from the content of the file.
+    #[serde(default)]
+    #[schema(example = "application/vnd.tcpdump.pcap")]
+    pub mime_type: Option<String>,
+    /// The content type sent by the uploader, it is not
+    /// used when the file is downloaded.
+    #[serde(default)]
+    pub declared_type: Option<String>,
+    /// Hex encoded SHA-256 checksum of the file.
+    #[serde(default)]
+    pub sha256: Option<String>,
+    /// Only clean files can be downloaded. Files uploaded
+    /// before scanning was introduced have no status until
+    /// the reconciler scans them.
+    #[serde(default)]
+    pub scan_status: Option<ScanStatus>,
+    /// The signature that matched an infected file.
+    #[serde(default)]
+    pub threat: Option<String>,
+    /// The datetime of the last scan.
+    #[serde(default)]
+    pub scanned_at: Option<Datetime>,
+    /// Number of the current revision, see `GET /files/{file_id}/revisions`.
+    #[serde(default)]
+    #[schema(example = 3)]
+    pub revision: Option<u32>,
+    /// Store key of the current revision, the file ID for the first one.
+    #[serde(default, skip_serializing)]
+    pub key: Option<String>,
+}
+
+impl FileMetadata {
+    /// Key of the current content in the file store
+    pub fn content_key(&self) -> String {
+        self.key.clone().unwrap_or_else(|| self.id.id.to_raw())
+    }
 }
 
 #[allow(dead_code)]
@@ -0,0 +1,46 @@
+use super::{datetime::Datetime, file_metadata::ScanStatus, thing::Thing};
+use serde::{Deserialize, Serialize};
+use utoipa::{ToResponse, ToSchema};
+
+/// A content of a file, stored in the `file_revision` table. Uploading new
+/// content or restoring an old revision adds a revision, the file always
+/// shows the latest one.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema, ToResponse)]
+pub struct FileRevision {
+    pub id: Thing,
+    pub file: Thing,
+    /// Revisions are numbered from 1 per file.
+    #[schema(example = 2)]
+    pub revision: u32,
+    /// Key of the content in the file store.
+    #[serde(skip_serializing)]
+    pub key: String,
+    /// The filename at the time of the revision.
+    pub filename: String,
+    #[serde(default)]
+    pub size: Option<u64>,
+    #[serde(default)]
+    pub mime_type: Option<String>,
+    #[serde(default)]
+    pub declared_type: Option<String>,
+    #[serde(default)]
+    pub sha256: Option<String>,
+    #[serde(default)]
+    pub scan_status: Option<ScanStatus>,
+    #[serde(default)]
+    pub threat: Option<String>,
+    #[serde(default)]
+    pub scanned_at: Option<Datetime>,
+    /// The user who uploaded or restored the revision, unknown for
+    /// files uploaded before revisions were tracked.
+    #[serde(default)]
+    pub uploaded_by: Option<Thing>,
+    /// The revision whose content was restored.
+    #[serde(default)]
+    pub restored_from: Option<u32>,
+    pub created_at: Datetime,
+}
+
+#[allow(dead_code)]
+#[derive(ToResponse)]
+pub struct FileRevisions(pub Vec<FileRevision>);
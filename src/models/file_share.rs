@@ -0,0 +1,19 @@
+use super::{datetime::Datetime, thing::Thing};
+use serde::{Deserialize, Serialize};
+use utoipa::{ToResponse, ToSchema};
+
+/// A share link for a single file, stored in the `file_share` table. The password hash is never
+/// read back, only whether one is set.
+#[derive(Serialize, Deserialize, ToSchema, Debug, Clone)]
+pub struct FileShare {
+    pub id: Thing,
+    pub file: Thing,
+    pub created_by: Thing,
+    /// Whether the link requires a password
+    pub password_protected: bool,
+    /// Number of downloads after which the link stops working, unlimited when missing
+    pub max_downloads: Option<u64>,
+    pub downloads: u64,
+    pub created_at: Datetime,
+    pub expires_at: Datetime,
+}
@@ -0,0 +1,100 @@
+use super::{datetime::Datetime, thing::Thing};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use utoipa::{ToResponse, ToSchema};
+
+/// A background job run by the scheduler
+#[derive(Serialize, Deserialize, ToSchema, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[serde(rename_all = "snake_case")]
+pub enum JobName {
+    /// Deletes expired sessions
+    PurgeSessions,
+    /// Deletes audit log entries and job runs older than `LOG_RETENTION_DAYS`
+    LogRetention,
+    /// Removes orphaned file records, contents and partial uploads
+    FileCleanup,
+    /// Embeds MITRE entries again with `EMBEDDING_MODEL`
+    Reembed,
+}
+
+impl JobName {
+    pub const ALL: [JobName; 4] = [
+        JobName::PurgeSessions,
+        JobName::LogRetention,
+        JobName::FileCleanup,
+        JobName::Reembed,
+    ];
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            JobName::PurgeSessions => "purge_sessions",
+            JobName::LogRetention => "log_retention",
+            JobName::FileCleanup => "file_cleanup",
+            JobName::Reembed => "reembed",
+        }
+    }
+}
+
+impl fmt::Display for JobName {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// What started a run
+#[derive(Serialize, Deserialize, ToSchema, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum JobTrigger {
+    Schedule,
+    /// An admin started the run
+    Manual,
+}
+
+#[derive(Serialize, Deserialize, ToSchema, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum JobRunStatus {
+    Running,
+    Succeeded,
+    Failed,
+    /// The job had nothing to do because it is not configured
+    Skipped,
+    /// The server stopped during the run
+    Interrupted,
+}
+
+impl JobRunStatus {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            JobRunStatus::Running => "running",
+            JobRunStatus::Succeeded => "succeeded",
+            JobRunStatus::Failed => "failed",
+            JobRunStatus::Skipped => "skipped",
+            JobRunStatus::Interrupted => "interrupted",
+        }
+    }
+}
+
+/// A run of a job, stored in the `job_run` table.
+#[derive(Serialize, Deserialize, ToSchema, ToResponse, Debug, Clone)]
+pub struct JobRun {
+    pub id: Thing,
+    pub job: JobName,
+    pub trigger: JobTrigger,
+    pub status: JobRunStatus,
+    /// The admin who started a manual run.
+    #[serde(default)]
+    pub triggered_by: Option<Thing>,
+    pub started_at: Datetime,
+    #[serde(default)]
+    pub finished_at: Option<Datetime>,
+    #[serde(default)]
+    pub duration_ms: Option<u64>,
+    /// What the run did or why it failed.
+    #[serde(default)]
+    #[schema(example = "12 expired sessions deleted")]
+    pub detail: Option<String>,
+}
+
+#[allow(dead_code)]
+#[derive(ToResponse)]
+pub struct JobRuns(pub Vec<JobRun>);
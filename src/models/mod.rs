@@ -5,22 +5,36 @@ use serde::{Deserialize, Serialize};
 use surrealdb::sql::Thing;
 
 pub mod access_token;
+pub mod audit;
 pub mod auth_for;
 pub mod datetime;
 pub mod embeddings;
 pub mod file_metadata;
+pub mod file_revision;
+pub mod file_share;
+pub mod job;
 pub mod refresh_token;
 pub mod session;
+pub mod team;
 pub mod thing;
+pub mod threat_model;
+pub mod upload_session;
 pub mod user_info;
 
 pub(crate) use access_token::*;
+pub(crate) use audit::*;
 pub(crate) use auth_for::*;
 pub(crate) use embeddings::*;
 pub(crate) use file_metadata::*;
+pub(crate) use file_revision::*;
+pub(crate) use file_share::*;
+pub(crate) use job::*;
 pub(crate) use refresh_token::*;
 pub(crate) use session::*;
+pub(crate) use team::*;
 pub(crate) use thing::*;
+pub(crate) use threat_model::*;
+pub(crate) use upload_session::*;
 pub(crate) use user_info::*;
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
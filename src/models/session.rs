@@ -1,3 +1,4 @@
+use crate::metrics::ObserveQuery;
 use crate::models::user_info::UserInfo;
 use crate::server::db::INTERNAL_DB;
 use anyhow::{bail, Result};
@@ -107,6 +108,7 @@ impl UserSession {
             .bind(("access_token", self.access_token))
             .bind(("refresh_token", self.refresh_token))
             .bind(("email", self.email))
+            .observed()
             .await?;
 
         let sessions: Option<Self> = res.take(0)?;
@@ -180,10 +182,6 @@ impl UserSession {
     async fn fetch_with_filter(filter: Filter) -> Option<Self> {
         let sql = Select::query("session").set_filter(filter).set_limit(1);
 
-        Self::delete_expired()
-            .await
-            .expect("Error deleting expired sessions");
-
         sql.run_lazy(&INTERNAL_DB, 0).await.unwrap_or_else(|e| {
             error!("Error fetching session: {}", e);
             None
@@ -226,17 +224,29 @@ impl UserSession {
             .add_condition("email", None, self.email)
             .construct();
 
-        INTERNAL_DB.query(sql).await?;
+        INTERNAL_DB.query(sql).observed().await?;
         Ok(())
     }
 
+    /// Number of sessions that have not expired
     #[tracing::instrument]
-    pub async fn delete_expired() -> Result<()> {
-        let query =
-            Delete::query("session").add_condition("expires_at", Some("<"), Datetime::default());
+    pub(crate) async fn count_active() -> Result<u64> {
+        let sql = "RETURN count(SELECT id FROM session WHERE expires_at > time::now());";
 
-        INTERNAL_DB.query(query.construct()).await?;
+        let count: Option<u64> = INTERNAL_DB.query(sql).observed().await?.take(0)?;
 
-        Ok(())
+        Ok(count.unwrap_or_default())
+    }
+
+    /// Deletes every expired session and returns how many there were, run by the
+    /// `purge_sessions` job
+    #[tracing::instrument]
+    pub async fn delete_expired() -> Result<usize> {
+        let sql =
+            "RETURN array::len((DELETE session WHERE expires_at < time::now() RETURN BEFORE));";
+
+        let deleted: Option<usize> = INTERNAL_DB.query(sql).observed().await?.take(0)?;
+
+        Ok(deleted.unwrap_or_default())
     }
 }
@@ -0,0 +1,78 @@
+use super::{datetime::Datetime, thing::Thing};
+use serde::{Deserialize, Serialize};
+use utoipa::{ToResponse, ToSchema};
+
+/// Role of a user in a team, ordered from least to most privileged.
+#[derive(
+    Serialize, Deserialize, ToSchema, Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash,
+)]
+#[serde(rename_all = "lowercase")]
+pub enum TeamRole {
+    /// Can read everything owned by the team
+    Viewer,
+    /// Can additionally create, change and delete files and threat models of the team
+    Editor,
+    /// Can additionally manage the team, its members and invitations
+    Owner,
+}
+
+impl TeamRole {
+    pub const ALL: [TeamRole; 3] = [TeamRole::Viewer, TeamRole::Editor, TeamRole::Owner];
+
+    /// Every role that grants at least the permissions of `self`
+    pub fn and_above(self) -> Vec<TeamRole> {
+        Self::ALL.into_iter().filter(|role| *role >= self).collect()
+    }
+}
+
+/// A group of users sharing files and threat models, stored in the `team` table.
+#[derive(Serialize, Deserialize, ToSchema, ToResponse, Debug, Clone)]
+pub struct Team {
+    pub id: Thing,
+    #[schema(example = "Platform security")]
+    pub name: String,
+    pub created_by: Thing,
+    pub created_at: Datetime,
+}
+
+/// A team together with the role of the current user in it.
+#[derive(Serialize, Deserialize, ToSchema, Debug, Clone)]
+pub struct TeamMembership {
+    #[serde(flatten)]
+    pub team: Team,
+    pub role: TeamRole,
+}
+
+#[allow(dead_code)]
+#[derive(ToResponse)]
+pub struct TeamMemberships(pub Vec<TeamMembership>);
+
+/// A member of a team, from the `member_of` relation between `user` and `team`.
+#[derive(Serialize, Deserialize, ToSchema, Debug, Clone)]
+pub struct TeamMember {
+    pub user: Thing,
+    #[schema(example = "johndoe@example.com")]
+    pub email: String,
+    #[schema(example = "John Doe")]
+    pub username: String,
+    pub role: TeamRole,
+    pub joined_at: Datetime,
+}
+
+/// An invitation to join a team, stored in the `team_invitation` table. It can only be accepted
+/// by the user with the invited email address.
+#[derive(Serialize, Deserialize, ToSchema, ToResponse, Debug, Clone)]
+pub struct TeamInvitation {
+    pub id: Thing,
+    pub team: Thing,
+    #[schema(example = "janedoe@example.com")]
+    pub email: String,
+    pub role: TeamRole,
+    pub invited_by: Thing,
+    pub created_at: Datetime,
+    pub expires_at: Datetime,
+}
+
+#[allow(dead_code)]
+#[derive(ToResponse)]
+pub struct TeamInvitations(pub Vec<TeamInvitation>);
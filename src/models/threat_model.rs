@@ -0,0 +1,89 @@
+use super::{datetime::Datetime, thing::Thing};
+use serde::{Deserialize, Serialize};
+use utoipa::{ToResponse, ToSchema};
+
+/// Decision taken for a threat or mitigation in a threat model.
+#[derive(Serialize, Deserialize, ToSchema, Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum ItemStatus {
+    /// The item is relevant to the system, for threats this means the risk is accepted until it
+    /// is mitigated
+    #[default]
+    Accepted,
+    /// The threat is handled or the mitigation is in place
+    Mitigated,
+    /// The item does not apply to the system
+    NotApplicable,
+}
+
+/// A `threat` record selected for a threat model.
+#[derive(Serialize, Deserialize, ToSchema, Debug, Clone, PartialEq)]
+pub struct ThreatItem {
+    #[schema(example = "T1190")]
+    pub mitre_id: String,
+    #[serde(default)]
+    pub status: ItemStatus,
+    pub notes: Option<String>,
+    /// MITRE IDs of the mitigations in this model that address the threat
+    #[serde(default)]
+    #[schema(example = json!(["M1050"]))]
+    pub mitigated_by: Vec<String>,
+}
+
+/// A `mitigation` record selected for a threat model.
+#[derive(Serialize, Deserialize, ToSchema, Debug, Clone, PartialEq)]
+pub struct MitigationItem {
+    #[schema(example = "M1050")]
+    pub mitre_id: String,
+    #[serde(default)]
+    pub status: ItemStatus,
+    pub notes: Option<String>,
+}
+
+/// A threat model of a system, stored in the `threat_model` table.
+#[derive(Serialize, Deserialize, ToSchema, ToResponse, Debug, Clone)]
+pub struct ThreatModel {
+    pub id: Thing,
+    /// The user that created the model
+    pub owner: Thing,
+    /// The team the model is shared with, models without a team are only visible to their owner
+    #[serde(default)]
+    pub team: Option<Thing>,
+    #[schema(example = "Customer portal")]
+    pub title: String,
+    /// Description of the system being modelled
+    pub description: String,
+    /// Files attached to the model
+    pub files: Vec<Thing>,
+    pub threats: Vec<ThreatItem>,
+    pub mitigations: Vec<MitigationItem>,
+    /// Incremented on every change, see the version history
+    pub version: u64,
+    pub updated_by: Thing,
+    pub created_at: Datetime,
+    pub updated_at: Datetime,
+}
+
+#[allow(dead_code)]
+#[derive(ToResponse)]
+pub struct ThreatModels(pub Vec<ThreatModel>);
+
+/// Snapshot of a threat model, stored in the `threat_model_version` table every time the model
+/// changes.
+#[derive(Serialize, Deserialize, ToSchema, ToResponse, Debug, Clone)]
+pub struct ThreatModelVersion {
+    pub model: Thing,
+    pub version: u64,
+    /// The user that made the change
+    pub author: Thing,
+    pub title: String,
+    pub description: String,
+    pub files: Vec<Thing>,
+    pub threats: Vec<ThreatItem>,
+    pub mitigations: Vec<MitigationItem>,
+    pub created_at: Datetime,
+}
+
+#[allow(dead_code)]
+#[derive(ToResponse)]
+pub struct ThreatModelVersions(pub Vec<ThreatModelVersion>);
@@ -0,0 +1,28 @@
+use super::{datetime::Datetime, thing::Thing};
+use serde::{Deserialize, Serialize};
+use utoipa::{ToResponse, ToSchema};
+
+/// A resumable upload in progress, stored in the `upload_session` table. The
+/// received bytes are kept on disk until the upload is finalized.
+#[derive(Serialize, Deserialize, ToSchema, ToResponse, Debug, Clone)]
+pub struct UploadSession {
+    pub id: Thing,
+    pub owner: Thing,
+    /// The team the file will belong to
+    #[serde(default)]
+    pub team: Option<Thing>,
+    #[schema(example = "capture.pcap")]
+    pub filename: String,
+    /// Size of the whole file in bytes
+    pub size: u64,
+    /// Bytes received so far, the next chunk has to start here
+    pub offset: u64,
+    /// Expected hex encoded SHA-256 of the whole file
+    pub sha256: String,
+    /// Content type declared by the client
+    #[serde(default)]
+    pub content_type: Option<String>,
+    pub created_at: Datetime,
+    /// Unfinished uploads are discarded after this
+    pub expires_at: Datetime,
+}
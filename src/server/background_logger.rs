@@ -1,49 +1,494 @@
+//! Writes the audit trail to the `log` table without slowing down requests. The
+//! [`LoggingMiddleware`](crate::middlewares::logger::LoggingMiddleware) hands entries to a
+//! bounded queue without waiting, a single task inserts them in batches. When the queue is full
+//! entries are dropped or, with `LOG_OVERFLOW=spill`, kept in a second bounded buffer that is
+//! written once the database catches up. The task reconnects after losing the database and
+//! drains the queue on shutdown. Entries older than the retention period are purged by the
+//! `log_retention` job.
+
+use crate::dto::audit::LoggerStatus;
+use crate::metrics::ObserveQuery;
 use crate::middlewares::logger::LogEntry;
+use crate::services::audit::PREPARE_LOG_TABLE;
 use crate::state::db;
-use tokio::sync::mpsc;
-use tokio::sync::mpsc::{Receiver, Sender};
+use std::collections::VecDeque;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use surrealdb::engine::remote::ws::Client;
+use surrealdb::Surreal;
+use tokio::sync::mpsc::error::TrySendError;
+use tokio::sync::mpsc::{self, Receiver, Sender};
+use tokio::sync::oneshot;
+use tokio::task::JoinHandle;
+use tracing::{error, info, warn};
+
+/// Shortest time between two attempts to reconnect to the database
+const RECONNECT_DELAY: Duration = Duration::from_secs(5);
 
-pub fn background_logger() -> Sender<LogEntry> {
-    let (log_sender, log_receiver) = mpsc::channel::<LogEntry>(100);
+/// Attempts to write a batch before it is dropped, e.g. because an entry is rejected
+const MAX_BATCH_ATTEMPTS: u32 = 5;
 
-    tokio::spawn(async move { logger(log_receiver).await });
+/// Longest time shutting down waits for the queue to be written
+const DRAIN_TIMEOUT: Duration = Duration::from_secs(10);
 
-    log_sender
+/// What happens to entries while the queue is full
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OverflowPolicy {
+    /// Entries are dropped and counted
+    Drop,
+    /// Entries are kept in a second buffer of `LOG_SPILL_CAPACITY` entries, only dropped when
+    /// that is full too
+    Spill,
 }
 
-#[inline]
-async fn logger(mut receiver: Receiver<LogEntry>) {
-    let db = db("default", "log")
-        .await
-        .expect("unable to connect to database");
+#[derive(Debug, Clone, PartialEq)]
+pub struct LoggerConfig {
+    /// Entries waiting to be written, `LOG_QUEUE_SIZE`
+    pub queue_size: usize,
+    /// Entries inserted at once, `LOG_BATCH_SIZE`
+    pub batch_size: usize,
+    /// Longest time an entry waits for its batch to fill up, `LOG_FLUSH_INTERVAL_MS`
+    pub flush_interval: Duration,
+    /// `LOG_OVERFLOW`, `drop` or `spill`
+    pub overflow: OverflowPolicy,
+    /// `LOG_SPILL_CAPACITY`
+    pub spill_capacity: usize,
+    /// Entries older than this are purged, `LOG_RETENTION_DAYS` with `0` keeping them forever
+    pub retention: Option<Duration>,
+}
 
-    while let Some(log) = receiver.recv().await {
-        /*let method = log.method;
-        let path = log.path;
-        let status = log.status;
-        let auth_method = log.user;
+impl Default for LoggerConfig {
+    fn default() -> Self {
+        Self {
+            queue_size: 10_000,
+            batch_size: 100,
+            flush_interval: Duration::from_secs(1),
+            overflow: OverflowPolicy::Drop,
+            spill_capacity: 100_000,
+            retention: Some(Duration::from_days(90)),
+        }
+    }
+}
 
-        let log = format!("Method: {}, Path: {}, Status: {}", method, path, status);
+impl LoggerConfig {
+    pub(crate) fn from_vars(var: impl Fn(&str) -> Option<String>) -> Result<Self, String> {
+        let defaults = Self::default();
+        let number = |name: &str| -> Result<Option<u64>, String> {
+            var(name)
+                .map(|value| {
+                    value
+                        .trim()
+                        .parse()
+                        .map_err(|_| format!("{name} is not a number: {value:?}"))
+                })
+                .transpose()
+        };
+        let positive = |name: &str| -> Result<Option<u64>, String> {
+            match number(name)? {
+                Some(0) => Err(format!("{name} has to be greater than 0")),
+                value => Ok(value),
+            }
+        };
 
-        match auth_method {
-            Some(AuthType::ApiKey(key)) => {
-                info!("{}, Access was granted using API key: {} to ", log, key);
+        let overflow = match var("LOG_OVERFLOW").map(|value| value.to_lowercase()) {
+            None => defaults.overflow,
+            Some(value) if value == "drop" => OverflowPolicy::Drop,
+            Some(value) if value == "spill" => OverflowPolicy::Spill,
+            Some(value) => {
+                return Err(format!(
+                    "unknown LOG_OVERFLOW {value:?}, expected drop or spill"
+                ))
             }
-            Some(AuthType::AccessToken(token)) => {
-                info!(
-                    "{}, Access was granted using access token: {} to ",
-                    log, token
-                );
+        };
+
+        let retention = match number("LOG_RETENTION_DAYS")? {
+            Some(0) => None,
+            Some(days) => Some(Duration::from_days(days)),
+            None => defaults.retention,
+        };
+
+        Ok(Self {
+            queue_size: positive("LOG_QUEUE_SIZE")?
+                .map(|size| size as usize)
+                .unwrap_or(defaults.queue_size),
+            batch_size: positive("LOG_BATCH_SIZE")?
+                .map(|size| size as usize)
+                .unwrap_or(defaults.batch_size),
+            flush_interval: positive("LOG_FLUSH_INTERVAL_MS")?
+                .map(Duration::from_millis)
+                .unwrap_or(defaults.flush_interval),
+            overflow,
+            spill_capacity: number("LOG_SPILL_CAPACITY")?
+                .map(|capacity| capacity as usize)
+                .unwrap_or(defaults.spill_capacity),
+            retention,
+        })
+    }
+}
+
+/// Counters of the background logger since startup
+#[derive(Debug, Default)]
+pub struct LoggerStats {
+    /// Entries waiting in the queue or the spill buffer
+    pub queued: AtomicU64,
+    pub written: AtomicU64,
+    /// Entries lost because the queue was full or their batch could not be written
+    pub dropped: AtomicU64,
+    /// Entries that went into the spill buffer because the queue was full
+    pub spilled: AtomicU64,
+    pub failed_writes: AtomicU64,
+    pub reconnects: AtomicU64,
+}
+
+impl LoggerStats {
+    pub fn status(&self) -> LoggerStatus {
+        LoggerStatus {
+            queued: self.queued.load(Ordering::Relaxed),
+            written: self.written.load(Ordering::Relaxed),
+            dropped: self.dropped.load(Ordering::Relaxed),
+            spilled: self.spilled.load(Ordering::Relaxed),
+            failed_writes: self.failed_writes.load(Ordering::Relaxed),
+            reconnects: self.reconnects.load(Ordering::Relaxed),
+        }
+    }
+
+    pub const fn new() -> Self {
+        Self {
+            queued: AtomicU64::new(0),
+            written: AtomicU64::new(0),
+            dropped: AtomicU64::new(0),
+            spilled: AtomicU64::new(0),
+            failed_writes: AtomicU64::new(0),
+            reconnects: AtomicU64::new(0),
+        }
+    }
+}
+
+/// Counters of the logger started by [`background_logger`]
+pub static LOGGER_STATS: LoggerStats = LoggerStats::new();
+
+type SpillBuffer = Arc<Mutex<VecDeque<LogEntry>>>;
+
+/// Hands entries to the background logger, cheap to clone
+#[derive(Clone)]
+pub struct LogQueue {
+    sender: Sender<LogEntry>,
+    spill: Option<SpillBuffer>,
+    spill_capacity: usize,
+    stats: &'static LoggerStats,
+}
+
+impl LogQueue {
+    /// Queues `entry` without waiting, see [`OverflowPolicy`]
+    pub fn submit(&self, entry: LogEntry) {
+        // Counted first so that the writer never sees more written than queued
+        self.stats.queued.fetch_add(1, Ordering::Relaxed);
+
+        let entry = match self.sender.try_send(entry) {
+            Ok(()) => return,
+            Err(TrySendError::Full(entry)) => entry,
+            Err(TrySendError::Closed(_)) => {
+                self.stats.queued.fetch_sub(1, Ordering::Relaxed);
+                self.stats.dropped.fetch_add(1, Ordering::Relaxed);
+                return;
             }
-            None => {
-                info!("{}, No Auth present for this request", log);
+        };
+
+        if let Some(spill) = &self.spill {
+            let mut spill = spill
+                .lock()
+                .unwrap_or_else(|poisoned| poisoned.into_inner());
+            if spill.len() < self.spill_capacity {
+                spill.push_back(entry);
+                self.stats.spilled.fetch_add(1, Ordering::Relaxed);
+                return;
             }
-        }*/
+        }
+
+        self.stats.queued.fetch_sub(1, Ordering::Relaxed);
+        self.stats.dropped.fetch_add(1, Ordering::Relaxed);
+    }
+}
+
+/// The running logger task. Dropping it leaves the task running until every
+/// [`LogQueue`] is dropped.
+pub struct BackgroundLogger {
+    queue: LogQueue,
+    shutdown: oneshot::Sender<()>,
+    task: JoinHandle<()>,
+}
+
+impl BackgroundLogger {
+    pub fn queue(&self) -> LogQueue {
+        self.queue.clone()
+    }
+
+    /// Stops accepting entries and waits until the queued ones are written
+    pub async fn shutdown(self) {
+        let _ = self.shutdown.send(());
 
-        let _: Option<LogEntry> = db
-            .create("log")
-            .content(log)
+        if tokio::time::timeout(DRAIN_TIMEOUT, self.task)
             .await
-            .expect("Unable to insert log");
+            .is_err()
+        {
+            warn!(
+                "The log queue was not written within {:?}, {} entries are lost",
+                DRAIN_TIMEOUT,
+                self.queue.stats.queued.load(Ordering::Relaxed)
+            );
+        }
+    }
+}
+
+/// Starts the background logger with the configured queue
+pub fn background_logger(config: &LoggerConfig) -> BackgroundLogger {
+    start_logger(config.clone(), &LOGGER_STATS)
+}
+
+pub(crate) fn start_logger(config: LoggerConfig, stats: &'static LoggerStats) -> BackgroundLogger {
+    let (sender, receiver) = mpsc::channel::<LogEntry>(config.queue_size);
+    let (shutdown, shutdown_receiver) = oneshot::channel();
+
+    let spill =
+        (config.overflow == OverflowPolicy::Spill).then(|| Arc::new(Mutex::new(VecDeque::new())));
+    let queue = LogQueue {
+        sender,
+        spill: spill.clone(),
+        spill_capacity: config.spill_capacity,
+        stats,
+    };
+
+    let writer = Writer {
+        db: None,
+        last_connect: None,
+        batch: Vec::with_capacity(config.batch_size),
+        attempts: 0,
+        spill,
+        stats,
+        config,
+    };
+    let task = tokio::spawn(writer.run(receiver, shutdown_receiver));
+
+    BackgroundLogger {
+        queue,
+        shutdown,
+        task,
+    }
+}
+
+struct Writer {
+    db: Option<Surreal<Client>>,
+    last_connect: Option<Instant>,
+    batch: Vec<LogEntry>,
+    /// Failed attempts to write the current batch
+    attempts: u32,
+    spill: Option<SpillBuffer>,
+    stats: &'static LoggerStats,
+    config: LoggerConfig,
+}
+
+impl Writer {
+    async fn run(mut self, mut receiver: Receiver<LogEntry>, mut shutdown: oneshot::Receiver<()>) {
+        let mut flush = tokio::time::interval(self.config.flush_interval);
+
+        loop {
+            tokio::select! {
+                entry = receiver.recv() => match entry {
+                    Some(entry) => {
+                        self.batch.push(entry);
+                        if self.batch.len() >= self.config.batch_size {
+                            self.flush().await;
+                        }
+                    }
+                    None => break,
+                },
+                _ = flush.tick() => {
+                    self.take_spilled();
+                    self.flush().await;
+                }
+                // Dropping the handle without shutting down leaves the logger running
+                Ok(()) = &mut shutdown => break,
+            }
+        }
+
+        // Entries sent before the queue closed are still written
+        receiver.close();
+        while let Some(entry) = receiver.recv().await {
+            self.batch.push(entry);
+        }
+        self.take_spilled();
+
+        for _ in 0..MAX_BATCH_ATTEMPTS {
+            self.flush().await;
+            if self.batch.is_empty() {
+                break;
+            }
+            tokio::time::sleep(RECONNECT_DELAY).await;
+        }
+        if !self.batch.is_empty() {
+            self.drop_entries(self.batch.len());
+        }
+
+        info!("Background logger stopped");
+    }
+
+    /// Moves spilled entries into the batch while the queue has room again
+    fn take_spilled(&mut self) {
+        let Some(spill) = &self.spill else {
+            return;
+        };
+        let mut spill = spill
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
+        let room = self.config.queue_size.saturating_sub(self.batch.len());
+        let count = spill.len().min(room);
+
+        self.batch.extend(spill.drain(..count));
+    }
+
+    /// Connects to the database unless connected, at most every [`RECONNECT_DELAY`]. Returns
+    /// whether there is a connection.
+    async fn connect(&mut self) -> bool {
+        if self.db.is_some() {
+            return true;
+        }
+        if self
+            .last_connect
+            .is_some_and(|last| last.elapsed() < RECONNECT_DELAY)
+        {
+            return false;
+        }
+
+        let reconnect = self.last_connect.is_some();
+        self.last_connect = Some(Instant::now());
+
+        match db("default", "log").await {
+            Ok(db) => {
+                if let Err(err) = db
+                    .query(PREPARE_LOG_TABLE)
+                    .observed()
+                    .await
+                    .and_then(|res| res.check())
+                {
+                    warn!("Failed to prepare the log table: {}", err);
+                }
+                if reconnect {
+                    self.stats.reconnects.fetch_add(1, Ordering::Relaxed);
+                    info!("Background logger reconnected to the database");
+                }
+                self.db = Some(db);
+                true
+            }
+            Err(err) => {
+                error!("Background logger cannot connect to the database: {}", err);
+                false
+            }
+        }
+    }
+
+    async fn flush(&mut self) {
+        if self.batch.is_empty() {
+            return;
+        }
+
+        if !self.connect().await {
+            self.limit_batch();
+            return;
+        }
+        let Some(db) = &self.db else {
+            return;
+        };
+
+        const SQL: &str = "INSERT INTO log $ENTRIES RETURN NONE;";
+        let result = db
+            .query(SQL)
+            .bind(("ENTRIES", self.batch.clone()))
+            .observed()
+            .await
+            .and_then(|res| res.check());
+
+        match result {
+            Ok(_) => {
+                let written = self.batch.len() as u64;
+                self.stats.written.fetch_add(written, Ordering::Relaxed);
+                self.stats.queued.fetch_sub(written, Ordering::Relaxed);
+                self.batch.clear();
+                self.attempts = 0;
+            }
+            Err(err) => {
+                error!("Failed to write {} log entries: {}", self.batch.len(), err);
+                self.stats.failed_writes.fetch_add(1, Ordering::Relaxed);
+                self.attempts += 1;
+
+                // The connection may be gone, a fresh one is used next time
+                self.db = None;
+
+                if self.attempts >= MAX_BATCH_ATTEMPTS {
+                    self.drop_entries(self.batch.len());
+                    self.attempts = 0;
+                } else {
+                    self.limit_batch();
+                }
+            }
+        }
+    }
+
+    /// Drops the oldest entries while the database is unavailable so that the
+    /// batch does not grow beyond the size of the queue
+    fn limit_batch(&mut self) {
+        let excess = self.batch.len().saturating_sub(self.config.queue_size);
+        self.drop_entries(excess);
+    }
+
+    fn drop_entries(&mut self, count: usize) {
+        if count == 0 {
+            return;
+        }
+
+        warn!("Dropping {} log entries", count);
+        self.batch.drain(..count);
+        self.stats
+            .dropped
+            .fetch_add(count as u64, Ordering::Relaxed);
+        self.stats.queued.fetch_sub(count as u64, Ordering::Relaxed);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{LoggerConfig, OverflowPolicy};
+    use std::time::Duration;
+
+    #[test]
+    fn logger_config_is_read_from_the_environment() {
+        let vars = |vars: &'static [(&'static str, &'static str)]| {
+            move |name: &str| {
+                vars.iter()
+                    .find(|(var, _)| *var == name)
+                    .map(|(_, value)| value.to_string())
+            }
+        };
+
+        assert_eq!(
+            LoggerConfig::from_vars(|_| None).unwrap(),
+            LoggerConfig::default()
+        );
+
+        let config = LoggerConfig::from_vars(vars(&[
+            ("LOG_QUEUE_SIZE", "50"),
+            ("LOG_FLUSH_INTERVAL_MS", "250"),
+            ("LOG_OVERFLOW", "Spill"),
+            ("LOG_RETENTION_DAYS", "0"),
+        ]))
+        .unwrap();
+        assert_eq!(config.queue_size, 50);
+        assert_eq!(config.flush_interval, Duration::from_millis(250));
+        assert_eq!(config.overflow, OverflowPolicy::Spill);
+        assert_eq!(config.retention, None);
+
+        assert!(LoggerConfig::from_vars(vars(&[("LOG_BATCH_SIZE", "0")])).is_err());
+        assert!(LoggerConfig::from_vars(vars(&[("LOG_OVERFLOW", "block")])).is_err());
+        assert!(LoggerConfig::from_vars(vars(&[("LOG_RETENTION_DAYS", "soon")])).is_err());
     }
 }
@@ -1,10 +1,101 @@
+use crate::metrics::ObserveQuery;
 use crate::server::db::INTERNAL_DB;
+use crate::server_error::ServerError;
+use serde::Deserialize;
+use surrealdb::sql::Datetime;
+use surrealdb::Surreal;
 use surrealdb_migrations::MigrationRunner;
 use tracing::{error, info};
 
-pub async fn migrations_runner() {
+/// Directory the migration scripts are read from, relative to the working directory
+pub const MIGRATIONS_DIR: &str = "migrations";
+
+/// Directory of the scripts that revert a migration, named like the migration they revert
+pub const DOWN_MIGRATIONS_DIR: &str = "migrations/down";
+
+/// Applies the schemas, events and pending migrations. Fails unless `allow_failure` is set,
+/// see `ALLOW_FAILED_MIGRATIONS`.
+pub async fn migrations_runner(allow_failure: bool) -> Result<(), ServerError> {
     match MigrationRunner::new(&INTERNAL_DB).up().await {
-        Ok(_) => info!("Migrations ran successfully"),
-        Err(e) => error!("Error running migrations: {}", e),
+        Ok(_) => {
+            info!("Migrations ran successfully");
+            Ok(())
+        }
+        Err(e) if allow_failure => {
+            error!(
+                "Error running migrations, starting anyway since ALLOW_FAILED_MIGRATIONS is set: {}",
+                e
+            );
+            Ok(())
+        }
+        Err(e) => Err(ServerError::Error(format!("Error running migrations: {e}"))),
+    }
+}
+
+/// Reverts the applied migrations newer than `to` with their down scripts, `0` reverts all
+pub async fn revert_migrations(to: &str) -> Result<(), ServerError> {
+    MigrationRunner::new(&INTERNAL_DB)
+        .down(to)
+        .await
+        .map_err(|e| ServerError::Error(format!("Error reverting migrations: {e}")))?;
+
+    info!("Migrations reverted to {}", to);
+
+    Ok(())
+}
+
+async fn scripts_in(dir: &str) -> std::io::Result<Vec<String>> {
+    let mut entries = tokio::fs::read_dir(dir).await?;
+    let mut scripts = Vec::new();
+
+    while let Some(entry) = entries.next_entry().await? {
+        let name = entry.file_name().to_string_lossy().into_owned();
+
+        if let Some(script) = name.strip_suffix(".surql") {
+            scripts.push(script.to_string());
+        }
     }
+
+    scripts.sort();
+
+    Ok(scripts)
+}
+
+/// Names of the migration scripts in [`MIGRATIONS_DIR`] without extension, oldest first
+pub async fn migration_scripts() -> std::io::Result<Vec<String>> {
+    scripts_in(MIGRATIONS_DIR).await
+}
+
+/// Names of the down scripts in [`DOWN_MIGRATIONS_DIR`] without extension, oldest first
+pub async fn down_scripts() -> std::io::Result<Vec<String>> {
+    scripts_in(DOWN_MIGRATIONS_DIR).await
+}
+
+/// Names of the migration scripts that were applied to `db`
+pub async fn applied_migrations<C>(db: &Surreal<C>) -> surrealdb::Result<Vec<String>>
+where
+    C: surrealdb::Connection,
+{
+    db.query("SELECT VALUE script_name FROM script_migration")
+        .observed()
+        .await?
+        .take(0)
+}
+
+/// A migration applied to the database, recorded in `script_migration`
+#[derive(Deserialize, Debug, Clone)]
+pub struct AppliedMigration {
+    pub script_name: String,
+    pub executed_at: Datetime,
+}
+
+/// The migrations applied to `db`, oldest first
+pub async fn migration_history<C>(db: &Surreal<C>) -> surrealdb::Result<Vec<AppliedMigration>>
+where
+    C: surrealdb::Connection,
+{
+    db.query("SELECT script_name, executed_at FROM script_migration ORDER BY script_name")
+        .observed()
+        .await?
+        .take(0)
 }
@@ -3,6 +3,8 @@ pub mod background_logger;
 pub mod db;
 pub mod migrations_runner;
 pub mod rate_limiter;
+pub mod scheduler;
+pub mod shutdown;
 pub(crate) mod test;
 
 #[cfg(not(test))]
@@ -10,16 +12,24 @@ use tracing::info;
 pub use migrations_runner::*;
 
 pub use background_logger::*;
+pub use scheduler::*;
+pub use shutdown::*;
+use crate::config::Config;
 use crate::init_env::init_env;
 use crate::server_error::ServerError;
 use db::init_internal_db;
 #[cfg(not(test))]
 use crate::logging::init_tracing;
+use std::sync::Arc;
 
 
-pub async fn setup() -> Result<(), ServerError> {
+/// Loads the `.env` files and the configuration, `args` are the command line flags. Connects
+/// to the database without touching the schema.
+pub async fn connect(args: Vec<String>) -> Result<Arc<Config>, ServerError> {
     init_env()?;
 
+    let config = Config::init(args)?;
+
     #[cfg(not(test))]
     {
         init_tracing()?;
@@ -28,59 +38,68 @@ pub async fn setup() -> Result<(), ServerError> {
 
     init_internal_db().await?;
 
-    migrations_runner().await;
+    Ok(config)
+}
+
+/// Like [`connect`], then runs the migrations. Fails when they do, unless
+/// `ALLOW_FAILED_MIGRATIONS` is set.
+pub async fn setup(args: Vec<String>) -> Result<Arc<Config>, ServerError> {
+    let config = connect(args).await?;
 
-    Ok(())
+    migrations_runner(config.allow_failed_migrations).await?;
+
+    Ok(config)
 }
 
 macro_rules! app {
-    ($state:ident, $limiter:ident, $logger:ident, $frontend_url:ident, $base_url:ident, $key:ident, $cors:ident, $identity:ident) => {
+    ($state:ident, $limiter:ident, $logger:ident, $frontend_url:ident, $base_url:ident, $session:ident, $cors:ident, $identity:ident) => {
         actix_web::App::new()
             .app_data($state.clone())
-            .app_data(actix_web::web::JsonConfig::default().limit(usize::MAX))
+            .app_data($state.files.policy.json_config())
+            .app_data($state.files.policy.multipart_config())
+            .app_data(crate::config::query_config())
+            .app_data(crate::config::path_config())
             //.wrap(AuthMiddleware) // proof of concept, this should be moved into each individual service we want to secure with auth
             .external_resource("frontend", $frontend_url.clone())
             .external_resource("base_url", $base_url.clone())
-            .external_resource("llm", tosic_utils::prelude::env!("LLM_BACKEND", "http://localhost:8000"))
             .service(crate::endpoints::index_scope($limiter, $logger))
+            .wrap(crate::middlewares::metrics::MetricsMiddleware {})
+            .wrap(tracing_actix_web::TracingLogger::<crate::middlewares::trace_context::TraceContextRootSpan>::new())
+            .wrap(crate::middlewares::request_id::RequestIdMiddleware {})
             .wrap($cors)
             .wrap($identity)
-            .wrap(
-                actix_session::SessionMiddleware::builder(actix_session::storage::CookieSessionStore::default(), $key.clone())
-                    .cookie_same_site(actix_web::cookie::SameSite::None)
-                    .session_lifecycle(actix_session::config::PersistentSession::default().session_ttl(actix_web::cookie::time::Duration::hours(1)))
-                    .build(),
-            )
+            .wrap($session.middleware())
+            .wrap(crate::middlewares::session_keys::SessionKeyRotation { keys: $session.keys.clone() })
     };
 }
 pub(crate) use app;
 
 /// A macro for creating the actix web server. this can be used in a `main` function or in tests to setup the basic configuration of the server, it does not start the server!
+/// Requests are logged to the given [`LogQueue`](crate::server::background_logger::LogQueue).
 macro_rules! server {
-    () => {{
-        let log_sender = crate::server::background_logger();
+    ($log_queue:expr) => {{
+        let log_queue = $log_queue;
 
         let crate::server::rate_limiter::RateLimiterData {
             backend,
             max_requests,
             limit_duration,
-        } = crate::server::rate_limiter::RateLimiterData::default();
+        } = crate::server::rate_limiter::RateLimiterData::from_config(&crate::config::Config::global().rate_limit);
 
         let state = crate::state::app_state().await?;
+        crate::server::scheduler(state.clone()).await?;
 
         actix_web::HttpServer::new(move || {
-            let cors = crate::config::cors();
+            let cors = crate::config::cors(&state.config.frontend_url);
             let limiter =
                 crate::config::rate_limiter(backend.clone(), max_requests, limit_duration);
-            let logger = crate::middlewares::logger::LoggingMiddleware::new(log_sender.clone());
-            let identity = actix_identity::IdentityMiddleware::builder()
-                .login_deadline(Some(std::time::Duration::from_hours(1)))
-                .build();
+            let logger = crate::middlewares::logger::LoggingMiddleware::new(log_queue.clone());
+            let identity = state.config.session.identity();
 
-            let frontend_url = tosic_utils::prelude::env!("FRONTEND_URL", "http://localhost:5173");
-            let base_url = tosic_utils::prelude::env!("BASE_URL", "http://localhost:9999");
+            let frontend_url = state.config.frontend_url.clone();
+            let base_url = state.config.base_url.clone();
 
-            let key = actix_web::cookie::Key::generate();
+            let session = state.config.session.clone();
 
             crate::server::app!(
                 state,
@@ -88,7 +107,7 @@ macro_rules! server {
                 logger,
                 frontend_url,
                 base_url,
-                key,
+                session,
                 cors,
                 identity
             )
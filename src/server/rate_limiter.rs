@@ -1,6 +1,6 @@
+use crate::config::settings::RateLimitConfig;
 use actix_extensible_rate_limit::backend::memory::InMemoryBackend;
 use actix_extensible_rate_limit::backend::Backend;
-use tracing::error;
 
 pub struct RateLimiterData<B>
 where
@@ -24,23 +24,10 @@ where
     }
 }
 
-impl Default for RateLimiterData<InMemoryBackend> {
-    fn default() -> Self {
-        let max_requests = tosic_utils::prelude::env!("LIMIT", "100")
-            .parse()
-            .unwrap_or_else(|e| {
-                error!("Failed to parse {}: {}", "LIMIT", e);
-                10
-            });
-        let limit_duration = tosic_utils::prelude::env!("LIMIT_DURATION", "30")
-            .parse()
-            .unwrap_or_else(|e| {
-                error!("Failed to parse {}: {}", "LIMIT_DURATION", e);
-                60
-            });
-
+impl RateLimiterData<InMemoryBackend> {
+    pub fn from_config(config: &RateLimitConfig) -> Self {
         let backend = InMemoryBackend::builder().build();
 
-        Self::new(backend, max_requests, limit_duration)
+        Self::new(backend, config.max_requests, config.duration.as_secs())
     }
 }
@@ -0,0 +1,68 @@
+use crate::models::job::JobName;
+use crate::server_error::ServerError;
+use crate::services::jobs::{JobSchedule, JOBS};
+use crate::state::AppState;
+use actix_web::web;
+use once_cell::sync::OnceCell;
+use std::future::Future;
+use std::pin::Pin;
+use tokio_cron_scheduler::{Job, JobScheduler, JobSchedulerError};
+use tracing::{error, info};
+
+static SCHEDULER: OnceCell<JobScheduler> = OnceCell::new();
+
+fn tick(job: JobName, state: web::Data<AppState>) -> Pin<Box<dyn Future<Output = ()> + Send>> {
+    Box::pin(async move { JOBS.run_scheduled(job, state).await })
+}
+
+/// Starts the [background jobs](crate::services::jobs) on the schedules of the
+/// [`Config`](crate::config::Config), jobs scheduled as `manual` are only run by admins
+pub async fn scheduler(state: web::Data<AppState>) -> Result<(), ServerError> {
+    JOBS.init(&state)
+        .await
+        .map_err(|err| ServerError::Error(format!("Failed to load the jobs: {err}")))?;
+
+    let failed = |job: JobName| {
+        move |err: JobSchedulerError| {
+            ServerError::Error(format!("Failed to schedule job {job}: {err:?}"))
+        }
+    };
+    let scheduler = JobScheduler::new()
+        .await
+        .map_err(|err| ServerError::Error(format!("Failed to create the scheduler: {err:?}")))?;
+
+    for job in JobName::ALL {
+        let schedule = state.config.jobs.schedule(job);
+        let state = state.clone();
+        let scheduled = match schedule {
+            JobSchedule::Cron(expression) => {
+                Job::new_async(expression.as_str(), move |_, _| tick(job, state.clone()))
+            }
+            JobSchedule::Every(interval) => {
+                Job::new_repeated_async(*interval, move |_, _| tick(job, state.clone()))
+            }
+            JobSchedule::Manual => continue,
+        }
+        .map_err(failed(job))?;
+
+        scheduler.add(scheduled).await.map_err(failed(job))?;
+        info!("Job {} scheduled: {}", job, schedule);
+    }
+
+    scheduler
+        .start()
+        .await
+        .map_err(|err| ServerError::Error(format!("Failed to start the scheduler: {err:?}")))?;
+    let _ = SCHEDULER.set(scheduler);
+
+    Ok(())
+}
+
+/// Stops scheduling runs, runs in progress continue
+pub async fn stop_scheduler() {
+    if let Some(scheduler) = SCHEDULER.get() {
+        if let Err(err) = scheduler.clone().shutdown().await {
+            error!("Failed to stop the scheduler: {:?}", err);
+        }
+    }
+}
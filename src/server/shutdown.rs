@@ -0,0 +1,45 @@
+use crate::services::jobs::JOBS;
+use actix_web::dev::ServerHandle;
+use tracing::{info, warn};
+
+async fn signal() {
+    #[cfg(unix)]
+    {
+        use tokio::signal::unix::{signal, SignalKind};
+
+        match signal(SignalKind::terminate()) {
+            Ok(mut terminate) => {
+                tokio::select! {
+                    _ = terminate.recv() => {}
+                    _ = tokio::signal::ctrl_c() => {}
+                }
+            }
+            Err(err) => {
+                warn!("Cannot listen for SIGTERM: {}", err);
+                let _ = tokio::signal::ctrl_c().await;
+            }
+        }
+    }
+
+    #[cfg(not(unix))]
+    let _ = tokio::signal::ctrl_c().await;
+}
+
+/// On SIGTERM or Ctrl-C the server stops accepting connections, no job run starts anymore and
+/// in-flight requests get the shutdown timeout of the server to finish. A second signal stops
+/// the server right away.
+pub fn shutdown_on_signal(handle: ServerHandle) {
+    tokio::spawn(async move {
+        signal().await;
+        info!("Shutting down, waiting for in-flight requests");
+
+        JOBS.close();
+        tokio::select! {
+            _ = handle.stop(true) => {}
+            _ = signal() => {
+                warn!("Shutting down immediately");
+                handle.stop(false).await;
+            }
+        }
+    });
+}
@@ -2,29 +2,27 @@
 
 macro_rules! init_test_app {
     () => {{
-        crate::server::setup().await?;
+        let config = crate::server::setup(Vec::new()).await?;
 
-        let log_sender = crate::server::background_logger();
+        let log_queue = crate::server::background_logger(&config.logger).queue();
 
         let crate::server::rate_limiter::RateLimiterData {
             backend,
             max_requests,
             limit_duration,
-        } = crate::server::rate_limiter::RateLimiterData::default();
+        } = crate::server::rate_limiter::RateLimiterData::from_config(&config.rate_limit);
 
         let state = crate::state::app_state().await?;
 
-        let cors = crate::config::cors();
+        let cors = crate::config::cors(&config.frontend_url);
         let limiter = crate::config::rate_limiter(backend.clone(), max_requests, limit_duration);
-        let logger = crate::middlewares::logger::LoggingMiddleware::new(log_sender.clone());
-        let identity = actix_identity::IdentityMiddleware::builder()
-            .login_deadline(Some(std::time::Duration::from_hours(1)))
-            .build();
+        let logger = crate::middlewares::logger::LoggingMiddleware::new(log_queue.clone());
+        let identity = config.session.identity();
 
-        let frontend_url = tosic_utils::prelude::env!("FRONTEND_URL", "http://localhost:5173");
-        let base_url = tosic_utils::prelude::env!("BASE_URL", "http://localhost:9999");
+        let frontend_url = config.frontend_url.clone();
+        let base_url = config.base_url.clone();
 
-        let key = actix_web::cookie::Key::generate();
+        let session = config.session.clone();
 
         actix_web::test::init_service(crate::server::app!(
             state,
@@ -32,7 +30,7 @@ macro_rules! init_test_app {
             logger,
             frontend_url,
             base_url,
-            key,
+            session,
             cors,
             identity
         ))
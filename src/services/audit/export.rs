@@ -0,0 +1,81 @@
+use crate::models::audit::AuditLogEntry;
+use crate::services::export::csv::escape;
+
+const HEADER: [&str; 14] = [
+    "date",
+    "request_id",
+    "user",
+    "auth_method",
+    "method",
+    "path",
+    "query",
+    "action",
+    "target",
+    "status",
+    "outcome",
+    "latency_ms",
+    "ip",
+    "user_agent",
+];
+
+/// Renders `entries` as RFC 4180 CSV, one row per entry
+pub fn render_csv(entries: &[AuditLogEntry]) -> String {
+    let mut out = String::new();
+    write_row(&mut out, HEADER.map(String::from));
+
+    for entry in entries {
+        write_row(
+            &mut out,
+            [
+                entry.date.to_raw(),
+                entry.request_id.clone().unwrap_or_default(),
+                entry
+                    .user
+                    .as_ref()
+                    .map(|user| user.to_raw())
+                    .unwrap_or_default(),
+                entry.auth_method.clone().unwrap_or_default(),
+                entry.method.clone(),
+                entry.path.clone(),
+                entry.query.clone().unwrap_or_default(),
+                entry.action.clone().unwrap_or_default(),
+                entry.target.clone().unwrap_or_default(),
+                entry.status.to_string(),
+                entry
+                    .outcome
+                    .map(|outcome| outcome.as_str().to_string())
+                    .unwrap_or_default(),
+                entry
+                    .latency_ms
+                    .map(|latency| latency.to_string())
+                    .unwrap_or_default(),
+                entry.ip.clone().unwrap_or_default(),
+                entry.user_agent.clone().unwrap_or_default(),
+            ],
+        );
+    }
+
+    out
+}
+
+fn write_row(out: &mut String, fields: impl IntoIterator<Item = String>) {
+    for (i, field) in fields.into_iter().enumerate() {
+        if i > 0 {
+            out.push(',');
+        }
+        out.push_str(&escape(&field));
+    }
+    out.push_str("\r\n");
+}
+
+/// Renders `entries` as JSON Lines, one object per entry
+pub fn render_jsonl(entries: &[AuditLogEntry]) -> Result<String, serde_json::Error> {
+    let mut out = String::new();
+
+    for entry in entries {
+        out.push_str(&serde_json::to_string(entry)?);
+        out.push('\n');
+    }
+
+    Ok(out)
+}
@@ -0,0 +1,116 @@
+//! The audit trail. Every `/api/v1` request is recorded by the
+//! [`LoggingMiddleware`](crate::middlewares::logger::LoggingMiddleware) in the `log` table of the
+//! separate `default/log` database with the resolved user, the action, the target record, the
+//! outcome, timing and client details. Credentials never reach the table: only the kind of
+//! authentication is kept and sensitive query parameters are redacted.
+
+pub mod export;
+pub mod query;
+pub mod retention;
+mod test;
+
+use crate::models::audit::Outcome;
+
+/// Replaces the values of query parameters that may carry credentials
+pub const REDACTED: &str = "[REDACTED]";
+
+/// Query parameters whose values are never recorded, matched case-insensitively against the
+/// parameter name or its last `_` separated part
+const SENSITIVE_PARAMETERS: [&str; 11] = [
+    "token",
+    "access_token",
+    "refresh_token",
+    "api_key",
+    "apikey",
+    "key",
+    "password",
+    "secret",
+    "code",
+    "state",
+    "signature",
+];
+
+/// Longest user agent that is recorded
+pub const MAX_USER_AGENT_LEN: usize = 512;
+
+/// Run once when the logger connects, indexes the columns the audit query filters on and strips
+/// the credentials that entries written before the audit trail kept in `user`
+pub const PREPARE_LOG_TABLE: &str = "
+    DEFINE INDEX IF NOT EXISTS log_date_index ON log FIELDS date;
+    DEFINE INDEX IF NOT EXISTS log_user_index ON log FIELDS user;
+    DEFINE INDEX IF NOT EXISTS log_action_index ON log FIELDS action;
+    DEFINE INDEX IF NOT EXISTS log_target_index ON log FIELDS target;
+    UPDATE log SET user = NONE WHERE user != NONE AND !type::is::record(user);
+";
+
+fn is_sensitive(name: &str) -> bool {
+    let name = name.to_lowercase();
+    let last = name.rsplit(['_', '-']).next().unwrap_or(&name);
+
+    SENSITIVE_PARAMETERS
+        .iter()
+        .any(|sensitive| name == *sensitive || last == *sensitive)
+}
+
+/// Returns `query` with the values of sensitive parameters replaced by [`REDACTED`], `None` if
+/// there is no query
+pub fn redact_query(query: &str) -> Option<String> {
+    if query.is_empty() {
+        return None;
+    }
+
+    let redacted: Vec<String> = query
+        .split('&')
+        .filter(|pair| !pair.is_empty())
+        .map(|pair| match pair.split_once('=') {
+            Some((name, _)) if is_sensitive(name) => format!("{name}={REDACTED}"),
+            _ => pair.to_string(),
+        })
+        .collect();
+
+    Some(redacted.join("&"))
+}
+
+/// Derives the record a request acts on from its route parameters, e.g. `file:abc` for
+/// `/files/{file_id}` with `file_id = abc`. The innermost `{table}_id` parameter wins, so
+/// `/files/{file_id}/shares/{share_id}` targets the share.
+pub fn target_of<'a>(parameters: impl IntoIterator<Item = (&'a str, &'a str)>) -> Option<String> {
+    parameters
+        .into_iter()
+        .filter_map(|(name, value)| {
+            let table = name.strip_suffix("_id")?;
+            let table = match table {
+                "share" => "file_share",
+                "invitation" => "team_invitation",
+                "model" => "threat_model",
+                "upload" => "upload_session",
+                table => table,
+            };
+            Some(format!("{table}:{value}"))
+        })
+        .last()
+}
+
+/// The action of a request, its method and route, e.g. `DELETE /api/v1/files/{file_id}`.
+/// Requests that matched no route use their path.
+pub fn action_of(method: &str, route: Option<&str>, path: &str) -> String {
+    format!("{method} {}", route.unwrap_or(path))
+}
+
+impl Outcome {
+    pub fn from_status(status: u16) -> Self {
+        match status {
+            401 | 403 => Self::Denied,
+            400.. => Self::Failure,
+            _ => Self::Success,
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Success => "success",
+            Self::Denied => "denied",
+            Self::Failure => "failure",
+        }
+    }
+}
@@ -0,0 +1,112 @@
+use crate::dto::audit::AuditQuery;
+use crate::error::ServerResponseError;
+use crate::metrics::ObserveQuery;
+use crate::models::audit::{AuditLogEntry, AuditLogPage};
+use std::sync::Arc;
+use surrealdb::Surreal;
+
+/// Entries per page unless the query asks for a different number
+pub const DEFAULT_PAGE_SIZE: u64 = 50;
+
+/// Most entries returned on one page
+pub const MAX_PAGE_SIZE: u64 = 500;
+
+/// Most entries in one export, narrow the time range for more
+pub const MAX_EXPORT_ENTRIES: u64 = 100_000;
+
+/// Unset filters are bound as `NONE` and match everything
+const FILTER: &str = "
+    ($USER = NONE OR user = type::thing('user', $USER))
+    AND ($ACTION = NONE OR action = $ACTION)
+    AND ($TARGET = NONE OR target = $TARGET)
+    AND ($OUTCOME = NONE OR outcome = $OUTCOME)
+    AND ($STATUS = NONE OR status = $STATUS)
+    AND ($FROM = NONE OR date >= $FROM)
+    AND ($TO = NONE OR date < $TO)
+";
+
+/// Reads the entries of the audit trail in `db` matching `query`, the newest first. Returns at
+/// most `limit` entries after skipping `offset` and the number of all matching entries.
+async fn select_entries<T>(
+    db: &Arc<Surreal<T>>,
+    query: &AuditQuery,
+    limit: u64,
+    offset: u64,
+) -> Result<(Vec<AuditLogEntry>, u64), ServerResponseError>
+where
+    T: surrealdb::Connection,
+{
+    let sql = format!(
+        "SELECT * FROM log WHERE {FILTER} ORDER BY date DESC LIMIT $LIMIT START $START;
+        RETURN (SELECT count() FROM log WHERE {FILTER} GROUP ALL)[0].count;"
+    );
+
+    let user = query
+        .user
+        .as_deref()
+        .map(|user| user.strip_prefix("user:").unwrap_or(user).to_string());
+
+    let mut response = db
+        .query(sql)
+        .bind(("USER", user))
+        .bind(("ACTION", query.action.clone()))
+        .bind(("TARGET", query.target.clone()))
+        .bind(("OUTCOME", query.outcome))
+        .bind(("STATUS", query.status))
+        .bind(("FROM", query.from.map(surrealdb::sql::Datetime::from)))
+        .bind(("TO", query.to.map(surrealdb::sql::Datetime::from)))
+        .bind(("LIMIT", limit))
+        .bind(("START", offset))
+        .observed()
+        .await?;
+
+    let entries: Vec<AuditLogEntry> = response.take(0)?;
+    let total: Option<u64> = response.take(1)?;
+
+    Ok((entries, total.unwrap_or(0)))
+}
+
+/// Returns the page of the audit trail in `db` selected by `query`
+pub async fn query_audit_log<T>(
+    db: &Arc<Surreal<T>>,
+    query: &AuditQuery,
+) -> Result<AuditLogPage, ServerResponseError>
+where
+    T: surrealdb::Connection,
+{
+    let limit = query
+        .limit
+        .unwrap_or(DEFAULT_PAGE_SIZE)
+        .clamp(1, MAX_PAGE_SIZE);
+    let offset = query.offset.unwrap_or(0);
+
+    let (entries, total) = select_entries(db, query, limit, offset).await?;
+
+    Ok(AuditLogPage {
+        entries,
+        total,
+        limit,
+        offset,
+    })
+}
+
+/// Returns all entries of the audit trail in `db` matching `query` for an
+/// export, ignoring its pagination. Fails if there are more than
+/// [`MAX_EXPORT_ENTRIES`].
+pub async fn export_audit_log<T>(
+    db: &Arc<Surreal<T>>,
+    query: &AuditQuery,
+) -> Result<Vec<AuditLogEntry>, ServerResponseError>
+where
+    T: surrealdb::Connection,
+{
+    let (entries, total) = select_entries(db, query, MAX_EXPORT_ENTRIES, 0).await?;
+
+    if total > MAX_EXPORT_ENTRIES {
+        return Err(ServerResponseError::BadRequest(format!(
+            "{total} entries match, at most {MAX_EXPORT_ENTRIES} can be exported at once, narrow the time range"
+        )));
+    }
+
+    Ok(entries)
+}
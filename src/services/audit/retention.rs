@@ -0,0 +1,24 @@
+use crate::metrics::ObserveQuery;
+use chrono::Utc;
+use std::time::Duration;
+use surrealdb::Surreal;
+
+/// Deletes entries of the audit trail older than `retention` and returns how many there were,
+/// run by the `log_retention` job
+pub async fn purge_log<C>(db: &Surreal<C>, retention: Duration) -> surrealdb::Result<usize>
+where
+    C: surrealdb::Connection,
+{
+    let cutoff =
+        Utc::now() - chrono::Duration::from_std(retention).unwrap_or(chrono::Duration::zero());
+
+    const SQL: &str = "RETURN array::len((DELETE log WHERE date < $CUTOFF RETURN BEFORE));";
+    let deleted: Option<usize> = db
+        .query(SQL)
+        .bind(("CUTOFF", surrealdb::sql::Datetime::from(cutoff)))
+        .observed()
+        .await?
+        .take(0)?;
+
+    Ok(deleted.unwrap_or_default())
+}
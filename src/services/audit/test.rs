@@ -0,0 +1,60 @@
+#![cfg(test)]
+
+use super::{action_of, redact_query, target_of, REDACTED};
+use crate::models::audit::Outcome;
+
+#[test]
+fn credentials_in_queries_are_redacted() {
+    assert_eq!(redact_query(""), None);
+    assert_eq!(
+        redact_query("team=abc&limit=10").as_deref(),
+        Some("team=abc&limit=10")
+    );
+    assert_eq!(
+        redact_query("code=4%2F0Ad&state=xyz&scope=email").unwrap(),
+        format!("code={REDACTED}&state={REDACTED}&scope=email")
+    );
+    assert_eq!(
+        redact_query("expires=1730000000&signature=ab12&Access_Token=t").unwrap(),
+        format!("expires=1730000000&signature={REDACTED}&Access_Token={REDACTED}")
+    );
+    assert_eq!(
+        redact_query("x-api-key=k&flag").unwrap(),
+        format!("x-api-key={REDACTED}&flag")
+    );
+}
+
+#[test]
+fn targets_come_from_the_innermost_id_parameter() {
+    let no_parameters: [(&str, &str); 0] = [];
+    assert_eq!(target_of(no_parameters), None);
+    assert_eq!(target_of([("version", "3")]), None);
+    assert_eq!(target_of([("file_id", "abc")]).as_deref(), Some("file:abc"));
+    assert_eq!(
+        target_of([("file_id", "abc"), ("share_id", "s1")]).as_deref(),
+        Some("file_share:s1")
+    );
+    assert_eq!(
+        target_of([("model_id", "m1"), ("version", "2")]).as_deref(),
+        Some("threat_model:m1")
+    );
+}
+
+#[test]
+fn actions_and_outcomes_are_derived() {
+    assert_eq!(
+        action_of(
+            "DELETE",
+            Some("/api/v1/files/{file_id}"),
+            "/api/v1/files/abc"
+        ),
+        "DELETE /api/v1/files/{file_id}"
+    );
+    assert_eq!(action_of("GET", None, "/api/v1/nope"), "GET /api/v1/nope");
+
+    assert_eq!(Outcome::from_status(204), Outcome::Success);
+    assert_eq!(Outcome::from_status(304), Outcome::Success);
+    assert_eq!(Outcome::from_status(403), Outcome::Denied);
+    assert_eq!(Outcome::from_status(409), Outcome::Failure);
+    assert_eq!(Outcome::from_status(500), Outcome::Failure);
+}
@@ -1,4 +1,5 @@
 use crate::auth::oauth::provider::{OauthProvider, OauthProviderName};
+use crate::metrics::ObserveQuery;
 use crate::models::auth_for::AuthForRelation;
 use crate::models::Record;
 use crate::server::db::INTERNAL_DB;
@@ -25,6 +26,7 @@ pub(crate) async fn create_auth_for_user(
             .query(sql)
             .bind(("providers", providers_ids))
             .bind(("password", password))
+            .observed()
             .await?
     } else {
         let sql = "CREATE user_auth set providers = $providers";
@@ -32,6 +34,7 @@ pub(crate) async fn create_auth_for_user(
         INTERNAL_DB
             .query(sql)
             .bind(("providers", providers_ids))
+            .observed()
             .await?
     };
 
@@ -43,6 +46,7 @@ pub(crate) async fn create_auth_for_user(
             .query(query)
             .bind(("user_auth", user_auth.id))
             .bind(("user_id", user_id))
+            .observed()
             .await?;
     } else {
         bail!("Error creating user_auth");
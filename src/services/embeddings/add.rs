@@ -1,4 +1,5 @@
 use crate::error::ServerResponseError;
+use crate::metrics::ObserveQuery;
 use crate::models::{Entry, EntryType};
 use std::sync::Arc;
 use surrealdb::Surreal;
@@ -12,6 +13,10 @@ pub async fn insert_embeddings<T>(
 where
     T: surrealdb::Connection,
 {
-    let embeddings: Vec<Entry> = db.insert(entry_type.to_string()).content(entries).await?;
+    let embeddings: Vec<Entry> = db
+        .insert(entry_type.to_string())
+        .content(entries)
+        .observed()
+        .await?;
     Ok(embeddings)
 }
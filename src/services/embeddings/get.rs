@@ -0,0 +1,34 @@
+use crate::error::ServerResponseError;
+use crate::metrics::ObserveQuery;
+use crate::models::MITREEntry;
+use crate::services::embeddings::EntryType;
+use std::sync::Arc;
+use surrealdb::Surreal;
+
+/// Returns the entries whose MITRE ID is in `mitre_ids`, IDs without an
+/// entry are left out
+pub async fn get_entries_by_mitre_id<T>(
+    db: &Arc<Surreal<T>>,
+    entry_type: EntryType,
+    mitre_ids: Vec<String>,
+) -> Result<Vec<MITREEntry>, ServerResponseError>
+where
+    T: surrealdb::Connection,
+{
+    if mitre_ids.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    let sql = format!(
+        "SELECT mitre_id, mitre_name, mitre_description, mitre_url FROM {} WHERE mitre_id IN $IDS;",
+        entry_type
+    );
+    let entries: Vec<MITREEntry> = db
+        .query(sql)
+        .bind(("IDS", mitre_ids))
+        .observed()
+        .await?
+        .take(0)?;
+
+    Ok(entries)
+}
@@ -3,6 +3,8 @@ use std::fmt::Display;
 use crate::models::EntryType;
 
 pub mod add;
+pub mod get;
+pub mod reembed;
 pub mod search;
 
 impl Display for EntryType {
@@ -0,0 +1,209 @@
+//! Computes the embeddings of MITRE entries again, e.g. after the embedding model changed.
+//! Entries remember the model that embedded them in `embedding_model`, a run embeds every entry
+//! whose model differs from `EMBEDDING_MODEL`, including entries added through the API.
+
+use crate::config::Secret;
+use crate::error::ServerResponseError;
+use crate::metrics::ObserveQuery;
+use crate::models::EntryType;
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use std::time::Duration;
+use surrealdb::sql::Thing;
+use surrealdb::Surreal;
+
+/// Dimension of the vector indexes on `threat` and `mitigation`
+pub const EMBEDDING_DIMENSION: usize = 384;
+
+/// Entries sent to the embedding API at once
+const BATCH_SIZE: usize = 64;
+
+/// Entries embedded by one run at most, the next run continues
+const MAX_ENTRIES_PER_RUN: usize = 5_000;
+
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// An OpenAI-compatible embedding API
+///
+/// - `EMBEDDING_URL` - base URL, requests go to `{EMBEDDING_URL}/embeddings`
+/// - `EMBEDDING_MODEL` - required with `EMBEDDING_URL`, has to produce 384 dimensions
+/// - `EMBEDDING_API_KEY` - sent as bearer token if set
+#[derive(Debug, Clone)]
+pub struct EmbeddingConfig {
+    pub url: String,
+    pub model: String,
+    pub api_key: Option<Secret>,
+}
+
+impl EmbeddingConfig {
+    /// `None` without `EMBEDDING_URL`, the `reembed` job is skipped then
+    pub(crate) fn from_vars(var: impl Fn(&str) -> Option<String>) -> Result<Option<Self>, String> {
+        let Some(url) = var("EMBEDDING_URL") else {
+            return Ok(None);
+        };
+        let model = var("EMBEDDING_MODEL")
+            .ok_or_else(|| "EMBEDDING_MODEL is required with EMBEDDING_URL".to_string())?;
+
+        Ok(Some(Self {
+            url: url.trim_end_matches('/').to_string(),
+            model,
+            api_key: var("EMBEDDING_API_KEY").map(Secret::new),
+        }))
+    }
+}
+
+#[derive(Deserialize, Debug)]
+struct StaleEntry {
+    id: Thing,
+    mitre_name: String,
+    mitre_description: String,
+}
+
+#[derive(Serialize, Debug)]
+struct EmbedRequest<'a> {
+    model: &'a str,
+    input: Vec<String>,
+}
+
+#[derive(Deserialize, Debug)]
+struct EmbedResponse {
+    data: Vec<EmbedData>,
+}
+
+#[derive(Deserialize, Debug)]
+struct EmbedData {
+    index: usize,
+    embedding: Vec<f32>,
+}
+
+#[derive(Serialize, Debug)]
+struct EmbeddedEntry {
+    id: Thing,
+    embedding: Vec<f32>,
+}
+
+/// Embeds every entry that was not embedded with the configured model, returns how many were
+pub async fn reembed<T>(
+    db: &Arc<Surreal<T>>,
+    config: &EmbeddingConfig,
+) -> Result<usize, ServerResponseError>
+where
+    T: surrealdb::Connection,
+{
+    let client = reqwest::Client::builder()
+        .timeout(REQUEST_TIMEOUT)
+        .build()
+        .map_err(|err| ServerResponseError::InternalError(err.to_string()))?;
+    let mut embedded = 0;
+
+    for entry_type in [EntryType::Threat, EntryType::Mitigation] {
+        while embedded < MAX_ENTRIES_PER_RUN {
+            let sql = format!(
+                "SELECT id, mitre_name, mitre_description FROM {entry_type} \
+                 WHERE embedding_model != $MODEL LIMIT {BATCH_SIZE};"
+            );
+            let entries: Vec<StaleEntry> = db
+                .query(sql)
+                .bind(("MODEL", config.model.clone()))
+                .observed()
+                .await?
+                .take(0)?;
+            if entries.is_empty() {
+                break;
+            }
+
+            let input = entries
+                .iter()
+                .map(|entry| format!("{}: {}", entry.mitre_name, entry.mitre_description))
+                .collect();
+            let embeddings = embed(&client, config, input).await?;
+
+            let entries: Vec<EmbeddedEntry> = entries
+                .into_iter()
+                .zip(embeddings)
+                .map(|(entry, embedding)| EmbeddedEntry {
+                    id: entry.id,
+                    embedding,
+                })
+                .collect();
+            embedded += entries.len();
+
+            const SQL: &str = "
+                FOR $ENTRY IN $ENTRIES {
+                    UPDATE $ENTRY.id SET embedding = $ENTRY.embedding, embedding_model = $MODEL;
+                };
+            ";
+            db.query(SQL)
+                .bind(("ENTRIES", entries))
+                .bind(("MODEL", config.model.clone()))
+                .observed()
+                .await?
+                .check()?;
+        }
+    }
+
+    Ok(embedded)
+}
+
+/// One embedding per input, in the order of the input
+async fn embed(
+    client: &reqwest::Client,
+    config: &EmbeddingConfig,
+    input: Vec<String>,
+) -> Result<Vec<Vec<f32>>, ServerResponseError> {
+    let failed = |message: String| ServerResponseError::FailedDependencyWithMessage(message);
+    let count = input.len();
+
+    let body = serde_json::to_vec(&EmbedRequest {
+        model: &config.model,
+        input,
+    })
+    .map_err(|err| ServerResponseError::InternalError(err.to_string()))?;
+    let mut request = client
+        .post(format!("{}/embeddings", config.url))
+        .header(reqwest::header::CONTENT_TYPE, "application/json")
+        .body(body);
+    if let Some(api_key) = &config.api_key {
+        request = request.bearer_auth(api_key.expose());
+    }
+
+    let response = request
+        .send()
+        .await
+        .map_err(|err| failed(format!("embedding API unreachable: {err}")))?;
+    let status = response.status();
+    let body = response
+        .bytes()
+        .await
+        .map_err(|err| failed(format!("embedding API response unreadable: {err}")))?;
+    if !status.is_success() {
+        return Err(failed(format!(
+            "embedding API answered {status}: {}",
+            String::from_utf8_lossy(&body)
+        )));
+    }
+
+    let mut data = serde_json::from_slice::<EmbedResponse>(&body)
+        .map_err(|err| failed(format!("unexpected embedding API response: {err}")))?
+        .data;
+    data.sort_by_key(|data| data.index);
+
+    if data.len() != count {
+        return Err(failed(format!(
+            "embedding API returned {} embeddings for {count} inputs",
+            data.len()
+        )));
+    }
+    if let Some(data) = data
+        .iter()
+        .find(|data| data.embedding.len() != EMBEDDING_DIMENSION)
+    {
+        return Err(failed(format!(
+            "{} produces {} dimensions, the index expects {EMBEDDING_DIMENSION}",
+            config.model,
+            data.embedding.len()
+        )));
+    }
+
+    Ok(data.into_iter().map(|data| data.embedding).collect())
+}
@@ -1,4 +1,5 @@
 use crate::error::ServerResponseError;
+use crate::metrics::ObserveQuery;
 use crate::models::MITREEntry;
 use crate::services::embeddings::EntryType;
 use std::sync::Arc;
@@ -25,6 +26,7 @@ where
     let entries: Vec<MITREEntry> = db
         .query(sql)
         .bind(("query_embedding", embedding))
+        .observed()
         .await?
         .take(0)?;
 
@@ -0,0 +1,66 @@
+use super::{status_label, ExportDocument};
+
+const HEADER: [&str; 8] = [
+    "type",
+    "mitre_id",
+    "name",
+    "status",
+    "notes",
+    "mitigated_by",
+    "url",
+    "description",
+];
+
+/// Renders the items of `document` as RFC 4180 CSV, one row per threat or mitigation
+pub fn render(document: &ExportDocument) -> String {
+    let mut out = String::new();
+
+    write_row(&mut out, HEADER);
+
+    for item in &document.items {
+        let entry_type = item.entry_type.to_string();
+        let mitigated_by = item.mitigated_by.join(";");
+
+        write_row(
+            &mut out,
+            [
+                entry_type.as_str(),
+                item.mitre_id.as_str(),
+                item.name(),
+                item.status.map(status_label).unwrap_or_default(),
+                item.notes.as_deref().unwrap_or_default(),
+                mitigated_by.as_str(),
+                item.url().unwrap_or_default(),
+                item.description(),
+            ],
+        );
+    }
+
+    out
+}
+
+fn write_row<'a>(out: &mut String, fields: impl IntoIterator<Item = &'a str>) {
+    for (i, field) in fields.into_iter().enumerate() {
+        if i > 0 {
+            out.push(',');
+        }
+        out.push_str(&escape(field));
+    }
+    out.push_str("\r\n");
+}
+
+/// Quotes `field` if it contains a separator, quote or line break. Fields starting with a formula
+/// character are prefixed with `'` so spreadsheets do not evaluate them.
+pub(crate) fn escape(field: &str) -> String {
+    let field = if field.starts_with(['=', '+', '-', '@']) {
+        format!("'{field}")
+    } else {
+        field.to_string()
+    };
+
+    if field.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field
+    }
+}
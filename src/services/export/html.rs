@@ -0,0 +1,136 @@
+use super::{status_label, ExportDocument, ExportItem};
+use std::fmt::Write;
+
+const STYLE: &str = "
+body { font-family: system-ui, sans-serif; max-width: 60rem; margin: 2rem auto; padding: 0 1rem; color: #1a1a1a; }
+h1 { border-bottom: 2px solid #1a1a1a; padding-bottom: .25rem; }
+table { border-collapse: collapse; width: 100%; margin-bottom: 1.5rem; }
+th, td { border: 1px solid #ccc; padding: .4rem .6rem; text-align: left; vertical-align: top; }
+th { background: #f2f2f2; }
+dl.meta { display: grid; grid-template-columns: max-content auto; gap: .2rem 1rem; }
+dl.meta dt { font-weight: bold; }
+.status-not-applicable { color: #777; }
+.uncovered { color: #b00020; }
+@page { size: A4; margin: 2cm; }
+@media print { body { margin: 0; max-width: none; } a { color: inherit; } tr { break-inside: avoid; } }
+";
+
+/// Renders `document` as a standalone HTML page that prints cleanly to PDF
+pub fn render(document: &ExportDocument) -> String {
+    let mut out = String::new();
+
+    let _ = write!(
+        out,
+        "<!DOCTYPE html>\n<html lang=\"en\">\n<head>\n<meta charset=\"utf-8\">\n<title>{}</title>\n<style>{}</style>\n</head>\n<body>\n",
+        escape(&document.title),
+        STYLE
+    );
+
+    let _ = writeln!(out, "<h1>{}</h1>", escape(&document.title));
+
+    if let Some(description) = &document.description {
+        for paragraph in description.split("\n\n") {
+            let _ = writeln!(out, "<p>{}</p>", escape(paragraph.trim()));
+        }
+    }
+
+    out.push_str("<dl class=\"meta\">\n");
+    for (label, value) in &document.metadata {
+        let _ = writeln!(out, "<dt>{}</dt><dd>{}</dd>", escape(label), escape(value));
+    }
+    out.push_str("</dl>\n");
+
+    if let Some(coverage) = &document.coverage {
+        let _ = writeln!(
+            out,
+            "<h2>Coverage</h2>\n<p>{} of {} applicable threats are covered ({:.0}%).</p>",
+            coverage.covered_threats,
+            coverage.applicable_threats,
+            coverage.coverage * 100.0
+        );
+
+        if !coverage.uncovered.is_empty() {
+            let _ = writeln!(
+                out,
+                "<p class=\"uncovered\">Threats without mitigation: {}</p>",
+                escape(&coverage.uncovered.join(", "))
+            );
+        }
+    }
+
+    render_section(&mut out, "Threats", document.threats());
+    render_section(&mut out, "Mitigations", document.mitigations());
+
+    out.push_str("</body>\n</html>\n");
+    out
+}
+
+fn render_section<'a>(out: &mut String, title: &str, items: impl Iterator<Item = &'a ExportItem>) {
+    let items: Vec<&ExportItem> = items.collect();
+
+    if items.is_empty() {
+        return;
+    }
+
+    let with_status = items.iter().any(|item| item.status.is_some());
+
+    let _ = writeln!(out, "<h2>{}</h2>\n<table>\n<thead><tr>", escape(title));
+    out.push_str("<th>ID</th><th>Name</th>");
+    if with_status {
+        out.push_str("<th>Status</th><th>Notes</th>");
+    }
+    out.push_str("<th>Description</th></tr></thead>\n<tbody>\n");
+
+    for item in items {
+        let id = match item.url() {
+            Some(url) => format!("<a href=\"{}\">{}</a>", escape(url), escape(&item.mitre_id)),
+            None => escape(&item.mitre_id),
+        };
+
+        let _ = write!(out, "<tr><td>{}</td><td>{}</td>", id, escape(item.name()));
+
+        if with_status {
+            let status = item.status.map(status_label).unwrap_or_default();
+            let mut notes = item.notes.as_deref().map(escape).unwrap_or_default();
+
+            if !item.mitigated_by.is_empty() {
+                let _ = write!(
+                    notes,
+                    "{}Mitigated by {}",
+                    if notes.is_empty() { "" } else { "<br>" },
+                    escape(&item.mitigated_by.join(", "))
+                );
+            }
+
+            let _ = write!(
+                out,
+                "<td class=\"status-{}\">{}</td><td>{}</td>",
+                status.replace(' ', "-"),
+                status,
+                notes
+            );
+        }
+
+        let _ = writeln!(out, "<td>{}</td></tr>", escape(item.description()));
+    }
+
+    out.push_str("</tbody>\n</table>\n");
+}
+
+/// Escapes text for use in HTML content and quoted attribute values
+pub(crate) fn escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&#39;"),
+            c => escaped.push(c),
+        }
+    }
+
+    escaped
+}
@@ -0,0 +1,95 @@
+use super::{status_label, ExportDocument, ExportItem};
+use std::fmt::Write;
+
+/// Renders `document` as CommonMark
+pub fn render(document: &ExportDocument) -> String {
+    let mut out = String::new();
+
+    let _ = writeln!(out, "# {}\n", escape(&document.title));
+
+    if let Some(description) = &document.description {
+        let _ = writeln!(out, "{}\n", description.trim());
+    }
+
+    for (label, value) in &document.metadata {
+        let _ = writeln!(out, "- **{}:** {}", label, escape(value));
+    }
+    out.push('\n');
+
+    if let Some(coverage) = &document.coverage {
+        let _ = writeln!(out, "## Coverage\n");
+        let _ = writeln!(
+            out,
+            "{} of {} applicable threats are covered ({:.0}%).\n",
+            coverage.covered_threats,
+            coverage.applicable_threats,
+            coverage.coverage * 100.0
+        );
+
+        if !coverage.uncovered.is_empty() {
+            let _ = writeln!(
+                out,
+                "Threats without mitigation: {}\n",
+                coverage.uncovered.join(", ")
+            );
+        }
+    }
+
+    render_section(&mut out, "Threats", document.threats());
+    render_section(&mut out, "Mitigations", document.mitigations());
+
+    out
+}
+
+fn render_section<'a>(out: &mut String, title: &str, items: impl Iterator<Item = &'a ExportItem>) {
+    let mut items = items.peekable();
+
+    if items.peek().is_none() {
+        return;
+    }
+
+    let _ = writeln!(out, "## {title}\n");
+
+    for item in items {
+        let _ = writeln!(out, "### {} {}\n", item.mitre_id, escape(item.name()));
+
+        if let Some(status) = item.status {
+            let _ = writeln!(out, "- **Status:** {}", status_label(status));
+        }
+        if !item.mitigated_by.is_empty() {
+            let _ = writeln!(out, "- **Mitigated by:** {}", item.mitigated_by.join(", "));
+        }
+        if let Some(url) = item.url() {
+            let _ = writeln!(out, "- **Reference:** <{url}>");
+        }
+        if let Some(notes) = &item.notes {
+            let _ = writeln!(out, "- **Notes:** {}", escape(notes));
+        }
+        out.push('\n');
+
+        if !item.description().is_empty() {
+            let _ = writeln!(out, "{}\n", item.description().trim());
+        }
+    }
+}
+
+/// Escapes characters that would otherwise start Markdown formatting in inline text
+pub(crate) fn escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+
+    for c in text.chars() {
+        if matches!(
+            c,
+            '\\' | '`' | '*' | '_' | '[' | ']' | '<' | '>' | '#' | '|'
+        ) {
+            escaped.push('\\');
+        }
+        if c == '\n' {
+            escaped.push(' ');
+        } else {
+            escaped.push(c);
+        }
+    }
+
+    escaped
+}
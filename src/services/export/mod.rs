@@ -0,0 +1,270 @@
+//! Renders threat models and MITRE search results as documents that can be handed to people
+//! outside of the application. The format is negotiated from the `Accept` header.
+
+pub mod csv;
+pub mod html;
+pub mod markdown;
+pub mod stix;
+mod test;
+
+use crate::dto::threat_model::{CoverageSummary, ThreatModelDetails};
+use crate::error::ServerResponseError;
+use crate::models::threat_model::ItemStatus;
+use crate::models::{EntryType, MITREEntry};
+use actix_web::http::header::{
+    Accept, ContentDisposition, DispositionParam, DispositionType, Header,
+};
+use actix_web::{HttpRequest, HttpResponse};
+use chrono::{DateTime, SecondsFormat, Utc};
+
+/// A document format that can be requested with the `Accept` header
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExportFormat {
+    Markdown,
+    Html,
+    Csv,
+    Stix,
+}
+
+impl ExportFormat {
+    /// Format used when the client accepts anything
+    pub const DEFAULT: ExportFormat = ExportFormat::Markdown;
+
+    /// Picks the format the client prefers most, failing with
+    /// [`ServerResponseError::NotAcceptable`] if none of the accepted types is supported
+    pub fn negotiate(req: &HttpRequest) -> Result<Self, ServerResponseError> {
+        let Ok(accept) = Accept::parse(req) else {
+            return Ok(Self::DEFAULT);
+        };
+
+        if accept.is_empty() {
+            return Ok(Self::DEFAULT);
+        }
+
+        accept
+            .ranked()
+            .iter()
+            .find_map(|mime| Self::from_mime(mime.type_().as_str(), mime.subtype().as_str()))
+            .ok_or(ServerResponseError::NotAcceptable)
+    }
+
+    fn from_mime(type_: &str, subtype: &str) -> Option<Self> {
+        match (type_, subtype) {
+            ("text", "markdown") | ("text", "x-markdown") => Some(Self::Markdown),
+            ("text", "html") => Some(Self::Html),
+            ("text", "csv") => Some(Self::Csv),
+            ("application", "stix+json") => Some(Self::Stix),
+            ("text", "*") | ("*", "*") => Some(Self::DEFAULT),
+            _ => None,
+        }
+    }
+
+    pub fn content_type(&self) -> &'static str {
+        match self {
+            ExportFormat::Markdown => "text/markdown; charset=utf-8",
+            ExportFormat::Html => "text/html; charset=utf-8",
+            ExportFormat::Csv => "text/csv; charset=utf-8",
+            ExportFormat::Stix => "application/stix+json;version=2.1",
+        }
+    }
+
+    pub fn extension(&self) -> &'static str {
+        match self {
+            ExportFormat::Markdown => "md",
+            ExportFormat::Html => "html",
+            ExportFormat::Csv => "csv",
+            ExportFormat::Stix => "json",
+        }
+    }
+}
+
+/// A threat or mitigation in an exported document
+#[derive(Debug, Clone)]
+pub struct ExportItem {
+    pub entry_type: EntryType,
+    pub mitre_id: String,
+    /// Missing when the ID is not in the database anymore
+    pub entry: Option<MITREEntry>,
+    pub status: Option<ItemStatus>,
+    pub notes: Option<String>,
+    pub mitigated_by: Vec<String>,
+}
+
+impl ExportItem {
+    pub fn name(&self) -> &str {
+        self.entry
+            .as_ref()
+            .map(|entry| entry.mitre_name.as_str())
+            .unwrap_or("Unknown entry")
+    }
+
+    pub fn description(&self) -> &str {
+        self.entry
+            .as_ref()
+            .map(|entry| entry.mitre_description.as_str())
+            .unwrap_or_default()
+    }
+
+    pub fn url(&self) -> Option<&str> {
+        self.entry.as_ref().map(|entry| entry.mitre_url.as_str())
+    }
+}
+
+/// Format independent content of an export
+#[derive(Debug, Clone)]
+pub struct ExportDocument {
+    /// Stable identifier of the exported resource, used to derive STIX IDs
+    pub id: String,
+    pub title: String,
+    pub description: Option<String>,
+    /// Label and value pairs shown below the title
+    pub metadata: Vec<(String, String)>,
+    pub coverage: Option<CoverageSummary>,
+    pub items: Vec<ExportItem>,
+    pub created_at: DateTime<Utc>,
+    pub modified_at: DateTime<Utc>,
+}
+
+impl ExportDocument {
+    pub fn from_threat_model(details: ThreatModelDetails) -> Self {
+        let ThreatModelDetails {
+            model,
+            mut entries,
+            coverage,
+        } = details;
+
+        let threats = model.threats.into_iter().map(|threat| ExportItem {
+            entry_type: EntryType::Threat,
+            entry: entries.remove(&threat.mitre_id),
+            mitre_id: threat.mitre_id,
+            status: Some(threat.status),
+            notes: threat.notes,
+            mitigated_by: threat.mitigated_by,
+        });
+
+        let mitigations = model.mitigations.into_iter().map(|mitigation| ExportItem {
+            entry_type: EntryType::Mitigation,
+            entry: entries.remove(&mitigation.mitre_id),
+            mitre_id: mitigation.mitre_id,
+            status: Some(mitigation.status),
+            notes: mitigation.notes,
+            mitigated_by: Vec::new(),
+        });
+
+        let items = threats.chain(mitigations).collect();
+        let created_at = parse_datetime(&model.created_at.to_raw());
+        let modified_at = parse_datetime(&model.updated_at.to_raw());
+
+        Self {
+            id: model.id.to_raw(),
+            description: Some(model.description).filter(|d| !d.trim().is_empty()),
+            metadata: vec![
+                ("Version".to_string(), model.version.to_string()),
+                ("Created".to_string(), format_datetime(&created_at)),
+                ("Last changed".to_string(), format_datetime(&modified_at)),
+                ("Attached files".to_string(), model.files.len().to_string()),
+            ],
+            title: model.title,
+            coverage: Some(coverage),
+            items,
+            created_at,
+            modified_at,
+        }
+    }
+
+    /// Search results have no state of their own, so every export is a new document
+    pub fn from_entries(entry_type: EntryType, entries: Vec<MITREEntry>) -> Self {
+        let now = Utc::now();
+        let kind = match entry_type {
+            EntryType::Threat => "Threats",
+            EntryType::Mitigation => "Mitigations",
+        };
+
+        Self {
+            id: format!(
+                "search:{}:{}",
+                entry_type,
+                now.timestamp_nanos_opt().unwrap_or_default()
+            ),
+            title: format!("{kind} search results"),
+            description: None,
+            metadata: vec![
+                ("Results".to_string(), entries.len().to_string()),
+                ("Exported".to_string(), format_datetime(&now)),
+            ],
+            coverage: None,
+            items: entries
+                .into_iter()
+                .map(|entry| ExportItem {
+                    entry_type,
+                    mitre_id: entry.mitre_id.clone(),
+                    entry: Some(entry),
+                    status: None,
+                    notes: None,
+                    mitigated_by: Vec::new(),
+                })
+                .collect(),
+            created_at: now,
+            modified_at: now,
+        }
+    }
+
+    pub fn threats(&self) -> impl Iterator<Item = &ExportItem> {
+        self.items
+            .iter()
+            .filter(|item| item.entry_type == EntryType::Threat)
+    }
+
+    pub fn mitigations(&self) -> impl Iterator<Item = &ExportItem> {
+        self.items
+            .iter()
+            .filter(|item| item.entry_type == EntryType::Mitigation)
+    }
+}
+
+/// Renders `document` in `format`
+pub fn render(document: &ExportDocument, format: ExportFormat) -> String {
+    match format {
+        ExportFormat::Markdown => markdown::render(document),
+        ExportFormat::Html => html::render(document),
+        ExportFormat::Csv => csv::render(document),
+        ExportFormat::Stix => stix::render(document),
+    }
+}
+
+/// Renders `document` as a download named `{filename}.{extension}`
+pub fn export_response(
+    document: &ExportDocument,
+    format: ExportFormat,
+    filename: &str,
+) -> HttpResponse {
+    HttpResponse::Ok()
+        .content_type(format.content_type())
+        .insert_header(ContentDisposition {
+            disposition: DispositionType::Attachment,
+            parameters: vec![DispositionParam::Filename(format!(
+                "{}.{}",
+                filename,
+                format.extension()
+            ))],
+        })
+        .body(render(document, format))
+}
+
+pub(crate) fn status_label(status: ItemStatus) -> &'static str {
+    match status {
+        ItemStatus::Accepted => "accepted",
+        ItemStatus::Mitigated => "mitigated",
+        ItemStatus::NotApplicable => "not applicable",
+    }
+}
+
+pub(crate) fn format_datetime(datetime: &DateTime<Utc>) -> String {
+    datetime.to_rfc3339_opts(SecondsFormat::Secs, true)
+}
+
+fn parse_datetime(raw: &str) -> DateTime<Utc> {
+    DateTime::parse_from_rfc3339(raw)
+        .map(|datetime| datetime.with_timezone(&Utc))
+        .unwrap_or_else(|_| Utc::now())
+}
@@ -0,0 +1,129 @@
+use super::{status_label, ExportDocument, ExportItem};
+use crate::models::EntryType;
+use chrono::{DateTime, SecondsFormat, Utc};
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use uuid::Uuid;
+
+/// Namespace for the UUIDv5 identifiers of exported STIX objects, so that exporting the same
+/// entry twice yields the same object ID
+const NAMESPACE: Uuid = Uuid::from_u128(0x5a0e_3c1f_7b2d_4e8a_9f61_c2d4_b7e3_a910);
+
+/// Renders `document` as a STIX 2.1 bundle.
+///
+/// Threats become `attack-pattern`s, mitigations `course-of-action`s and `mitigated_by` links
+/// `mitigates` relationships. Threat models are additionally wrapped in a `report`, the status
+/// and notes of each item are kept in `x_threatmapper_*` properties.
+pub fn render(document: &ExportDocument) -> String {
+    let created = timestamp(&document.created_at);
+    let modified = timestamp(&document.modified_at);
+
+    let mut objects = Vec::new();
+    let mut ids = HashMap::new();
+
+    for item in &document.items {
+        let (object, id) = stix_object(item, &created, &modified);
+        ids.insert((item.entry_type, item.mitre_id.as_str()), id);
+        objects.push(object);
+    }
+
+    for threat in document.threats() {
+        let Some(target) = ids.get(&(EntryType::Threat, threat.mitre_id.as_str())) else {
+            continue;
+        };
+
+        for mitigation in &threat.mitigated_by {
+            let Some(source) = ids.get(&(EntryType::Mitigation, mitigation.as_str())) else {
+                continue;
+            };
+
+            objects.push(json!({
+                "type": "relationship",
+                "spec_version": "2.1",
+                "id": stix_id("relationship", &format!("{}:{}:{}", document.id, source, target)),
+                "created": created,
+                "modified": modified,
+                "relationship_type": "mitigates",
+                "source_ref": source,
+                "target_ref": target,
+            }));
+        }
+    }
+
+    if document.coverage.is_some() && !objects.is_empty() {
+        let object_refs: Vec<Value> = objects.iter().map(|object| object["id"].clone()).collect();
+        let mut report = json!({
+            "type": "report",
+            "spec_version": "2.1",
+            "id": stix_id("report", &document.id),
+            "created": created,
+            "modified": modified,
+            "name": document.title,
+            "published": modified,
+            "report_types": ["threat-report"],
+            "object_refs": object_refs,
+        });
+
+        if let Some(description) = &document.description {
+            report["description"] = json!(description);
+        }
+
+        objects.push(report);
+    }
+
+    let bundle = json!({
+        "type": "bundle",
+        "id": stix_id("bundle", &format!("{}:{}", document.id, modified)),
+        "objects": objects,
+    });
+
+    serde_json::to_string_pretty(&bundle).unwrap_or_default()
+}
+
+fn stix_object(item: &ExportItem, created: &str, modified: &str) -> (Value, String) {
+    let object_type = match item.entry_type {
+        EntryType::Threat => "attack-pattern",
+        EntryType::Mitigation => "course-of-action",
+    };
+    let id = stix_id(object_type, &item.mitre_id);
+
+    let mut reference = json!({
+        "source_name": "mitre-attack",
+        "external_id": item.mitre_id,
+    });
+    if let Some(url) = item.url() {
+        reference["url"] = json!(url);
+    }
+
+    let mut object = json!({
+        "type": object_type,
+        "spec_version": "2.1",
+        "id": id,
+        "created": created,
+        "modified": modified,
+        "name": item.name(),
+        "external_references": [reference],
+    });
+
+    if !item.description().is_empty() {
+        object["description"] = json!(item.description());
+    }
+    if let Some(status) = item.status {
+        object["x_threatmapper_status"] = json!(status_label(status));
+    }
+    if let Some(notes) = &item.notes {
+        object["x_threatmapper_notes"] = json!(notes);
+    }
+
+    (object, id)
+}
+
+/// `{object_type}--{uuid}` with a UUID derived from `name`
+pub(crate) fn stix_id(object_type: &str, name: &str) -> String {
+    let uuid = Uuid::new_v5(&NAMESPACE, format!("{object_type}:{name}").as_bytes());
+    format!("{object_type}--{uuid}")
+}
+
+fn timestamp(datetime: &DateTime<Utc>) -> String {
+    datetime.to_rfc3339_opts(SecondsFormat::Millis, true)
+}
@@ -0,0 +1,154 @@
+#![cfg(test)]
+
+use super::{csv, html, markdown, stix, ExportDocument, ExportFormat, ExportItem};
+use crate::dto::threat_model::CoverageSummary;
+use crate::error::ServerResponseError;
+use crate::models::threat_model::ItemStatus;
+use crate::models::{EntryType, MITREEntry};
+use actix_web::test::TestRequest;
+use chrono::Utc;
+
+fn entry(mitre_id: &str, name: &str) -> MITREEntry {
+    MITREEntry {
+        mitre_id: mitre_id.to_string(),
+        mitre_name: name.to_string(),
+        mitre_description: format!("Description of {name}"),
+        mitre_url: format!("https://attack.mitre.org/{mitre_id}"),
+    }
+}
+
+fn document() -> ExportDocument {
+    ExportDocument {
+        id: "threat_model:portal".to_string(),
+        title: "Customer <portal>".to_string(),
+        description: Some("Public web application".to_string()),
+        metadata: vec![("Version".to_string(), "2".to_string())],
+        coverage: Some(CoverageSummary {
+            applicable_threats: 2,
+            covered_threats: 1,
+            coverage: 0.5,
+            uncovered: vec!["T1110".to_string()],
+        }),
+        items: vec![
+            ExportItem {
+                entry_type: EntryType::Threat,
+                mitre_id: "T1190".to_string(),
+                entry: Some(entry("T1190", "Exploit Public-Facing Application")),
+                status: Some(ItemStatus::Accepted),
+                notes: Some("Login form, \"admin\" panel".to_string()),
+                mitigated_by: vec!["M1050".to_string()],
+            },
+            ExportItem {
+                entry_type: EntryType::Threat,
+                mitre_id: "T1110".to_string(),
+                entry: Some(entry("T1110", "Brute Force")),
+                status: Some(ItemStatus::Accepted),
+                notes: None,
+                mitigated_by: Vec::new(),
+            },
+            ExportItem {
+                entry_type: EntryType::Mitigation,
+                mitre_id: "M1050".to_string(),
+                entry: Some(entry("M1050", "Exploit Protection")),
+                status: Some(ItemStatus::Mitigated),
+                notes: None,
+                mitigated_by: Vec::new(),
+            },
+        ],
+        created_at: Utc::now(),
+        modified_at: Utc::now(),
+    }
+}
+
+fn negotiate(accept: Option<&str>) -> Result<ExportFormat, ServerResponseError> {
+    let mut req = TestRequest::default();
+    if let Some(accept) = accept {
+        req = req.insert_header(("Accept", accept));
+    }
+    ExportFormat::negotiate(&req.to_http_request())
+}
+
+#[test]
+fn format_is_negotiated_from_accept() {
+    assert_eq!(negotiate(None).unwrap(), ExportFormat::Markdown);
+    assert_eq!(negotiate(Some("*/*")).unwrap(), ExportFormat::Markdown);
+    assert_eq!(negotiate(Some("text/csv")).unwrap(), ExportFormat::Csv);
+    assert_eq!(
+        negotiate(Some("text/csv;q=0.5, text/html")).unwrap(),
+        ExportFormat::Html
+    );
+    assert_eq!(
+        negotiate(Some("application/stix+json;version=2.1")).unwrap(),
+        ExportFormat::Stix
+    );
+    assert!(matches!(
+        negotiate(Some("application/pdf")),
+        Err(ServerResponseError::NotAcceptable)
+    ));
+}
+
+#[test]
+fn csv_fields_are_quoted() {
+    let out = csv::render(&document());
+    let mut lines = out.split("\r\n");
+
+    assert_eq!(
+        lines.next().unwrap(),
+        "type,mitre_id,name,status,notes,mitigated_by,url,description"
+    );
+    assert!(lines
+        .next()
+        .unwrap()
+        .contains(",accepted,\"Login form, \"\"admin\"\" panel\",M1050,"));
+    assert_eq!(csv::escape("=SUM(A1)"), "'=SUM(A1)");
+}
+
+#[test]
+fn html_is_escaped() {
+    let out = html::render(&document());
+
+    assert!(out.starts_with("<!DOCTYPE html>"));
+    assert!(out.contains("<h1>Customer &lt;portal&gt;</h1>"));
+    assert!(!out.contains("<portal>"));
+}
+
+#[test]
+fn markdown_lists_uncovered_threats() {
+    let out = markdown::render(&document());
+
+    assert!(out.starts_with("# Customer \\<portal\\>"));
+    assert!(out.contains("Threats without mitigation: T1110"));
+    assert!(out.contains("### M1050 Exploit Protection"));
+}
+
+#[test]
+fn stix_bundle_links_mitigations() {
+    let bundle: serde_json::Value = serde_json::from_str(&stix::render(&document())).unwrap();
+    let objects = bundle["objects"].as_array().unwrap();
+
+    assert_eq!(bundle["type"], "bundle");
+    assert_eq!(
+        objects
+            .iter()
+            .filter(|o| o["type"] == "attack-pattern")
+            .count(),
+        2
+    );
+
+    let relationship = objects
+        .iter()
+        .find(|o| o["type"] == "relationship")
+        .unwrap();
+    assert_eq!(relationship["relationship_type"], "mitigates");
+    assert_eq!(
+        relationship["source_ref"],
+        stix::stix_id("course-of-action", "M1050")
+    );
+    assert_eq!(
+        relationship["target_ref"],
+        stix::stix_id("attack-pattern", "T1190")
+    );
+
+    let report = objects.iter().find(|o| o["type"] == "report").unwrap();
+    assert_eq!(report["object_refs"].as_array().unwrap().len(), 4);
+}
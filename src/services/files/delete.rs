@@ -1,23 +1,137 @@
+use super::store::FileStore;
 use crate::error::ServerResponseError;
+use crate::metrics::ObserveQuery;
+use crate::models::file_metadata::FileMetadata;
+use crate::models::team::TeamRole;
+use crate::services::team::access::LET_TEAMS;
 use std::sync::Arc;
 use surrealdb::sql::Thing;
 use surrealdb::Surreal;
+use tracing::{error, warn};
 
-/// Deletes the metadata of a file with ID `file_id` that
-/// was uploaded by the user holding the token `token`.
+/// Deletes the metadata of a file with ID `file_id` if `user_id` uploaded
+/// it or is an editor of the team owning it. Returns the keys of the
+/// contents of all its revisions, they are queued for removal, see
+/// [`remove_stored_content`].
 pub async fn delete_file_metadata<T>(
     db: &Arc<Surreal<T>>,
     file_id: String,
     user_id: Thing,
-) -> Result<(), ServerResponseError>
+) -> Result<Vec<String>, ServerResponseError>
 where
     T: surrealdb::Connection,
 {
-    const SQL: &str =
-        "DELETE file WHERE meta::id(id) = $FILE AND ->files_for->user.id CONTAINS $USER;";
-    db.query(SQL)
+    let sql = format!(
+        "{LET_TEAMS}
+        LET $KEYS = array::distinct(array::append(
+            (SELECT VALUE key FROM file_revision WHERE file = type::thing('file', $FILE)),
+            $FILE
+        ));
+        DELETE type::thing('file', $FILE)
+        WHERE (team = NONE AND ->files_for->user CONTAINS $USER) OR team IN $TEAMS
+        RETURN BEFORE;
+        RETURN $KEYS;"
+    );
+    let mut response = db
+        .query(sql)
         .bind(("FILE", file_id))
         .bind(("USER", user_id))
+        .bind(("ROLES", TeamRole::Editor.and_above()))
+        .observed()
         .await?;
-    Ok(())
+    let deleted: Option<FileMetadata> = response.take(2)?;
+    let keys: Vec<String> = response.take(3)?;
+    match deleted {
+        Some(_) => Ok(keys),
+        None => Err(ServerResponseError::NotFound),
+    }
+}
+
+/// Removes the deleted file content with key `key` from `store`.
+/// Failures are only logged, the content stays queued in `deleted_file`
+/// and is removed by the reconciler later.
+pub async fn remove_stored_content<T>(
+    db: &Arc<Surreal<T>>,
+    store: &dyn FileStore,
+    key: &str,
+) -> bool
+where
+    T: surrealdb::Connection,
+{
+    if let Err(err) = store.delete(key).await {
+        warn!(
+            "Failed to remove the content of deleted file {}: {}",
+            key, err
+        );
+        return false;
+    }
+
+    const SQL: &str = "DELETE type::thing('deleted_file', $KEY);";
+    if let Err(err) = db
+        .query(SQL)
+        .bind(("KEY", key.to_string()))
+        .observed()
+        .await
+    {
+        warn!(
+            "Failed to dequeue the content of deleted file {}: {}",
+            key, err
+        );
+    }
+
+    true
+}
+
+/// Contents removed by one call of [`purge_deleted_contents`]
+pub const PURGE_BATCH_SIZE: usize = 1000;
+
+/// Removes queued contents of deleted files from `store`, at most `limit`
+/// of them. Returns how many were removed and how many failed.
+pub async fn purge_deleted_contents<T>(
+    db: &Arc<Surreal<T>>,
+    store: &dyn FileStore,
+    limit: usize,
+) -> Result<(usize, usize), ServerResponseError>
+where
+    T: surrealdb::Connection,
+{
+    const SQL: &str =
+        "SELECT VALUE record::id(id) FROM deleted_file ORDER BY deleted_at LIMIT $LIMIT;";
+    let keys: Vec<String> = db
+        .query(SQL)
+        .bind(("LIMIT", limit))
+        .observed()
+        .await?
+        .take(0)?;
+
+    let mut removed = 0;
+    for key in &keys {
+        if remove_stored_content(db, store, key).await {
+            removed += 1;
+        }
+    }
+
+    Ok((removed, keys.len() - removed))
+}
+
+/// Deletes files whose content could not be stored, together with any
+/// content that made it into `store`
+pub async fn discard_files<T>(db: &Arc<Surreal<T>>, store: &dyn FileStore, files: &[FileMetadata])
+where
+    T: surrealdb::Connection,
+{
+    let ids: Vec<Thing> = files
+        .iter()
+        .map(|file| Thing::from((file.id.tb.clone(), file.id.id.clone())))
+        .collect();
+
+    const SQL: &str = "DELETE $FILES;";
+    if let Err(err) = db.query(SQL).bind(("FILES", ids)).observed().await {
+        error!("Failed to discard files that were not stored: {}", err);
+        return;
+    }
+
+    for file in files {
+        remove_stored_content(db, store, &file.id.id.to_raw()).await;
+    }
 }
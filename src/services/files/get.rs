@@ -1,35 +1,59 @@
 use crate::error::ServerResponseError;
+use crate::metrics::ObserveQuery;
 use crate::models::file_metadata::FileMetadata;
+use crate::models::team::TeamRole;
+use crate::services::team::access::LET_TEAMS;
 use std::sync::Arc;
 use surrealdb::sql::Thing;
 use surrealdb::Surreal;
 
-/// Returns the metadata of the file with ID `file_id` uploaded by
-/// the user holding the token `token`.
-pub async fn get_file_metadata<T>(
+/// Returns the metadata of the file with ID `file_id` if `user_id` has at
+/// least the role `required` for it. Files without a team are only
+/// accessible to their uploader, files of a team to its members.
+pub async fn get_accessible_file_metadata<T>(
     db: &Arc<Surreal<T>>,
     file_id: String,
     user_id: Thing,
+    required: TeamRole,
 ) -> Result<FileMetadata, ServerResponseError>
 where
     T: surrealdb::Connection,
 {
-    const SQL: &str =
-        "SELECT VALUE in FROM files_for WHERE meta::id(in) = $FILE AND out = $USER FETCH in;";
+    let sql = format!(
+        "{LET_TEAMS}
+        SELECT * FROM type::thing('file', $FILE)
+        WHERE stored != false
+            AND ((team = NONE AND ->files_for->user CONTAINS $USER) OR team IN $TEAMS);"
+    );
     let found = db
-        .query(SQL)
+        .query(sql)
         .bind(("FILE", file_id))
         .bind(("USER", user_id))
+        .bind(("ROLES", required.and_above()))
+        .observed()
         .await?
-        .take(0)?;
+        .take(1)?;
     match found {
         Some(file) => Ok(file),
         None => Err(ServerResponseError::NotFound),
     }
 }
 
-/// Returns metadata of all files uploaded by the user
-/// holding token `token`.
+/// Returns the metadata of the file with ID `file_id` if `user_id` may
+/// read it.
+pub async fn get_file_metadata<T>(
+    db: &Arc<Surreal<T>>,
+    file_id: String,
+    user_id: Thing,
+) -> Result<FileMetadata, ServerResponseError>
+where
+    T: surrealdb::Connection,
+{
+    get_accessible_file_metadata(db, file_id, user_id, TeamRole::Viewer).await
+}
+
+/// Returns metadata of all files uploaded by the user and of all
+/// files owned by the user's teams.
 pub async fn get_file_metadata_by_token<T>(
     db: &Arc<Surreal<T>>,
     user_id: Thing,
@@ -37,7 +61,38 @@ pub async fn get_file_metadata_by_token<T>(
 where
     T: surrealdb::Connection,
 {
-    const SQL: &str = "SELECT VALUE in FROM files_for WHERE out = $USER FETCH in;";
-    let files: Vec<FileMetadata> = db.query(SQL).bind(("USER", user_id)).await?.take(0)?;
+    let sql = format!(
+        "{LET_TEAMS}
+        SELECT * FROM array::union(
+            (SELECT VALUE in FROM files_for
+                WHERE out = $USER AND in.team = NONE AND in.stored != false),
+            (SELECT VALUE id FROM file WHERE team IN $TEAMS AND stored != false)
+        );"
+    );
+    let files: Vec<FileMetadata> = db
+        .query(sql)
+        .bind(("USER", user_id))
+        .bind(("ROLES", TeamRole::Viewer.and_above()))
+        .observed()
+        .await?
+        .take(1)?;
+    Ok(files)
+}
+
+/// Returns metadata of all files owned by `team`.
+pub async fn get_team_file_metadata<T>(
+    db: &Arc<Surreal<T>>,
+    team: Thing,
+) -> Result<Vec<FileMetadata>, ServerResponseError>
+where
+    T: surrealdb::Connection,
+{
+    const SQL: &str = "SELECT * FROM file WHERE team = $TEAM AND stored != false;";
+    let files: Vec<FileMetadata> = db
+        .query(SQL)
+        .bind(("TEAM", team))
+        .observed()
+        .await?
+        .take(0)?;
     Ok(files)
 }
@@ -1,32 +1,65 @@
+use super::delete::discard_files;
+use super::inspect::FileProperties;
+use super::store::FileStore;
 use crate::error::ServerResponseError;
-use crate::models::file_metadata::FileMetadata;
-use crate::services::user::get::get_user_by_token;
+use crate::metrics::ObserveQuery;
+use crate::models::file_metadata::{FileMetadata, ScanStatus};
 use serde::{Deserialize, Serialize};
+use std::path::Path;
 use std::sync::Arc;
+use surrealdb::sql::Thing;
 use surrealdb::Surreal;
+use tracing::error;
+
+/// A file that is about to be stored
+#[derive(Debug, Clone)]
+pub struct NewFile {
+    pub filename: String,
+    /// Content type sent by the uploader
+    pub declared_type: Option<String>,
+    pub properties: FileProperties,
+}
 
 #[derive(Debug, Serialize, Deserialize)]
-struct Filename {
+struct FileRecord {
     filename: String,
+    size: u64,
+    mime_type: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    declared_type: Option<String>,
+    sha256: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    team: Option<Thing>,
+    stored: bool,
+    scan_status: ScanStatus,
+    revision: u32,
 }
 
 /// Inserts metadata for multiple files and relates them
-/// to the user holding token `token`.
-pub async fn insert_file_metadata<T>(
+/// to the user with ID `user_id`. The files belong to
+/// `team` if one is given. Every file starts with its
+/// first revision.
+pub async fn insert_file_metadata_for<T>(
     db: &Arc<Surreal<T>>,
-    filenames: Vec<&str>,
-    token: String,
+    files: Vec<NewFile>,
+    user_id: Thing,
+    team: Option<Thing>,
 ) -> Result<Vec<FileMetadata>, ServerResponseError>
 where
     T: surrealdb::Connection,
 {
-    let user = get_user_by_token(db, &token).await?;
-    let user_id = user.id.ok_or(ServerResponseError::NotFound)?;
-
-    let files: Vec<Filename> = filenames
-        .iter()
-        .map(|filename| Filename {
-            filename: filename.to_string(),
+    let files: Vec<FileRecord> = files
+        .into_iter()
+        .map(|file| FileRecord {
+            filename: file.filename,
+            size: file.properties.size,
+            mime_type: file.properties.mime_type,
+            declared_type: file.declared_type,
+            sha256: file.properties.sha256,
+            team: team.clone(),
+            stored: false,
+            scan_status: ScanStatus::Pending,
+            revision: 1,
         })
         .collect();
 
@@ -34,6 +67,21 @@ where
         BEGIN TRANSACTION;
         LET $FILE_RECORDS = (INSERT INTO file $FILES);
         RELATE ($FILE_RECORDS) -> files_for -> ($USER);
+        FOR $file IN $FILE_RECORDS {
+            CREATE file_revision CONTENT {
+                file: $file.id,
+                revision: 1,
+                key: record::id($file.id),
+                filename: $file.filename,
+                size: $file.size,
+                mime_type: $file.mime_type,
+                declared_type: $file.declared_type,
+                sha256: $file.sha256,
+                scan_status: $file.scan_status,
+                uploaded_by: $USER,
+                stored: false
+            };
+        };
         COMMIT TRANSACTION;
         SELECT * FROM $FILE_RECORDS;
     ";
@@ -42,8 +90,52 @@ where
         .query(SQL)
         .bind(("FILES", files))
         .bind(("USER", user_id))
+        .observed()
         .await?
-        .take(2)?;
+        .take(3)?;
 
     Ok(created)
 }
+
+/// Puts the content of freshly inserted `files` from `sources` into
+/// `store` and marks the files as stored. If any content cannot be stored
+/// all `files` are discarded so that no file is left without content.
+pub async fn store_file_contents<T>(
+    db: &Arc<Surreal<T>>,
+    store: &dyn FileStore,
+    files: &[FileMetadata],
+    sources: &[&Path],
+) -> Result<(), ServerResponseError>
+where
+    T: surrealdb::Connection,
+{
+    for (file, source) in files.iter().zip(sources) {
+        if let Err(err) = store.put(&file.content_key(), source).await {
+            error!(
+                "Failed to store the content of file {}: {}",
+                file.id.id, err
+            );
+            discard_files(db, store, files).await;
+            return Err(err.into());
+        }
+    }
+
+    let ids: Vec<Thing> = files
+        .iter()
+        .map(|file| Thing::from((file.id.tb.clone(), file.id.id.clone())))
+        .collect();
+
+    const SQL: &str = "
+        BEGIN TRANSACTION;
+        UPDATE $FILES SET stored = true;
+        UPDATE file_revision SET stored = true WHERE file IN $FILES;
+        COMMIT TRANSACTION;
+    ";
+    db.query(SQL)
+        .bind(("FILES", ids))
+        .observed()
+        .await?
+        .check()?;
+
+    Ok(())
+}
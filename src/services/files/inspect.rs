@@ -0,0 +1,81 @@
+//! Properties of uploaded files that are recorded with their metadata.
+//! The MIME type is detected from the content, the type declared by the
+//! uploader is kept separately and never trusted for serving.
+
+use super::hex::encode_hex;
+use sha2::{Digest, Sha256};
+use std::path::Path;
+use tokio::fs;
+use tokio::io::AsyncReadExt;
+
+/// Bytes looked at to detect the MIME type
+const SNIFF_LEN: usize = 8 * 1024;
+
+/// Size, detected MIME type and checksum of a file
+#[derive(Debug, Clone, PartialEq)]
+pub struct FileProperties {
+    pub size: u64,
+    pub mime_type: String,
+    /// Hex encoded SHA-256 of the content
+    pub sha256: String,
+}
+
+/// Detects the MIME type of content starting with `head` from its magic
+/// bytes. Content without a known signature is `text/plain` if it is valid
+/// UTF-8 and `application/octet-stream` otherwise.
+pub fn sniff_mime_type(head: &[u8]) -> &'static str {
+    if let Some(kind) = infer::get(head) {
+        return kind.mime_type();
+    }
+
+    let is_text = match std::str::from_utf8(head) {
+        Ok(text) => !text.contains('\0'),
+        // `head` may end in the middle of a character
+        Err(err) => err.error_len().is_none() && !head[..err.valid_up_to()].contains(&0),
+    };
+
+    if is_text && !head.is_empty() {
+        "text/plain"
+    } else {
+        "application/octet-stream"
+    }
+}
+
+/// Normalizes a content type declared by the uploader to its essence,
+/// e.g. `Text/HTML; charset=utf-8` becomes `text/html`. Unparsable types are
+/// dropped.
+pub fn normalize_declared_type(declared: Option<&str>) -> Option<String> {
+    declared
+        .and_then(|declared| declared.parse::<mime::Mime>().ok())
+        .map(|mime| mime.essence_str().to_lowercase())
+}
+
+/// Reads the file at `path` once to compute its properties
+pub async fn inspect_file(path: &Path) -> std::io::Result<FileProperties> {
+    let mut file = fs::File::open(path).await?;
+    let mut hasher = Sha256::new();
+    let mut head = Vec::with_capacity(SNIFF_LEN);
+    let mut buffer = vec![0; 64 * 1024];
+    let mut size = 0u64;
+
+    loop {
+        let read = file.read(&mut buffer).await?;
+        if read == 0 {
+            break;
+        }
+
+        if head.len() < SNIFF_LEN {
+            let missing = (SNIFF_LEN - head.len()).min(read);
+            head.extend_from_slice(&buffer[..missing]);
+        }
+
+        hasher.update(&buffer[..read]);
+        size += read as u64;
+    }
+
+    Ok(FileProperties {
+        size,
+        mime_type: sniff_mime_type(&head).to_string(),
+        sha256: encode_hex(&hasher.finalize()),
+    })
+}
@@ -1,4 +1,14 @@
 pub mod delete;
 pub mod get;
+pub(crate) mod hex;
 pub mod insert;
+pub mod inspect;
+pub mod policy;
+pub mod reconcile;
+pub mod revision;
+pub mod scan;
+pub mod share;
 pub mod state;
+pub mod store;
+pub mod upload;
+mod test;
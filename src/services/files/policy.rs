@@ -0,0 +1,305 @@
+//! Limits on what users may upload. Multipart uploads are checked after the
+//! request is received into temporary files but before anything is stored,
+//! resumable uploads when they are created and again when they are
+//! finalized.
+
+use crate::dto::problem::ProblemDetails;
+use crate::error::ServerResponseError;
+use crate::metrics::ObserveQuery;
+use actix_multipart::form::MultipartFormConfig;
+use actix_multipart::MultipartError;
+use actix_web::error::PayloadError;
+use actix_web::http::StatusCode;
+use actix_web::web::JsonConfig;
+use serde_json::json;
+use std::sync::Arc;
+use surrealdb::sql::Thing;
+use surrealdb::Surreal;
+
+const KIB: u64 = 1024;
+const MIB: u64 = 1024 * KIB;
+const GIB: u64 = 1024 * MIB;
+
+#[derive(Debug, thiserror::Error)]
+pub enum UploadPolicyError {
+    #[error("{filename} is {size} bytes, the limit is {limit} bytes")]
+    FileTooLarge {
+        filename: String,
+        size: u64,
+        limit: u64,
+    },
+    #[error("the request exceeds the limit of {limit} bytes")]
+    RequestTooLarge { limit: u64 },
+    #[error("{count} files were sent, at most {limit} are allowed per request")]
+    TooManyFiles { count: usize, limit: usize },
+    #[error("storing {size} more bytes exceeds the quota of {quota} bytes, {used} bytes are used")]
+    QuotaExceeded { size: u64, used: u64, quota: u64 },
+    #[error("{filename} is of type {mime_type} which is not allowed")]
+    TypeNotAllowed { filename: String, mime_type: String },
+}
+
+impl UploadPolicyError {
+    pub fn status_code(&self) -> StatusCode {
+        match self {
+            UploadPolicyError::TypeNotAllowed { .. } => StatusCode::UNSUPPORTED_MEDIA_TYPE,
+            _ => StatusCode::PAYLOAD_TOO_LARGE,
+        }
+    }
+
+    /// Machine readable name of the error
+    pub fn kind(&self) -> &'static str {
+        match self {
+            UploadPolicyError::FileTooLarge { .. } => "file_too_large",
+            UploadPolicyError::RequestTooLarge { .. } => "request_too_large",
+            UploadPolicyError::TooManyFiles { .. } => "too_many_files",
+            UploadPolicyError::QuotaExceeded { .. } => "quota_exceeded",
+            UploadPolicyError::TypeNotAllowed { .. } => "type_not_allowed",
+        }
+    }
+
+    /// Adds the limit that was exceeded to the problem details of the error
+    pub fn problem_details(&self, problem: ProblemDetails) -> ProblemDetails {
+        let details = match self {
+            UploadPolicyError::FileTooLarge {
+                filename,
+                size,
+                limit,
+            } => json!({ "filename": filename, "size": size, "limit": limit }),
+            UploadPolicyError::RequestTooLarge { limit } => json!({ "limit": limit }),
+            UploadPolicyError::TooManyFiles { count, limit } => {
+                json!({ "count": count, "limit": limit })
+            }
+            UploadPolicyError::QuotaExceeded { size, used, quota } => {
+                json!({ "size": size, "used": used, "quota": quota })
+            }
+            UploadPolicyError::TypeNotAllowed {
+                filename,
+                mime_type,
+            } => json!({ "filename": filename, "mime_type": mime_type }),
+        };
+
+        problem.with_details(details)
+    }
+}
+
+/// Parses sizes like `1048576`, `512KiB`, `100MiB` or `2GiB`
+pub(crate) fn parse_size(value: &str) -> Option<u64> {
+    let value = value.trim();
+    let split = value
+        .find(|c: char| !c.is_ascii_digit())
+        .unwrap_or(value.len());
+    let (number, unit) = value.split_at(split);
+    let number: u64 = number.parse().ok()?;
+
+    let factor = match unit.trim().to_ascii_lowercase().as_str() {
+        "" | "b" => 1,
+        "k" | "kb" | "kib" => KIB,
+        "m" | "mb" | "mib" => MIB,
+        "g" | "gb" | "gib" => GIB,
+        _ => return None,
+    };
+
+    number.checked_mul(factor)
+}
+
+/// Upload limits, read from the [`Config`](crate::config::Config)
+///
+/// - `UPLOAD_MAX_FILE_SIZE` - largest single file, defaults to `100MiB`
+/// - `UPLOAD_MAX_FILES` - files per multipart request, defaults to `10`
+/// - `UPLOAD_ALLOWED_TYPES` - comma separated MIME types, `type/*` matches all subtypes.
+///   Everything is allowed when unset.
+/// - `UPLOAD_USER_QUOTA` - bytes a user may store, defaults to `1GiB`, `0` disables the quota
+/// - `MAX_JSON_SIZE` - largest JSON request body, defaults to `4MiB`
+#[derive(Debug, Clone, PartialEq)]
+pub struct UploadPolicy {
+    pub max_file_size: u64,
+    pub max_files: usize,
+    pub allowed_types: Option<Vec<String>>,
+    pub user_quota: Option<u64>,
+    pub max_json_size: usize,
+}
+
+impl Default for UploadPolicy {
+    fn default() -> Self {
+        Self {
+            max_file_size: 100 * MIB,
+            max_files: 10,
+            allowed_types: None,
+            user_quota: Some(GIB),
+            max_json_size: 4 * MIB as usize,
+        }
+    }
+}
+
+impl UploadPolicy {
+    pub(crate) fn from_vars(var: impl Fn(&str) -> Option<String>) -> Result<Self, String> {
+        let defaults = Self::default();
+        let size = |name: &str| match var(name) {
+            Some(value) => parse_size(&value)
+                .map(Some)
+                .ok_or_else(|| format!("{name} is not a valid size: {value:?}")),
+            None => Ok(None),
+        };
+
+        let max_files = match var("UPLOAD_MAX_FILES") {
+            Some(value) => value
+                .parse()
+                .map_err(|_| format!("UPLOAD_MAX_FILES is not a number: {value:?}"))?,
+            None => defaults.max_files,
+        };
+
+        let allowed_types = var("UPLOAD_ALLOWED_TYPES").map(|types| {
+            types
+                .split(',')
+                .map(|mime_type| mime_type.trim().to_lowercase())
+                .filter(|mime_type| !mime_type.is_empty())
+                .collect()
+        });
+
+        let user_quota = match size("UPLOAD_USER_QUOTA")? {
+            Some(0) => None,
+            Some(quota) => Some(quota),
+            None => defaults.user_quota,
+        };
+
+        Ok(Self {
+            max_file_size: size("UPLOAD_MAX_FILE_SIZE")?.unwrap_or(defaults.max_file_size),
+            max_files,
+            allowed_types,
+            user_quota,
+            max_json_size: size("MAX_JSON_SIZE")?
+                .map(|size| size as usize)
+                .unwrap_or(defaults.max_json_size),
+        })
+    }
+
+    /// Largest multipart request body that can pass the policy
+    pub fn max_request_size(&self) -> u64 {
+        self.max_file_size.saturating_mul(self.max_files as u64)
+    }
+
+    pub fn json_config(&self) -> JsonConfig {
+        crate::config::json_config(self.max_json_size)
+    }
+
+    /// Stops receiving multipart requests that cannot pass the policy
+    /// instead of writing them to temporary files first
+    pub fn multipart_config(&self) -> MultipartFormConfig {
+        let limit = self.max_request_size();
+
+        MultipartFormConfig::default()
+            .total_limit(usize::try_from(limit).unwrap_or(usize::MAX))
+            .error_handler(move |err, _| match err {
+                MultipartError::Payload(PayloadError::Overflow) => {
+                    ServerResponseError::from(UploadPolicyError::RequestTooLarge { limit }).into()
+                }
+                err => err.into(),
+            })
+    }
+
+    pub fn is_type_allowed(&self, mime_type: &str) -> bool {
+        let Some(allowed_types) = &self.allowed_types else {
+            return true;
+        };
+        let mime_type = mime_type.to_lowercase();
+
+        allowed_types
+            .iter()
+            .any(|allowed| match allowed.strip_suffix("/*") {
+                Some(prefix) => mime_type
+                    .strip_prefix(prefix)
+                    .is_some_and(|subtype| subtype.starts_with('/')),
+                None => *allowed == mime_type,
+            })
+    }
+
+    pub fn check_count(&self, count: usize) -> Result<(), UploadPolicyError> {
+        if count > self.max_files {
+            return Err(UploadPolicyError::TooManyFiles {
+                count,
+                limit: self.max_files,
+            });
+        }
+
+        Ok(())
+    }
+
+    pub fn check_size(&self, filename: &str, size: u64) -> Result<(), UploadPolicyError> {
+        if size > self.max_file_size {
+            return Err(UploadPolicyError::FileTooLarge {
+                filename: filename.to_string(),
+                size,
+                limit: self.max_file_size,
+            });
+        }
+
+        Ok(())
+    }
+
+    pub fn check_type(&self, filename: &str, mime_type: &str) -> Result<(), UploadPolicyError> {
+        if !self.is_type_allowed(mime_type) {
+            return Err(UploadPolicyError::TypeNotAllowed {
+                filename: filename.to_string(),
+                mime_type: mime_type.to_string(),
+            });
+        }
+
+        Ok(())
+    }
+
+    /// Checks that `user_id` may store `size` more bytes. Unfinished
+    /// uploads count towards the quota with their declared size unless
+    /// `include_uploads` is false.
+    pub async fn check_quota<T>(
+        &self,
+        db: &Arc<Surreal<T>>,
+        user_id: Thing,
+        size: u64,
+        include_uploads: bool,
+    ) -> Result<(), ServerResponseError>
+    where
+        T: surrealdb::Connection,
+    {
+        let Some(quota) = self.user_quota else {
+            return Ok(());
+        };
+
+        let used = storage_used(db, user_id, include_uploads).await?;
+
+        if used.saturating_add(size) > quota {
+            return Err(UploadPolicyError::QuotaExceeded { size, used, quota }.into());
+        }
+
+        Ok(())
+    }
+}
+
+/// Bytes stored by `user_id`, counting every revision they uploaded, to
+/// team files as well. Restored revisions share their content and files
+/// uploaded before sizes were recorded are not counted.
+pub async fn storage_used<T>(
+    db: &Arc<Surreal<T>>,
+    user_id: Thing,
+    include_uploads: bool,
+) -> Result<u64, ServerResponseError>
+where
+    T: surrealdb::Connection,
+{
+    const SQL: &str = "
+        RETURN math::sum((SELECT VALUE size FROM file_revision
+                WHERE uploaded_by = $USER AND restored_from = NONE AND size != NONE))
+            + (IF $UPLOADS {
+                math::sum((SELECT VALUE size FROM upload_session
+                    WHERE owner = $USER AND expires_at > time::now()))
+            } ELSE { 0 });
+    ";
+    let used: Option<u64> = db
+        .query(SQL)
+        .bind(("USER", user_id))
+        .bind(("UPLOADS", include_uploads))
+        .observed()
+        .await?
+        .take(0)?;
+
+    Ok(used.unwrap_or(0))
+}
@@ -0,0 +1,197 @@
+//! Brings file records, the file store and the scratch directory back in
+//! line after failures. File records are the source of truth: content is
+//! only removed once its record is deleted and records are only removed
+//! when their content never made it into the store. Files whose malware
+//! scan failed are scanned again.
+
+use super::delete::{purge_deleted_contents, PURGE_BATCH_SIZE};
+use super::scan::scan_file;
+use super::state::FilesServiceState;
+use super::upload::remove_partial;
+use crate::error::ServerResponseError;
+use crate::metrics::ObserveQuery;
+use chrono::Utc;
+use std::collections::HashSet;
+use std::sync::Arc;
+use std::time::{Duration, SystemTime};
+use surrealdb::Surreal;
+use tokio::fs;
+use tracing::warn;
+
+/// How long a file may stay unstored and a partial upload may exist without
+/// a session before they are considered orphaned. Uploads in progress are
+/// far quicker than this.
+pub const ORPHAN_GRACE_PERIOD: Duration = Duration::from_secs(60 * 60);
+
+/// Minutes a file may stay `pending` before its scan counts as failed
+const SCAN_RETRY_AFTER_MINUTES: i64 = 10;
+
+/// Files scanned by one reconciler run at most
+const SCAN_BATCH_SIZE: usize = 100;
+
+/// What a reconciler run cleaned up
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct ReconcileReport {
+    /// File records whose content was never stored
+    pub unstored_files: usize,
+    /// File revisions whose content was never stored
+    pub unstored_revisions: usize,
+    /// Contents of deleted files removed from the store
+    pub contents_removed: usize,
+    /// Contents of deleted files that could not be removed, retried on the next run
+    pub contents_failed: usize,
+    /// Expired upload sessions
+    pub expired_uploads: usize,
+    /// Partial uploads in the scratch directory without a session
+    pub partial_files_removed: usize,
+    /// File contents scanned because an earlier scan failed or they predate scanning
+    pub files_scanned: usize,
+    /// File contents that still could not be scanned
+    pub scans_failed: usize,
+}
+
+impl ReconcileReport {
+    pub fn is_empty(&self) -> bool {
+        *self == Self::default()
+    }
+}
+
+/// Runs all cleanups once
+pub async fn reconcile<T>(
+    db: &Arc<Surreal<T>>,
+    files: &FilesServiceState,
+) -> Result<ReconcileReport, ServerResponseError>
+where
+    T: surrealdb::Connection,
+{
+    let mut report = ReconcileReport::default();
+    let cutoff = Utc::now()
+        - chrono::Duration::from_std(ORPHAN_GRACE_PERIOD).unwrap_or(chrono::Duration::zero());
+    let scan_cutoff = Utc::now() - chrono::Duration::minutes(SCAN_RETRY_AFTER_MINUTES);
+
+    // Deleting the records queues their content, in case some of it was stored
+    const UNSTORED_SQL: &str = "
+        DELETE file WHERE stored = false AND created_at < $CUTOFF RETURN VALUE record::id(id);
+    ";
+    let unstored: Vec<String> = db
+        .query(UNSTORED_SQL)
+        .bind(("CUTOFF", surrealdb::sql::Datetime::from(cutoff)))
+        .observed()
+        .await?
+        .take(0)?;
+    report.unstored_files = unstored.len();
+
+    // Revisions of files that were not deleted above, e.g. a failed upload of new content
+    const UNSTORED_REVISIONS_SQL: &str = "
+        DELETE file_revision WHERE stored = false AND created_at < $CUTOFF
+        RETURN VALUE record::id(id);
+    ";
+    let unstored_revisions: Vec<String> = db
+        .query(UNSTORED_REVISIONS_SQL)
+        .bind(("CUTOFF", surrealdb::sql::Datetime::from(cutoff)))
+        .observed()
+        .await?
+        .take(0)?;
+    report.unstored_revisions = unstored_revisions.len();
+
+    let (removed, failed) =
+        purge_deleted_contents(db, files.store.as_ref(), PURGE_BATCH_SIZE).await?;
+    report.contents_removed = removed;
+    report.contents_failed = failed;
+
+    const EXPIRED_SQL: &str = "
+        DELETE upload_session WHERE expires_at < time::now() RETURN VALUE record::id(id);
+    ";
+    let expired: Vec<String> = db.query(EXPIRED_SQL).observed().await?.take(0)?;
+    for upload_id in &expired {
+        remove_partial(files, upload_id).await;
+    }
+    report.expired_uploads = expired.len();
+
+    report.partial_files_removed = remove_orphaned_partials(db, files).await?;
+
+    // Scans of new files run in the background right after the upload, only
+    // those that are pending for a while are picked up here
+    const UNSCANNED_SQL: &str = "
+        RETURN array::distinct((SELECT VALUE key FROM file_revision
+            WHERE stored = true
+                AND (scan_status = NONE OR (scan_status = 'pending' AND created_at < $CUTOFF))
+            LIMIT $LIMIT));
+    ";
+    let unscanned: Vec<String> = db
+        .query(UNSCANNED_SQL)
+        .bind(("CUTOFF", surrealdb::sql::Datetime::from(scan_cutoff)))
+        .bind(("LIMIT", SCAN_BATCH_SIZE))
+        .observed()
+        .await?
+        .take(0)?;
+
+    for key in &unscanned {
+        match scan_file(db, files.store.as_ref(), files.scanner.as_ref(), key).await {
+            Ok(_) => report.files_scanned += 1,
+            Err(err) => {
+                warn!("Scanning file content {} failed again: {}", key, err);
+                report.scans_failed += 1;
+            }
+        }
+    }
+
+    Ok(report)
+}
+
+/// Removes partial uploads that have no session, e.g. because finalizing
+/// failed half way or the session was deleted with its user
+async fn remove_orphaned_partials<T>(
+    db: &Arc<Surreal<T>>,
+    files: &FilesServiceState,
+) -> Result<usize, ServerResponseError>
+where
+    T: surrealdb::Connection,
+{
+    const SQL: &str = "SELECT VALUE record::id(id) FROM upload_session;";
+    let sessions: HashSet<String> = db
+        .query(SQL)
+        .observed()
+        .await?
+        .take::<Vec<String>>(0)?
+        .into_iter()
+        .collect();
+
+    let mut entries = match fs::read_dir(&files.scratch_path).await {
+        Ok(entries) => entries,
+        Err(err) => {
+            warn!("Failed to read {}: {}", files.scratch_path.display(), err);
+            return Ok(0);
+        }
+    };
+
+    let cutoff = SystemTime::now() - ORPHAN_GRACE_PERIOD;
+    let mut removed = 0;
+
+    while let Ok(Some(entry)) = entries.next_entry().await {
+        let name = entry.file_name();
+        let Some(upload_id) = name.to_str().and_then(|name| name.strip_suffix(".part")) else {
+            continue;
+        };
+
+        if sessions.contains(upload_id) {
+            continue;
+        }
+
+        // A session may have been created after the sessions were read
+        let modified = entry
+            .metadata()
+            .await
+            .and_then(|metadata| metadata.modified());
+        if !modified.is_ok_and(|modified| modified < cutoff) {
+            continue;
+        }
+
+        match fs::remove_file(entry.path()).await {
+            Ok(()) => removed += 1,
+            Err(err) => warn!("Failed to remove orphaned upload {}: {}", upload_id, err),
+        }
+    }
+
+    Ok(removed)
+}
@@ -0,0 +1,274 @@
+//! File revisions. Uploading new content for a file or restoring an older
+//! revision adds a revision, the `file` record always carries the
+//! properties of the latest one. The first revision keeps its content
+//! under the file ID, later ones under a key of their own. Restored
+//! revisions share the content of the revision they were restored from.
+
+use super::get::get_accessible_file_metadata;
+use super::insert::NewFile;
+use super::store::FileStore;
+use crate::error::ServerResponseError;
+use crate::metrics::ObserveQuery;
+use crate::models::file_metadata::{FileMetadata, ScanStatus};
+use crate::models::file_revision::FileRevision;
+use crate::models::team::TeamRole;
+use rand::distributions::{Alphanumeric, DistString};
+use rand::thread_rng;
+use serde::Serialize;
+use std::path::Path;
+use std::sync::Arc;
+use surrealdb::sql::Thing;
+use surrealdb::Surreal;
+use tracing::error;
+
+#[derive(Debug, Serialize)]
+struct RevisionRecord {
+    file: Thing,
+    key: String,
+    filename: String,
+    size: Option<u64>,
+    mime_type: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    declared_type: Option<String>,
+    sha256: Option<String>,
+    scan_status: Option<ScanStatus>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    threat: Option<String>,
+    uploaded_by: Thing,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    restored_from: Option<u32>,
+    stored: bool,
+}
+
+fn file_thing(file: &FileMetadata) -> Thing {
+    Thing::from((file.id.tb.clone(), file.id.id.clone()))
+}
+
+/// Creates `record` as the next revision of its file. Concurrent revisions
+/// of the same file collide on the unique revision number, the later one
+/// fails.
+async fn create_revision<T>(
+    db: &Arc<Surreal<T>>,
+    record: RevisionRecord,
+) -> Result<FileRevision, ServerResponseError>
+where
+    T: surrealdb::Connection,
+{
+    const SQL: &str = "
+        LET $NEXT = (math::max((SELECT VALUE revision FROM file_revision WHERE file = $REVISION.file)) ?? 0) + 1;
+        CREATE ONLY file_revision CONTENT $REVISION SET revision = $NEXT;
+    ";
+    let created: Option<FileRevision> = db
+        .query(SQL)
+        .bind(("REVISION", record))
+        .observed()
+        .await?
+        .take(1)?;
+
+    created.ok_or_else(|| ServerResponseError::InternalError("revision not created".to_string()))
+}
+
+/// Makes `revision` the current revision of its file
+async fn make_current<T>(
+    db: &Arc<Surreal<T>>,
+    revision: &FileRevision,
+) -> Result<FileMetadata, ServerResponseError>
+where
+    T: surrealdb::Connection,
+{
+    const SQL: &str = "
+        BEGIN TRANSACTION;
+        LET $REVISION = (UPDATE ONLY $ID SET stored = true);
+        UPDATE ONLY $REVISION.file SET
+            filename = $REVISION.filename,
+            size = $REVISION.size,
+            mime_type = $REVISION.mime_type,
+            declared_type = $REVISION.declared_type,
+            sha256 = $REVISION.sha256,
+            scan_status = $REVISION.scan_status,
+            threat = $REVISION.threat,
+            scanned_at = $REVISION.scanned_at,
+            revision = $REVISION.revision,
+            key = $REVISION.key;
+        COMMIT TRANSACTION;
+    ";
+    let updated: Option<FileMetadata> = db
+        .query(SQL)
+        .bind((
+            "ID",
+            Thing::from((revision.id.tb.clone(), revision.id.id.clone())),
+        ))
+        .observed()
+        .await?
+        .take(1)?;
+
+    updated.ok_or(ServerResponseError::NotFound)
+}
+
+/// Uploads `source` as new content of the file with ID `file_id` if
+/// `user_id` may edit it. The content has to pass the upload policy
+/// already. The filename of the file changes to that of `new`. The new
+/// revision is `pending` until it has been scanned.
+pub async fn add_revision<T>(
+    db: &Arc<Surreal<T>>,
+    store: &dyn FileStore,
+    file_id: String,
+    user_id: Thing,
+    new: NewFile,
+    source: &Path,
+) -> Result<FileRevision, ServerResponseError>
+where
+    T: surrealdb::Connection,
+{
+    let file = get_accessible_file_metadata(db, file_id, user_id.clone(), TeamRole::Editor).await?;
+    let key = format!(
+        "{}.{}",
+        file.id.id.to_raw(),
+        Alphanumeric.sample_string(&mut thread_rng(), 20)
+    );
+
+    let revision = create_revision(
+        db,
+        RevisionRecord {
+            file: file_thing(&file),
+            key: key.clone(),
+            filename: new.filename,
+            size: Some(new.properties.size),
+            mime_type: Some(new.properties.mime_type),
+            declared_type: new.declared_type,
+            sha256: Some(new.properties.sha256),
+            scan_status: Some(ScanStatus::Pending),
+            threat: None,
+            uploaded_by: user_id,
+            restored_from: None,
+            stored: false,
+        },
+    )
+    .await?;
+
+    // Deleting the unstored revision queues whatever made it into the store
+    let discard = || async {
+        const SQL: &str = "DELETE $ID;";
+        let id = Thing::from((revision.id.tb.clone(), revision.id.id.clone()));
+        if let Err(err) = db.query(SQL).bind(("ID", id)).observed().await {
+            error!("Failed to discard revision {}: {}", revision.id.id, err);
+        }
+    };
+
+    if let Err(err) = store.put(&key, source).await {
+        error!(
+            "Failed to store revision {} of file {}: {}",
+            revision.revision, file.id.id, err
+        );
+        discard().await;
+        return Err(err.into());
+    }
+
+    match make_current(db, &revision).await {
+        Ok(_) => Ok(revision),
+        Err(err) => {
+            discard().await;
+            Err(err)
+        }
+    }
+}
+
+/// Returns all revisions of the file with ID `file_id` if `user_id` may
+/// read it, the latest first
+pub async fn list_revisions<T>(
+    db: &Arc<Surreal<T>>,
+    file_id: String,
+    user_id: Thing,
+) -> Result<Vec<FileRevision>, ServerResponseError>
+where
+    T: surrealdb::Connection,
+{
+    let file = get_accessible_file_metadata(db, file_id, user_id, TeamRole::Viewer).await?;
+
+    const SQL: &str = "
+        SELECT * FROM file_revision WHERE file = $FILE AND stored = true ORDER BY revision DESC;
+    ";
+    let revisions: Vec<FileRevision> = db
+        .query(SQL)
+        .bind(("FILE", file_thing(&file)))
+        .observed()
+        .await?
+        .take(0)?;
+
+    Ok(revisions)
+}
+
+/// Returns revision `revision` of the file with ID `file_id` if `user_id`
+/// has at least the role `required` for the file
+pub async fn get_revision<T>(
+    db: &Arc<Surreal<T>>,
+    file_id: String,
+    revision: u32,
+    user_id: Thing,
+    required: TeamRole,
+) -> Result<FileRevision, ServerResponseError>
+where
+    T: surrealdb::Connection,
+{
+    let file = get_accessible_file_metadata(db, file_id, user_id, required).await?;
+
+    const SQL: &str = "
+        SELECT * FROM ONLY file_revision
+        WHERE file = $FILE AND revision = $REVISION AND stored = true
+        LIMIT 1;
+    ";
+    let found: Option<FileRevision> = db
+        .query(SQL)
+        .bind(("FILE", file_thing(&file)))
+        .bind(("REVISION", revision))
+        .observed()
+        .await?
+        .take(0)?;
+
+    found.ok_or(ServerResponseError::NotFound)
+}
+
+/// Restores revision `revision` of the file with ID `file_id` by adding a
+/// revision with its content, if `user_id` may edit the file. Infected
+/// revisions cannot be restored.
+pub async fn restore_revision<T>(
+    db: &Arc<Surreal<T>>,
+    file_id: String,
+    revision: u32,
+    user_id: Thing,
+) -> Result<FileRevision, ServerResponseError>
+where
+    T: surrealdb::Connection,
+{
+    let source = get_revision(db, file_id, revision, user_id.clone(), TeamRole::Editor).await?;
+
+    if source.scan_status == Some(ScanStatus::Infected) {
+        return Err(ServerResponseError::ForbiddenWithMessage(
+            "the revision contains malware and cannot be restored".to_string(),
+        ));
+    }
+
+    let restored = create_revision(
+        db,
+        RevisionRecord {
+            file: Thing::from((source.file.tb.clone(), source.file.id.clone())),
+            key: source.key,
+            filename: source.filename,
+            size: source.size,
+            mime_type: source.mime_type,
+            declared_type: source.declared_type,
+            sha256: source.sha256,
+            scan_status: source.scan_status,
+            threat: source.threat,
+            uploaded_by: user_id,
+            restored_from: Some(source.revision),
+            // The content is already in the store
+            stored: true,
+        },
+    )
+    .await?;
+
+    make_current(db, &restored).await?;
+
+    Ok(restored)
+}
@@ -0,0 +1,185 @@
+use super::{ScanError, Scanner, Verdict};
+use crate::services::files::store::ByteStream;
+use futures::future::BoxFuture;
+use futures::StreamExt;
+use std::path::PathBuf;
+use std::str::FromStr;
+use std::time::Duration;
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+use tokio::net::TcpStream;
+use tokio::time::timeout;
+
+/// Longest time a single scan may take, large archives take a while
+const SCAN_TIMEOUT: Duration = Duration::from_secs(5 * 60);
+
+/// Longest reply clamd sends, anything longer is not clamd
+const MAX_REPLY_LEN: usize = 4096;
+
+/// Where clamd listens, `CLAMAV_ADDRESS` is either `tcp://host:port` (or just `host:port`) or
+/// `unix:///path/to/clamd.sock` (or just the path)
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ClamAvAddress {
+    Tcp(String),
+    Unix(PathBuf),
+}
+
+impl FromStr for ClamAvAddress {
+    type Err = ScanError;
+
+    fn from_str(address: &str) -> Result<Self, Self::Err> {
+        if let Some(path) = address.strip_prefix("unix://") {
+            return Ok(Self::Unix(PathBuf::from(path)));
+        }
+        if address.starts_with('/') {
+            return Ok(Self::Unix(PathBuf::from(address)));
+        }
+
+        let host = address.strip_prefix("tcp://").unwrap_or(address);
+        if host
+            .rsplit_once(':')
+            .is_some_and(|(_, port)| port.parse::<u16>().is_ok())
+        {
+            Ok(Self::Tcp(host.to_string()))
+        } else {
+            Err(ScanError::Config(format!(
+                "invalid CLAMAV_ADDRESS {address:?}, expected tcp://host:port or unix:///path"
+            )))
+        }
+    }
+}
+
+trait Connection: AsyncRead + AsyncWrite + Unpin + Send {}
+
+impl<C: AsyncRead + AsyncWrite + Unpin + Send> Connection for C {}
+
+/// Parses the reply of clamd to `INSTREAM`, e.g. `stream: OK` or
+/// `stream: Eicar-Signature FOUND`
+pub(crate) fn parse_reply(reply: &str) -> Result<Verdict, ScanError> {
+    let reply = reply.trim_end_matches(['\0', '\n']).trim();
+    let result = reply.strip_prefix("stream:").unwrap_or(reply).trim();
+
+    if result == "OK" {
+        Ok(Verdict::Clean)
+    } else if let Some(threat) = result.strip_suffix(" FOUND") {
+        Ok(Verdict::Infected(threat.trim().to_string()))
+    } else {
+        Err(ScanError::Scanner(format!("clamd replied {reply:?}")))
+    }
+}
+
+/// Scans files with a clamd daemon over its socket protocol. Content is
+/// streamed with `INSTREAM`, so clamd does not need access to the files.
+/// Files larger than its `StreamMaxLength` are reported as scan failures.
+pub struct ClamAvScanner {
+    address: ClamAvAddress,
+}
+
+impl ClamAvScanner {
+    pub fn new(address: ClamAvAddress) -> Self {
+        Self { address }
+    }
+
+    async fn connect(&self) -> Result<Box<dyn Connection>, ScanError> {
+        Ok(match &self.address {
+            ClamAvAddress::Tcp(address) => Box::new(TcpStream::connect(address).await?),
+            #[cfg(unix)]
+            ClamAvAddress::Unix(path) => Box::new(tokio::net::UnixStream::connect(path).await?),
+            #[cfg(not(unix))]
+            ClamAvAddress::Unix(_) => {
+                return Err(ScanError::Config(
+                    "unix sockets are not supported on this platform".to_string(),
+                ))
+            }
+        })
+    }
+
+    /// Reads the reply to the last command, clamd terminates it with a
+    /// null byte when the command is prefixed with `z`
+    async fn read_reply(connection: &mut dyn Connection) -> Result<String, ScanError> {
+        let mut reply = Vec::new();
+        let mut buffer = [0; 256];
+
+        loop {
+            let read = connection.read(&mut buffer).await?;
+            if read == 0 {
+                break;
+            }
+
+            reply.extend_from_slice(&buffer[..read]);
+            if reply.contains(&0) {
+                break;
+            }
+            if reply.len() > MAX_REPLY_LEN {
+                return Err(ScanError::Scanner("clamd reply is too long".to_string()));
+            }
+        }
+
+        Ok(String::from_utf8_lossy(&reply).into_owned())
+    }
+
+    async fn instream(&self, mut content: ByteStream) -> Result<Verdict, ScanError> {
+        let mut connection = self.connect().await?;
+        connection.write_all(b"zINSTREAM\0").await?;
+
+        while let Some(chunk) = content.next().await {
+            let chunk = chunk?;
+            if chunk.is_empty() {
+                continue;
+            }
+
+            for part in chunk.chunks(u32::MAX as usize) {
+                connection
+                    .write_all(&(part.len() as u32).to_be_bytes())
+                    .await?;
+                connection.write_all(part).await?;
+            }
+        }
+
+        connection.write_all(&[0; 4]).await?;
+        connection.flush().await?;
+
+        parse_reply(&Self::read_reply(connection.as_mut()).await?)
+    }
+}
+
+impl Scanner for ClamAvScanner {
+    fn name(&self) -> &'static str {
+        "clamav"
+    }
+
+    fn check(&self) -> BoxFuture<'_, Result<(), ScanError>> {
+        Box::pin(async move {
+            let unusable = |reason: String| {
+                ScanError::Config(format!(
+                    "clamd at {:?} is not usable: {reason}",
+                    self.address
+                ))
+            };
+
+            let mut connection = self
+                .connect()
+                .await
+                .map_err(|err| unusable(err.to_string()))?;
+            connection
+                .write_all(b"zPING\0")
+                .await
+                .map_err(|err| unusable(err.to_string()))?;
+            let reply = Self::read_reply(connection.as_mut())
+                .await
+                .map_err(|err| unusable(err.to_string()))?;
+
+            match reply.trim_end_matches('\0').trim() {
+                "PONG" => Ok(()),
+                reply => Err(unusable(format!("PING was answered with {reply:?}"))),
+            }
+        })
+    }
+
+    fn scan(&self, content: ByteStream) -> BoxFuture<'_, Result<Verdict, ScanError>> {
+        Box::pin(async move {
+            timeout(SCAN_TIMEOUT, self.instream(content))
+                .await
+                .map_err(|_| ScanError::Scanner("clamd timed out".to_string()))?
+        })
+    }
+}
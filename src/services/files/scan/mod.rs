@@ -0,0 +1,183 @@
+//! Malware scanning of uploaded files.
+//!
+//! New files are `pending` until a [`Scanner`] has looked at their content, only `clean` files
+//! can be downloaded. Infected files are quarantined: they stay in the store for review but are
+//! never served and their share links are revoked. The scanner is selected with `FILE_SCANNER`:
+//!
+//! - `none` (default) - every file is clean, see [`NoopScanner`]
+//! - `clamav` - a clamd daemon at `CLAMAV_ADDRESS`, see [`ClamAvScanner`]
+
+pub mod clamav;
+pub mod noop;
+mod test;
+
+use super::store::{ByteStream, FileStore, StoreError};
+use crate::error::ServerResponseError;
+use crate::metrics::ObserveQuery;
+use futures::future::BoxFuture;
+use std::sync::Arc;
+use surrealdb::Surreal;
+use tracing::{error, info, warn};
+
+pub use clamav::{ClamAvAddress, ClamAvScanner};
+pub use noop::NoopScanner;
+
+#[derive(Debug, thiserror::Error)]
+pub enum ScanError {
+    #[error("scanner is misconfigured: {0}")]
+    Config(String),
+    #[error("scanner I/O error: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("scanner failed: {0}")]
+    Scanner(String),
+    #[error(transparent)]
+    Store(#[from] StoreError),
+}
+
+/// Result of scanning a file
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Verdict {
+    Clean,
+    /// Names the signature that matched
+    Infected(String),
+}
+
+pub trait Scanner: Send + Sync {
+    /// Name of the scanner, used in logs
+    fn name(&self) -> &'static str;
+
+    /// Makes sure the scanner can be used, called once on startup
+    fn check(&self) -> BoxFuture<'_, Result<(), ScanError>>;
+
+    /// Scans `content`
+    fn scan(&self, content: ByteStream) -> BoxFuture<'_, Result<Verdict, ScanError>>;
+}
+
+/// Which scanner to use, read from the [`Config`](crate::config::Config)
+#[derive(Debug, Clone, PartialEq)]
+pub enum ScannerConfig {
+    None,
+    ClamAv(ClamAvAddress),
+}
+
+impl ScannerConfig {
+    pub(crate) fn from_vars(var: impl Fn(&str) -> Option<String>) -> Result<Self, ScanError> {
+        let kind = var("FILE_SCANNER").unwrap_or_else(|| "none".to_string());
+
+        match kind.to_lowercase().as_str() {
+            "none" => Ok(Self::None),
+            "clamav" => {
+                let address = var("CLAMAV_ADDRESS").ok_or_else(|| {
+                    ScanError::Config("CLAMAV_ADDRESS is required for FILE_SCANNER=clamav".into())
+                })?;
+                Ok(Self::ClamAv(address.parse()?))
+            }
+            other => Err(ScanError::Config(format!(
+                "unknown FILE_SCANNER {other:?}, expected none or clamav"
+            ))),
+        }
+    }
+
+    /// Builds the scanner and makes sure it can be used
+    pub async fn build(self) -> Result<Arc<dyn Scanner>, ScanError> {
+        let scanner: Arc<dyn Scanner> = match self {
+            Self::None => {
+                warn!("FILE_SCANNER is not set, uploaded files are not scanned for malware");
+                Arc::new(NoopScanner)
+            }
+            Self::ClamAv(address) => Arc::new(ClamAvScanner::new(address)),
+        };
+
+        scanner.check().await?;
+        info!("Scanning files with {}", scanner.name());
+
+        Ok(scanner)
+    }
+}
+
+/// Files whose current content has the store key `$KEY`, the first
+/// revision of a file is keyed by the file ID
+const LET_FILES_WITH_KEY: &str = "
+    LET $FILES = array::union(
+        (SELECT VALUE id FROM file WHERE key = $KEY),
+        (SELECT VALUE id FROM type::thing('file', $KEY) WHERE key = NONE)
+    );
+";
+
+/// Scans the stored content with key `key` and records the verdict on
+/// the revisions and files having it. Infected files are quarantined. When
+/// scanning fails they stay `pending` and are scanned again by the
+/// reconciler.
+pub async fn scan_file<T>(
+    db: &Arc<Surreal<T>>,
+    store: &dyn FileStore,
+    scanner: &dyn Scanner,
+    key: &str,
+) -> Result<Verdict, ServerResponseError>
+where
+    T: surrealdb::Connection,
+{
+    let verdict = async { scanner.scan(store.get(key).await?.body).await }
+        .await
+        .map_err(|err| ServerResponseError::FailedDependencyWithMessage(err.to_string()))?;
+
+    match &verdict {
+        Verdict::Clean => {
+            let sql = format!(
+                "BEGIN TRANSACTION;
+                {LET_FILES_WITH_KEY}
+                UPDATE $FILES SET scan_status = 'clean', threat = NONE, scanned_at = time::now();
+                UPDATE file_revision SET scan_status = 'clean', threat = NONE, scanned_at = time::now()
+                WHERE key = $KEY;
+                COMMIT TRANSACTION;"
+            );
+            db.query(sql)
+                .bind(("KEY", key.to_string()))
+                .observed()
+                .await?
+                .check()?;
+        }
+        Verdict::Infected(threat) => {
+            warn!(
+                "File content {} is infected with {}, quarantining it",
+                key, threat
+            );
+
+            let sql = format!(
+                "BEGIN TRANSACTION;
+                {LET_FILES_WITH_KEY}
+                UPDATE $FILES SET scan_status = 'infected', threat = $THREAT, scanned_at = time::now();
+                UPDATE file_revision SET scan_status = 'infected', threat = $THREAT, scanned_at = time::now()
+                WHERE key = $KEY;
+                DELETE file_share WHERE file IN $FILES;
+                COMMIT TRANSACTION;"
+            );
+            db.query(sql)
+                .bind(("KEY", key.to_string()))
+                .bind(("THREAT", threat.clone()))
+                .observed()
+                .await?
+                .check()?;
+        }
+    }
+
+    Ok(verdict)
+}
+
+/// Scans the contents with `keys` in the background
+pub fn spawn_scans<T>(
+    db: Arc<Surreal<T>>,
+    store: Arc<dyn FileStore>,
+    scanner: Arc<dyn Scanner>,
+    keys: Vec<String>,
+) where
+    T: surrealdb::Connection,
+{
+    actix_web::rt::spawn(async move {
+        for key in keys {
+            if let Err(err) = scan_file(&db, store.as_ref(), scanner.as_ref(), &key).await {
+                error!("Scanning file content {} failed: {}", key, err);
+            }
+        }
+    });
+}
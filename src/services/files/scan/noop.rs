@@ -0,0 +1,20 @@
+use super::{ScanError, Scanner, Verdict};
+use crate::services::files::store::ByteStream;
+use futures::future::BoxFuture;
+
+/// Accepts every file without looking at it
+pub struct NoopScanner;
+
+impl Scanner for NoopScanner {
+    fn name(&self) -> &'static str {
+        "none"
+    }
+
+    fn check(&self) -> BoxFuture<'_, Result<(), ScanError>> {
+        Box::pin(async { Ok(()) })
+    }
+
+    fn scan(&self, _content: ByteStream) -> BoxFuture<'_, Result<Verdict, ScanError>> {
+        Box::pin(async { Ok(Verdict::Clean) })
+    }
+}
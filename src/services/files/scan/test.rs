@@ -0,0 +1,123 @@
+#![cfg(test)]
+
+use super::clamav::parse_reply;
+use super::{ClamAvAddress, ClamAvScanner, ScanError, Scanner, ScannerConfig, Verdict};
+use actix_web::web::Bytes;
+use futures::{stream, StreamExt};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpListener;
+
+/// The EICAR test file, detected by every virus scanner
+const EICAR: &[u8] = br"X5O!P%@AP[4\PZX54(P^)7CC)7}$EICAR-STANDARD-ANTIVIRUS-TEST-FILE!$H+H*";
+
+#[test]
+fn clamd_replies_are_parsed() {
+    assert_eq!(parse_reply("stream: OK\0").unwrap(), Verdict::Clean);
+    assert_eq!(
+        parse_reply("stream: Eicar-Signature FOUND\0").unwrap(),
+        Verdict::Infected("Eicar-Signature".to_string())
+    );
+    assert!(matches!(
+        parse_reply("INSTREAM size limit exceeded. ERROR\0"),
+        Err(ScanError::Scanner(_))
+    ));
+}
+
+#[test]
+fn clamav_addresses_are_parsed() {
+    assert_eq!(
+        "tcp://clamd:3310".parse::<ClamAvAddress>().unwrap(),
+        ClamAvAddress::Tcp("clamd:3310".to_string())
+    );
+    assert_eq!(
+        "localhost:3310".parse::<ClamAvAddress>().unwrap(),
+        ClamAvAddress::Tcp("localhost:3310".to_string())
+    );
+    assert_eq!(
+        "unix:///run/clamav/clamd.ctl"
+            .parse::<ClamAvAddress>()
+            .unwrap(),
+        ClamAvAddress::Unix("/run/clamav/clamd.ctl".into())
+    );
+    assert!("clamd".parse::<ClamAvAddress>().is_err());
+
+    assert_eq!(
+        ScannerConfig::from_vars(|_| None).unwrap(),
+        ScannerConfig::None
+    );
+    assert!(
+        ScannerConfig::from_vars(|name| (name == "FILE_SCANNER").then(|| "clamav".into())).is_err()
+    );
+}
+
+/// Accepts one `INSTREAM` command and answers like clamd would for `content`
+async fn fake_clamd() -> (String, tokio::task::JoinHandle<Vec<u8>>) {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let address = listener.local_addr().unwrap().to_string();
+
+    let server = tokio::spawn(async move {
+        let (mut socket, _) = listener.accept().await.unwrap();
+
+        let mut command = [0; 10];
+        socket.read_exact(&mut command).await.unwrap();
+        assert_eq!(&command, b"zINSTREAM\0");
+
+        let mut content = Vec::new();
+        loop {
+            let len = socket.read_u32().await.unwrap() as usize;
+            if len == 0 {
+                break;
+            }
+            let mut chunk = vec![0; len];
+            socket.read_exact(&mut chunk).await.unwrap();
+            content.extend_from_slice(&chunk);
+        }
+
+        let reply: &[u8] = if content == EICAR {
+            b"stream: Eicar-Signature FOUND\0"
+        } else {
+            b"stream: OK\0"
+        };
+        socket.write_all(reply).await.unwrap();
+
+        content
+    });
+
+    (address, server)
+}
+
+#[actix::test]
+async fn clamav_scanner_streams_content() {
+    let (address, server) = fake_clamd().await;
+    let scanner = ClamAvScanner::new(ClamAvAddress::Tcp(address));
+
+    let content = stream::iter([
+        Bytes::from_static(&EICAR[..10]),
+        Bytes::from_static(&EICAR[10..]),
+    ])
+    .map(Ok)
+    .boxed();
+    let verdict = scanner.scan(content).await.unwrap();
+
+    assert_eq!(verdict, Verdict::Infected("Eicar-Signature".to_string()));
+    assert_eq!(server.await.unwrap(), EICAR);
+}
+
+/// Runs against a real clamd, e.g. `docker compose --profile clamav up` and
+/// `CLAMAV_TEST_ADDRESS=localhost:3310 cargo test -- --ignored`
+#[actix::test]
+#[ignore]
+async fn clamav_detects_eicar() {
+    let address = std::env::var("CLAMAV_TEST_ADDRESS").expect("CLAMAV_TEST_ADDRESS is not set");
+    let scanner = ClamAvScanner::new(address.parse().unwrap());
+    scanner.check().await.unwrap();
+
+    let eicar = stream::once(async { Ok(Bytes::from_static(EICAR)) }).boxed();
+    assert!(matches!(
+        scanner.scan(eicar).await.unwrap(),
+        Verdict::Infected(_)
+    ));
+
+    let clean = stream::once(async { Ok(Bytes::from_static(b"threat model")) }).boxed();
+    assert_eq!(scanner.scan(clean).await.unwrap(), Verdict::Clean);
+}
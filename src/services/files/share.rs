@@ -0,0 +1,318 @@
+//! Share links give unauthenticated access to a single file. A link
+//! carries the ID of a `file_share` record and its expiry, signed with
+//! HMAC-SHA256 so that neither can be guessed or altered. The record
+//! itself holds the optional password and download limit and is deleted
+//! when the link is revoked.
+
+use crate::config::Secret;
+use crate::dto::file_share::{ShareLink, ShareLinkRequest};
+use crate::error::ServerResponseError;
+use crate::metrics::ObserveQuery;
+use crate::models::file_metadata::FileMetadata;
+use crate::models::file_share::FileShare;
+use crate::models::team::TeamRole;
+use crate::services::files::get::get_accessible_file_metadata;
+use crate::services::files::hex::{decode_hex, encode_hex};
+use chrono::{DateTime, Utc};
+use hmac::{Hmac, Mac};
+use rand::distributions::{Alphanumeric, DistString};
+use rand::thread_rng;
+use serde::Deserialize;
+use sha2::Sha256;
+use std::sync::Arc;
+use surrealdb::sql::Thing;
+use surrealdb::Surreal;
+use tracing::warn;
+
+type HmacSha256 = Hmac<Sha256>;
+
+/// Longest lifetime of a share link, 30 days
+pub const MAX_SHARE_LIFETIME: u64 = 30 * 24 * 60 * 60;
+
+const SHARE_FIELDS: &str = "*, password != NONE AS password_protected OMIT password";
+
+/// Signs and verifies share link URLs.
+pub struct ShareLinkSigner {
+    key: Vec<u8>,
+}
+
+impl ShareLinkSigner {
+    pub fn new(key: impl Into<Vec<u8>>) -> Self {
+        Self { key: key.into() }
+    }
+
+    /// Uses the configured `SHARE_LINK_SECRET` as key. Without it a random key is
+    /// generated, links then stop working when the server restarts.
+    pub fn from_secret(secret: Option<&Secret>) -> Self {
+        match secret {
+            Some(secret) => Self::new(secret.expose()),
+            None => {
+                warn!("SHARE_LINK_SECRET is not set, share links will not survive a restart");
+                Self::new(Alphanumeric.sample_string(&mut thread_rng(), 64))
+            }
+        }
+    }
+
+    fn mac(&self, share_id: &str, expires: i64) -> HmacSha256 {
+        let mut mac =
+            HmacSha256::new_from_slice(&self.key).expect("HMAC accepts keys of any length");
+        mac.update(format!("{share_id}:{expires}").as_bytes());
+        mac
+    }
+
+    /// Hex encoded signature of `share_id` and `expires`
+    pub fn sign(&self, share_id: &str, expires: i64) -> String {
+        encode_hex(&self.mac(share_id, expires).finalize().into_bytes())
+    }
+
+    /// Checks `signature` in constant time
+    pub fn verify(&self, share_id: &str, expires: i64, signature: &str) -> bool {
+        match decode_hex(signature) {
+            Some(signature) => self.mac(share_id, expires).verify_slice(&signature).is_ok(),
+            None => false,
+        }
+    }
+
+    /// URL of the unauthenticated download route for a share
+    pub fn url(&self, share: &FileShare) -> String {
+        let share_id = share.id.id.to_raw();
+        let expires = share.expires_at.timestamp();
+
+        format!(
+            "/api/v1/shared/{share_id}?expires={expires}&signature={}",
+            self.sign(&share_id, expires)
+        )
+    }
+
+    pub fn link(&self, share: FileShare) -> ShareLink {
+        ShareLink {
+            url: self.url(&share),
+            share,
+        }
+    }
+}
+
+fn file_thing(file: &FileMetadata) -> Thing {
+    Thing::from((file.id.tb.clone(), file.id.id.clone()))
+}
+
+/// Creates a share link for the file with ID `file_id`. Only users that
+/// may change the file can share it.
+pub async fn create_share<T>(
+    db: &Arc<Surreal<T>>,
+    signer: &ShareLinkSigner,
+    file_id: String,
+    user_id: Thing,
+    request: ShareLinkRequest,
+) -> Result<ShareLink, ServerResponseError>
+where
+    T: surrealdb::Connection,
+{
+    if request.expires_in == 0 || request.expires_in > MAX_SHARE_LIFETIME {
+        return Err(ServerResponseError::invalid_field(
+            "expires_in",
+            format!("must be between 1 and {MAX_SHARE_LIFETIME} seconds"),
+        ));
+    }
+
+    if request.max_downloads == Some(0) {
+        return Err(ServerResponseError::invalid_field(
+            "max_downloads",
+            "must be at least 1",
+        ));
+    }
+
+    if request.password.as_ref().is_some_and(|p| p.is_empty()) {
+        return Err(ServerResponseError::invalid_field(
+            "password",
+            "must not be empty",
+        ));
+    }
+
+    let file = get_accessible_file_metadata(db, file_id, user_id.clone(), TeamRole::Editor).await?;
+
+    // Whole seconds so that the expiry in the URL matches the stored one
+    let expires_at = Utc::now().timestamp() + request.expires_in as i64;
+    let expires_at = DateTime::from_timestamp(expires_at, 0)
+        .ok_or_else(|| ServerResponseError::InternalError("invalid expiry".to_string()))?;
+
+    let sql = format!(
+        "LET $SHARE = (CREATE ONLY file_share CONTENT {{
+            file: $FILE,
+            created_by: $USER,
+            password: fn::hash_string($PASSWORD),
+            max_downloads: $MAX_DOWNLOADS,
+            expires_at: $EXPIRES_AT,
+        }});
+        SELECT {SHARE_FIELDS} FROM ONLY $SHARE.id;"
+    );
+    let created: Option<FileShare> = db
+        .query(sql)
+        .bind(("FILE", file_thing(&file)))
+        .bind(("USER", user_id))
+        .bind(("PASSWORD", request.password))
+        .bind(("MAX_DOWNLOADS", request.max_downloads))
+        .bind(("EXPIRES_AT", surrealdb::sql::Datetime::from(expires_at)))
+        .observed()
+        .await?
+        .take(1)?;
+
+    created
+        .map(|share| signer.link(share))
+        .ok_or_else(|| ServerResponseError::InternalError("share was not created".to_string()))
+}
+
+/// Returns the links of the file with ID `file_id` that can still be used
+pub async fn list_shares<T>(
+    db: &Arc<Surreal<T>>,
+    signer: &ShareLinkSigner,
+    file_id: String,
+    user_id: Thing,
+) -> Result<Vec<ShareLink>, ServerResponseError>
+where
+    T: surrealdb::Connection,
+{
+    let file = get_accessible_file_metadata(db, file_id, user_id, TeamRole::Editor).await?;
+
+    let sql = format!(
+        "SELECT {SHARE_FIELDS} FROM file_share
+        WHERE file = $FILE
+            AND expires_at > time::now()
+            AND (max_downloads = NONE OR downloads < max_downloads)
+        ORDER BY created_at DESC;"
+    );
+    let shares: Vec<FileShare> = db
+        .query(sql)
+        .bind(("FILE", file_thing(&file)))
+        .observed()
+        .await?
+        .take(0)?;
+
+    Ok(shares.into_iter().map(|share| signer.link(share)).collect())
+}
+
+/// Revokes the link with ID `share_id` of the file with ID `file_id`
+pub async fn revoke_share<T>(
+    db: &Arc<Surreal<T>>,
+    file_id: String,
+    share_id: String,
+    user_id: Thing,
+) -> Result<(), ServerResponseError>
+where
+    T: surrealdb::Connection,
+{
+    let file = get_accessible_file_metadata(db, file_id, user_id, TeamRole::Editor).await?;
+
+    const SQL: &str = "
+        SELECT VALUE id FROM type::thing('file_share', $ID) WHERE file = $FILE;
+        DELETE type::thing('file_share', $ID) WHERE file = $FILE;
+    ";
+    let revoked: Option<Thing> = db
+        .query(SQL)
+        .bind(("ID", share_id))
+        .bind(("FILE", file_thing(&file)))
+        .observed()
+        .await?
+        .take(0)?;
+
+    revoked.map(|_| ()).ok_or(ServerResponseError::NotFound)
+}
+
+#[derive(Deserialize)]
+struct ShareState {
+    password_protected: bool,
+    password_ok: bool,
+    active: bool,
+    scanned: bool,
+    file: Option<FileMetadata>,
+}
+
+/// Checks that the share with ID `share_id` can be downloaded and returns
+/// the metadata of the shared file. The signature of the link has to be
+/// verified before, the download is counted by [`count_share_download`].
+pub async fn redeem_share<T>(
+    db: &Arc<Surreal<T>>,
+    share_id: String,
+    password: Option<String>,
+) -> Result<FileMetadata, ServerResponseError>
+where
+    T: surrealdb::Connection,
+{
+    const CHECK_SQL: &str = "
+        SELECT
+            password != NONE AS password_protected,
+            (IF password = NONE { true }
+                ELSE IF $PASSWORD = NONE { false }
+                ELSE { crypto::argon2::compare(password, $PASSWORD) }) AS password_ok,
+            expires_at > time::now()
+                AND (max_downloads = NONE OR downloads < max_downloads) AS active,
+            file.scan_status = 'clean' AS scanned,
+            file.* AS file
+        FROM type::thing('file_share', $ID);
+    ";
+    let state: Option<ShareState> = db
+        .query(CHECK_SQL)
+        .bind(("ID", share_id.clone()))
+        .bind(("PASSWORD", password.clone()))
+        .observed()
+        .await?
+        .take(0)?;
+
+    let Some(state) = state else {
+        return Err(ServerResponseError::NotFound);
+    };
+
+    if !state.active {
+        return Err(ServerResponseError::Gone);
+    }
+
+    if state.password_protected && password.is_none() {
+        return Err(ServerResponseError::UnauthorizedWithMessage(
+            "password required".to_string(),
+        ));
+    }
+
+    if !state.password_ok {
+        return Err(ServerResponseError::UnauthorizedWithMessage(
+            "wrong password".to_string(),
+        ));
+    }
+
+    // Checked here so that a refused download does not use up the link
+    if !state.scanned {
+        return Err(ServerResponseError::Conflict(
+            "the file has not been scanned for malware yet, try again later".to_string(),
+        ));
+    }
+
+    state.file.ok_or(ServerResponseError::Gone)
+}
+
+/// Counts a download of the share with ID `share_id`. The limit is checked
+/// again so that concurrent downloads cannot exceed it.
+pub async fn count_share_download<T>(
+    db: &Arc<Surreal<T>>,
+    share_id: String,
+) -> Result<(), ServerResponseError>
+where
+    T: surrealdb::Connection,
+{
+    const SQL: &str = "
+        UPDATE type::thing('file_share', $ID) SET downloads += 1
+            WHERE expires_at > time::now()
+                AND (max_downloads = NONE OR downloads < max_downloads)
+            RETURN VALUE id;
+    ";
+    let counted: Vec<Thing> = db
+        .query(SQL)
+        .bind(("ID", share_id))
+        .observed()
+        .await?
+        .take(0)?;
+
+    if counted.is_empty() {
+        return Err(ServerResponseError::Gone);
+    }
+
+    Ok(())
+}
@@ -1,27 +1,50 @@
-use std::{env::temp_dir, fs, path::PathBuf};
+use super::policy::UploadPolicy;
+use super::scan::Scanner;
+use super::share::ShareLinkSigner;
+use super::store::{FileStore, StoreError};
+use crate::config::Config;
+use std::path::PathBuf;
+use std::sync::Arc;
+use tracing::info;
 
 pub struct FilesServiceState {
-    pub upload_path: PathBuf,
+    /// Where file contents are kept, see [`FileStoreConfig`]
+    pub store: Arc<dyn FileStore>,
+    /// Local directory for uploads that are still being received, see
+    /// [`Config::scratch_path`]
+    pub scratch_path: PathBuf,
+    pub share_links: ShareLinkSigner,
+    pub policy: UploadPolicy,
+    /// Scans new files for malware, see [`ScannerConfig`](super::scan::ScannerConfig)
+    pub scanner: Arc<dyn Scanner>,
 }
 
 impl FilesServiceState {
-    pub fn new() -> Self {
-        let upload_path = match dirs::home_dir() {
-            Some(path) => {
-                let directory_name = "threatmapper_files";
-                let full_path = path.join(directory_name);
-                match fs::create_dir_all(&full_path) {
-                    Ok(_) => full_path,
-                    Err(_) => temp_dir(),
-                }
-            }
-            None => temp_dir(),
-        };
+    /// Builds the configured store and makes sure it can be used. Errors are meant to stop the
+    /// server from starting.
+    pub async fn new(config: &Config, scanner: Arc<dyn Scanner>) -> Result<Self, StoreError> {
+        let store = config.file_store.clone().build()?;
+        store.check().await?;
 
-        Self { upload_path }
+        let scratch_path = config.scratch_path.clone();
+        tokio::fs::create_dir_all(&scratch_path)
+            .await
+            .map_err(|err| {
+                StoreError::Config(format!("{} is not writable: {err}", scratch_path.display()))
+            })?;
+
+        info!("Storing files in the {} file store", store.name());
+
+        Ok(Self {
+            store,
+            scratch_path,
+            share_links: ShareLinkSigner::from_secret(config.share_link_secret.as_ref()),
+            policy: config.upload_policy.clone(),
+            scanner,
+        })
     }
 
-    pub fn get_path_for(&self, filename: &str) -> PathBuf {
-        self.upload_path.join(filename)
+    pub fn get_partial_path_for(&self, upload_id: &str) -> PathBuf {
+        self.scratch_path.join(format!("{upload_id}.part"))
     }
 }
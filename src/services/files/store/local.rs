@@ -0,0 +1,133 @@
+use super::{validate_key, ByteStream, FileStore, StoreError, StoredObject};
+use actix_web::web::Bytes;
+use futures::future::BoxFuture;
+use futures::{stream, StreamExt};
+use std::io::ErrorKind;
+use std::io::SeekFrom;
+use std::ops::Range;
+use std::path::{Path, PathBuf};
+use tokio::fs;
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncSeekExt};
+
+const CHUNK_SIZE: usize = 64 * 1024;
+
+/// Stores files in a directory, one file per key
+pub struct LocalFileStore {
+    root: PathBuf,
+}
+
+impl LocalFileStore {
+    pub fn new(root: impl Into<PathBuf>) -> Self {
+        Self { root: root.into() }
+    }
+
+    fn path_for(&self, key: &str) -> Result<PathBuf, StoreError> {
+        validate_key(key)?;
+        Ok(self.root.join(key))
+    }
+}
+
+fn not_found(err: std::io::Error) -> StoreError {
+    match err.kind() {
+        ErrorKind::NotFound => StoreError::NotFound,
+        _ => err.into(),
+    }
+}
+
+/// Reads `file` in chunks, the stream ends after the first error
+fn read_stream<R>(file: R) -> ByteStream
+where
+    R: AsyncRead + Unpin + Send + 'static,
+{
+    stream::unfold(Some(file), |file| async move {
+        let mut file = file?;
+        let mut buffer = vec![0; CHUNK_SIZE];
+
+        match file.read(&mut buffer).await {
+            Ok(0) => None,
+            Ok(read) => {
+                buffer.truncate(read);
+                Some((Ok(Bytes::from(buffer)), Some(file)))
+            }
+            Err(err) => Some((Err(err.into()), None)),
+        }
+    })
+    .boxed()
+}
+
+impl FileStore for LocalFileStore {
+    fn name(&self) -> &'static str {
+        "local"
+    }
+
+    fn check(&self) -> BoxFuture<'_, Result<(), StoreError>> {
+        Box::pin(async move {
+            let unusable = |err: std::io::Error| {
+                StoreError::Config(format!("{} is not writable: {err}", self.root.display()))
+            };
+
+            fs::create_dir_all(&self.root).await.map_err(unusable)?;
+
+            let probe = self.root.join(".write-check");
+            fs::write(&probe, b"ok").await.map_err(unusable)?;
+            fs::remove_file(&probe).await.map_err(unusable)?;
+
+            Ok(())
+        })
+    }
+
+    fn put<'a>(&'a self, key: &'a str, source: &'a Path) -> BoxFuture<'a, Result<(), StoreError>> {
+        Box::pin(async move {
+            fs::copy(source, self.path_for(key)?).await?;
+            Ok(())
+        })
+    }
+
+    fn get<'a>(&'a self, key: &'a str) -> BoxFuture<'a, Result<StoredObject, StoreError>> {
+        Box::pin(async move {
+            let file = fs::File::open(self.path_for(key)?)
+                .await
+                .map_err(not_found)?;
+            let size = file.metadata().await?.len();
+
+            Ok(StoredObject {
+                size,
+                body: read_stream(file),
+            })
+        })
+    }
+
+    fn get_range<'a>(
+        &'a self,
+        key: &'a str,
+        range: Range<u64>,
+    ) -> BoxFuture<'a, Result<StoredObject, StoreError>> {
+        Box::pin(async move {
+            let mut file = fs::File::open(self.path_for(key)?)
+                .await
+                .map_err(not_found)?;
+            let len = file.metadata().await?.len();
+            let start = range.start.min(len);
+            let size = range.end.min(len).saturating_sub(start);
+
+            file.seek(SeekFrom::Start(start)).await?;
+
+            Ok(StoredObject {
+                size,
+                body: read_stream(file.take(size)),
+            })
+        })
+    }
+
+    fn delete<'a>(&'a self, key: &'a str) -> BoxFuture<'a, Result<(), StoreError>> {
+        Box::pin(async move {
+            match fs::remove_file(self.path_for(key)?)
+                .await
+                .map_err(not_found)
+            {
+                Ok(()) | Err(StoreError::NotFound) => Ok(()),
+                Err(err) => Err(err),
+            }
+        })
+    }
+}
@@ -0,0 +1,91 @@
+use super::{validate_key, FileStore, StoreError, StoredObject};
+use actix_web::web::Bytes;
+use futures::future::BoxFuture;
+use futures::{stream, StreamExt};
+use std::collections::HashMap;
+use std::ops::Range;
+use std::path::Path;
+use std::sync::RwLock;
+use tokio::fs;
+
+/// Keeps files in memory, everything is lost when the server stops
+#[derive(Default)]
+pub struct MemoryFileStore {
+    objects: RwLock<HashMap<String, Bytes>>,
+}
+
+impl MemoryFileStore {
+    fn objects(&self) -> std::sync::RwLockReadGuard<'_, HashMap<String, Bytes>> {
+        self.objects.read().unwrap_or_else(|err| err.into_inner())
+    }
+
+    fn objects_mut(&self) -> std::sync::RwLockWriteGuard<'_, HashMap<String, Bytes>> {
+        self.objects.write().unwrap_or_else(|err| err.into_inner())
+    }
+}
+
+impl FileStore for MemoryFileStore {
+    fn name(&self) -> &'static str {
+        "memory"
+    }
+
+    fn check(&self) -> BoxFuture<'_, Result<(), StoreError>> {
+        Box::pin(async { Ok(()) })
+    }
+
+    fn put<'a>(&'a self, key: &'a str, source: &'a Path) -> BoxFuture<'a, Result<(), StoreError>> {
+        Box::pin(async move {
+            validate_key(key)?;
+            let content = fs::read(source).await?;
+            self.objects_mut()
+                .insert(key.to_string(), Bytes::from(content));
+            Ok(())
+        })
+    }
+
+    fn get<'a>(&'a self, key: &'a str) -> BoxFuture<'a, Result<StoredObject, StoreError>> {
+        Box::pin(async move {
+            let content = self
+                .objects()
+                .get(key)
+                .cloned()
+                .ok_or(StoreError::NotFound)?;
+
+            Ok(StoredObject {
+                size: content.len() as u64,
+                body: stream::once(async move { Ok(content) }).boxed(),
+            })
+        })
+    }
+
+    fn get_range<'a>(
+        &'a self,
+        key: &'a str,
+        range: Range<u64>,
+    ) -> BoxFuture<'a, Result<StoredObject, StoreError>> {
+        Box::pin(async move {
+            let content = self
+                .objects()
+                .get(key)
+                .cloned()
+                .ok_or(StoreError::NotFound)?;
+
+            let len = content.len() as u64;
+            let start = range.start.min(len);
+            let end = range.end.min(len).max(start);
+            let part = content.slice(start as usize..end as usize);
+
+            Ok(StoredObject {
+                size: part.len() as u64,
+                body: stream::once(async move { Ok(part) }).boxed(),
+            })
+        })
+    }
+
+    fn delete<'a>(&'a self, key: &'a str) -> BoxFuture<'a, Result<(), StoreError>> {
+        Box::pin(async move {
+            self.objects_mut().remove(key);
+            Ok(())
+        })
+    }
+}
@@ -0,0 +1,145 @@
+//! Storage of uploaded file contents.
+//!
+//! Metadata lives in the `file` table, the bytes are kept in a [`FileStore`] under the ID of the
+//! file record. The store is selected with `FILE_STORE`:
+//!
+//! - `local` (default) - a directory on the local filesystem, `FILE_STORE_PATH`
+//! - `s3` - a bucket of an S3-compatible object storage, see [`S3Config`]
+//! - `memory` - kept in memory, for tests and local experiments only
+
+pub mod local;
+pub mod memory;
+pub mod s3;
+mod test;
+
+use crate::error::ServerResponseError;
+use actix_web::web::Bytes;
+use futures::future::BoxFuture;
+use futures::stream::BoxStream;
+use std::ops::Range;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+pub use local::LocalFileStore;
+pub use memory::MemoryFileStore;
+pub use s3::{S3Config, S3FileStore};
+
+pub type ByteStream = BoxStream<'static, Result<Bytes, StoreError>>;
+
+#[derive(Debug, thiserror::Error)]
+pub enum StoreError {
+    #[error("object not found")]
+    NotFound,
+    #[error("invalid object key {0:?}")]
+    InvalidKey(String),
+    #[error("file store is misconfigured: {0}")]
+    Config(String),
+    #[error("file store I/O error: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("file store request failed: {0}")]
+    Backend(String),
+}
+
+impl From<StoreError> for ServerResponseError {
+    fn from(err: StoreError) -> Self {
+        match err {
+            StoreError::NotFound => ServerResponseError::NotFound,
+            err => ServerResponseError::InternalError(err.to_string()),
+        }
+    }
+}
+
+/// Content of a stored file
+pub struct StoredObject {
+    /// Size in bytes of `body`
+    pub size: u64,
+    pub body: ByteStream,
+}
+
+pub trait FileStore: Send + Sync {
+    /// Name of the backend, used in logs
+    fn name(&self) -> &'static str;
+
+    /// Makes sure the store can be used, called on startup and by readiness checks
+    fn check(&self) -> BoxFuture<'_, Result<(), StoreError>>;
+
+    /// Stores the content of the local file at `source` under `key`, replacing any previous
+    /// content. `source` is left in place.
+    fn put<'a>(&'a self, key: &'a str, source: &'a Path) -> BoxFuture<'a, Result<(), StoreError>>;
+
+    /// Streams the content stored under `key`
+    fn get<'a>(&'a self, key: &'a str) -> BoxFuture<'a, Result<StoredObject, StoreError>>;
+
+    /// Streams the bytes in `range` of the content stored under `key`. Parts of the range past
+    /// the end of the content are left out, `size` of the result is the number of bytes streamed.
+    fn get_range<'a>(
+        &'a self,
+        key: &'a str,
+        range: Range<u64>,
+    ) -> BoxFuture<'a, Result<StoredObject, StoreError>>;
+
+    /// Removes the content stored under `key`, removing a missing key is not an error
+    fn delete<'a>(&'a self, key: &'a str) -> BoxFuture<'a, Result<(), StoreError>>;
+}
+
+/// Keys are file record IDs, anything that could escape a directory or bucket is rejected
+pub(crate) fn validate_key(key: &str) -> Result<(), StoreError> {
+    let valid = !key.is_empty()
+        && !key.starts_with('.')
+        && key
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '_' | '-' | '.'));
+
+    if valid {
+        Ok(())
+    } else {
+        Err(StoreError::InvalidKey(key.to_string()))
+    }
+}
+
+/// Which store to use, read from the [`Config`](crate::config::Config)
+#[derive(Debug, Clone, PartialEq)]
+pub enum FileStoreConfig {
+    Local { path: PathBuf },
+    S3(S3Config),
+    Memory,
+}
+
+impl FileStoreConfig {
+    /// Reads the configuration with `var`, unknown stores and missing settings are errors so
+    /// that a typo never silently stores files somewhere unexpected
+    pub(crate) fn from_vars(var: impl Fn(&str) -> Option<String>) -> Result<Self, StoreError> {
+        let kind = var("FILE_STORE").unwrap_or_else(|| "local".to_string());
+
+        match kind.to_lowercase().as_str() {
+            "local" => {
+                let path = match var("FILE_STORE_PATH") {
+                    Some(path) => PathBuf::from(path),
+                    None => dirs::home_dir()
+                        .map(|home| home.join("threatmapper_files"))
+                        .ok_or_else(|| {
+                            StoreError::Config(
+                                "FILE_STORE_PATH is not set and there is no home directory"
+                                    .to_string(),
+                            )
+                        })?,
+                };
+
+                Ok(Self::Local { path })
+            }
+            "s3" => Ok(Self::S3(S3Config::from_vars(var)?)),
+            "memory" => Ok(Self::Memory),
+            other => Err(StoreError::Config(format!(
+                "unknown FILE_STORE {other:?}, expected local, s3 or memory"
+            ))),
+        }
+    }
+
+    pub fn build(self) -> Result<Arc<dyn FileStore>, StoreError> {
+        Ok(match self {
+            Self::Local { path } => Arc::new(LocalFileStore::new(path)),
+            Self::S3(config) => Arc::new(S3FileStore::new(config)?),
+            Self::Memory => Arc::new(MemoryFileStore::default()),
+        })
+    }
+}
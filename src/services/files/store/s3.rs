@@ -0,0 +1,553 @@
+use super::{validate_key, FileStore, StoreError, StoredObject};
+use crate::services::files::hex::encode_hex;
+use chrono::Utc;
+use futures::future::BoxFuture;
+use futures::{stream, StreamExt};
+use hmac::{Hmac, Mac};
+use reqwest::header::{AUTHORIZATION, CONTENT_LENGTH, ETAG, RANGE};
+use reqwest::{Body, Client, Method, RequestBuilder, Response, StatusCode, Url};
+use sha2::{Digest, Sha256};
+use std::fmt;
+use std::io::SeekFrom;
+use std::ops::Range;
+use std::path::Path;
+use std::time::Duration;
+use tokio::fs;
+use tokio::io::{AsyncReadExt, AsyncSeekExt};
+use tracing::warn;
+
+/// Files larger than this are uploaded in parts
+const MULTIPART_THRESHOLD: u64 = 64 * 1024 * 1024;
+
+/// Size of the parts of a multipart upload, S3 requires at least 5 MiB for all but the last
+const PART_SIZE: u64 = 16 * 1024 * 1024;
+
+/// Size of the reads when streaming a file to the store
+const CHUNK_SIZE: usize = 64 * 1024;
+
+/// Payload hash of requests whose body is streamed instead of being hashed up front
+const UNSIGNED_PAYLOAD: &str = "UNSIGNED-PAYLOAD";
+
+/// Connection settings of an S3-compatible store, read from
+///
+/// - `FILE_STORE_S3_ENDPOINT` - e.g. `https://s3.eu-north-1.amazonaws.com` or `http://localhost:9000`
+/// - `FILE_STORE_S3_BUCKET` - the bucket has to exist
+/// - `FILE_STORE_S3_REGION` - defaults to `us-east-1`
+/// - `FILE_STORE_S3_ACCESS_KEY`, `FILE_STORE_S3_SECRET_KEY`
+///
+/// Objects are addressed path-style (`{endpoint}/{bucket}/{key}`) which every S3-compatible
+/// server supports.
+#[derive(Clone, PartialEq)]
+pub struct S3Config {
+    pub endpoint: String,
+    pub bucket: String,
+    pub region: String,
+    pub access_key: String,
+    pub secret_key: String,
+}
+
+impl fmt::Debug for S3Config {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("S3Config")
+            .field("endpoint", &self.endpoint)
+            .field("bucket", &self.bucket)
+            .field("region", &self.region)
+            .field("access_key", &self.access_key)
+            .field("secret_key", &"<redacted>")
+            .finish()
+    }
+}
+
+impl S3Config {
+    pub(crate) fn from_vars(var: impl Fn(&str) -> Option<String>) -> Result<Self, StoreError> {
+        let required = |name: &str| {
+            var(name)
+                .ok_or_else(|| StoreError::Config(format!("{name} is required for FILE_STORE=s3")))
+        };
+
+        Ok(Self {
+            endpoint: required("FILE_STORE_S3_ENDPOINT")?,
+            bucket: required("FILE_STORE_S3_BUCKET")?,
+            region: var("FILE_STORE_S3_REGION").unwrap_or_else(|| "us-east-1".to_string()),
+            access_key: required("FILE_STORE_S3_ACCESS_KEY")?,
+            secret_key: required("FILE_STORE_S3_SECRET_KEY")?,
+        })
+    }
+}
+
+type HmacSha256 = Hmac<Sha256>;
+
+fn hmac(key: &[u8], message: &str) -> Vec<u8> {
+    let mut mac = HmacSha256::new_from_slice(key).expect("HMAC accepts keys of any length");
+    mac.update(message.as_bytes());
+    mac.finalize().into_bytes().to_vec()
+}
+
+/// `Authorization` header of an AWS Signature Version 4 signed request.
+///
+/// `query` is the canonical query string, see [`with_query`]. `headers` are the signed headers
+/// with lowercase names, sorted by name. They have to include `host`, `x-amz-content-sha256`
+/// and `x-amz-date`.
+pub(crate) fn sign_v4(
+    config: &S3Config,
+    method: &str,
+    path: &str,
+    query: &str,
+    headers: &[(&str, &str)],
+    payload_hash: &str,
+    amz_date: &str,
+) -> String {
+    let date = &amz_date[..8];
+    let scope = format!("{date}/{}/s3/aws4_request", config.region);
+
+    let canonical_headers: String = headers
+        .iter()
+        .map(|(name, value)| format!("{name}:{}\n", value.trim()))
+        .collect();
+    let signed_headers = headers
+        .iter()
+        .map(|(name, _)| *name)
+        .collect::<Vec<_>>()
+        .join(";");
+
+    let canonical_request =
+        format!("{method}\n{path}\n{query}\n{canonical_headers}\n{signed_headers}\n{payload_hash}");
+    let string_to_sign = format!(
+        "AWS4-HMAC-SHA256\n{amz_date}\n{scope}\n{}",
+        encode_hex(&Sha256::digest(canonical_request.as_bytes()))
+    );
+
+    let key = hmac(format!("AWS4{}", config.secret_key).as_bytes(), date);
+    let key = hmac(&key, &config.region);
+    let key = hmac(&key, "s3");
+    let key = hmac(&key, "aws4_request");
+    let signature = encode_hex(&hmac(&key, &string_to_sign));
+
+    format!(
+        "AWS4-HMAC-SHA256 Credential={}/{scope}, SignedHeaders={signed_headers}, Signature={signature}",
+        config.access_key
+    )
+}
+
+/// Percent-encodes everything but the unreserved characters, as SigV4 expects
+fn uri_encode(value: &str) -> String {
+    value
+        .bytes()
+        .map(|byte| match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' => {
+                (byte as char).to_string()
+            }
+            byte => format!("%{byte:02X}"),
+        })
+        .collect()
+}
+
+/// Sets the query of `url` to the canonical form of `params` so that it can be signed as is
+pub(crate) fn with_query(mut url: Url, params: &[(&str, &str)]) -> Url {
+    let mut params: Vec<_> = params
+        .iter()
+        .map(|(name, value)| (uri_encode(name), uri_encode(value)))
+        .collect();
+    params.sort();
+
+    let query = params
+        .iter()
+        .map(|(name, value)| format!("{name}={value}"))
+        .collect::<Vec<_>>()
+        .join("&");
+
+    url.set_query(Some(&query));
+    url
+}
+
+/// Text of the first `<name>` element in an XML response
+fn xml_element<'a>(xml: &'a str, name: &str) -> Option<&'a str> {
+    let start = xml.find(&format!("<{name}>"))? + name.len() + 2;
+    let end = start + xml[start..].find(&format!("</{name}>"))?;
+
+    Some(&xml[start..end])
+}
+
+/// Streams `length` bytes of `source` starting at `offset`
+async fn file_body(source: &Path, offset: u64, length: u64) -> Result<Body, StoreError> {
+    let mut file = fs::File::open(source).await?;
+    file.seek(SeekFrom::Start(offset)).await?;
+
+    let chunks = stream::unfold(Some(file.take(length)), |file| async move {
+        let mut file = file?;
+        let mut buffer = vec![0; CHUNK_SIZE];
+
+        match file.read(&mut buffer).await {
+            Ok(0) => None,
+            Ok(read) => {
+                buffer.truncate(read);
+                Some((Ok(buffer), Some(file)))
+            }
+            Err(err) => Some((Err(err), None)),
+        }
+    });
+
+    Ok(Body::wrap_stream(chunks))
+}
+
+fn failed(action: String, response: &Response) -> StoreError {
+    StoreError::Backend(format!("{action} failed with HTTP {}", response.status()))
+}
+
+/// Stores files as objects in a bucket of an S3-compatible object storage
+pub struct S3FileStore {
+    config: S3Config,
+    bucket_url: Url,
+    client: Client,
+}
+
+impl S3FileStore {
+    pub fn new(config: S3Config) -> Result<Self, StoreError> {
+        let bucket_url = Url::parse(&format!(
+            "{}/{}",
+            config.endpoint.trim_end_matches('/'),
+            config.bucket
+        ))
+        .map_err(|err| StoreError::Config(format!("invalid FILE_STORE_S3_ENDPOINT: {err}")))?;
+
+        let client = Client::builder()
+            .connect_timeout(Duration::from_secs(10))
+            .build()
+            .map_err(|err| StoreError::Config(err.to_string()))?;
+
+        Ok(Self {
+            config,
+            bucket_url,
+            client,
+        })
+    }
+
+    fn object_url(&self, key: &str) -> Result<Url, StoreError> {
+        validate_key(key)?;
+
+        let mut url = self.bucket_url.clone();
+        url.set_path(&format!("{}/{key}", self.bucket_url.path()));
+        Ok(url)
+    }
+
+    /// Builds a signed request, the body is signed as well
+    fn request(
+        &self,
+        method: Method,
+        url: Url,
+        body: Option<Vec<u8>>,
+    ) -> Result<RequestBuilder, StoreError> {
+        let payload_hash = encode_hex(&Sha256::digest(body.as_deref().unwrap_or_default()));
+        let request = self.signed(method, url, &payload_hash)?;
+
+        Ok(match body {
+            Some(body) => request.body(body),
+            None => request,
+        })
+    }
+
+    /// Builds a request signed for a payload with the given hash or [`UNSIGNED_PAYLOAD`]
+    fn signed(
+        &self,
+        method: Method,
+        url: Url,
+        payload_hash: &str,
+    ) -> Result<RequestBuilder, StoreError> {
+        let amz_date = Utc::now().format("%Y%m%dT%H%M%SZ").to_string();
+        let host = match (url.host_str(), url.port()) {
+            (Some(host), Some(port)) => format!("{host}:{port}"),
+            (Some(host), None) => host.to_string(),
+            (None, _) => return Err(StoreError::Config("endpoint has no host".to_string())),
+        };
+
+        let authorization = sign_v4(
+            &self.config,
+            method.as_str(),
+            url.path(),
+            url.query().unwrap_or_default(),
+            &[
+                ("host", host.as_str()),
+                ("x-amz-content-sha256", payload_hash),
+                ("x-amz-date", amz_date.as_str()),
+            ],
+            payload_hash,
+            &amz_date,
+        );
+
+        Ok(self
+            .client
+            .request(method, url)
+            .header("x-amz-content-sha256", payload_hash)
+            .header("x-amz-date", amz_date)
+            .header(AUTHORIZATION, authorization))
+    }
+
+    async fn send(
+        &self,
+        method: Method,
+        url: Url,
+        body: Option<Vec<u8>>,
+    ) -> Result<Response, StoreError> {
+        self.request(method, url, body)?
+            .send()
+            .await
+            .map_err(|err| StoreError::Backend(err.to_string()))
+    }
+
+    /// Streams `length` bytes of `source` from `offset` with a `PUT` to `url`
+    async fn send_file(
+        &self,
+        url: Url,
+        source: &Path,
+        offset: u64,
+        length: u64,
+    ) -> Result<Response, StoreError> {
+        self.signed(Method::PUT, url, UNSIGNED_PAYLOAD)?
+            .header(CONTENT_LENGTH, length)
+            .body(file_body(source, offset, length).await?)
+            .send()
+            .await
+            .map_err(|err| StoreError::Backend(err.to_string()))
+    }
+
+    /// Uploads a large file in parts of [`PART_SIZE`], the upload is aborted if a part fails
+    async fn put_multipart(&self, key: &str, source: &Path, size: u64) -> Result<(), StoreError> {
+        let url = self.object_url(key)?;
+
+        let response = self
+            .send(
+                Method::POST,
+                with_query(url.clone(), &[("uploads", "")]),
+                None,
+            )
+            .await?;
+        if !response.status().is_success() {
+            return Err(failed(format!("starting the upload of {key}"), &response));
+        }
+
+        let created = response
+            .text()
+            .await
+            .map_err(|err| StoreError::Backend(err.to_string()))?;
+        let upload_id = xml_element(&created, "UploadId").ok_or_else(|| {
+            StoreError::Backend(format!("starting the upload of {key} returned no UploadId"))
+        })?;
+
+        let result = self.upload_parts(key, &url, upload_id, source, size).await;
+
+        if result.is_err() {
+            let abort = with_query(url, &[("uploadId", upload_id)]);
+
+            match self.send(Method::DELETE, abort, None).await {
+                Ok(response) if response.status().is_success() => {}
+                Ok(response) => warn!(
+                    "Aborting the upload of {} failed with HTTP {}",
+                    key,
+                    response.status()
+                ),
+                Err(err) => warn!("Aborting the upload of {} failed: {}", key, err),
+            }
+        }
+
+        result
+    }
+
+    async fn upload_parts(
+        &self,
+        key: &str,
+        url: &Url,
+        upload_id: &str,
+        source: &Path,
+        size: u64,
+    ) -> Result<(), StoreError> {
+        let mut parts = String::new();
+        let mut offset = 0;
+        let mut number = 1;
+
+        while offset < size {
+            let length = PART_SIZE.min(size - offset);
+            let part_url = with_query(
+                url.clone(),
+                &[("partNumber", &number.to_string()), ("uploadId", upload_id)],
+            );
+
+            let response = self.send_file(part_url, source, offset, length).await?;
+            if !response.status().is_success() {
+                return Err(failed(format!("storing part {number} of {key}"), &response));
+            }
+
+            let etag = response
+                .headers()
+                .get(ETAG)
+                .and_then(|etag| etag.to_str().ok())
+                .ok_or_else(|| {
+                    StoreError::Backend(format!("storing part {number} of {key} returned no ETag"))
+                })?;
+            parts.push_str(&format!(
+                "<Part><PartNumber>{number}</PartNumber><ETag>{etag}</ETag></Part>"
+            ));
+
+            offset += length;
+            number += 1;
+        }
+
+        let complete = format!("<CompleteMultipartUpload>{parts}</CompleteMultipartUpload>");
+        let response = self
+            .send(
+                Method::POST,
+                with_query(url.clone(), &[("uploadId", upload_id)]),
+                Some(complete.into_bytes()),
+            )
+            .await?;
+        if !response.status().is_success() {
+            return Err(failed(format!("completing the upload of {key}"), &response));
+        }
+
+        // Completing can still fail after the response has started, S3 then sends an error
+        // document with status 200
+        let completed = response
+            .text()
+            .await
+            .map_err(|err| StoreError::Backend(err.to_string()))?;
+        if let Some(error) = xml_element(&completed, "Message") {
+            return Err(StoreError::Backend(format!(
+                "completing the upload of {key} failed: {error}"
+            )));
+        }
+
+        Ok(())
+    }
+
+    /// Streams the object stored under `key`, only the bytes in `range` if one is given
+    async fn fetch(
+        &self,
+        key: &str,
+        range: Option<Range<u64>>,
+    ) -> Result<StoredObject, StoreError> {
+        let url = self.object_url(key)?;
+        let mut request = self.request(Method::GET, url, None)?;
+
+        if let Some(range) = &range {
+            if range.start >= range.end {
+                return Ok(StoredObject {
+                    size: 0,
+                    body: stream::empty().boxed(),
+                });
+            }
+
+            request = request.header(RANGE, format!("bytes={}-{}", range.start, range.end - 1));
+        }
+
+        let response = request
+            .send()
+            .await
+            .map_err(|err| StoreError::Backend(err.to_string()))?;
+
+        match response.status() {
+            StatusCode::NOT_FOUND => return Err(StoreError::NotFound),
+            // The range starts past the end of the object
+            StatusCode::RANGE_NOT_SATISFIABLE if range.is_some() => {
+                return Ok(StoredObject {
+                    size: 0,
+                    body: stream::empty().boxed(),
+                })
+            }
+            status if !status.is_success() => {
+                return Err(StoreError::Backend(format!(
+                    "reading {key} failed with HTTP {status}"
+                )))
+            }
+            _ => {}
+        }
+
+        let size = response.content_length().ok_or_else(|| {
+            StoreError::Backend(format!("reading {key} returned no Content-Length"))
+        })?;
+
+        let body = stream::unfold(Some(response), |response| async move {
+            let mut response = response?;
+
+            match response.chunk().await {
+                Ok(Some(chunk)) => Some((Ok(chunk), Some(response))),
+                Ok(None) => None,
+                Err(err) => Some((Err(StoreError::Backend(err.to_string())), None)),
+            }
+        })
+        .boxed();
+
+        Ok(StoredObject { size, body })
+    }
+}
+
+impl FileStore for S3FileStore {
+    fn name(&self) -> &'static str {
+        "s3"
+    }
+
+    fn check(&self) -> BoxFuture<'_, Result<(), StoreError>> {
+        Box::pin(async move {
+            let unusable = |reason: String| {
+                StoreError::Config(format!(
+                    "bucket {} is not usable: {reason}",
+                    self.bucket_url
+                ))
+            };
+
+            let response = self
+                .send(Method::HEAD, self.bucket_url.clone(), None)
+                .await
+                .map_err(|err| unusable(err.to_string()))?;
+
+            if response.status().is_success() {
+                Ok(())
+            } else {
+                Err(unusable(format!("HTTP {}", response.status())))
+            }
+        })
+    }
+
+    fn put<'a>(&'a self, key: &'a str, source: &'a Path) -> BoxFuture<'a, Result<(), StoreError>> {
+        Box::pin(async move {
+            let size = fs::metadata(source).await?.len();
+
+            if size > MULTIPART_THRESHOLD {
+                return self.put_multipart(key, source, size).await;
+            }
+
+            let url = self.object_url(key)?;
+            let response = self.send_file(url, source, 0, size).await?;
+
+            if response.status().is_success() {
+                Ok(())
+            } else {
+                Err(failed(format!("storing {key}"), &response))
+            }
+        })
+    }
+
+    fn get<'a>(&'a self, key: &'a str) -> BoxFuture<'a, Result<StoredObject, StoreError>> {
+        Box::pin(self.fetch(key, None))
+    }
+
+    fn get_range<'a>(
+        &'a self,
+        key: &'a str,
+        range: Range<u64>,
+    ) -> BoxFuture<'a, Result<StoredObject, StoreError>> {
+        Box::pin(self.fetch(key, Some(range)))
+    }
+
+    fn delete<'a>(&'a self, key: &'a str) -> BoxFuture<'a, Result<(), StoreError>> {
+        Box::pin(async move {
+            let url = self.object_url(key)?;
+            let response = self.send(Method::DELETE, url, None).await?;
+
+            match response.status() {
+                status if status.is_success() || status == StatusCode::NOT_FOUND => Ok(()),
+                status => Err(StoreError::Backend(format!(
+                    "deleting {key} failed with HTTP {status}"
+                ))),
+            }
+        })
+    }
+}
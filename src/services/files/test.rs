@@ -0,0 +1,123 @@
+#![cfg(test)]
+
+use super::hex::{decode_hex, encode_hex};
+use super::inspect::{normalize_declared_type, sniff_mime_type};
+use super::policy::{parse_size, UploadPolicy, UploadPolicyError};
+use super::share::ShareLinkSigner;
+use std::collections::HashMap;
+
+#[test]
+fn share_signatures_verify() {
+    let signer = ShareLinkSigner::new("secret");
+    let signature = signer.sign("abc", 1_700_000_000);
+
+    assert_eq!(signature.len(), 64);
+    assert!(signer.verify("abc", 1_700_000_000, &signature));
+    assert!(signer.verify("abc", 1_700_000_000, &signature.to_uppercase()));
+}
+
+#[test]
+fn tampered_share_links_are_rejected() {
+    let signer = ShareLinkSigner::new("secret");
+    let signature = signer.sign("abc", 1_700_000_000);
+
+    assert!(!signer.verify("abd", 1_700_000_000, &signature));
+    assert!(!signer.verify("abc", 1_800_000_000, &signature));
+    assert!(!ShareLinkSigner::new("other").verify("abc", 1_700_000_000, &signature));
+    assert!(!signer.verify("abc", 1_700_000_000, "not hex"));
+    assert!(!signer.verify("abc", 1_700_000_000, &signature[..62]));
+}
+
+#[test]
+fn hex_round_trips() {
+    let bytes = [0x00, 0x7f, 0xab, 0xff];
+    let hex = encode_hex(&bytes);
+
+    assert_eq!(hex, "007fabff");
+    assert_eq!(decode_hex(&hex), Some(bytes.to_vec()));
+    assert_eq!(decode_hex("abc"), None);
+    assert_eq!(decode_hex("zz"), None);
+}
+
+#[test]
+fn mime_types_are_sniffed_from_content() {
+    assert_eq!(
+        sniff_mime_type(b"\x89PNG\r\n\x1a\n\0\0\0\rIHDR"),
+        "image/png"
+    );
+    assert_eq!(sniff_mime_type(b"%PDF-1.7\n"), "application/pdf");
+    assert_eq!(sniff_mime_type("threat model ✓".as_bytes()), "text/plain");
+    // A multi byte character cut off at the end of the sniffed bytes
+    assert_eq!(sniff_mime_type(&"✓".as_bytes()[..2]), "text/plain");
+    assert_eq!(
+        sniff_mime_type(b"\0\x01\x02\xff"),
+        "application/octet-stream"
+    );
+    assert_eq!(sniff_mime_type(b""), "application/octet-stream");
+}
+
+#[test]
+fn declared_types_are_normalized() {
+    assert_eq!(
+        normalize_declared_type(Some("Text/HTML; charset=utf-8")).as_deref(),
+        Some("text/html")
+    );
+    assert_eq!(normalize_declared_type(Some("not a type")), None);
+    assert_eq!(normalize_declared_type(None), None);
+}
+
+#[test]
+fn sizes_are_parsed_with_units() {
+    assert_eq!(parse_size("1048576"), Some(1048576));
+    assert_eq!(parse_size("512KiB"), Some(512 * 1024));
+    assert_eq!(parse_size("100 MiB"), Some(100 * 1024 * 1024));
+    assert_eq!(parse_size("2gb"), Some(2 * 1024 * 1024 * 1024));
+    assert_eq!(parse_size("MiB"), None);
+    assert_eq!(parse_size("10 parsecs"), None);
+}
+
+#[test]
+fn upload_policy_is_read_from_vars() {
+    let vars = HashMap::from([
+        ("UPLOAD_MAX_FILE_SIZE", "10MiB"),
+        ("UPLOAD_ALLOWED_TYPES", "image/*, Application/PDF"),
+        ("UPLOAD_USER_QUOTA", "0"),
+    ]);
+    let policy = UploadPolicy::from_vars(|name| vars.get(name).map(|v| v.to_string())).unwrap();
+
+    assert_eq!(policy.max_file_size, 10 * 1024 * 1024);
+    assert_eq!(policy.max_files, UploadPolicy::default().max_files);
+    assert_eq!(policy.user_quota, None);
+    assert!(policy.is_type_allowed("image/png"));
+    assert!(policy.is_type_allowed("application/pdf"));
+    assert!(!policy.is_type_allowed("imagex/png"));
+    assert!(!policy.is_type_allowed("text/html"));
+
+    assert!(UploadPolicy::from_vars(|name| {
+        (name == "UPLOAD_MAX_FILES").then(|| "many".to_string())
+    })
+    .is_err());
+}
+
+#[test]
+fn upload_policy_violations_map_to_status_codes() {
+    let policy = UploadPolicy {
+        allowed_types: Some(vec!["text/plain".to_string()]),
+        ..UploadPolicy::default()
+    };
+
+    let too_large = policy
+        .check_size("a.bin", policy.max_file_size + 1)
+        .unwrap_err();
+    assert_eq!(too_large.status_code().as_u16(), 413);
+    assert!(policy.check_size("a.bin", policy.max_file_size).is_ok());
+
+    let wrong_type = policy.check_type("a.html", "text/html").unwrap_err();
+    assert!(matches!(
+        wrong_type,
+        UploadPolicyError::TypeNotAllowed { .. }
+    ));
+    assert_eq!(wrong_type.status_code().as_u16(), 415);
+
+    assert!(policy.check_count(policy.max_files + 1).is_err());
+}
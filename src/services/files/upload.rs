@@ -0,0 +1,354 @@
+//! Resumable uploads. A client creates an upload session with the size and
+//! SHA-256 of the file, sends the content in chunks that each start at the
+//! current offset of the session and finally asks the server to finalize
+//! the upload. Only then is the checksum verified and a `file` record
+//! created. An interrupted chunk keeps the bytes that arrived, the client
+//! reads the offset of the session and continues from there.
+
+use super::insert::{insert_file_metadata_for, store_file_contents, NewFile};
+use super::inspect::{inspect_file, normalize_declared_type};
+use super::policy::UploadPolicy;
+use super::state::FilesServiceState;
+use crate::dto::upload_session::UploadSessionRequest;
+use crate::error::ServerResponseError;
+use crate::metrics::ObserveQuery;
+use crate::models::file_metadata::FileMetadata;
+use crate::models::upload_session::UploadSession;
+use actix_web::web::Bytes;
+use futures::{Stream, StreamExt};
+use std::fmt::Display;
+use std::io::SeekFrom;
+use std::sync::Arc;
+use surrealdb::sql::Thing;
+use surrealdb::Surreal;
+use tokio::fs::{self, OpenOptions};
+use tokio::io::{AsyncSeekExt, AsyncWriteExt};
+use tracing::{error, warn};
+
+fn io_error(err: std::io::Error) -> ServerResponseError {
+    ServerResponseError::InternalError(err.to_string())
+}
+
+fn is_sha256(checksum: &str) -> bool {
+    checksum.len() == 64 && checksum.bytes().all(|b| b.is_ascii_hexdigit())
+}
+
+/// Starts an upload for `user_id`, the file will belong to `team` if one is
+/// given. Team permissions have to be checked by the caller. The declared
+/// size and content type have to pass `policy`.
+pub async fn create_upload<T>(
+    db: &Arc<Surreal<T>>,
+    policy: &UploadPolicy,
+    user_id: Thing,
+    team: Option<Thing>,
+    request: UploadSessionRequest,
+) -> Result<UploadSession, ServerResponseError>
+where
+    T: surrealdb::Connection,
+{
+    if request.filename.trim().is_empty() {
+        return Err(ServerResponseError::invalid_field(
+            "filename",
+            "must not be empty",
+        ));
+    }
+
+    if request.size == 0 {
+        return Err(ServerResponseError::invalid_field(
+            "size",
+            "must be at least 1 byte",
+        ));
+    }
+
+    if !is_sha256(&request.sha256) {
+        return Err(ServerResponseError::invalid_field(
+            "sha256",
+            "must be a hex encoded SHA-256 checksum",
+        ));
+    }
+
+    let content_type = normalize_declared_type(request.content_type.as_deref());
+
+    policy.check_size(&request.filename, request.size)?;
+    if let Some(content_type) = &content_type {
+        policy.check_type(&request.filename, content_type)?;
+    }
+    policy
+        .check_quota(db, user_id.clone(), request.size, true)
+        .await?;
+
+    const SQL: &str = "
+        CREATE ONLY upload_session CONTENT {
+            owner: $USER,
+            team: $TEAM,
+            filename: $FILENAME,
+            size: $SIZE,
+            sha256: $SHA256,
+            content_type: $CONTENT_TYPE,
+        };
+    ";
+    let created: Option<UploadSession> = db
+        .query(SQL)
+        .bind(("USER", user_id))
+        .bind(("TEAM", team))
+        .bind(("FILENAME", request.filename))
+        .bind(("SIZE", request.size))
+        .bind(("SHA256", request.sha256.to_lowercase()))
+        .bind(("CONTENT_TYPE", content_type))
+        .observed()
+        .await?
+        .take(0)?;
+
+    created.ok_or_else(|| {
+        ServerResponseError::InternalError("upload session was not created".to_string())
+    })
+}
+
+/// Returns the upload with ID `upload_id` if it belongs to `user_id` and has
+/// not expired
+pub async fn get_upload<T>(
+    db: &Arc<Surreal<T>>,
+    upload_id: String,
+    user_id: Thing,
+) -> Result<UploadSession, ServerResponseError>
+where
+    T: surrealdb::Connection,
+{
+    const SQL: &str = "
+        SELECT * FROM type::thing('upload_session', $ID)
+        WHERE owner = $USER AND expires_at > time::now();
+    ";
+    let found: Option<UploadSession> = db
+        .query(SQL)
+        .bind(("ID", upload_id))
+        .bind(("USER", user_id))
+        .observed()
+        .await?
+        .take(0)?;
+
+    found.ok_or(ServerResponseError::NotFound)
+}
+
+/// Writes a chunk starting at `offset` and returns the new offset.
+///
+/// The offset has to match the offset of the session, a chunk must not
+/// extend past the declared size. When the chunk stream fails the bytes
+/// received until then are kept and the error is returned.
+pub async fn append_chunk<T, S, E>(
+    db: &Arc<Surreal<T>>,
+    files: &FilesServiceState,
+    upload_id: String,
+    user_id: Thing,
+    offset: u64,
+    mut chunk: S,
+) -> Result<u64, ServerResponseError>
+where
+    T: surrealdb::Connection,
+    S: Stream<Item = Result<Bytes, E>> + Unpin,
+    E: Display,
+{
+    let upload = get_upload(db, upload_id.clone(), user_id.clone()).await?;
+
+    if offset != upload.offset {
+        return Err(ServerResponseError::Conflict(format!(
+            "expected offset {}",
+            upload.offset
+        )));
+    }
+
+    let mut file = OpenOptions::new()
+        .write(true)
+        .create(true)
+        .truncate(false)
+        .open(files.get_partial_path_for(&upload_id))
+        .await
+        .map_err(io_error)?;
+
+    // Drops bytes of an earlier chunk that were written but not committed
+    file.set_len(offset).await.map_err(io_error)?;
+    file.seek(SeekFrom::Start(offset)).await.map_err(io_error)?;
+
+    let mut written = 0u64;
+    let mut failure = None;
+
+    while let Some(bytes) = chunk.next().await {
+        let bytes = match bytes {
+            Ok(bytes) => bytes,
+            Err(err) => {
+                warn!("Chunk of upload {} was interrupted: {}", upload_id, err);
+                failure = Some(ServerResponseError::BadRequest(format!(
+                    "chunk was interrupted: {err}"
+                )));
+                break;
+            }
+        };
+
+        if offset + written + bytes.len() as u64 > upload.size {
+            failure = Some(ServerResponseError::PayloadTooLarge(format!(
+                "chunk exceeds the declared size of {} bytes",
+                upload.size
+            )));
+            break;
+        }
+
+        file.write_all(&bytes).await.map_err(io_error)?;
+        written += bytes.len() as u64;
+    }
+
+    file.flush().await.map_err(io_error)?;
+
+    let new_offset = offset + written;
+
+    // Compare and set so that concurrent chunks for the same offset cannot
+    // both be committed
+    const SQL: &str = "
+        UPDATE type::thing('upload_session', $ID) SET offset = $NEW_OFFSET
+        WHERE owner = $USER AND offset = $OFFSET
+        RETURN VALUE offset;
+    ";
+    let committed: Option<u64> = db
+        .query(SQL)
+        .bind(("ID", upload_id))
+        .bind(("USER", user_id))
+        .bind(("OFFSET", offset))
+        .bind(("NEW_OFFSET", new_offset))
+        .observed()
+        .await?
+        .take(0)?;
+
+    if committed.is_none() {
+        return Err(ServerResponseError::Conflict(
+            "another chunk was written concurrently".to_string(),
+        ));
+    }
+
+    match failure {
+        Some(err) => Err(err),
+        None => Ok(new_offset),
+    }
+}
+
+pub(crate) async fn remove_partial(files: &FilesServiceState, upload_id: &str) {
+    if let Err(err) = fs::remove_file(files.get_partial_path_for(upload_id)).await {
+        if err.kind() != std::io::ErrorKind::NotFound {
+            error!("Failed to remove partial upload {}: {}", upload_id, err);
+        }
+    }
+}
+
+/// Verifies the checksum of a complete upload and turns it into a file.
+/// Uploads with a wrong checksum, a type that is not allowed or that no
+/// longer fit into the quota of the user are discarded.
+pub async fn finalize_upload<T>(
+    db: &Arc<Surreal<T>>,
+    files: &FilesServiceState,
+    upload_id: String,
+    user_id: Thing,
+) -> Result<FileMetadata, ServerResponseError>
+where
+    T: surrealdb::Connection,
+{
+    let upload = get_upload(db, upload_id.clone(), user_id.clone()).await?;
+
+    if upload.offset != upload.size {
+        return Err(ServerResponseError::Conflict(format!(
+            "upload is incomplete, received {} of {} bytes",
+            upload.offset, upload.size
+        )));
+    }
+
+    let partial_path = files.get_partial_path_for(&upload_id);
+    let properties = inspect_file(&partial_path).await.map_err(io_error)?;
+
+    if properties.sha256 != upload.sha256 {
+        abort_upload(db, files, upload_id, user_id).await?;
+        return Err(ServerResponseError::BadRequest(format!(
+            "checksum mismatch, the upload was discarded: expected {}, got {}",
+            upload.sha256, properties.sha256
+        )));
+    }
+
+    let policy = &files.policy;
+    let allowed = match policy.check_type(&upload.filename, &properties.mime_type) {
+        Ok(()) => {
+            policy
+                .check_quota(db, user_id.clone(), properties.size, false)
+                .await
+        }
+        Err(err) => Err(err.into()),
+    };
+
+    if let Err(err) = allowed {
+        abort_upload(db, files, upload_id, user_id).await?;
+        return Err(err);
+    }
+
+    // Only one request may finalize the upload
+    const SQL: &str =
+        "DELETE type::thing('upload_session', $ID) WHERE owner = $USER RETURN BEFORE;";
+    let claimed: Option<UploadSession> = db
+        .query(SQL)
+        .bind(("ID", upload_id.clone()))
+        .bind(("USER", user_id.clone()))
+        .observed()
+        .await?
+        .take(0)?;
+
+    if claimed.is_none() {
+        return Err(ServerResponseError::NotFound);
+    }
+
+    let team = upload
+        .team
+        .map(|team| Thing::from((team.tb.clone(), team.id.clone())));
+    let file = NewFile {
+        filename: upload.filename,
+        declared_type: upload.content_type,
+        properties,
+    };
+    let mut created = insert_file_metadata_for(db, vec![file], user_id, team).await?;
+    let metadata = created.pop().ok_or_else(|| {
+        ServerResponseError::InternalError("file metadata was not created".to_string())
+    })?;
+
+    let stored = store_file_contents(
+        db,
+        files.store.as_ref(),
+        std::slice::from_ref(&metadata),
+        &[partial_path.as_path()],
+    )
+    .await;
+    remove_partial(files, &upload_id).await;
+    stored?;
+
+    Ok(metadata)
+}
+
+/// Discards the upload with ID `upload_id` and the bytes received so far
+pub async fn abort_upload<T>(
+    db: &Arc<Surreal<T>>,
+    files: &FilesServiceState,
+    upload_id: String,
+    user_id: Thing,
+) -> Result<(), ServerResponseError>
+where
+    T: surrealdb::Connection,
+{
+    const SQL: &str =
+        "DELETE type::thing('upload_session', $ID) WHERE owner = $USER RETURN BEFORE;";
+    let deleted: Option<UploadSession> = db
+        .query(SQL)
+        .bind(("ID", upload_id.clone()))
+        .bind(("USER", user_id))
+        .observed()
+        .await?
+        .take(0)?;
+
+    if deleted.is_none() {
+        return Err(ServerResponseError::NotFound);
+    }
+
+    remove_partial(files, &upload_id).await;
+
+    Ok(())
+}
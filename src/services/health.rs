@@ -1,29 +1,276 @@
-use crate::error::ServerResponseError;
-use actix_web::{HttpResponse, Responder};
-use api_forge::{ApiRequest, Request};
-use serde::Serialize;
-use tracing::error;
+//! Liveness and readiness of the server. Readiness checks every dependency, the reports are
+//! cached for [`CACHE_TTL`] and concurrent probes wait for the check that is already running
+//! instead of starting their own.
 
-pub(crate) async fn check_health() -> Result<impl Responder, ServerResponseError> {
-    #[derive(Request, Serialize, Debug)]
-    #[request(endpoint = "/health")]
-    struct DbHealthCheck;
+use crate::auth::oauth::Oauth;
+use crate::dto::health::{
+    DependencyHealth, HealthReport, HealthStatus, HealthSummary, MigrationStatus,
+};
+use crate::dto::llm_backend::{BreakerState, LlmBackendHealth};
+use crate::server::db::INTERNAL_DB;
+use crate::server::migrations_runner::{applied_migrations, migration_scripts};
+use crate::services::files::store::FileStore;
+use crate::services::llm::LlmBackends;
+use crate::state::AppState;
+use chrono::Utc;
+use once_cell::sync::Lazy;
+use std::fmt::Display;
+use std::future::Future;
+use std::time::{Duration, Instant};
+use surrealdb::Surreal;
+use tokio::sync::Mutex;
+use tracing::warn;
 
-    let request = DbHealthCheck;
+/// How long a readiness report is reused, keeps probes from hammering the dependencies
+const CACHE_TTL: Duration = Duration::from_secs(5);
 
-    let mut url = tosic_utils::prelude::env!("SURREALDB_URL");
+/// Time a single dependency has to answer before it is reported as down
+const CHECK_TIMEOUT: Duration = Duration::from_secs(3);
 
-    if !url.starts_with("http://") || !url.starts_with("https://") {
-        url = format!("http://{}", url);
+static LAST_REPORT: Lazy<Mutex<Option<(Instant, HealthReport)>>> = Lazy::new(|| Mutex::new(None));
+
+/// Liveness of the process, no dependency is checked
+pub(crate) fn liveness() -> HealthSummary {
+    HealthSummary {
+        status: HealthStatus::Up,
+    }
+}
+
+/// Checks all dependencies, or returns the last report if it is younger than [`CACHE_TTL`]
+pub(crate) async fn readiness(state: &AppState) -> HealthReport {
+    let mut last = LAST_REPORT.lock().await;
+
+    if let Some((checked, report)) = last.as_ref() {
+        if checked.elapsed() < CACHE_TTL {
+            return report.clone();
+        }
+    }
+
+    let report = check_dependencies(state).await;
+    if report.status == HealthStatus::Down {
+        warn!("Server is not ready: {:?}", report.dependencies);
+    }
+
+    *last = Some((Instant::now(), report.clone()));
+
+    report
+}
+
+async fn check_dependencies(state: &AppState) -> HealthReport {
+    let llm = state.llm.health();
+    let (database, log_database, llm_backends, file_store) = futures::join!(
+        check_database(&state.db),
+        check_surrealdb("log_database", &state.log_db),
+        check_llm(&state.llm, &llm),
+        check_file_store(state.files.store.as_ref()),
+    );
+
+    let dependencies = vec![
+        database,
+        log_database,
+        llm_backends,
+        file_store,
+        check_oauth(&state.oauth),
+    ];
+    let status = dependencies
+        .iter()
+        .map(|dependency| dependency.status)
+        .max_by_key(|status| match status {
+            HealthStatus::Up => 0,
+            HealthStatus::Degraded => 1,
+            HealthStatus::Down => 2,
+        })
+        .unwrap_or(HealthStatus::Up);
+
+    HealthReport {
+        status,
+        dependencies,
+        llm,
+        checked_at: Utc::now(),
     }
+}
 
-    request
-        .send_request(url.as_str(), None, None)
-        .await
-        .map_err(|err| {
-            error!("Database not responding, error: {}", err);
-            ServerResponseError::FailedDependencyWithMessage("Database not responding".into())
-        })?;
+/// Runs `check` with [`CHECK_TIMEOUT`] and returns its result and how long it took in
+/// milliseconds
+async fn timed<T, E: Display>(
+    check: impl Future<Output = Result<T, E>>,
+) -> (Result<T, String>, f64) {
+    let started = Instant::now();
+    let result = match tokio::time::timeout(CHECK_TIMEOUT, check).await {
+        Ok(result) => result.map_err(|err| err.to_string()),
+        Err(_) => Err(format!(
+            "no answer within {} seconds",
+            CHECK_TIMEOUT.as_secs()
+        )),
+    };
+
+    (result, started.elapsed().as_secs_f64() * 1000.0)
+}
+
+fn dependency(name: &str, status: HealthStatus) -> DependencyHealth {
+    DependencyHealth {
+        name: name.to_string(),
+        status,
+        latency_ms: None,
+        version: None,
+        detail: None,
+        migrations: None,
+    }
+}
+
+async fn check_surrealdb<C>(name: &str, db: &Surreal<C>) -> DependencyHealth
+where
+    C: surrealdb::Connection,
+{
+    let (version, latency) = timed(db.version()).await;
+
+    match version {
+        Ok(version) => DependencyHealth {
+            latency_ms: Some(latency),
+            version: Some(format!("surrealdb-{version}")),
+            ..dependency(name, HealthStatus::Up)
+        },
+        Err(err) => DependencyHealth {
+            latency_ms: Some(latency),
+            detail: Some(err),
+            ..dependency(name, HealthStatus::Down)
+        },
+    }
+}
+
+/// The database is down if it does not answer or not every migration script was applied.
+/// Applied migrations are read through the connection the migrations were run with.
+async fn check_database<C>(db: &Surreal<C>) -> DependencyHealth
+where
+    C: surrealdb::Connection,
+{
+    let mut health = check_surrealdb("database", db).await;
+    if health.status == HealthStatus::Down {
+        return health;
+    }
 
-    Ok(HttpResponse::Ok())
+    let (applied, _) = timed(applied_migrations(&INTERNAL_DB)).await;
+    let (scripts, _) = timed(migration_scripts()).await;
+
+    match (applied, scripts) {
+        (Ok(applied), Ok(scripts)) => {
+            let pending: Vec<String> = scripts
+                .into_iter()
+                .filter(|script| !applied.contains(script))
+                .collect();
+
+            if !pending.is_empty() {
+                health.status = HealthStatus::Down;
+                health.detail = Some(format!("{} migrations are pending", pending.len()));
+            }
+
+            health.migrations = Some(MigrationStatus {
+                up_to_date: pending.is_empty(),
+                applied: applied.len(),
+                pending,
+            });
+        }
+        (Err(err), _) | (_, Err(err)) => {
+            health.status = HealthStatus::Down;
+            health.detail = Some(format!("unable to compare migrations: {err}"));
+        }
+    }
+
+    health
+}
+
+/// Every backend is probed, a backend is unavailable if its probe fails or its circuit breaker
+/// is open. LLM backends only degrade the server, chat fails but everything else keeps working.
+async fn check_llm(llm: &LlmBackends, breakers: &[LlmBackendHealth]) -> DependencyHealth {
+    let probes = llm.probes().into_iter().map(|(name, probe)| async move {
+        let (result, latency) = timed(probe).await;
+        (name, result, latency)
+    });
+    let probes = futures::future::join_all(probes).await;
+
+    let mut unavailable = Vec::new();
+    let mut versions = Vec::new();
+    let mut latency: f64 = 0.0;
+
+    for (name, result, probe_latency) in &probes {
+        latency = latency.max(*probe_latency);
+
+        let open = breakers
+            .iter()
+            .any(|backend| &backend.name == name && backend.state == BreakerState::Open);
+
+        match result {
+            Ok(_) if open => unavailable.push(format!("{name} (circuit open)")),
+            Ok(version) => versions.extend(version.as_ref().map(|v| format!("{name}-{v}"))),
+            Err(err) => unavailable.push(format!("{name} ({err})")),
+        }
+    }
+
+    let health = DependencyHealth {
+        latency_ms: Some(latency),
+        version: (!versions.is_empty()).then(|| versions.join(", ")),
+        ..dependency("llm", HealthStatus::Up)
+    };
+
+    if !probes.is_empty() && unavailable.len() == probes.len() {
+        DependencyHealth {
+            status: HealthStatus::Degraded,
+            detail: Some(format!(
+                "every backend is unavailable: {}",
+                unavailable.join(", ")
+            )),
+            ..health
+        }
+    } else if !unavailable.is_empty() {
+        DependencyHealth {
+            detail: Some(format!("unavailable: {}", unavailable.join(", "))),
+            ..health
+        }
+    } else {
+        health
+    }
+}
+
+async fn check_file_store(store: &dyn FileStore) -> DependencyHealth {
+    let (result, latency) = timed(store.check()).await;
+    let health = DependencyHealth {
+        latency_ms: Some(latency),
+        detail: Some(format!("{} store", store.name())),
+        ..dependency("file_store", HealthStatus::Up)
+    };
+
+    match result {
+        Ok(()) => health,
+        Err(err) => DependencyHealth {
+            status: HealthStatus::Down,
+            detail: Some(format!("{} store: {err}", store.name())),
+            ..health
+        },
+    }
+}
+
+/// The providers are loaded on startup, which fails if one is not configured. A provider
+/// loaded without a user info URL cannot complete logins, which only degrades the server.
+fn check_oauth(oauth: &Oauth) -> DependencyHealth {
+    let mut loaded = Vec::new();
+    let mut unusable = Vec::new();
+
+    for provider in [oauth.google.check(), oauth.github.check()] {
+        match provider {
+            Ok(name) => loaded.push(name),
+            Err(problem) => unusable.push(problem),
+        }
+    }
+
+    if unusable.is_empty() {
+        DependencyHealth {
+            detail: Some(format!("{} providers loaded", loaded.join(" and "))),
+            ..dependency("oauth", HealthStatus::Up)
+        }
+    } else {
+        DependencyHealth {
+            detail: Some(unusable.join(", ")),
+            ..dependency("oauth", HealthStatus::Degraded)
+        }
+    }
 }
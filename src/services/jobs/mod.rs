@@ -0,0 +1,302 @@
+//! Background jobs. The scheduler started by [`scheduler`](crate::server::scheduler::scheduler)
+//! runs every job on its schedule, admins can run, pause and resume them. Every run is recorded
+//! in the `job_run` table, paused jobs are kept in the `job` table and stay paused across
+//! restarts. A job never runs twice at the same time and no run starts once the server shuts
+//! down.
+
+pub mod runs;
+mod tasks;
+
+use crate::dto::jobs::JobInfo;
+use crate::error::ServerResponseError;
+use crate::metrics::METRICS;
+use crate::models::job::{JobName, JobRun, JobRunStatus, JobTrigger};
+use crate::state::AppState;
+use actix_web::web;
+use once_cell::sync::{Lazy, OnceCell};
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::time::{Duration, Instant};
+use surrealdb::sql::Thing;
+use tokio::runtime::Handle;
+use tokio::sync::Notify;
+use tracing::{error, info, warn};
+
+pub static JOBS: Lazy<Jobs> = Lazy::new(Jobs::new);
+
+/// When a job runs
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum JobSchedule {
+    /// A cron expression with seconds, e.g. `0 */15 * * * *`
+    Cron(String),
+    Every(Duration),
+    /// Only run by admins
+    Manual,
+}
+
+impl JobSchedule {
+    /// A number of seconds, `manual` or a cron expression
+    fn parse(name: &str, value: &str) -> Result<Self, String> {
+        let value = value.trim();
+
+        if value.eq_ignore_ascii_case("manual") {
+            Ok(Self::Manual)
+        } else if value.bytes().all(|b| b.is_ascii_digit()) {
+            match value.parse::<u64>() {
+                Ok(seconds) if seconds > 0 => Ok(Self::Every(Duration::from_secs(seconds))),
+                _ => Err(format!("{name} has to be greater than 0")),
+            }
+        } else if value.split_whitespace().count() == 6 {
+            Ok(Self::Cron(value.to_string()))
+        } else {
+            Err(format!(
+                "{name} has to be a number of seconds, manual or a cron expression with six \
+                 fields: {value:?}"
+            ))
+        }
+    }
+}
+
+impl fmt::Display for JobSchedule {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            JobSchedule::Cron(expression) => f.write_str(expression),
+            JobSchedule::Every(interval) => write!(f, "every {}s", interval.as_secs()),
+            JobSchedule::Manual => f.write_str("manual"),
+        }
+    }
+}
+
+/// The schedule of every job, `JOB_<NAME>_SCHEDULE`, e.g. `JOB_PURGE_SESSIONS_SCHEDULE`
+#[derive(Debug, Clone)]
+pub struct JobsConfig {
+    schedules: HashMap<JobName, JobSchedule>,
+}
+
+impl JobsConfig {
+    pub(crate) fn from_vars(var: impl Fn(&str) -> Option<String>) -> Result<Self, String> {
+        // The interval of the file reconciler this job replaces is still honoured
+        let file_cleanup = match var("FILE_RECONCILE_INTERVAL") {
+            Some(value) => JobSchedule::parse("FILE_RECONCILE_INTERVAL", &value)?,
+            None => JobSchedule::Every(Duration::from_secs(60 * 60)),
+        };
+
+        let mut schedules = HashMap::new();
+        for job in JobName::ALL {
+            let name = format!("JOB_{}_SCHEDULE", job.as_str().to_uppercase());
+            let schedule = match var(&name) {
+                Some(value) => JobSchedule::parse(&name, &value)?,
+                None => match job {
+                    JobName::PurgeSessions => JobSchedule::Cron("0 */15 * * * *".to_string()),
+                    JobName::LogRetention => JobSchedule::Cron("0 30 * * * *".to_string()),
+                    JobName::FileCleanup => file_cleanup.clone(),
+                    JobName::Reembed => JobSchedule::Cron("0 0 3 * * *".to_string()),
+                },
+            };
+            schedules.insert(job, schedule);
+        }
+
+        Ok(Self { schedules })
+    }
+
+    pub fn schedule(&self, job: JobName) -> &JobSchedule {
+        &self.schedules[&job]
+    }
+}
+
+#[derive(Default)]
+struct JobSlot {
+    paused: AtomicBool,
+    running: AtomicBool,
+}
+
+/// State of every job, see the [module documentation](self)
+pub struct Jobs {
+    slots: HashMap<JobName, JobSlot>,
+    /// Set once the server shuts down
+    closed: AtomicBool,
+    /// Runs in progress
+    active: AtomicUsize,
+    idle: Notify,
+    /// Runtime of the scheduler, runs started by requests must not die with their worker
+    runtime: OnceCell<Handle>,
+}
+
+/// Clears the running flag of a job once its run ends
+struct RunGuard {
+    job: JobName,
+}
+
+impl Drop for RunGuard {
+    fn drop(&mut self) {
+        JOBS.slots[&self.job].running.store(false, Ordering::SeqCst);
+        if JOBS.active.fetch_sub(1, Ordering::SeqCst) == 1 {
+            JOBS.idle.notify_waiters();
+        }
+    }
+}
+
+impl Jobs {
+    fn new() -> Self {
+        Self {
+            slots: JobName::ALL
+                .into_iter()
+                .map(|job| (job, JobSlot::default()))
+                .collect(),
+            closed: AtomicBool::new(false),
+            active: AtomicUsize::new(0),
+            idle: Notify::new(),
+            runtime: OnceCell::new(),
+        }
+    }
+
+    /// Restores paused jobs and marks runs that a previous process left running as interrupted,
+    /// runs are started on the current runtime from now on
+    pub async fn init(&self, state: &AppState) -> Result<(), ServerResponseError> {
+        let _ = self.runtime.set(Handle::current());
+
+        for job in runs::paused_jobs(&state.db).await? {
+            self.slots[&job].paused.store(true, Ordering::SeqCst);
+        }
+
+        let interrupted = runs::interrupt_runs(&state.db).await?;
+        if interrupted > 0 {
+            warn!(
+                "{} job runs were interrupted by the last shutdown",
+                interrupted
+            );
+        }
+
+        Ok(())
+    }
+
+    pub fn is_paused(&self, job: JobName) -> bool {
+        self.slots[&job].paused.load(Ordering::SeqCst)
+    }
+
+    pub fn is_running(&self, job: JobName) -> bool {
+        self.slots[&job].running.load(Ordering::SeqCst)
+    }
+
+    /// The schedule, state and last run of `job`
+    pub async fn info(
+        &self,
+        job: JobName,
+        state: &AppState,
+    ) -> Result<JobInfo, ServerResponseError> {
+        let last_run = runs::list_runs(&state.db, Some(job), 1).await?.pop();
+
+        Ok(JobInfo {
+            name: job,
+            schedule: state.config.jobs.schedule(job).to_string(),
+            paused: self.is_paused(job),
+            running: self.is_running(job),
+            last_run,
+        })
+    }
+
+    /// Pauses or resumes the scheduled runs of `job`, admins can still run it
+    pub async fn set_paused(
+        &self,
+        job: JobName,
+        paused: bool,
+        state: &AppState,
+    ) -> Result<(), ServerResponseError> {
+        runs::set_paused(&state.db, job, paused).await?;
+        self.slots[&job].paused.store(paused, Ordering::SeqCst);
+
+        info!("Job {} {}", job, if paused { "paused" } else { "resumed" });
+
+        Ok(())
+    }
+
+    /// Called by the scheduler, does nothing while the job is paused or still running
+    pub async fn run_scheduled(&self, job: JobName, state: web::Data<AppState>) {
+        if self.is_paused(job) {
+            return;
+        }
+
+        match self.start(job, JobTrigger::Schedule, None, state).await {
+            Ok(_) | Err(ServerResponseError::Conflict(_)) => {}
+            Err(err) => error!("Failed to start job {}: {}", job, err),
+        }
+    }
+
+    /// Records a run of `job` and executes it in the background. Fails with a conflict while
+    /// the job is running or the server shuts down.
+    pub async fn start(
+        &self,
+        job: JobName,
+        trigger: JobTrigger,
+        triggered_by: Option<Thing>,
+        state: web::Data<AppState>,
+    ) -> Result<JobRun, ServerResponseError> {
+        if self.closed.load(Ordering::SeqCst) {
+            return Err(ServerResponseError::Conflict(
+                "the server is shutting down".to_string(),
+            ));
+        }
+        if self.slots[&job].running.swap(true, Ordering::SeqCst) {
+            return Err(ServerResponseError::Conflict(format!(
+                "{job} is already running"
+            )));
+        }
+        self.active.fetch_add(1, Ordering::SeqCst);
+        let guard = RunGuard { job };
+
+        let run = runs::create_run(&state.db, job, trigger, triggered_by).await?;
+        let id = run.id.clone();
+
+        let runtime = self.runtime.get().cloned().unwrap_or_else(Handle::current);
+        runtime.spawn(async move {
+            let _guard = guard;
+            let started = Instant::now();
+
+            let (status, detail) = match tasks::execute(job, &state).await {
+                Ok(tasks::Outcome::Done(detail)) => (JobRunStatus::Succeeded, detail),
+                Ok(tasks::Outcome::Skipped(detail)) => (JobRunStatus::Skipped, detail),
+                Err(err) => {
+                    error!("Job {} failed: {}", job, err);
+                    (JobRunStatus::Failed, err)
+                }
+            };
+            let elapsed = started.elapsed();
+            METRICS.observe_job(job.as_str(), status.as_str(), elapsed);
+
+            if let Err(err) = runs::finish_run(&state.db, &id, status, detail, elapsed).await {
+                error!("Failed to record the run of job {}: {}", job, err);
+            }
+        });
+
+        Ok(run)
+    }
+
+    /// Lets no new run start
+    pub fn close(&self) {
+        self.closed.store(true, Ordering::SeqCst);
+    }
+
+    /// Lets no new run start and waits up to `timeout` for the running ones
+    pub async fn shutdown(&self, timeout: Duration) {
+        self.close();
+
+        let wait = async {
+            loop {
+                let idle = self.idle.notified();
+                if self.active.load(Ordering::SeqCst) == 0 {
+                    break;
+                }
+                idle.await;
+            }
+        };
+
+        if tokio::time::timeout(timeout, wait).await.is_err() {
+            warn!(
+                "{} job runs did not finish within {:?} and are interrupted",
+                self.active.load(Ordering::SeqCst),
+                timeout
+            );
+        }
+    }
+}
@@ -0,0 +1,178 @@
+//! Queries of the `job` and `job_run` tables
+
+use crate::error::ServerResponseError;
+use crate::metrics::ObserveQuery;
+use crate::models::job::{JobName, JobRun, JobRunStatus, JobTrigger};
+use crate::models::thing::Thing;
+use serde::Deserialize;
+use std::time::Duration;
+use surrealdb::Surreal;
+
+/// Runs returned by [`list_runs`] at most
+pub const MAX_RUNS: u64 = 500;
+
+pub(super) async fn paused_jobs<C>(db: &Surreal<C>) -> Result<Vec<JobName>, ServerResponseError>
+where
+    C: surrealdb::Connection,
+{
+    #[derive(Deserialize)]
+    struct Job {
+        name: String,
+    }
+
+    const SQL: &str = "SELECT record::id(id) AS name FROM job WHERE paused = true;";
+    let jobs: Vec<Job> = db.query(SQL).observed().await?.take(0)?;
+
+    // Jobs that no longer exist are ignored
+    Ok(JobName::ALL
+        .into_iter()
+        .filter(|job| jobs.iter().any(|paused| paused.name == job.as_str()))
+        .collect())
+}
+
+pub(super) async fn set_paused<C>(
+    db: &Surreal<C>,
+    job: JobName,
+    paused: bool,
+) -> Result<(), ServerResponseError>
+where
+    C: surrealdb::Connection,
+{
+    const SQL: &str = "UPSERT type::thing('job', $JOB) SET paused = $PAUSED RETURN NONE;";
+    db.query(SQL)
+        .bind(("JOB", job.as_str()))
+        .bind(("PAUSED", paused))
+        .observed()
+        .await?
+        .check()?;
+
+    Ok(())
+}
+
+/// Marks runs that are still `running` as interrupted, only called before the scheduler starts
+pub(super) async fn interrupt_runs<C>(db: &Surreal<C>) -> Result<usize, ServerResponseError>
+where
+    C: surrealdb::Connection,
+{
+    const SQL: &str = "
+        RETURN array::len((
+            UPDATE job_run
+            SET status = 'interrupted', finished_at = time::now(),
+                detail = 'the server stopped during the run'
+            WHERE status = 'running'
+        ));
+    ";
+    let interrupted: Option<usize> = db.query(SQL).observed().await?.take(0)?;
+
+    Ok(interrupted.unwrap_or_default())
+}
+
+pub(super) async fn create_run<C>(
+    db: &Surreal<C>,
+    job: JobName,
+    trigger: JobTrigger,
+    triggered_by: Option<surrealdb::sql::Thing>,
+) -> Result<JobRun, ServerResponseError>
+where
+    C: surrealdb::Connection,
+{
+    const SQL: &str = "
+        CREATE ONLY job_run CONTENT {
+            job: $JOB,
+            trigger: $TRIGGER,
+            status: 'running',
+            triggered_by: $USER
+        };
+    ";
+    let run: Option<JobRun> = db
+        .query(SQL)
+        .bind(("JOB", job))
+        .bind(("TRIGGER", trigger))
+        .bind(("USER", triggered_by))
+        .observed()
+        .await?
+        .take(0)?;
+
+    run.ok_or_else(|| ServerResponseError::InternalError("job run was not created".to_string()))
+}
+
+pub(super) async fn finish_run<C>(
+    db: &Surreal<C>,
+    run: &Thing,
+    status: JobRunStatus,
+    detail: String,
+    elapsed: Duration,
+) -> Result<(), ServerResponseError>
+where
+    C: surrealdb::Connection,
+{
+    const SQL: &str = "
+        UPDATE $RUN SET
+            status = $STATUS,
+            detail = $DETAIL,
+            finished_at = time::now(),
+            duration_ms = $DURATION
+        RETURN NONE;
+    ";
+    db.query(SQL)
+        .bind(("RUN", run.clone()))
+        .bind(("STATUS", status))
+        .bind(("DETAIL", detail))
+        .bind(("DURATION", elapsed.as_millis() as u64))
+        .observed()
+        .await?
+        .check()?;
+
+    Ok(())
+}
+
+/// The newest runs first, of `job` only if given
+pub async fn list_runs<C>(
+    db: &Surreal<C>,
+    job: Option<JobName>,
+    limit: u64,
+) -> Result<Vec<JobRun>, ServerResponseError>
+where
+    C: surrealdb::Connection,
+{
+    const SQL: &str = "
+        SELECT * FROM job_run
+        WHERE $JOB = NONE OR job = $JOB
+        ORDER BY started_at DESC
+        LIMIT $LIMIT;
+    ";
+    let runs: Vec<JobRun> = db
+        .query(SQL)
+        .bind(("JOB", job))
+        .bind(("LIMIT", limit.min(MAX_RUNS)))
+        .observed()
+        .await?
+        .take(0)?;
+
+    Ok(runs)
+}
+
+/// Deletes finished runs older than `retention`, returns how many there were
+pub(super) async fn purge_runs<C>(
+    db: &Surreal<C>,
+    retention: Duration,
+) -> Result<usize, ServerResponseError>
+where
+    C: surrealdb::Connection,
+{
+    const SQL: &str = "
+        RETURN array::len((
+            DELETE job_run
+            WHERE status != 'running' AND started_at < time::now() - $RETENTION
+            RETURN BEFORE
+        ));
+    ";
+    let deleted: Option<usize> = db
+        .query(SQL)
+        .bind(("RETENTION", surrealdb::sql::Duration::from(retention)))
+        .observed()
+        .await?
+        .take(0)?;
+
+    Ok(deleted.unwrap_or_default())
+}
@@ -0,0 +1,65 @@
+use crate::metrics::METRICS;
+use crate::models::job::JobName;
+use crate::models::session::UserSession;
+use crate::services::audit::retention::purge_log;
+use crate::services::embeddings::reembed::reembed;
+use crate::services::files::reconcile::reconcile;
+use crate::state::AppState;
+
+use super::runs;
+
+/// How a run that did not fail ended, with a summary stored in the run's `detail`
+pub(super) enum Outcome {
+    Done(String),
+    Skipped(String),
+}
+
+pub(super) async fn execute(job: JobName, state: &AppState) -> Result<Outcome, String> {
+    match job {
+        JobName::PurgeSessions => {
+            let deleted = UserSession::delete_expired()
+                .await
+                .map_err(|err| err.to_string())?;
+            let active = UserSession::count_active()
+                .await
+                .map_err(|err| err.to_string())?;
+            METRICS.active_sessions.set(active as i64);
+
+            Ok(Outcome::Done(format!(
+                "{deleted} expired sessions deleted, {active} active"
+            )))
+        }
+        JobName::LogRetention => {
+            let Some(retention) = state.config.logger.retention else {
+                return Ok(Outcome::Skipped("LOG_RETENTION_DAYS is 0".to_string()));
+            };
+            let entries = purge_log(&state.log_db, retention)
+                .await
+                .map_err(|err| err.to_string())?;
+            let runs = runs::purge_runs(&state.db, retention)
+                .await
+                .map_err(|err| err.to_string())?;
+
+            Ok(Outcome::Done(format!(
+                "{entries} log entries and {runs} job runs deleted"
+            )))
+        }
+        JobName::FileCleanup => {
+            let report = reconcile(&state.db, &state.files)
+                .await
+                .map_err(|err| err.to_string())?;
+
+            Ok(Outcome::Done(format!("{report:?}")))
+        }
+        JobName::Reembed => {
+            let Some(config) = &state.config.embedding else {
+                return Ok(Outcome::Skipped("EMBEDDING_URL is not set".to_string()));
+            };
+            let embedded = reembed(&state.db, config)
+                .await
+                .map_err(|err| err.to_string())?;
+
+            Ok(Outcome::Done(format!("{embedded} entries embedded")))
+        }
+    }
+}
@@ -0,0 +1,145 @@
+use super::LlmError;
+use crate::dto::llm_backend::BreakerState;
+use std::sync::{Mutex, MutexGuard, PoisonError};
+use std::time::{Duration, Instant};
+
+/// Stops sending requests to a backend after repeated failures so that a dead LLM service does
+/// not tie up a worker for every chat request.
+///
+/// After `threshold` consecutive failures the breaker opens and rejects requests for `cooldown`.
+/// Then a single trial request is let through, its outcome closes or re-opens the breaker.
+#[derive(Debug)]
+pub(crate) struct CircuitBreaker {
+    threshold: u32,
+    cooldown: Duration,
+    state: Mutex<State>,
+}
+
+#[derive(Debug, Default)]
+struct State {
+    failures: u32,
+    opened_at: Option<Instant>,
+    trial_in_flight: bool,
+    last_error: Option<String>,
+}
+
+/// Permission to send one request through a [`CircuitBreaker`].
+///
+/// If the request is the half-open trial, dropping the permit without a verdict, e.g. when the
+/// request is cancelled, releases the trial so that the next request can try again.
+#[derive(Debug)]
+pub(crate) struct BreakerPermit<'a> {
+    breaker: &'a CircuitBreaker,
+    trial: bool,
+}
+
+/// Snapshot of a [`CircuitBreaker`] for health reporting
+#[derive(Debug, Clone)]
+pub(crate) struct BreakerStatus {
+    pub(crate) state: BreakerState,
+    pub(crate) consecutive_failures: u32,
+    pub(crate) last_error: Option<String>,
+    pub(crate) retry_after: Option<Duration>,
+}
+
+impl CircuitBreaker {
+    pub(crate) fn new(threshold: u32, cooldown: Duration) -> Self {
+        Self {
+            threshold: threshold.max(1),
+            cooldown,
+            state: Mutex::new(State::default()),
+        }
+    }
+
+    fn lock(&self) -> MutexGuard<'_, State> {
+        self.state.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// Asks for permission to send a request, returns how long to wait if the breaker is open
+    pub(crate) fn acquire(&self) -> Result<BreakerPermit<'_>, Duration> {
+        let mut state = self.lock();
+
+        let Some(opened_at) = state.opened_at else {
+            return Ok(BreakerPermit {
+                breaker: self,
+                trial: false,
+            });
+        };
+
+        let elapsed = opened_at.elapsed();
+        if elapsed < self.cooldown {
+            Err(self.cooldown - elapsed)
+        } else if state.trial_in_flight {
+            Err(Duration::from_secs(1))
+        } else {
+            state.trial_in_flight = true;
+            Ok(BreakerPermit {
+                breaker: self,
+                trial: true,
+            })
+        }
+    }
+
+    pub(crate) fn record_success(&self) {
+        *self.lock() = State::default();
+    }
+
+    pub(crate) fn record_failure(&self, err: &LlmError) {
+        let mut state = self.lock();
+
+        state.failures = state.failures.saturating_add(1);
+        state.trial_in_flight = false;
+        state.last_error = Some(err.to_string());
+
+        if state.failures >= self.threshold {
+            state.opened_at = Some(Instant::now());
+        }
+    }
+
+    fn release_trial(&self) {
+        self.lock().trial_in_flight = false;
+    }
+
+    pub(crate) fn status(&self) -> BreakerStatus {
+        let state = self.lock();
+
+        let (breaker_state, retry_after) = match state.opened_at {
+            None => (BreakerState::Closed, None),
+            Some(opened_at) => match self.cooldown.checked_sub(opened_at.elapsed()) {
+                Some(remaining) if !remaining.is_zero() => (BreakerState::Open, Some(remaining)),
+                _ => (BreakerState::HalfOpen, None),
+            },
+        };
+
+        BreakerStatus {
+            state: breaker_state,
+            consecutive_failures: state.failures,
+            last_error: state.last_error.clone(),
+            retry_after,
+        }
+    }
+}
+
+impl BreakerPermit<'_> {
+    pub(crate) fn success(mut self) {
+        self.trial = false;
+        self.breaker.record_success();
+    }
+
+    /// Records a failed request. Errors that are not the backend's fault, like a rejected
+    /// request, give no verdict and only release the trial.
+    pub(crate) fn failure(mut self, err: &LlmError) {
+        if err.is_backend_failure() {
+            self.trial = false;
+            self.breaker.record_failure(err);
+        }
+    }
+}
+
+impl Drop for BreakerPermit<'_> {
+    fn drop(&mut self) {
+        if self.trial {
+            self.breaker.release_trial();
+        }
+    }
+}
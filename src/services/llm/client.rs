@@ -0,0 +1,94 @@
+use super::LlmError;
+use awc::{Client, Connector};
+use std::cell::OnceCell;
+use std::time::Duration;
+
+thread_local! {
+    // awc clients are not `Send`, so every worker thread gets its own pooled client
+    static CLIENT: OnceCell<Client> = const { OnceCell::new() };
+}
+
+/// Timeouts and retry settings for requests to LLM backends
+#[derive(Debug, Clone)]
+pub struct LlmClientConfig {
+    /// Max time to establish a connection, including DNS resolution
+    pub connect_timeout: Duration,
+    /// Max time between two chunks of a streamed response
+    pub read_timeout: Duration,
+    /// Max time for a whole completion
+    pub request_timeout: Duration,
+    /// How many times a failed request is retried before any output was streamed
+    pub max_retries: u32,
+    /// Delay before the first retry, doubled for every further attempt
+    pub retry_backoff: Duration,
+    /// Consecutive failures before the circuit breaker opens
+    pub breaker_threshold: u32,
+    /// How long the circuit breaker stays open before a trial request is let through
+    pub breaker_cooldown: Duration,
+}
+
+impl Default for LlmClientConfig {
+    fn default() -> Self {
+        Self {
+            connect_timeout: Duration::from_secs(5),
+            read_timeout: Duration::from_secs(60),
+            request_timeout: Duration::from_mins(20),
+            max_retries: 2,
+            retry_backoff: Duration::from_millis(250),
+            breaker_threshold: 5,
+            breaker_cooldown: Duration::from_secs(30),
+        }
+    }
+}
+
+impl LlmClientConfig {
+    /// Reads `LLM_CONNECT_TIMEOUT`, `LLM_READ_TIMEOUT`, `LLM_REQUEST_TIMEOUT` and
+    /// `LLM_BREAKER_COOLDOWN` in seconds, `LLM_MAX_RETRIES` and `LLM_BREAKER_THRESHOLD`
+    pub(crate) fn from_vars(var: impl Fn(&str) -> Option<String>) -> Result<Self, LlmError> {
+        let default = Self::default();
+        let secs = |name: &str, default: Duration| {
+            parse_var(&var, name, default.as_secs()).map(Duration::from_secs)
+        };
+
+        Ok(Self {
+            connect_timeout: secs("LLM_CONNECT_TIMEOUT", default.connect_timeout)?,
+            read_timeout: secs("LLM_READ_TIMEOUT", default.read_timeout)?,
+            request_timeout: secs("LLM_REQUEST_TIMEOUT", default.request_timeout)?,
+            max_retries: parse_var(&var, "LLM_MAX_RETRIES", default.max_retries)?,
+            retry_backoff: default.retry_backoff,
+            breaker_threshold: parse_var(&var, "LLM_BREAKER_THRESHOLD", default.breaker_threshold)?,
+            breaker_cooldown: secs("LLM_BREAKER_COOLDOWN", default.breaker_cooldown)?,
+        })
+    }
+}
+
+/// Returns this worker's shared client, creating it on first use
+pub(crate) fn shared_client(config: &LlmClientConfig) -> Client {
+    CLIENT.with(|client| {
+        client
+            .get_or_init(|| {
+                Client::builder()
+                    .connector(Connector::new().timeout(config.connect_timeout))
+                    .timeout(config.request_timeout)
+                    .disable_redirects()
+                    .finish()
+            })
+            .clone()
+    })
+}
+
+fn parse_var<T: std::str::FromStr>(
+    var: impl Fn(&str) -> Option<String>,
+    name: &str,
+    default: T,
+) -> Result<T, LlmError>
+where
+    T::Err: std::fmt::Display,
+{
+    match var(name) {
+        Some(value) => value
+            .parse()
+            .map_err(|err| LlmError::Config(format!("{name}: {err}"))),
+        None => Ok(default),
+    }
+}
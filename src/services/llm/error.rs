@@ -0,0 +1,124 @@
+use crate::dto::problem::ProblemDetails;
+use actix_web::http::StatusCode;
+use awc::error::SendRequestError;
+use serde_json::json;
+use std::time::Duration;
+
+#[derive(Debug, thiserror::Error)]
+pub enum LlmError {
+    #[error("Unknown LLM backend: {0}")]
+    UnknownBackend(String),
+    #[error("Model {model} is not allowed on backend {backend}")]
+    ModelNotAllowed { backend: String, model: String },
+    #[error("Unable to connect to LLM backend: {0}")]
+    Connect(String),
+    #[error("LLM backend timed out")]
+    Timeout,
+    #[error("LLM backend {backend} is unavailable")]
+    CircuitOpen {
+        backend: String,
+        retry_after: Duration,
+    },
+    #[error("{period} LLM token quota of {limit} tokens exceeded")]
+    QuotaExceeded {
+        period: &'static str,
+        limit: u64,
+        retry_after: Duration,
+    },
+    #[error("LLM backend request failed: {0}")]
+    Upstream(String),
+    #[error("LLM backend responded with status {status}: {body}")]
+    UpstreamStatus { status: u16, body: String },
+    #[error("Invalid response from LLM backend: {0}")]
+    Decode(String),
+    #[error("LLM output does not match the requested schema: {0}")]
+    InvalidOutput(String),
+    #[error("Invalid LLM configuration: {0}")]
+    Config(String),
+}
+
+impl From<SendRequestError> for LlmError {
+    fn from(err: SendRequestError) -> Self {
+        match err {
+            SendRequestError::Connect(err) => LlmError::Connect(err.to_string()),
+            SendRequestError::Timeout => LlmError::Timeout,
+            err => LlmError::Upstream(err.to_string()),
+        }
+    }
+}
+
+impl LlmError {
+    pub fn status_code(&self) -> StatusCode {
+        match self {
+            LlmError::UnknownBackend(_) | LlmError::ModelNotAllowed { .. } => {
+                StatusCode::BAD_REQUEST
+            }
+            LlmError::CircuitOpen { .. } => StatusCode::SERVICE_UNAVAILABLE,
+            LlmError::QuotaExceeded { .. } => StatusCode::TOO_MANY_REQUESTS,
+            LlmError::Timeout => StatusCode::GATEWAY_TIMEOUT,
+            LlmError::Connect(_)
+            | LlmError::Upstream(_)
+            | LlmError::UpstreamStatus { .. }
+            | LlmError::Decode(_)
+            | LlmError::InvalidOutput(_) => StatusCode::BAD_GATEWAY,
+            LlmError::Config(_) => StatusCode::INTERNAL_SERVER_ERROR,
+        }
+    }
+
+    /// Machine readable name of the error
+    pub fn kind(&self) -> &'static str {
+        match self {
+            LlmError::UnknownBackend(_) => "unknown_backend",
+            LlmError::ModelNotAllowed { .. } => "model_not_allowed",
+            LlmError::Connect(_) => "llm_unreachable",
+            LlmError::Timeout => "llm_timeout",
+            LlmError::CircuitOpen { .. } => "llm_unavailable",
+            LlmError::QuotaExceeded { .. } => "quota_exceeded",
+            LlmError::Upstream(_) | LlmError::UpstreamStatus { .. } => "llm_failed",
+            LlmError::Decode(_) => "llm_invalid_response",
+            LlmError::InvalidOutput(_) => "llm_invalid_output",
+            LlmError::Config(_) => "llm_config",
+        }
+    }
+
+    /// Whether the backend itself misbehaved, as opposed to the request being invalid
+    pub fn is_backend_failure(&self) -> bool {
+        match self {
+            LlmError::Connect(_) | LlmError::Timeout | LlmError::Upstream(_) => true,
+            LlmError::UpstreamStatus { status, .. } => *status >= 500,
+            _ => false,
+        }
+    }
+
+    /// Whether sending the same request again may succeed. Only failures that happen before any
+    /// output was produced are retried so that a completion is never duplicated. A request that
+    /// timed out already waited the full `request_timeout` and is not sent again, connect
+    /// timeouts are reported as [`LlmError::Connect`] and retried.
+    pub fn is_retryable(&self) -> bool {
+        match self {
+            LlmError::Connect(_) => true,
+            LlmError::UpstreamStatus { status, .. } => matches!(status, 502..=504),
+            _ => false,
+        }
+    }
+
+    /// Adds the backend and when to retry to the problem details of the error
+    pub fn problem_details(&self, mut problem: ProblemDetails) -> ProblemDetails {
+        if let LlmError::CircuitOpen { retry_after, .. }
+        | LlmError::QuotaExceeded { retry_after, .. } = self
+        {
+            // round up so clients never retry before requests are let through again
+            let seconds = retry_after.as_secs() + u64::from(retry_after.subsec_nanos() > 0);
+            problem = problem.with_retry_after(seconds);
+        }
+
+        match self {
+            LlmError::CircuitOpen { backend, .. }
+            | LlmError::ModelNotAllowed { backend, .. }
+            | LlmError::UnknownBackend(backend) => {
+                problem.with_details(json!({ "backend": backend }))
+            }
+            _ => problem,
+        }
+    }
+}
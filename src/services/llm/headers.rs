@@ -0,0 +1,39 @@
+use actix_web::http::header::{self, HeaderMap, HeaderName, HeaderValue};
+
+/// Headers that only apply to a single connection and must never be forwarded (RFC 9110 7.6.1)
+const HOP_BY_HOP: [&str; 8] = [
+    "connection",
+    "keep-alive",
+    "proxy-authenticate",
+    "proxy-authorization",
+    "te",
+    "trailer",
+    "transfer-encoding",
+    "upgrade",
+];
+
+/// Headers describing the upstream body, which is re-encoded before it reaches the client
+const BODY_FRAMING: [&str; 3] = ["content-type", "content-length", "content-encoding"];
+
+/// Returns the upstream response headers that are safe to pass on to the client
+pub(crate) fn forwardable_headers(headers: &HeaderMap) -> Vec<(HeaderName, HeaderValue)> {
+    // headers listed in `Connection` are hop-by-hop as well
+    let connection_tokens: Vec<String> = headers
+        .get_all(header::CONNECTION)
+        .filter_map(|value| value.to_str().ok())
+        .flat_map(|value| value.split(','))
+        .map(|token| token.trim().to_ascii_lowercase())
+        .collect();
+
+    headers
+        .iter()
+        .filter(|(name, _)| {
+            let name = name.as_str();
+
+            !HOP_BY_HOP.contains(&name)
+                && !BODY_FRAMING.contains(&name)
+                && !connection_tokens.iter().any(|token| token == name)
+        })
+        .map(|(name, value)| (name.clone(), value.clone()))
+        .collect()
+}
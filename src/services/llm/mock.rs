@@ -0,0 +1,68 @@
+use super::{LlmBackend, LlmError, LlmResponse, Upstream};
+use crate::dto::chat_chunk::{ChatChunk, TokenUsage};
+use crate::dto::chat_request::ChatRequest;
+use futures::future::LocalBoxFuture;
+use futures::{future, stream, StreamExt};
+use once_cell::sync::Lazy;
+
+pub const NAME: &str = "mock";
+
+static MODELS: Lazy<Vec<String>> = Lazy::new(|| vec!["mock".to_string()]);
+
+/// Answers every request with the same text without contacting anything, for tests and local
+/// development.
+pub struct MockBackend;
+
+impl MockBackend {
+    /// The full text the mock replies with for `request`. Structured requests get a valid but
+    /// empty threat model.
+    pub fn reply(request: &ChatRequest) -> String {
+        match request {
+            ChatRequest::Structured { .. } => r#"{"threats": [], "mitigations": []}"#.to_string(),
+            ChatRequest::Chat { prompt } => format!("Mock response to: {prompt}"),
+        }
+    }
+}
+
+impl LlmBackend for MockBackend {
+    fn name(&self) -> &str {
+        NAME
+    }
+
+    fn models(&self) -> &[String] {
+        &MODELS
+    }
+
+    fn chat(
+        &self,
+        _upstream: &Upstream,
+        request: ChatRequest,
+        model: Option<String>,
+    ) -> LocalBoxFuture<'static, Result<LlmResponse, LlmError>> {
+        let reply = Self::reply(&request);
+        let words: Vec<&str> = reply.split_inclusive(' ').collect();
+
+        let usage = TokenUsage {
+            prompt_tokens: request.prompt().split_whitespace().count() as u64,
+            completion_tokens: words.len() as u64,
+        };
+
+        let mut chunks: Vec<Result<ChatChunk, LlmError>> = words
+            .iter()
+            .map(|word| Ok(ChatChunk::delta(NAME, model.as_deref(), *word)))
+            .collect();
+        chunks.push(Ok(ChatChunk::finish(NAME, model.as_deref(), Some(usage))));
+
+        Box::pin(future::ready(Ok(LlmResponse {
+            headers: Vec::new(),
+            chunks: stream::iter(chunks).boxed_local(),
+        })))
+    }
+
+    fn probe(
+        &self,
+        _upstream: &Upstream,
+    ) -> LocalBoxFuture<'static, Result<Option<String>, LlmError>> {
+        Box::pin(future::ready(Ok(None)))
+    }
+}
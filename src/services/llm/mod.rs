@@ -0,0 +1,430 @@
+//! Pluggable LLM backends used by the chat endpoint.
+//!
+//! Every backend implements [`LlmBackend`] and translates its upstream response into a stream of
+//! [`ChatChunk`]s so that the client sees the same format regardless of which backend answered.
+
+pub(crate) mod breaker;
+pub mod client;
+pub mod error;
+pub(crate) mod headers;
+pub mod mock;
+pub mod openai;
+pub mod preference;
+pub(crate) mod sse;
+pub mod structured;
+mod test;
+pub mod threatmapper;
+pub mod usage;
+
+use crate::config::Secret;
+use crate::dto::chat_chunk::ChatChunk;
+use crate::dto::chat_request::{ChatRequest, LlmSelection};
+use crate::dto::llm_backend::{LlmBackendHealth, LlmBackendInfo};
+use crate::metrics::METRICS;
+use crate::middlewares::request_id::{RequestId, REQUEST_ID_HEADER};
+use crate::middlewares::trace_context::trace_headers;
+use actix_web::http::header::{HeaderName, HeaderValue};
+use actix_web::web::Bytes;
+use awc::error::PayloadError;
+use awc::{Client, ClientRequest, ClientResponse};
+use breaker::CircuitBreaker;
+use client::{shared_client, LlmClientConfig};
+use futures::future::LocalBoxFuture;
+use futures::stream::LocalBoxStream;
+use futures::{stream, Stream, StreamExt};
+use std::collections::BTreeMap;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tracing::warn;
+use usage::LlmQuota;
+
+pub use error::LlmError;
+use mock::MockBackend;
+use openai::OpenAiBackend;
+use threatmapper::ThreatMapperBackend;
+
+pub(crate) type ChatStream = LocalBoxStream<'static, Result<ChatChunk, LlmError>>;
+pub(crate) type ByteStream = LocalBoxStream<'static, Result<Bytes, LlmError>>;
+
+/// A started completion
+pub(crate) struct LlmResponse {
+    /// Upstream headers that may be passed on to the client
+    pub(crate) headers: Vec<(HeaderName, HeaderValue)>,
+    pub(crate) chunks: ChatStream,
+}
+
+/// Connection to the LLM backends for the current worker
+pub(crate) struct Upstream {
+    pub(crate) client: Client,
+    pub(crate) read_timeout: Duration,
+}
+
+pub(crate) trait LlmBackend: Send + Sync {
+    /// Name used to select the backend in requests and user preferences
+    fn name(&self) -> &str;
+
+    /// Models this backend may serve. An empty list allows any model, otherwise the first entry
+    /// is used when the request does not ask for a model.
+    fn models(&self) -> &[String];
+
+    /// Sends `request` upstream and returns the translated response stream
+    fn chat(
+        &self,
+        upstream: &Upstream,
+        request: ChatRequest,
+        model: Option<String>,
+    ) -> LocalBoxFuture<'static, Result<LlmResponse, LlmError>>;
+
+    /// Asks the backend whether it is up, returns the version it reports if any
+    fn probe(
+        &self,
+        upstream: &Upstream,
+    ) -> LocalBoxFuture<'static, Result<Option<String>, LlmError>>;
+
+    /// Checks `requested` against the allow-list, falling back to the default model
+    fn resolve_model(&self, requested: Option<&str>) -> Result<Option<String>, LlmError> {
+        let models = self.models();
+
+        match requested {
+            Some(model) if models.is_empty() || models.iter().any(|m| m == model) => {
+                Ok(Some(model.to_string()))
+            }
+            Some(model) => Err(LlmError::ModelNotAllowed {
+                backend: self.name().to_string(),
+                model: model.to_string(),
+            }),
+            None => Ok(models.first().cloned()),
+        }
+    }
+}
+
+struct RegisteredBackend {
+    backend: Arc<dyn LlmBackend>,
+    breaker: Arc<CircuitBreaker>,
+}
+
+/// An OpenAI-compatible server
+#[derive(Debug, Clone)]
+pub struct OpenAiConfig {
+    pub url: String,
+    pub api_key: Option<Secret>,
+    pub models: Vec<String>,
+}
+
+/// Which LLM backends are registered
+///
+/// - `LLM_BACKEND`, `LLM_BACKEND_MODELS` - our own LLM service, always registered
+/// - `LLM_OPENAI_URL`, `LLM_OPENAI_API_KEY`, `LLM_OPENAI_MODELS` - any OpenAI-compatible server
+/// - `LLM_MOCK_ENABLED` - registers the deterministic mock backend
+/// - `LLM_DEFAULT_BACKEND` - backend used when neither the request nor the user picks one
+///
+/// Timeouts, retries and the circuit breaker are configured as described in
+/// [`LlmClientConfig::from_vars`], token quotas as described in [`LlmQuota::from_vars`].
+#[derive(Debug, Clone)]
+pub struct LlmConfig {
+    pub default_backend: String,
+    pub threatmapper_url: String,
+    pub threatmapper_models: Vec<String>,
+    pub openai: Option<OpenAiConfig>,
+    pub mock_enabled: bool,
+    pub client: LlmClientConfig,
+    pub quota: LlmQuota,
+}
+
+impl LlmConfig {
+    pub(crate) fn from_vars(var: impl Fn(&str) -> Option<String>) -> Result<Self, LlmError> {
+        let list = |name: &str| {
+            var(name)
+                .map(|value| {
+                    value
+                        .split(',')
+                        .map(str::trim)
+                        .filter(|v| !v.is_empty())
+                        .map(str::to_string)
+                        .collect()
+                })
+                .unwrap_or_default()
+        };
+
+        Ok(Self {
+            default_backend: var("LLM_DEFAULT_BACKEND")
+                .unwrap_or_else(|| "threatmapper".to_string()),
+            threatmapper_url: var("LLM_BACKEND")
+                .unwrap_or_else(|| "http://localhost:8000".to_string()),
+            threatmapper_models: list("LLM_BACKEND_MODELS"),
+            openai: var("LLM_OPENAI_URL").map(|url| OpenAiConfig {
+                url,
+                api_key: var("LLM_OPENAI_API_KEY").map(Secret::new),
+                models: list("LLM_OPENAI_MODELS"),
+            }),
+            mock_enabled: var("LLM_MOCK_ENABLED").is_some_and(|v| v == "true" || v == "1"),
+            client: LlmClientConfig::from_vars(&var)?,
+            quota: LlmQuota::from_vars(&var)?,
+        })
+    }
+}
+
+/// All configured LLM backends
+pub struct LlmBackends {
+    backends: BTreeMap<String, RegisteredBackend>,
+    default_backend: String,
+    config: LlmClientConfig,
+    quota: LlmQuota,
+}
+
+impl LlmBackends {
+    pub(crate) fn new(default_backend: impl Into<String>, config: LlmClientConfig) -> Self {
+        Self {
+            backends: BTreeMap::new(),
+            default_backend: default_backend.into(),
+            config,
+            quota: LlmQuota::default(),
+        }
+    }
+
+    /// Registers the backends enabled in `config`
+    pub fn from_config(config: &LlmConfig) -> Result<Self, LlmError> {
+        let mut backends = Self::new(config.default_backend.clone(), config.client.clone());
+        backends.quota = config.quota;
+
+        backends.register(ThreatMapperBackend::new(
+            config.threatmapper_url.clone(),
+            config.threatmapper_models.clone(),
+        ));
+
+        if let Some(openai) = &config.openai {
+            backends.register(OpenAiBackend::new(
+                openai.url.clone(),
+                openai.api_key.as_ref().map(|key| key.expose().to_string()),
+                openai.models.clone(),
+            ));
+        }
+
+        if config.mock_enabled {
+            backends.register(MockBackend);
+        }
+
+        if !backends.backends.contains_key(&backends.default_backend) {
+            return Err(LlmError::Config(format!(
+                "default backend {} is not configured",
+                backends.default_backend
+            )));
+        }
+
+        Ok(backends)
+    }
+
+    pub(crate) fn register(&mut self, backend: impl LlmBackend + 'static) {
+        let breaker =
+            CircuitBreaker::new(self.config.breaker_threshold, self.config.breaker_cooldown);
+
+        self.backends.insert(
+            backend.name().to_string(),
+            RegisteredBackend {
+                backend: Arc::new(backend),
+                breaker: Arc::new(breaker),
+            },
+        );
+    }
+
+    pub(crate) fn quota(&self) -> &LlmQuota {
+        &self.quota
+    }
+
+    fn entry(&self, name: &str) -> Result<&RegisteredBackend, LlmError> {
+        self.backends
+            .get(name)
+            .ok_or_else(|| LlmError::UnknownBackend(name.to_string()))
+    }
+
+    pub(crate) fn get(&self, name: &str) -> Result<Arc<dyn LlmBackend>, LlmError> {
+        self.entry(name).map(|entry| entry.backend.clone())
+    }
+
+    /// Resolves the backend and model that should serve a request
+    pub(crate) fn select(
+        &self,
+        selection: &LlmSelection,
+    ) -> Result<(Arc<dyn LlmBackend>, Option<String>), LlmError> {
+        let backend = self.get(
+            selection
+                .backend
+                .as_deref()
+                .unwrap_or(&self.default_backend),
+        )?;
+        let model = backend.resolve_model(selection.model.as_deref())?;
+
+        Ok((backend, model))
+    }
+
+    /// Sends `request` to `backend`, retrying failures that happen before any output was
+    /// streamed and tracking the backend's health in its circuit breaker
+    pub(crate) async fn chat(
+        &self,
+        backend: &str,
+        request: ChatRequest,
+        model: Option<String>,
+    ) -> Result<LlmResponse, LlmError> {
+        let entry = self.entry(backend)?;
+        let upstream = Upstream {
+            client: shared_client(&self.config),
+            read_timeout: self.config.read_timeout,
+        };
+
+        let mut attempt = 0;
+
+        loop {
+            let permit = entry
+                .breaker
+                .acquire()
+                .map_err(|retry_after| LlmError::CircuitOpen {
+                    backend: backend.to_string(),
+                    retry_after,
+                })?;
+
+            let started = Instant::now();
+            let result = entry
+                .backend
+                .chat(&upstream, request.clone(), model.clone())
+                .await;
+            METRICS.observe_upstream(backend, result.is_ok(), started.elapsed());
+
+            let err = match result {
+                Ok(mut response) => {
+                    permit.success();
+
+                    let breaker = entry.breaker.clone();
+                    response.chunks = response
+                        .chunks
+                        .inspect(move |chunk| {
+                            if let Err(err) = chunk {
+                                if err.is_backend_failure() {
+                                    breaker.record_failure(err);
+                                }
+                            }
+                        })
+                        .boxed_local();
+
+                    return Ok(response);
+                }
+                Err(err) => err,
+            };
+
+            permit.failure(&err);
+
+            if !err.is_retryable() || attempt >= self.config.max_retries {
+                return Err(err);
+            }
+
+            attempt += 1;
+            warn!(
+                "LLM backend {} failed ({}), retry {} of {}",
+                backend, err, attempt, self.config.max_retries
+            );
+            actix_web::rt::time::sleep(self.config.retry_backoff * 2u32.pow(attempt - 1)).await;
+        }
+    }
+
+    pub(crate) fn describe(&self) -> Vec<LlmBackendInfo> {
+        self.backends
+            .values()
+            .map(|entry| LlmBackendInfo {
+                name: entry.backend.name().to_string(),
+                models: entry.backend.models().to_vec(),
+                default: entry.backend.name() == self.default_backend,
+            })
+            .collect()
+    }
+
+    /// Health probes of every backend by name, they are not counted by the circuit breakers
+    pub(crate) fn probes(
+        &self,
+    ) -> Vec<(
+        String,
+        LocalBoxFuture<'static, Result<Option<String>, LlmError>>,
+    )> {
+        let upstream = Upstream {
+            client: shared_client(&self.config),
+            read_timeout: self.config.read_timeout,
+        };
+
+        self.backends
+            .iter()
+            .map(|(name, entry)| (name.clone(), entry.backend.probe(&upstream)))
+            .collect()
+    }
+
+    /// Circuit breaker state of every backend
+    pub(crate) fn health(&self) -> Vec<LlmBackendHealth> {
+        self.backends
+            .iter()
+            .map(|(name, entry)| {
+                let status = entry.breaker.status();
+
+                LlmBackendHealth {
+                    name: name.clone(),
+                    state: status.state,
+                    consecutive_failures: status.consecutive_failures,
+                    last_error: status.last_error,
+                    retry_after_secs: status.retry_after.map(|d| d.as_secs()),
+                }
+            })
+            .collect()
+    }
+}
+
+/// Turns an upstream response into a stream of body chunks. The stream fails with
+/// [`LlmError::Timeout`] if the backend stays silent for longer than `read_timeout`.
+pub(crate) fn body_stream<S>(response: ClientResponse<S>, read_timeout: Duration) -> ByteStream
+where
+    S: Stream<Item = Result<Bytes, PayloadError>> + Unpin + 'static,
+{
+    stream::unfold(Some(Box::pin(response)), move |response| async move {
+        let mut response = response?;
+
+        match actix_web::rt::time::timeout(read_timeout, response.next()).await {
+            Ok(Some(Ok(bytes))) => {
+                METRICS.llm_streamed_bytes.inc_by(bytes.len() as u64);
+                Some((Ok(bytes), Some(response)))
+            }
+            Ok(Some(Err(err))) => Some((Err(LlmError::Upstream(err.to_string())), None)),
+            Ok(None) => None,
+            Err(_) => Some((Err(LlmError::Timeout), None)),
+        }
+    })
+    .boxed_local()
+}
+
+/// Adds the trace context and the ID of the current request to an upstream request so that
+/// the backend's traces and logs can be joined with ours
+pub(crate) fn with_trace_context(mut request: ClientRequest) -> ClientRequest {
+    for header in trace_headers() {
+        request = request.insert_header(header);
+    }
+    if let Some(request_id) = RequestId::current() {
+        request = request.insert_header((REQUEST_ID_HEADER, request_id.0));
+    }
+
+    request
+}
+
+/// Returns the response if it was successful, otherwise reads the body into an error
+pub(crate) async fn check_status<S>(
+    mut response: ClientResponse<S>,
+) -> Result<ClientResponse<S>, LlmError>
+where
+    S: Stream<Item = Result<Bytes, PayloadError>> + Unpin + 'static,
+{
+    let status = response.status();
+
+    if status.is_success() {
+        return Ok(response);
+    }
+
+    let body = response.body().limit(64 * 1024).await.unwrap_or_default();
+
+    Err(LlmError::UpstreamStatus {
+        status: status.as_u16(),
+        body: String::from_utf8_lossy(&body).into_owned(),
+    })
+}
@@ -0,0 +1,264 @@
+use super::headers::forwardable_headers;
+use super::sse::SseDecoder;
+use super::structured::{instructions, threat_model_schema};
+use super::{
+    body_stream, check_status, with_trace_context, ByteStream, ChatStream, LlmBackend, LlmError,
+    LlmResponse, Upstream,
+};
+use crate::dto::chat_chunk::{ChatChunk, TokenUsage};
+use crate::dto::chat_request::ChatRequest;
+use actix_web::http::header;
+use futures::future::LocalBoxFuture;
+use futures::{future, stream, StreamExt};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+
+pub const NAME: &str = "openai";
+
+const SYSTEM_PROMPT: &str = "You are a security assistant that helps users build threat models \
+    of their systems. Base your answers on the MITRE ATT&CK framework and refer to techniques and \
+    mitigations by their MITRE ID where possible.";
+
+/// Any server implementing the OpenAI `/v1/chat/completions` API, e.g. vLLM, llama.cpp or Ollama.
+pub struct OpenAiBackend {
+    url: String,
+    api_key: Option<String>,
+    models: Vec<String>,
+}
+
+#[derive(Serialize, Debug)]
+pub(crate) struct Message {
+    pub(crate) role: &'static str,
+    pub(crate) content: String,
+}
+
+#[derive(Serialize, Debug)]
+struct StreamOptions {
+    include_usage: bool,
+}
+
+#[derive(Serialize, Debug)]
+struct CompletionRequest {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    model: Option<String>,
+    messages: Vec<Message>,
+    stream: bool,
+    stream_options: StreamOptions,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    response_format: Option<Value>,
+}
+
+#[derive(Deserialize, Debug)]
+struct CompletionChunk {
+    model: Option<String>,
+    #[serde(default)]
+    choices: Vec<Choice>,
+    usage: Option<Usage>,
+}
+
+#[derive(Deserialize, Debug)]
+struct Choice {
+    #[serde(default)]
+    delta: Delta,
+    finish_reason: Option<String>,
+}
+
+#[derive(Deserialize, Debug, Default)]
+struct Delta {
+    content: Option<String>,
+}
+
+#[derive(Deserialize, Debug)]
+struct Usage {
+    prompt_tokens: u64,
+    completion_tokens: u64,
+}
+
+impl OpenAiBackend {
+    pub fn new(url: String, api_key: Option<String>, models: Vec<String>) -> Self {
+        Self {
+            url: url.trim_end_matches('/').to_string(),
+            api_key,
+            models,
+        }
+    }
+}
+
+/// Builds the conversation sent to the model from a [`ChatRequest`]
+pub(crate) fn messages(request: &ChatRequest) -> Vec<Message> {
+    let mut messages = vec![Message {
+        role: "system",
+        content: SYSTEM_PROMPT.to_string(),
+    }];
+
+    let keywords = request.keywords();
+    if !keywords.is_empty() {
+        let keywords = keywords
+            .iter()
+            .map(|keyword| format!("{keyword:?}"))
+            .collect::<Vec<_>>()
+            .join(", ");
+
+        messages.push(Message {
+            role: "system",
+            content: format!("The system being analysed involves: {keywords}."),
+        });
+    }
+
+    if let ChatRequest::Structured { .. } = request {
+        messages.push(Message {
+            role: "system",
+            content: instructions(),
+        });
+    }
+
+    messages.push(Message {
+        role: "user",
+        content: request.prompt().to_string(),
+    });
+
+    messages
+}
+
+impl LlmBackend for OpenAiBackend {
+    fn name(&self) -> &str {
+        NAME
+    }
+
+    fn models(&self) -> &[String] {
+        &self.models
+    }
+
+    fn chat(
+        &self,
+        upstream: &Upstream,
+        request: ChatRequest,
+        model: Option<String>,
+    ) -> LocalBoxFuture<'static, Result<LlmResponse, LlmError>> {
+        let read_timeout = upstream.read_timeout;
+        let mut request_builder = with_trace_context(
+            upstream
+                .client
+                .post(format!("{}/v1/chat/completions", self.url)),
+        )
+        .insert_header((header::ACCEPT, "text/event-stream"));
+
+        if let Some(api_key) = &self.api_key {
+            request_builder = request_builder.bearer_auth(api_key);
+        }
+
+        Box::pin(async move {
+            let body = CompletionRequest {
+                model: model.clone(),
+                messages: messages(&request),
+                stream: true,
+                stream_options: StreamOptions {
+                    include_usage: true,
+                },
+                response_format: response_format(&request),
+            };
+
+            let response = request_builder.send_json(&body).await?;
+            let response = check_status(response).await?;
+
+            Ok(LlmResponse {
+                headers: forwardable_headers(response.headers()),
+                chunks: decode_stream(body_stream(response, read_timeout), model),
+            })
+        })
+    }
+
+    /// `GET /v1/models`, which every OpenAI-compatible server answers. There is no common way
+    /// to report a version, the `Server` header is used if present.
+    fn probe(
+        &self,
+        upstream: &Upstream,
+    ) -> LocalBoxFuture<'static, Result<Option<String>, LlmError>> {
+        let mut request_builder =
+            with_trace_context(upstream.client.get(format!("{}/v1/models", self.url)));
+
+        if let Some(api_key) = &self.api_key {
+            request_builder = request_builder.bearer_auth(api_key);
+        }
+
+        Box::pin(async move {
+            let response = request_builder.send().await?;
+            let response = check_status(response).await?;
+
+            Ok(response
+                .headers()
+                .get(header::SERVER)
+                .and_then(|server| server.to_str().ok())
+                .map(str::to_string))
+        })
+    }
+}
+
+/// Constrains structured requests to the threat model schema
+fn response_format(request: &ChatRequest) -> Option<Value> {
+    match request {
+        ChatRequest::Structured { .. } => Some(json!({
+            "type": "json_schema",
+            "json_schema": {
+                "name": "threat_model",
+                "strict": true,
+                "schema": threat_model_schema(),
+            }
+        })),
+        ChatRequest::Chat { .. } => None,
+    }
+}
+
+/// Translates an OpenAI server-sent event stream into [`ChatChunk`]s
+pub(crate) fn decode_stream(bytes: ByteStream, model: Option<String>) -> ChatStream {
+    bytes
+        .map(Some)
+        .chain(stream::once(future::ready(None)))
+        .scan(SseDecoder::default(), |decoder, item| {
+            let events: Vec<Result<String, LlmError>> = match item {
+                Some(Ok(bytes)) => decoder.push(&bytes).into_iter().map(Ok).collect(),
+                Some(Err(err)) => vec![Err(err)],
+                None => decoder.finish().into_iter().map(Ok).collect(),
+            };
+
+            future::ready(Some(stream::iter(events)))
+        })
+        .flatten()
+        .take_while(|event| future::ready(!matches!(event, Ok(data) if data.trim() == "[DONE]")))
+        .filter_map(move |event| future::ready(parse_event(event, model.as_deref()).transpose()))
+        .boxed_local()
+}
+
+fn parse_event(
+    event: Result<String, LlmError>,
+    requested_model: Option<&str>,
+) -> Result<Option<ChatChunk>, LlmError> {
+    let chunk: CompletionChunk =
+        serde_json::from_str(&event?).map_err(|err| LlmError::Decode(err.to_string()))?;
+
+    let model = chunk.model.as_deref().or(requested_model);
+    let choice = chunk.choices.into_iter().next();
+
+    let (delta, finish_reason) = match choice {
+        Some(choice) => (
+            choice.delta.content.unwrap_or_default(),
+            choice.finish_reason,
+        ),
+        None => (String::new(), None),
+    };
+
+    let usage = chunk.usage.map(|usage| TokenUsage {
+        prompt_tokens: usage.prompt_tokens,
+        completion_tokens: usage.completion_tokens,
+    });
+
+    if delta.is_empty() && finish_reason.is_none() && usage.is_none() {
+        return Ok(None);
+    }
+
+    Ok(Some(ChatChunk {
+        finish_reason,
+        usage,
+        ..ChatChunk::delta(NAME, model, delta)
+    }))
+}
@@ -0,0 +1,42 @@
+use crate::dto::chat_request::LlmSelection;
+use crate::error::ServerResponseError;
+use crate::metrics::ObserveQuery;
+use crate::services::llm::LlmBackends;
+use std::sync::Arc;
+use surrealdb::sql::Thing;
+use surrealdb::Surreal;
+use tracing::warn;
+
+/// Returns the backend and model the user has chosen as their default, if any. A preference
+/// naming a backend that is no longer configured is ignored.
+pub async fn get_llm_preference<T>(
+    db: &Arc<Surreal<T>>,
+    llm: &LlmBackends,
+    user_id: Thing,
+) -> Result<LlmSelection, ServerResponseError>
+where
+    T: surrealdb::Connection,
+{
+    const SQL: &str = "SELECT llm_backend AS backend, llm_model AS model FROM ONLY $USER;";
+
+    let preference: Option<LlmSelection> = db
+        .query(SQL)
+        .bind(("USER", user_id))
+        .observed()
+        .await?
+        .take(0)?;
+
+    let preference = preference.unwrap_or_default();
+
+    if let Some(backend) = &preference.backend {
+        if llm.get(backend).is_err() {
+            warn!(
+                "Ignoring preferred LLM backend {} as it is not configured",
+                backend
+            );
+            return Ok(LlmSelection::default());
+        }
+    }
+
+    Ok(preference)
+}
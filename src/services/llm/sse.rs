@@ -0,0 +1,91 @@
+/// Incremental decoder for `text/event-stream` bodies.
+///
+/// Bytes are pushed in as they arrive from the upstream connection and the `data` payload of
+/// every complete event is returned. Multi-line data fields are joined with `\n`, comments and
+/// other fields are ignored.
+#[derive(Debug, Default)]
+pub(crate) struct SseDecoder {
+    buffer: Vec<u8>,
+}
+
+impl SseDecoder {
+    pub(crate) fn push(&mut self, bytes: &[u8]) -> Vec<String> {
+        self.buffer.extend_from_slice(bytes);
+
+        let mut events = Vec::new();
+
+        while let Some((end, separator_len)) = find_event_end(&self.buffer) {
+            let raw: Vec<u8> = self.buffer.drain(..end + separator_len).take(end).collect();
+            if let Some(data) = parse_event(&String::from_utf8_lossy(&raw)) {
+                events.push(data);
+            }
+        }
+
+        events
+    }
+
+    /// Returns the last event if the stream ended without a trailing blank line
+    pub(crate) fn finish(&mut self) -> Option<String> {
+        let raw = std::mem::take(&mut self.buffer);
+        parse_event(&String::from_utf8_lossy(&raw))
+    }
+}
+
+fn find_event_end(buffer: &[u8]) -> Option<(usize, usize)> {
+    let lf = buffer.windows(2).position(|w| w == b"\n\n").map(|i| (i, 2));
+    let crlf = buffer
+        .windows(4)
+        .position(|w| w == b"\r\n\r\n")
+        .map(|i| (i, 4));
+
+    match (lf, crlf) {
+        (Some(a), Some(b)) => Some(if a.0 < b.0 { a } else { b }),
+        (a, b) => a.or(b),
+    }
+}
+
+fn parse_event(raw: &str) -> Option<String> {
+    let data: Vec<&str> = raw
+        .lines()
+        .filter_map(|line| line.strip_prefix("data:"))
+        .map(|data| data.strip_prefix(' ').unwrap_or(data))
+        .collect();
+
+    if data.is_empty() {
+        None
+    } else {
+        Some(data.join("\n"))
+    }
+}
+
+/// Decodes UTF-8 text that may be split at arbitrary byte boundaries across chunks.
+#[derive(Debug, Default)]
+pub(crate) struct Utf8Decoder {
+    buffer: Vec<u8>,
+}
+
+impl Utf8Decoder {
+    pub(crate) fn push(&mut self, bytes: &[u8]) -> String {
+        self.buffer.extend_from_slice(bytes);
+
+        let valid_up_to = match std::str::from_utf8(&self.buffer) {
+            Ok(_) => self.buffer.len(),
+            // an incomplete sequence at the end is kept until the next chunk arrives
+            Err(err) if err.error_len().is_none() => err.valid_up_to(),
+            Err(_) => {
+                let text = String::from_utf8_lossy(&self.buffer).into_owned();
+                self.buffer.clear();
+                return text;
+            }
+        };
+
+        let rest = self.buffer.split_off(valid_up_to);
+        String::from_utf8(std::mem::replace(&mut self.buffer, rest)).unwrap_or_default()
+    }
+
+    pub(crate) fn finish(&mut self) -> String {
+        let text = String::from_utf8_lossy(&self.buffer).into_owned();
+        self.buffer.clear();
+        text
+    }
+}
@@ -0,0 +1,211 @@
+//! Structured output for [`ChatRequest::Structured`](crate::dto::chat_request::ChatRequest).
+//!
+//! Backends ask the model for a JSON document matching [`threat_model_schema`], the complete
+//! answer is then validated and every `mitre_id` is looked up in the `threat` and `mitigation`
+//! tables so that made up IDs can be flagged.
+
+use super::{LlmError, LlmResponse};
+use crate::dto::chat_chunk::TokenUsage;
+use crate::dto::threat_analysis::{
+    AnalysisEntry, ProposedEntry, ThreatAnalysis, ThreatModelOutput,
+};
+use crate::error::ServerResponseError;
+use crate::models::{EntryType, MITREEntry};
+use crate::services::embeddings::get::get_entries_by_mitre_id;
+use futures::StreamExt;
+use once_cell::sync::Lazy;
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::sync::Arc;
+use surrealdb::Surreal;
+
+const THREAT_ID_PATTERN: &str = r"^T\d{4}(\.\d{3})?$";
+const MITIGATION_ID_PATTERN: &str = r"^M\d{4}$";
+
+static SCHEMA: Lazy<Value> = Lazy::new(|| {
+    let entry = |pattern: &str| {
+        json!({
+            "type": "object",
+            "properties": {
+                "mitre_id": { "type": "string", "pattern": pattern },
+                "rationale": { "type": "string" }
+            },
+            "required": ["mitre_id", "rationale"],
+            "additionalProperties": false
+        })
+    };
+
+    json!({
+        "type": "object",
+        "properties": {
+            "threats": { "type": "array", "items": entry(THREAT_ID_PATTERN) },
+            "mitigations": { "type": "array", "items": entry(MITIGATION_ID_PATTERN) }
+        },
+        "required": ["threats", "mitigations"],
+        "additionalProperties": false
+    })
+});
+
+/// JSON schema of [`ThreatModelOutput`], sent to backends that support constrained output
+pub(crate) fn threat_model_schema() -> &'static Value {
+    &SCHEMA
+}
+
+/// Instructions added to the prompt of structured requests, for backends that cannot enforce the
+/// schema themselves
+pub(crate) fn instructions() -> String {
+    format!(
+        "Identify the MITRE ATT&CK techniques that threaten the described system and the MITRE \
+        mitigations that address them. Answer with a single JSON object and nothing else. The \
+        object must match this JSON schema: {}",
+        threat_model_schema()
+    )
+}
+
+/// A complete, non-streamed answer
+#[derive(Debug, Default)]
+pub(crate) struct Completion {
+    pub(crate) backend: String,
+    pub(crate) model: Option<String>,
+    pub(crate) text: String,
+    pub(crate) usage: Option<TokenUsage>,
+}
+
+/// Reads the whole response stream
+pub(crate) async fn collect(response: LlmResponse) -> Result<Completion, LlmError> {
+    let mut completion = Completion::default();
+    let mut chunks = response.chunks;
+
+    while let Some(chunk) = chunks.next().await {
+        let chunk = chunk?;
+
+        completion.backend = chunk.backend;
+        completion.model = chunk.model.or(completion.model);
+        completion.text.push_str(&chunk.delta);
+        completion.usage = chunk.usage.or(completion.usage);
+    }
+
+    Ok(completion)
+}
+
+/// Parses and validates the model's answer. Models like to wrap JSON in Markdown code fences or
+/// add a sentence around it, so only the outermost object is considered.
+pub(crate) fn parse_output(text: &str) -> Result<ThreatModelOutput, LlmError> {
+    let json = match (text.find('{'), text.rfind('}')) {
+        (Some(start), Some(end)) if start < end => &text[start..=end],
+        _ => return Err(LlmError::InvalidOutput("no JSON object found".to_string())),
+    };
+
+    let output: ThreatModelOutput =
+        serde_json::from_str(json).map_err(|err| LlmError::InvalidOutput(err.to_string()))?;
+
+    validate_entries(&output.threats, "threats", is_threat_id)?;
+    validate_entries(&output.mitigations, "mitigations", is_mitigation_id)?;
+
+    Ok(output)
+}
+
+fn validate_entries(
+    entries: &[ProposedEntry],
+    field: &str,
+    is_valid_id: fn(&str) -> bool,
+) -> Result<(), LlmError> {
+    for (i, entry) in entries.iter().enumerate() {
+        if !is_valid_id(&entry.mitre_id) {
+            return Err(LlmError::InvalidOutput(format!(
+                "{field}[{i}].mitre_id {:?} is not a valid MITRE ID",
+                entry.mitre_id
+            )));
+        }
+
+        if entry.rationale.trim().is_empty() {
+            return Err(LlmError::InvalidOutput(format!(
+                "{field}[{i}].rationale is empty"
+            )));
+        }
+    }
+
+    Ok(())
+}
+
+/// `T` followed by four digits and an optional `.` and three digit sub-technique
+fn is_threat_id(id: &str) -> bool {
+    let Some(rest) = id.strip_prefix('T') else {
+        return false;
+    };
+
+    match rest.split_once('.') {
+        Some((technique, sub)) => is_digits(technique, 4) && is_digits(sub, 3),
+        None => is_digits(rest, 4),
+    }
+}
+
+/// `M` followed by four digits
+fn is_mitigation_id(id: &str) -> bool {
+    id.strip_prefix('M').is_some_and(|rest| is_digits(rest, 4))
+}
+
+fn is_digits(s: &str, len: usize) -> bool {
+    s.len() == len && s.bytes().all(|b| b.is_ascii_digit())
+}
+
+/// Looks up every proposed ID and flags the ones that do not exist
+pub(crate) async fn resolve_output<T>(
+    db: &Arc<Surreal<T>>,
+    completion: Completion,
+    output: ThreatModelOutput,
+) -> Result<ThreatAnalysis, ServerResponseError>
+where
+    T: surrealdb::Connection,
+{
+    let ids = |entries: &[ProposedEntry]| -> Vec<String> {
+        entries.iter().map(|entry| entry.mitre_id.clone()).collect()
+    };
+
+    let threats = get_entries_by_mitre_id(db, EntryType::Threat, ids(&output.threats)).await?;
+    let mitigations =
+        get_entries_by_mitre_id(db, EntryType::Mitigation, ids(&output.mitigations)).await?;
+
+    let threats = resolve_entries(output.threats, threats);
+    let mitigations = resolve_entries(output.mitigations, mitigations);
+
+    let hallucinated_ids = threats
+        .iter()
+        .chain(mitigations.iter())
+        .filter(|entry| entry.hallucinated)
+        .map(|entry| entry.mitre_id.clone())
+        .collect();
+
+    Ok(ThreatAnalysis {
+        backend: completion.backend,
+        model: completion.model,
+        threats,
+        mitigations,
+        hallucinated_ids,
+        usage: completion.usage,
+    })
+}
+
+pub(crate) fn resolve_entries(
+    proposed: Vec<ProposedEntry>,
+    known: Vec<MITREEntry>,
+) -> Vec<AnalysisEntry> {
+    let known: HashMap<String, MITREEntry> = known
+        .into_iter()
+        .map(|entry| (entry.mitre_id.clone(), entry))
+        .collect();
+
+    proposed
+        .into_iter()
+        .map(|proposed| {
+            let entry = known.get(&proposed.mitre_id).cloned();
+
+            AnalysisEntry {
+                hallucinated: entry.is_none(),
+                mitre_id: proposed.mitre_id,
+                rationale: proposed.rationale,
+                entry,
+            }
+        })
+        .collect()
+}
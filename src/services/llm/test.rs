@@ -0,0 +1,253 @@
+#![cfg(test)]
+
+use super::breaker::CircuitBreaker;
+use super::headers::forwardable_headers;
+use super::sse::{SseDecoder, Utf8Decoder};
+use super::structured::{parse_output, resolve_entries};
+use super::usage::estimate_tokens;
+use super::{openai, LlmBackend, LlmError};
+use crate::dto::chat_chunk::TokenUsage;
+use crate::dto::chat_request::LlmSelection;
+use crate::dto::llm_backend::BreakerState;
+use crate::models::MITREEntry;
+use actix_web::http::header::{HeaderMap, HeaderName, HeaderValue};
+use actix_web::web::Bytes;
+use futures::{stream, StreamExt};
+use std::time::Duration;
+
+#[test]
+fn sse_events_split_across_chunks() {
+    let mut decoder = SseDecoder::default();
+
+    assert!(decoder.push(b"data: {\"a\"").is_empty());
+    assert_eq!(
+        decoder.push(b":1}\n\ndata: [DONE]\n\n"),
+        vec!["{\"a\":1}", "[DONE]"]
+    );
+    assert_eq!(decoder.finish(), None);
+}
+
+#[test]
+fn utf8_split_across_chunks() {
+    let mut decoder = Utf8Decoder::default();
+    let text = "å".as_bytes();
+
+    assert_eq!(decoder.push(&text[..1]), "");
+    assert_eq!(decoder.push(&text[1..]), "å");
+}
+
+#[test]
+fn model_allow_list() {
+    let backend = openai::OpenAiBackend::new(
+        "http://localhost".to_string(),
+        None,
+        vec!["a".to_string(), "b".to_string()],
+    );
+
+    assert_eq!(backend.resolve_model(None).unwrap(), Some("a".to_string()));
+    assert_eq!(
+        backend.resolve_model(Some("b")).unwrap(),
+        Some("b".to_string())
+    );
+    assert!(matches!(
+        backend.resolve_model(Some("c")),
+        Err(LlmError::ModelNotAllowed { .. })
+    ));
+}
+
+#[test]
+fn preferred_model_only_applies_to_its_backend() {
+    let preference = || LlmSelection {
+        backend: Some("openai".to_string()),
+        model: Some("gpt".to_string()),
+    };
+    let select = |backend: Option<&str>| LlmSelection {
+        backend: backend.map(str::to_string),
+        model: None,
+    };
+
+    let merged = select(None).or(preference());
+    assert_eq!(merged.backend.as_deref(), Some("openai"));
+    assert_eq!(merged.model.as_deref(), Some("gpt"));
+
+    let merged = select(Some("openai")).or(preference());
+    assert_eq!(merged.model.as_deref(), Some("gpt"));
+
+    let merged = select(Some("mock")).or(preference());
+    assert_eq!(merged.backend.as_deref(), Some("mock"));
+    assert_eq!(merged.model, None);
+}
+
+#[actix::test]
+async fn openai_stream_is_translated() {
+    let body = concat!(
+        "data: {\"model\":\"m\",\"choices\":[{\"delta\":{\"role\":\"assistant\"},\"finish_reason\":null}]}\n\n",
+        "data: {\"model\":\"m\",\"choices\":[{\"delta\":{\"content\":\"Hi\"},\"finish_reason\":null}]}\n\n",
+        "data: {\"model\":\"m\",\"choices\":[{\"delta\":{},\"finish_reason\":\"stop\"}]}\n\n",
+        "data: {\"model\":\"m\",\"choices\":[],\"usage\":{\"prompt_tokens\":3,\"completion_tokens\":1}}\n\n",
+        "data: [DONE]\n\n",
+    );
+
+    let bytes = stream::iter(
+        body.as_bytes()
+            .chunks(7)
+            .map(|chunk| Ok(Bytes::copy_from_slice(chunk)))
+            .collect::<Vec<_>>(),
+    )
+    .boxed_local();
+
+    let chunks: Vec<_> = openai::decode_stream(bytes, None)
+        .map(Result::unwrap)
+        .collect()
+        .await;
+
+    assert_eq!(chunks.len(), 3);
+    assert_eq!(chunks[0].delta, "Hi");
+    assert_eq!(chunks[1].finish_reason.as_deref(), Some("stop"));
+    assert_eq!(
+        chunks[2].usage,
+        Some(TokenUsage {
+            prompt_tokens: 3,
+            completion_tokens: 1
+        })
+    );
+}
+
+#[test]
+fn timed_out_requests_are_not_retried() {
+    assert!(!LlmError::Timeout.is_retryable());
+    assert!(LlmError::Connect("connect timed out".to_string()).is_retryable());
+}
+
+#[test]
+fn breaker_opens_after_threshold() {
+    let breaker = CircuitBreaker::new(2, Duration::from_secs(60));
+
+    breaker.record_failure(&LlmError::Timeout);
+    assert!(breaker.acquire().is_ok());
+
+    breaker.record_failure(&LlmError::Timeout);
+    assert!(breaker.acquire().is_err());
+    assert_eq!(breaker.status().state, BreakerState::Open);
+}
+
+#[test]
+fn breaker_lets_one_trial_through_after_cooldown() {
+    let breaker = CircuitBreaker::new(1, Duration::ZERO);
+
+    breaker.record_failure(&LlmError::Timeout);
+    let trial = breaker.acquire().unwrap();
+    assert!(breaker.acquire().is_err());
+
+    trial.success();
+    assert!(breaker.acquire().is_ok());
+    assert_eq!(breaker.status().state, BreakerState::Closed);
+}
+
+#[test]
+fn breaker_trial_is_released_without_verdict() {
+    let breaker = CircuitBreaker::new(1, Duration::ZERO);
+    breaker.record_failure(&LlmError::Timeout);
+
+    breaker
+        .acquire()
+        .unwrap()
+        .failure(&LlmError::UpstreamStatus {
+            status: 400,
+            body: String::new(),
+        });
+    let trial = breaker.acquire().unwrap();
+    assert_eq!(breaker.status().state, BreakerState::HalfOpen);
+
+    drop(trial);
+    assert!(breaker.acquire().is_ok());
+}
+
+#[test]
+fn hop_by_hop_headers_are_stripped() {
+    let mut headers = HeaderMap::new();
+    headers.insert(
+        HeaderName::from_static("connection"),
+        HeaderValue::from_static("keep-alive, x-internal"),
+    );
+    headers.insert(
+        HeaderName::from_static("transfer-encoding"),
+        HeaderValue::from_static("chunked"),
+    );
+    headers.insert(
+        HeaderName::from_static("x-internal"),
+        HeaderValue::from_static("1"),
+    );
+    headers.insert(
+        HeaderName::from_static("x-request-id"),
+        HeaderValue::from_static("abc"),
+    );
+
+    let forwarded = forwardable_headers(&headers);
+
+    assert_eq!(forwarded.len(), 1);
+    assert_eq!(forwarded[0].0.as_str(), "x-request-id");
+}
+
+#[test]
+fn token_estimate_rounds_up() {
+    assert_eq!(estimate_tokens(""), 0);
+    assert_eq!(estimate_tokens("abc"), 1);
+    assert_eq!(estimate_tokens("abcd"), 1);
+    assert_eq!(estimate_tokens("abcde"), 2);
+}
+
+#[test]
+fn structured_output_is_extracted_from_fences() {
+    let text = "Here is the threat model:\n```json\n{\"threats\": [{\"mitre_id\": \"T1190\", \"rationale\": \"Public login form\"}], \"mitigations\": []}\n```";
+
+    let output = parse_output(text).unwrap();
+
+    assert_eq!(output.threats.len(), 1);
+    assert_eq!(output.threats[0].mitre_id, "T1190");
+    assert!(output.mitigations.is_empty());
+}
+
+#[test]
+fn structured_output_must_match_schema() {
+    let invalid = [
+        "no json at all",
+        r#"{"threats": []}"#,
+        r#"{"threats": [], "mitigations": [], "summary": "extra"}"#,
+        r#"{"threats": [{"mitre_id": "M1050", "rationale": "wrong table"}], "mitigations": []}"#,
+        r#"{"threats": [{"mitre_id": "T1190.1", "rationale": "bad sub-technique"}], "mitigations": []}"#,
+        r#"{"threats": [], "mitigations": [{"mitre_id": "M1050", "rationale": " "}]}"#,
+    ];
+
+    for text in invalid {
+        assert!(
+            matches!(parse_output(text), Err(LlmError::InvalidOutput(_))),
+            "{text}"
+        );
+    }
+}
+
+#[test]
+fn unknown_mitre_ids_are_flagged() {
+    let output = parse_output(
+        r#"{"threats": [
+            {"mitre_id": "T1190", "rationale": "Public login form"},
+            {"mitre_id": "T9999", "rationale": "Made up"}
+        ], "mitigations": []}"#,
+    )
+    .unwrap();
+
+    let known = vec![MITREEntry {
+        mitre_id: "T1190".to_string(),
+        mitre_name: "Exploit Public-Facing Application".to_string(),
+        mitre_description: String::new(),
+        mitre_url: "https://attack.mitre.org/techniques/T1190".to_string(),
+    }];
+
+    let resolved = resolve_entries(output.threats, known);
+
+    assert!(!resolved[0].hallucinated);
+    assert!(resolved[0].entry.is_some());
+    assert!(resolved[1].hallucinated);
+    assert!(resolved[1].entry.is_none());
+}
@@ -0,0 +1,142 @@
+use super::headers::forwardable_headers;
+use super::sse::Utf8Decoder;
+use super::structured::threat_model_schema;
+use super::{
+    body_stream, check_status, with_trace_context, ByteStream, ChatStream, LlmBackend, LlmError,
+    LlmResponse, Upstream,
+};
+use crate::dto::chat_chunk::ChatChunk;
+use crate::dto::chat_request::ChatRequest;
+use futures::future::LocalBoxFuture;
+use futures::{future, stream, StreamExt};
+use serde::Serialize;
+use serde_json::Value;
+
+pub const NAME: &str = "threatmapper";
+
+/// Our own Python LLM service. It accepts a [`ChatRequest`] as is and streams back plain text,
+/// structured requests also carry the JSON schema the answer has to match.
+pub struct ThreatMapperBackend {
+    url: String,
+    models: Vec<String>,
+}
+
+#[derive(Serialize)]
+struct ThreatMapperRequest {
+    #[serde(flatten)]
+    request: ChatRequest,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    model: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    response_schema: Option<&'static Value>,
+}
+
+impl ThreatMapperBackend {
+    pub fn new(url: String, models: Vec<String>) -> Self {
+        Self {
+            url: url.trim_end_matches('/').to_string(),
+            models,
+        }
+    }
+}
+
+impl LlmBackend for ThreatMapperBackend {
+    fn name(&self) -> &str {
+        NAME
+    }
+
+    fn models(&self) -> &[String] {
+        &self.models
+    }
+
+    fn chat(
+        &self,
+        upstream: &Upstream,
+        request: ChatRequest,
+        model: Option<String>,
+    ) -> LocalBoxFuture<'static, Result<LlmResponse, LlmError>> {
+        let read_timeout = upstream.read_timeout;
+        let request_builder = with_trace_context(
+            upstream
+                .client
+                .post(format!("{}/chat/completions", self.url)),
+        );
+
+        Box::pin(async move {
+            let response_schema = match request {
+                ChatRequest::Structured { .. } => Some(threat_model_schema()),
+                ChatRequest::Chat { .. } => None,
+            };
+
+            let body = ThreatMapperRequest {
+                request,
+                model: model.clone(),
+                response_schema,
+            };
+
+            let response = request_builder.send_json(&body).await?;
+            let response = check_status(response).await?;
+
+            Ok(LlmResponse {
+                headers: forwardable_headers(response.headers()),
+                chunks: decode_stream(body_stream(response, read_timeout), model),
+            })
+        })
+    }
+
+    /// `GET /health`, the version is taken from a `version` field of the answer
+    fn probe(
+        &self,
+        upstream: &Upstream,
+    ) -> LocalBoxFuture<'static, Result<Option<String>, LlmError>> {
+        let request_builder =
+            with_trace_context(upstream.client.get(format!("{}/health", self.url)));
+
+        Box::pin(async move {
+            let response = request_builder.send().await?;
+            let mut response = check_status(response).await?;
+            let health: Value = response.json().await.unwrap_or_default();
+
+            Ok(health["version"].as_str().map(str::to_string))
+        })
+    }
+}
+
+/// Every body chunk becomes one delta, the end of the body finishes the completion
+pub(crate) fn decode_stream(bytes: ByteStream, model: Option<String>) -> ChatStream {
+    bytes
+        .map(Some)
+        .chain(stream::once(future::ready(None)))
+        .scan(
+            (Utf8Decoder::default(), false),
+            move |(decoder, failed), item| {
+                if *failed {
+                    return future::ready(None);
+                }
+
+                let chunk = match item {
+                    Some(Ok(bytes)) => Ok(ChatChunk::delta(
+                        NAME,
+                        model.as_deref(),
+                        decoder.push(&bytes),
+                    )),
+                    Some(Err(err)) => {
+                        *failed = true;
+                        Err(err)
+                    }
+                    None => Ok(ChatChunk {
+                        delta: decoder.finish(),
+                        ..ChatChunk::finish(NAME, model.as_deref(), None)
+                    }),
+                };
+
+                future::ready(Some(chunk))
+            },
+        )
+        .filter(|chunk| {
+            future::ready(
+                !matches!(chunk, Ok(c) if c.delta.is_empty() && c.finish_reason.is_none()),
+            )
+        })
+        .boxed_local()
+}
@@ -0,0 +1,355 @@
+use super::{ChatStream, LlmError, LlmResponse};
+use crate::dto::chat_chunk::{ChatChunk, TokenUsage};
+use crate::dto::usage::{
+    DailyUsage, QuotaStatus, UsageTotals, UserUsageResponse, UserUsageSummary,
+};
+use crate::error::ServerResponseError;
+use crate::metrics::ObserveQuery;
+use chrono::{Datelike, Duration as ChronoDuration, NaiveDate, Utc};
+use futures::{stream, StreamExt};
+use std::sync::Arc;
+use std::time::Duration;
+use surrealdb::sql::Thing;
+use surrealdb::Surreal;
+use tracing::{error, warn};
+
+/// Token quotas per user, `None` means unlimited
+#[derive(Debug, Clone, Copy, Default)]
+pub struct LlmQuota {
+    pub daily_tokens: Option<u64>,
+    pub monthly_tokens: Option<u64>,
+}
+
+impl LlmQuota {
+    /// Reads `LLM_DAILY_TOKEN_QUOTA` and `LLM_MONTHLY_TOKEN_QUOTA`, unset or `0` disables a quota
+    pub(crate) fn from_vars(var: impl Fn(&str) -> Option<String>) -> Result<Self, LlmError> {
+        let quota = |name: &str| match var(name) {
+            Some(value) => value
+                .parse::<u64>()
+                .map(|v| Some(v).filter(|v| *v > 0))
+                .map_err(|err| LlmError::Config(format!("{name}: {err}"))),
+            None => Ok(None),
+        };
+
+        Ok(Self {
+            daily_tokens: quota("LLM_DAILY_TOKEN_QUOTA")?,
+            monthly_tokens: quota("LLM_MONTHLY_TOKEN_QUOTA")?,
+        })
+    }
+}
+
+/// Rough token count for backends that do not report usage, about four characters per token
+pub(crate) fn estimate_tokens(text: &str) -> u64 {
+    (text.chars().count() as u64).div_ceil(4)
+}
+
+fn day_key(date: NaiveDate) -> String {
+    date.format("%Y-%m-%d").to_string()
+}
+
+fn first_of_month(date: NaiveDate) -> NaiveDate {
+    date.with_day(1).unwrap_or(date)
+}
+
+fn first_of_next_month(date: NaiveDate) -> NaiveDate {
+    let (year, month) = if date.month() == 12 {
+        (date.year() + 1, 1)
+    } else {
+        (date.year(), date.month() + 1)
+    };
+
+    NaiveDate::from_ymd_opt(year, month, 1).unwrap_or(date)
+}
+
+/// Time left until `date` starts in UTC
+fn until(date: NaiveDate) -> Duration {
+    date.and_hms_opt(0, 0, 0)
+        .and_then(|start| (start.and_utc() - Utc::now()).to_std().ok())
+        .unwrap_or_default()
+}
+
+/// Adds the tokens of one completion to the user's usage of the current day
+pub async fn record_usage<T>(
+    db: &Arc<Surreal<T>>,
+    user_id: Thing,
+    usage: TokenUsage,
+) -> Result<(), ServerResponseError>
+where
+    T: surrealdb::Connection,
+{
+    const SQL: &str = "
+        UPSERT type::thing('llm_usage', [$USER, $DAY]) SET
+            user = $USER,
+            day = $DAY,
+            prompt_tokens += $PROMPT_TOKENS,
+            completion_tokens += $COMPLETION_TOKENS,
+            requests += 1;
+    ";
+
+    db.query(SQL)
+        .bind(("USER", user_id))
+        .bind(("DAY", day_key(Utc::now().date_naive())))
+        .bind(("PROMPT_TOKENS", usage.prompt_tokens))
+        .bind(("COMPLETION_TOKENS", usage.completion_tokens))
+        .observed()
+        .await?
+        .check()?;
+
+    Ok(())
+}
+
+async fn usage_between<T>(
+    db: &Arc<Surreal<T>>,
+    user_id: Thing,
+    from: NaiveDate,
+    to: NaiveDate,
+) -> Result<UsageTotals, ServerResponseError>
+where
+    T: surrealdb::Connection,
+{
+    const SQL: &str = "
+        SELECT
+            math::sum(prompt_tokens) AS prompt_tokens,
+            math::sum(completion_tokens) AS completion_tokens,
+            math::sum(requests) AS requests
+        FROM llm_usage
+        WHERE user = $USER AND day >= $FROM AND day <= $TO
+        GROUP ALL;
+    ";
+
+    let totals: Option<UsageTotals> = db
+        .query(SQL)
+        .bind(("USER", user_id))
+        .bind(("FROM", day_key(from)))
+        .bind(("TO", day_key(to)))
+        .observed()
+        .await?
+        .take(0)?;
+
+    Ok(totals.unwrap_or_default())
+}
+
+/// Rejects the request if the user has used up their daily or monthly quota
+pub async fn check_quota<T>(
+    db: &Arc<Surreal<T>>,
+    quota: &LlmQuota,
+    user_id: &Thing,
+) -> Result<(), ServerResponseError>
+where
+    T: surrealdb::Connection,
+{
+    let today = Utc::now().date_naive();
+
+    if let Some(limit) = quota.daily_tokens {
+        let used = usage_between(db, user_id.clone(), today, today).await?;
+        if used.tokens() >= limit {
+            return Err(LlmError::QuotaExceeded {
+                period: "Daily",
+                limit,
+                retry_after: until(today + ChronoDuration::days(1)),
+            }
+            .into());
+        }
+    }
+
+    if let Some(limit) = quota.monthly_tokens {
+        let used = usage_between(db, user_id.clone(), first_of_month(today), today).await?;
+        if used.tokens() >= limit {
+            return Err(LlmError::QuotaExceeded {
+                period: "Monthly",
+                limit,
+                retry_after: until(first_of_next_month(today)),
+            }
+            .into());
+        }
+    }
+
+    Ok(())
+}
+
+/// Returns the user's usage per day in the range together with their quota status
+pub async fn get_user_usage<T>(
+    db: &Arc<Surreal<T>>,
+    quota: &LlmQuota,
+    user_id: Thing,
+    from: Option<NaiveDate>,
+    to: Option<NaiveDate>,
+) -> Result<UserUsageResponse, ServerResponseError>
+where
+    T: surrealdb::Connection,
+{
+    const SQL: &str = "
+        SELECT day, prompt_tokens, completion_tokens, requests
+        FROM llm_usage
+        WHERE user = $USER AND day >= $FROM AND day <= $TO
+        ORDER BY day DESC;
+    ";
+
+    let today = Utc::now().date_naive();
+    let to = to.unwrap_or(today);
+    let from = from.unwrap_or(to - ChronoDuration::days(29));
+
+    let days: Vec<DailyUsage> = db
+        .query(SQL)
+        .bind(("USER", user_id.clone()))
+        .bind(("FROM", day_key(from)))
+        .bind(("TO", day_key(to)))
+        .observed()
+        .await?
+        .take(0)?;
+
+    let used_today = usage_between(db, user_id.clone(), today, today).await?;
+    let used_month = usage_between(db, user_id, first_of_month(today), today).await?;
+
+    Ok(UserUsageResponse {
+        days,
+        daily_quota: QuotaStatus {
+            limit: quota.daily_tokens,
+            used: used_today.tokens(),
+        },
+        monthly_quota: QuotaStatus {
+            limit: quota.monthly_tokens,
+            used: used_month.tokens(),
+        },
+    })
+}
+
+/// Returns the usage of every user in the range, heaviest users first
+pub async fn get_usage_summary<T>(
+    db: &Arc<Surreal<T>>,
+    from: Option<NaiveDate>,
+    to: Option<NaiveDate>,
+) -> Result<Vec<UserUsageSummary>, ServerResponseError>
+where
+    T: surrealdb::Connection,
+{
+    const SQL: &str = "
+        SELECT
+            user,
+            user.email AS email,
+            math::sum(prompt_tokens) AS prompt_tokens,
+            math::sum(completion_tokens) AS completion_tokens,
+            math::sum(requests) AS requests
+        FROM llm_usage
+        WHERE day >= $FROM AND day <= $TO
+        GROUP BY user, email;
+    ";
+
+    let to = to.unwrap_or(Utc::now().date_naive());
+    let from = from.unwrap_or(to - ChronoDuration::days(29));
+
+    let mut summaries: Vec<UserUsageSummary> = db
+        .query(SQL)
+        .bind(("FROM", day_key(from)))
+        .bind(("TO", day_key(to)))
+        .observed()
+        .await?
+        .take(0)?;
+
+    summaries.sort_by_key(|summary| std::cmp::Reverse(summary.totals.tokens()));
+
+    Ok(summaries)
+}
+
+/// Accumulates what a completion produced and records it when the stream ends. If the stream is
+/// dropped early because the client went away, the usage is recorded in the background.
+struct UsageTracker<T: surrealdb::Connection> {
+    db: Arc<Surreal<T>>,
+    user_id: Thing,
+    prompt: String,
+    completion: String,
+    reported: Option<TokenUsage>,
+    recorded: bool,
+}
+
+impl<T: surrealdb::Connection> UsageTracker<T> {
+    fn observe(&mut self, chunk: &ChatChunk) {
+        self.completion.push_str(&chunk.delta);
+
+        if let Some(usage) = chunk.usage {
+            self.reported = Some(usage);
+        }
+    }
+
+    fn usage(&self) -> TokenUsage {
+        self.reported.unwrap_or_else(|| TokenUsage {
+            prompt_tokens: estimate_tokens(&self.prompt),
+            completion_tokens: estimate_tokens(&self.completion),
+        })
+    }
+
+    async fn finish(mut self) {
+        self.recorded = true;
+
+        if let Err(err) = record_usage(&self.db, self.user_id.clone(), self.usage()).await {
+            error!("Failed to record LLM usage: {}", err);
+        }
+    }
+}
+
+impl<T: surrealdb::Connection> Drop for UsageTracker<T> {
+    fn drop(&mut self) {
+        if self.recorded {
+            return;
+        }
+
+        let Ok(runtime) = tokio::runtime::Handle::try_current() else {
+            warn!("No runtime to record LLM usage of {}", self.user_id);
+            return;
+        };
+
+        let db = self.db.clone();
+        let user_id = self.user_id.clone();
+        let usage = self.usage();
+
+        runtime.spawn(async move {
+            if let Err(err) = record_usage(&db, user_id, usage).await {
+                error!("Failed to record LLM usage: {}", err);
+            }
+        });
+    }
+}
+
+/// Wraps the response so that its token usage is recorded for `user_id`
+pub(crate) fn track_usage<T>(
+    response: LlmResponse,
+    db: Arc<Surreal<T>>,
+    user_id: Thing,
+    prompt: String,
+) -> LlmResponse
+where
+    T: surrealdb::Connection,
+{
+    let tracker = UsageTracker {
+        db,
+        user_id,
+        prompt,
+        completion: String::new(),
+        reported: None,
+        recorded: false,
+    };
+
+    let chunks: ChatStream = stream::unfold(
+        (response.chunks, Some(tracker)),
+        |(mut chunks, mut tracker)| async move {
+            let Some(chunk) = chunks.next().await else {
+                if let Some(tracker) = tracker.take() {
+                    tracker.finish().await;
+                }
+                return None;
+            };
+
+            if let (Ok(chunk), Some(tracker)) = (&chunk, tracker.as_mut()) {
+                tracker.observe(chunk);
+            }
+
+            Some((chunk, (chunks, tracker)))
+        },
+    )
+    .boxed_local();
+
+    LlmResponse {
+        headers: response.headers,
+        chunks,
+    }
+}
@@ -1,9 +1,15 @@
 //! A service is any business logic that can be called from the API. That being said, endpoints should do minimal logic and instead call a service to do the heavy lifting,
 //! this allows us to call the functions of more complex logic from within the API.
 
+pub(crate) mod audit;
 pub(crate) mod auth_for;
 pub(crate) mod embeddings;
+pub(crate) mod export;
 pub(crate) mod files;
 pub(crate) mod health;
+pub(crate) mod jobs;
+pub(crate) mod llm;
+pub(crate) mod team;
+pub(crate) mod threat_model;
 pub(crate) mod token;
 pub(crate) mod user;
@@ -0,0 +1,79 @@
+use crate::dto::CountResponse;
+use crate::error::ServerResponseError;
+use crate::metrics::ObserveQuery;
+use crate::models::team::TeamRole;
+use std::sync::Arc;
+use surrealdb::sql::Thing;
+use surrealdb::Surreal;
+
+/// Binds `$TEAMS` to the teams in which `$USER` has one of the roles in `$ROLES`, prepend it to
+/// queries that check access to team owned resources
+pub(crate) const LET_TEAMS: &str =
+    "LET $TEAMS = (SELECT VALUE out FROM member_of WHERE in = $USER AND role IN $ROLES);";
+
+pub(crate) fn team_thing(team_id: &str) -> Thing {
+    Thing::from(("team", team_id))
+}
+
+/// Returns the role of `user_id` in `team`, `None` if the user is not a member
+pub async fn get_team_role<T>(
+    db: &Arc<Surreal<T>>,
+    team: &Thing,
+    user_id: &Thing,
+) -> Result<Option<TeamRole>, ServerResponseError>
+where
+    T: surrealdb::Connection,
+{
+    const SQL: &str = "SELECT VALUE role FROM member_of WHERE in = $USER AND out = $TEAM;";
+    let role: Option<TeamRole> = db
+        .query(SQL)
+        .bind(("USER", user_id.clone()))
+        .bind(("TEAM", team.clone()))
+        .observed()
+        .await?
+        .take(0)?;
+
+    Ok(role)
+}
+
+/// Makes sure `user_id` has at least the role `required` in the team with ID `team_id`.
+///
+/// Fails with [`ServerResponseError::NotFound`] for non-members so that the existence of a team
+/// is not revealed, and with [`ServerResponseError::Forbidden`] for members with a lower role.
+pub async fn require_team_role<T>(
+    db: &Arc<Surreal<T>>,
+    team_id: &str,
+    user_id: &Thing,
+    required: TeamRole,
+) -> Result<(Thing, TeamRole), ServerResponseError>
+where
+    T: surrealdb::Connection,
+{
+    let team = team_thing(team_id);
+
+    match get_team_role(db, &team, user_id).await? {
+        Some(role) if role >= required => Ok((team, role)),
+        Some(_) => Err(ServerResponseError::Forbidden),
+        None => Err(ServerResponseError::NotFound),
+    }
+}
+
+/// Number of owners of `team`
+pub(crate) async fn count_owners<T>(
+    db: &Arc<Surreal<T>>,
+    team: &Thing,
+) -> Result<u64, ServerResponseError>
+where
+    T: surrealdb::Connection,
+{
+    const SQL: &str =
+        "SELECT count() FROM member_of WHERE out = $TEAM AND role = 'owner' GROUP ALL;";
+    let count: Option<CountResponse> = db
+        .query(SQL)
+        .bind(("TEAM", team.clone()))
+        .observed()
+        .await?
+        .take(0)?;
+
+    Ok(count.map(|c| c.count).unwrap_or_default())
+}
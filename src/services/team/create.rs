@@ -0,0 +1,42 @@
+use crate::dto::team::TeamRequest;
+use crate::error::ServerResponseError;
+use crate::metrics::ObserveQuery;
+use crate::models::team::Team;
+use std::sync::Arc;
+use surrealdb::sql::Thing;
+use surrealdb::Surreal;
+
+/// Creates a team with `user_id` as its only owner
+pub async fn create_team<T>(
+    db: &Arc<Surreal<T>>,
+    user_id: Thing,
+    request: TeamRequest,
+) -> Result<Team, ServerResponseError>
+where
+    T: surrealdb::Connection,
+{
+    if request.name.trim().is_empty() {
+        return Err(ServerResponseError::invalid_field(
+            "name",
+            "must not be empty",
+        ));
+    }
+
+    const SQL: &str = "
+        BEGIN TRANSACTION;
+        LET $TEAM = (CREATE team CONTENT { name: $NAME, created_by: $USER });
+        RELATE ($USER) -> member_of -> ($TEAM) SET role = 'owner';
+        COMMIT TRANSACTION;
+        SELECT * FROM $TEAM;
+    ";
+
+    let created: Option<Team> = db
+        .query(SQL)
+        .bind(("NAME", request.name.trim().to_string()))
+        .bind(("USER", user_id))
+        .observed()
+        .await?
+        .take(2)?;
+
+    created.ok_or_else(|| ServerResponseError::InternalError("team was not created".to_string()))
+}
@@ -0,0 +1,29 @@
+use super::access::require_team_role;
+use crate::error::ServerResponseError;
+use crate::metrics::ObserveQuery;
+use crate::models::team::TeamRole;
+use std::sync::Arc;
+use surrealdb::sql::Thing;
+use surrealdb::Surreal;
+
+/// Deletes the team with ID `team_id`, only owners may do this. Memberships, invitations and
+/// threat models of the team are removed by the `team_deleted` event, files go back to their
+/// uploaders.
+pub async fn delete_team<T>(
+    db: &Arc<Surreal<T>>,
+    team_id: String,
+    user_id: Thing,
+) -> Result<(), ServerResponseError>
+where
+    T: surrealdb::Connection,
+{
+    let (team, _) = require_team_role(db, &team_id, &user_id, TeamRole::Owner).await?;
+
+    db.query("DELETE $TEAM;")
+        .bind(("TEAM", team))
+        .observed()
+        .await?
+        .check()?;
+
+    Ok(())
+}
@@ -0,0 +1,66 @@
+use super::access::require_team_role;
+use crate::dto::team::TeamDetails;
+use crate::error::ServerResponseError;
+use crate::metrics::ObserveQuery;
+use crate::models::team::{Team, TeamInvitation, TeamMember, TeamMembership, TeamRole};
+use std::sync::Arc;
+use surrealdb::sql::Thing;
+use surrealdb::Surreal;
+
+/// Returns every team `user_id` is a member of together with the user's role
+pub async fn list_teams<T>(
+    db: &Arc<Surreal<T>>,
+    user_id: Thing,
+) -> Result<Vec<TeamMembership>, ServerResponseError>
+where
+    T: surrealdb::Connection,
+{
+    const SQL: &str = "
+        SELECT
+            *,
+            (SELECT VALUE role FROM member_of WHERE in = $USER AND out = $parent.id)[0] AS role
+        FROM team
+        WHERE id IN (SELECT VALUE out FROM member_of WHERE in = $USER)
+        ORDER BY name;
+    ";
+
+    let teams: Vec<TeamMembership> = db
+        .query(SQL)
+        .bind(("USER", user_id))
+        .observed()
+        .await?
+        .take(0)?;
+    Ok(teams)
+}
+
+/// Returns the team with ID `team_id` and its members, `user_id` has to be a member
+pub async fn get_team_details<T>(
+    db: &Arc<Surreal<T>>,
+    team_id: String,
+    user_id: Thing,
+) -> Result<TeamDetails, ServerResponseError>
+where
+    T: surrealdb::Connection,
+{
+    let (team, role) = require_team_role(db, &team_id, &user_id, TeamRole::Viewer).await?;
+
+    const SQL: &str = "
+        SELECT * FROM $TEAM;
+        SELECT in AS user, in.email AS email, in.username AS username, role, joined_at
+            FROM member_of WHERE out = $TEAM ORDER BY joined_at;
+        SELECT * FROM team_invitation WHERE team = $TEAM AND expires_at > time::now() ORDER BY created_at;
+    ";
+
+    let mut response = db.query(SQL).bind(("TEAM", team)).observed().await?;
+
+    let team: Option<Team> = response.take(0)?;
+    let members: Vec<TeamMember> = response.take(1)?;
+    let invitations: Vec<TeamInvitation> = response.take(2)?;
+
+    Ok(TeamDetails {
+        team: team.ok_or(ServerResponseError::NotFound)?,
+        role,
+        members,
+        invitations: (role == TeamRole::Owner).then_some(invitations),
+    })
+}
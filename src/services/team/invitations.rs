@@ -0,0 +1,187 @@
+use super::access::{get_team_role, require_team_role};
+use crate::dto::team::InvitationRequest;
+use crate::error::ServerResponseError;
+use crate::metrics::ObserveQuery;
+use crate::models::team::{TeamInvitation, TeamRole};
+use std::sync::Arc;
+use surrealdb::sql::Thing;
+use surrealdb::Surreal;
+
+fn invitation_thing(invitation_id: &str) -> Thing {
+    Thing::from(("team_invitation", invitation_id))
+}
+
+/// Invites `request.email` to the team with ID `team_id`, only owners may do this. Inviting the
+/// same address again replaces the previous invitation.
+pub async fn create_invitation<T>(
+    db: &Arc<Surreal<T>>,
+    team_id: String,
+    user_id: Thing,
+    request: InvitationRequest,
+) -> Result<TeamInvitation, ServerResponseError>
+where
+    T: surrealdb::Connection,
+{
+    let (team, _) = require_team_role(db, &team_id, &user_id, TeamRole::Owner).await?;
+    let email = request.email.trim().to_lowercase();
+
+    if !email.contains('@') {
+        return Err(ServerResponseError::invalid_field(
+            "email",
+            "must be a valid email address",
+        ));
+    }
+
+    const SQL: &str = "
+        SELECT VALUE in FROM member_of WHERE out = $TEAM AND string::lowercase(in.email) = $EMAIL;
+        DELETE team_invitation WHERE team = $TEAM AND email = $EMAIL;
+        CREATE ONLY team_invitation CONTENT {
+            team: $TEAM,
+            email: $EMAIL,
+            role: $ROLE,
+            invited_by: $USER,
+        };
+    ";
+
+    let mut response = db
+        .query(SQL)
+        .bind(("TEAM", team))
+        .bind(("EMAIL", email))
+        .bind(("ROLE", request.role))
+        .bind(("USER", user_id))
+        .observed()
+        .await?;
+
+    let members: Vec<Thing> = response.take(0)?;
+    if !members.is_empty() {
+        return Err(ServerResponseError::Conflict(
+            "user is already a member of the team".to_string(),
+        ));
+    }
+
+    let created: Option<TeamInvitation> = response.take(2)?;
+    created
+        .ok_or_else(|| ServerResponseError::InternalError("invitation was not created".to_string()))
+}
+
+/// Deletes a pending invitation of the team with ID `team_id`, only owners may do this
+pub async fn revoke_invitation<T>(
+    db: &Arc<Surreal<T>>,
+    team_id: String,
+    invitation_id: String,
+    user_id: Thing,
+) -> Result<(), ServerResponseError>
+where
+    T: surrealdb::Connection,
+{
+    let (team, _) = require_team_role(db, &team_id, &user_id, TeamRole::Owner).await?;
+
+    const SQL: &str = "DELETE $INVITATION WHERE team = $TEAM RETURN BEFORE;";
+    let deleted: Option<TeamInvitation> = db
+        .query(SQL)
+        .bind(("INVITATION", invitation_thing(&invitation_id)))
+        .bind(("TEAM", team))
+        .observed()
+        .await?
+        .take(0)?;
+
+    deleted.map(|_| ()).ok_or(ServerResponseError::NotFound)
+}
+
+/// Returns the pending invitations addressed to `email`
+pub async fn list_invitations_for<T>(
+    db: &Arc<Surreal<T>>,
+    email: &str,
+) -> Result<Vec<TeamInvitation>, ServerResponseError>
+where
+    T: surrealdb::Connection,
+{
+    const SQL: &str = "SELECT * FROM team_invitation WHERE email = $EMAIL AND expires_at > time::now() ORDER BY created_at DESC;";
+    let invitations: Vec<TeamInvitation> = db
+        .query(SQL)
+        .bind(("EMAIL", email.trim().to_lowercase()))
+        .observed()
+        .await?
+        .take(0)?;
+
+    Ok(invitations)
+}
+
+async fn find_invitation<T>(
+    db: &Arc<Surreal<T>>,
+    invitation_id: &str,
+    email: &str,
+) -> Result<TeamInvitation, ServerResponseError>
+where
+    T: surrealdb::Connection,
+{
+    const SQL: &str =
+        "SELECT * FROM $INVITATION WHERE email = $EMAIL AND expires_at > time::now();";
+    let invitation: Option<TeamInvitation> = db
+        .query(SQL)
+        .bind(("INVITATION", invitation_thing(invitation_id)))
+        .bind(("EMAIL", email.trim().to_lowercase()))
+        .observed()
+        .await?
+        .take(0)?;
+
+    invitation.ok_or(ServerResponseError::NotFound)
+}
+
+/// Accepts an invitation addressed to `email` and makes `user_id` a member of the team
+pub async fn accept_invitation<T>(
+    db: &Arc<Surreal<T>>,
+    invitation_id: String,
+    user_id: Thing,
+    email: &str,
+) -> Result<(), ServerResponseError>
+where
+    T: surrealdb::Connection,
+{
+    let invitation = find_invitation(db, &invitation_id, email).await?;
+    let team = Thing::from((invitation.team.tb.clone(), invitation.team.id.clone()));
+
+    if get_team_role(db, &team, &user_id).await?.is_some() {
+        return Err(ServerResponseError::Conflict(
+            "already a member of the team".to_string(),
+        ));
+    }
+
+    const SQL: &str = "
+        BEGIN TRANSACTION;
+        RELATE ($USER) -> member_of -> ($TEAM) SET role = $ROLE;
+        DELETE $INVITATION;
+        COMMIT TRANSACTION;
+    ";
+
+    db.query(SQL)
+        .bind(("USER", user_id))
+        .bind(("TEAM", team))
+        .bind(("ROLE", invitation.role))
+        .bind(("INVITATION", invitation_thing(&invitation_id)))
+        .observed()
+        .await?
+        .check()?;
+
+    Ok(())
+}
+
+/// Declines an invitation addressed to `email`
+pub async fn decline_invitation<T>(
+    db: &Arc<Surreal<T>>,
+    invitation_id: String,
+    email: &str,
+) -> Result<(), ServerResponseError>
+where
+    T: surrealdb::Connection,
+{
+    find_invitation(db, &invitation_id, email).await?;
+
+    db.query("DELETE $INVITATION;")
+        .bind(("INVITATION", invitation_thing(&invitation_id)))
+        .observed()
+        .await?
+        .check()?;
+
+    Ok(())
+}
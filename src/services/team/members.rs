@@ -0,0 +1,99 @@
+use super::access::{count_owners, get_team_role, require_team_role};
+use crate::error::ServerResponseError;
+use crate::metrics::ObserveQuery;
+use crate::models::team::TeamRole;
+use std::sync::Arc;
+use surrealdb::sql::Thing;
+use surrealdb::Surreal;
+
+fn user_thing(user_id: &str) -> Thing {
+    Thing::from(("user", user_id))
+}
+
+/// Refuses changes that would leave `team` without an owner
+async fn keep_an_owner<T>(
+    db: &Arc<Surreal<T>>,
+    team: &Thing,
+    current: TeamRole,
+    new: Option<TeamRole>,
+) -> Result<(), ServerResponseError>
+where
+    T: surrealdb::Connection,
+{
+    if current == TeamRole::Owner
+        && new != Some(TeamRole::Owner)
+        && count_owners(db, team).await? <= 1
+    {
+        return Err(ServerResponseError::Conflict(
+            "a team needs at least one owner".to_string(),
+        ));
+    }
+
+    Ok(())
+}
+
+/// Changes the role of the member with ID `member_id`, only owners may do this
+pub async fn update_member_role<T>(
+    db: &Arc<Surreal<T>>,
+    team_id: String,
+    member_id: String,
+    user_id: Thing,
+    role: TeamRole,
+) -> Result<(), ServerResponseError>
+where
+    T: surrealdb::Connection,
+{
+    let (team, _) = require_team_role(db, &team_id, &user_id, TeamRole::Owner).await?;
+    let member = user_thing(&member_id);
+
+    let current = get_team_role(db, &team, &member)
+        .await?
+        .ok_or(ServerResponseError::NotFound)?;
+    keep_an_owner(db, &team, current, Some(role)).await?;
+
+    const SQL: &str = "UPDATE member_of SET role = $ROLE WHERE in = $MEMBER AND out = $TEAM;";
+    db.query(SQL)
+        .bind(("ROLE", role))
+        .bind(("MEMBER", member))
+        .bind(("TEAM", team))
+        .observed()
+        .await?
+        .check()?;
+
+    Ok(())
+}
+
+/// Removes the member with ID `member_id` from the team. Owners may remove anyone, every
+/// member may remove themselves.
+pub async fn remove_member<T>(
+    db: &Arc<Surreal<T>>,
+    team_id: String,
+    member_id: String,
+    user_id: Thing,
+) -> Result<(), ServerResponseError>
+where
+    T: surrealdb::Connection,
+{
+    let member = user_thing(&member_id);
+    let required = if member == user_id {
+        TeamRole::Viewer
+    } else {
+        TeamRole::Owner
+    };
+    let (team, _) = require_team_role(db, &team_id, &user_id, required).await?;
+
+    let current = get_team_role(db, &team, &member)
+        .await?
+        .ok_or(ServerResponseError::NotFound)?;
+    keep_an_owner(db, &team, current, None).await?;
+
+    const SQL: &str = "DELETE member_of WHERE in = $MEMBER AND out = $TEAM;";
+    db.query(SQL)
+        .bind(("MEMBER", member))
+        .bind(("TEAM", team))
+        .observed()
+        .await?
+        .check()?;
+
+    Ok(())
+}
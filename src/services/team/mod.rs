@@ -0,0 +1,13 @@
+//! Teams own files and threat models on behalf of their members. Membership is stored in the
+//! `member_of` relation from `user` to `team` together with the member's [`TeamRole`].
+//!
+//! [`TeamRole`]: crate::models::team::TeamRole
+
+pub mod access;
+pub mod create;
+pub mod delete;
+pub mod get;
+pub mod invitations;
+pub mod members;
+mod test;
+pub mod update;
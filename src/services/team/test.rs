@@ -0,0 +1,26 @@
+#![cfg(test)]
+
+use crate::models::team::TeamRole;
+
+#[test]
+fn roles_are_ordered_by_privilege() {
+    assert!(TeamRole::Owner > TeamRole::Editor);
+    assert!(TeamRole::Editor > TeamRole::Viewer);
+    assert_eq!(
+        TeamRole::Editor.and_above(),
+        vec![TeamRole::Editor, TeamRole::Owner]
+    );
+    assert_eq!(TeamRole::Viewer.and_above(), TeamRole::ALL.to_vec());
+}
+
+#[test]
+fn roles_use_lowercase_names() {
+    assert_eq!(
+        serde_json::to_string(&TeamRole::Viewer).unwrap(),
+        "\"viewer\""
+    );
+    assert_eq!(
+        serde_json::from_str::<TeamRole>("\"owner\"").unwrap(),
+        TeamRole::Owner
+    );
+}
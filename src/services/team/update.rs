@@ -0,0 +1,39 @@
+use super::access::require_team_role;
+use crate::dto::team::TeamRequest;
+use crate::error::ServerResponseError;
+use crate::metrics::ObserveQuery;
+use crate::models::team::{Team, TeamRole};
+use std::sync::Arc;
+use surrealdb::sql::Thing;
+use surrealdb::Surreal;
+
+/// Renames the team with ID `team_id`, only owners may do this
+pub async fn update_team<T>(
+    db: &Arc<Surreal<T>>,
+    team_id: String,
+    user_id: Thing,
+    request: TeamRequest,
+) -> Result<Team, ServerResponseError>
+where
+    T: surrealdb::Connection,
+{
+    if request.name.trim().is_empty() {
+        return Err(ServerResponseError::invalid_field(
+            "name",
+            "must not be empty",
+        ));
+    }
+
+    let (team, _) = require_team_role(db, &team_id, &user_id, TeamRole::Owner).await?;
+
+    const SQL: &str = "UPDATE $TEAM SET name = $NAME RETURN AFTER;";
+    let updated: Option<Team> = db
+        .query(SQL)
+        .bind(("TEAM", team))
+        .bind(("NAME", request.name.trim().to_string()))
+        .observed()
+        .await?
+        .take(0)?;
+
+    updated.ok_or(ServerResponseError::NotFound)
+}
@@ -0,0 +1,45 @@
+use crate::dto::threat_model::CoverageSummary;
+use crate::models::threat_model::{ItemStatus, MitigationItem, ThreatItem};
+use std::collections::HashSet;
+
+/// Computes which applicable threats of a model are not addressed by any mitigation.
+///
+/// A threat is covered when it is marked as mitigated or when one of its `mitigated_by`
+/// entries is a mitigation of the model that is not marked as not applicable.
+pub fn compute_coverage(threats: &[ThreatItem], mitigations: &[MitigationItem]) -> CoverageSummary {
+    let mitigations: HashSet<&str> = mitigations
+        .iter()
+        .filter(|mitigation| mitigation.status != ItemStatus::NotApplicable)
+        .map(|mitigation| mitigation.mitre_id.as_str())
+        .collect();
+
+    let mut summary = CoverageSummary::default();
+
+    for threat in threats {
+        if threat.status == ItemStatus::NotApplicable {
+            continue;
+        }
+
+        summary.applicable_threats += 1;
+
+        let covered = threat.status == ItemStatus::Mitigated
+            || threat
+                .mitigated_by
+                .iter()
+                .any(|id| mitigations.contains(id.as_str()));
+
+        if covered {
+            summary.covered_threats += 1;
+        } else {
+            summary.uncovered.push(threat.mitre_id.clone());
+        }
+    }
+
+    summary.coverage = if summary.applicable_threats == 0 {
+        1.0
+    } else {
+        summary.covered_threats as f64 / summary.applicable_threats as f64
+    };
+
+    summary
+}
@@ -0,0 +1,60 @@
+use super::validate::validate_request;
+use crate::dto::threat_model::ThreatModelRequest;
+use crate::error::ServerResponseError;
+use crate::metrics::ObserveQuery;
+use crate::models::team::TeamRole;
+use crate::models::threat_model::ThreatModel;
+use crate::services::team::access::require_team_role;
+use std::sync::Arc;
+use surrealdb::sql::Thing;
+use surrealdb::Surreal;
+
+/// Creates a threat model owned by `user_id`, this is version 1 of the model. Models created for
+/// a team require the user to be an editor of that team.
+pub async fn create_threat_model<T>(
+    db: &Arc<Surreal<T>>,
+    user_id: Thing,
+    request: ThreatModelRequest,
+) -> Result<ThreatModel, ServerResponseError>
+where
+    T: surrealdb::Connection,
+{
+    let files = validate_request(db, &request, &user_id).await?;
+    let team = match &request.team {
+        Some(team_id) => {
+            let (team, _) = require_team_role(db, team_id, &user_id, TeamRole::Editor).await?;
+            Some(team)
+        }
+        None => None,
+    };
+
+    const SQL: &str = "
+        CREATE ONLY threat_model CONTENT {
+            owner: $USER,
+            team: $TEAM,
+            title: $TITLE,
+            description: $DESCRIPTION,
+            files: $FILES,
+            threats: $THREATS,
+            mitigations: $MITIGATIONS,
+            updated_by: $USER,
+        };
+    ";
+
+    let created: Option<ThreatModel> = db
+        .query(SQL)
+        .bind(("USER", user_id))
+        .bind(("TEAM", team))
+        .bind(("TITLE", request.title))
+        .bind(("DESCRIPTION", request.description))
+        .bind(("FILES", files))
+        .bind(("THREATS", request.threats))
+        .bind(("MITIGATIONS", request.mitigations))
+        .observed()
+        .await?
+        .take(0)?;
+
+    created.ok_or_else(|| {
+        ServerResponseError::InternalError("threat model was not created".to_string())
+    })
+}
@@ -0,0 +1,36 @@
+use crate::error::ServerResponseError;
+use crate::metrics::ObserveQuery;
+use crate::models::team::TeamRole;
+use crate::models::threat_model::ThreatModel;
+use crate::services::team::access::LET_TEAMS;
+use std::sync::Arc;
+use surrealdb::sql::Thing;
+use surrealdb::Surreal;
+
+/// Deletes the threat model with ID `model_id` if `user_id` owns it or is an editor of its team,
+/// the version history is removed by the `threat_model_deleted` event
+pub async fn delete_threat_model<T>(
+    db: &Arc<Surreal<T>>,
+    model_id: String,
+    user_id: Thing,
+) -> Result<(), ServerResponseError>
+where
+    T: surrealdb::Connection,
+{
+    let sql = format!(
+        "{LET_TEAMS}
+        DELETE type::thing('threat_model', $ID)
+        WHERE (team = NONE AND owner = $USER) OR team IN $TEAMS
+        RETURN BEFORE;"
+    );
+    let deleted: Option<ThreatModel> = db
+        .query(sql)
+        .bind(("ID", model_id))
+        .bind(("USER", user_id))
+        .bind(("ROLES", TeamRole::Editor.and_above()))
+        .observed()
+        .await?
+        .take(1)?;
+
+    deleted.map(|_| ()).ok_or(ServerResponseError::NotFound)
+}
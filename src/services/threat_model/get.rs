@@ -0,0 +1,108 @@
+use super::coverage::compute_coverage;
+use crate::dto::threat_model::ThreatModelDetails;
+use crate::error::ServerResponseError;
+use crate::metrics::ObserveQuery;
+use crate::models::team::TeamRole;
+use crate::models::threat_model::ThreatModel;
+use crate::models::EntryType;
+use crate::services::embeddings::get::get_entries_by_mitre_id;
+use crate::services::team::access::LET_TEAMS;
+use std::sync::Arc;
+use surrealdb::sql::Thing;
+use surrealdb::Surreal;
+
+/// Returns the threat model with ID `model_id` if `user_id` has at least the role `required`
+/// for it. Models without a team are only accessible to their owner, models of a team to its
+/// members.
+pub async fn get_accessible_threat_model<T>(
+    db: &Arc<Surreal<T>>,
+    model_id: String,
+    user_id: Thing,
+    required: TeamRole,
+) -> Result<ThreatModel, ServerResponseError>
+where
+    T: surrealdb::Connection,
+{
+    let sql = format!(
+        "{LET_TEAMS}
+        SELECT * FROM type::thing('threat_model', $ID)
+        WHERE (team = NONE AND owner = $USER) OR team IN $TEAMS;"
+    );
+    let found: Option<ThreatModel> = db
+        .query(sql)
+        .bind(("ID", model_id))
+        .bind(("USER", user_id))
+        .bind(("ROLES", required.and_above()))
+        .observed()
+        .await?
+        .take(1)?;
+
+    found.ok_or(ServerResponseError::NotFound)
+}
+
+/// Returns the threat model with ID `model_id` if `user_id` may read it
+pub async fn get_threat_model<T>(
+    db: &Arc<Surreal<T>>,
+    model_id: String,
+    user_id: Thing,
+) -> Result<ThreatModel, ServerResponseError>
+where
+    T: surrealdb::Connection,
+{
+    get_accessible_threat_model(db, model_id, user_id, TeamRole::Viewer).await
+}
+
+/// Returns all threat models of `user_id` and of the user's teams, most recently changed first
+pub async fn list_threat_models<T>(
+    db: &Arc<Surreal<T>>,
+    user_id: Thing,
+) -> Result<Vec<ThreatModel>, ServerResponseError>
+where
+    T: surrealdb::Connection,
+{
+    let sql = format!(
+        "{LET_TEAMS}
+        SELECT * FROM threat_model
+        WHERE (team = NONE AND owner = $USER) OR team IN $TEAMS
+        ORDER BY updated_at DESC;"
+    );
+    let models: Vec<ThreatModel> = db
+        .query(sql)
+        .bind(("USER", user_id))
+        .bind(("ROLES", TeamRole::Viewer.and_above()))
+        .observed()
+        .await?
+        .take(1)?;
+    Ok(models)
+}
+
+/// Resolves the MITRE entries referenced by `model` and computes its coverage
+pub async fn get_threat_model_details<T>(
+    db: &Arc<Surreal<T>>,
+    model: ThreatModel,
+) -> Result<ThreatModelDetails, ServerResponseError>
+where
+    T: surrealdb::Connection,
+{
+    let threat_ids = model.threats.iter().map(|t| t.mitre_id.clone()).collect();
+    let mitigation_ids = model
+        .mitigations
+        .iter()
+        .map(|m| m.mitre_id.clone())
+        .collect();
+
+    let threats = get_entries_by_mitre_id(db, EntryType::Threat, threat_ids).await?;
+    let mitigations = get_entries_by_mitre_id(db, EntryType::Mitigation, mitigation_ids).await?;
+
+    let entries = threats
+        .into_iter()
+        .chain(mitigations)
+        .map(|entry| (entry.mitre_id.clone(), entry))
+        .collect();
+
+    Ok(ThreatModelDetails {
+        coverage: compute_coverage(&model.threats, &model.mitigations),
+        model,
+        entries,
+    })
+}
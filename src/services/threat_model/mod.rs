@@ -0,0 +1,12 @@
+//! Threat models are owned by a user and reference entries of the `threat` and `mitigation`
+//! tables by MITRE ID. Every change bumps the model's version, the `threat_model_versioned`
+//! event keeps a snapshot of each version in `threat_model_version`.
+
+pub mod coverage;
+pub mod create;
+pub mod delete;
+pub mod get;
+mod test;
+pub mod update;
+pub(crate) mod validate;
+pub mod versions;
@@ -0,0 +1,50 @@
+#![cfg(test)]
+
+use super::coverage::compute_coverage;
+use crate::models::threat_model::{ItemStatus, MitigationItem, ThreatItem};
+
+fn threat(mitre_id: &str, status: ItemStatus, mitigated_by: &[&str]) -> ThreatItem {
+    ThreatItem {
+        mitre_id: mitre_id.to_string(),
+        status,
+        notes: None,
+        mitigated_by: mitigated_by.iter().map(|id| id.to_string()).collect(),
+    }
+}
+
+fn mitigation(mitre_id: &str, status: ItemStatus) -> MitigationItem {
+    MitigationItem {
+        mitre_id: mitre_id.to_string(),
+        status,
+        notes: None,
+    }
+}
+
+#[test]
+fn coverage_lists_threats_without_mitigations() {
+    let threats = [
+        threat("T1190", ItemStatus::Accepted, &["M1050"]),
+        threat("T1110", ItemStatus::Accepted, &["M1032"]),
+        threat("T1078", ItemStatus::Mitigated, &[]),
+        threat("T1566", ItemStatus::NotApplicable, &[]),
+    ];
+    let mitigations = [
+        mitigation("M1050", ItemStatus::Accepted),
+        mitigation("M1032", ItemStatus::NotApplicable),
+    ];
+
+    let coverage = compute_coverage(&threats, &mitigations);
+
+    assert_eq!(coverage.applicable_threats, 3);
+    assert_eq!(coverage.covered_threats, 2);
+    assert_eq!(coverage.uncovered, vec!["T1110".to_string()]);
+    assert!((coverage.coverage - 2.0 / 3.0).abs() < f64::EPSILON);
+}
+
+#[test]
+fn empty_model_is_fully_covered() {
+    let coverage = compute_coverage(&[], &[]);
+
+    assert_eq!(coverage.applicable_threats, 0);
+    assert_eq!(coverage.coverage, 1.0);
+}
@@ -0,0 +1,54 @@
+use super::validate::validate_request;
+use crate::dto::threat_model::ThreatModelRequest;
+use crate::error::ServerResponseError;
+use crate::metrics::ObserveQuery;
+use crate::models::team::TeamRole;
+use crate::models::threat_model::ThreatModel;
+use crate::services::team::access::LET_TEAMS;
+use std::sync::Arc;
+use surrealdb::sql::Thing;
+use surrealdb::Surreal;
+
+/// Replaces the content of the threat model with ID `model_id` and bumps its version. `user_id`
+/// has to own the model or be an editor of its team, the team of a model cannot be changed.
+pub async fn update_threat_model<T>(
+    db: &Arc<Surreal<T>>,
+    model_id: String,
+    user_id: Thing,
+    request: ThreatModelRequest,
+) -> Result<ThreatModel, ServerResponseError>
+where
+    T: surrealdb::Connection,
+{
+    let files = validate_request(db, &request, &user_id).await?;
+
+    let sql = format!(
+        "{LET_TEAMS}
+        UPDATE type::thing('threat_model', $ID) SET
+            title = $TITLE,
+            description = $DESCRIPTION,
+            files = $FILES,
+            threats = $THREATS,
+            mitigations = $MITIGATIONS,
+            updated_by = $USER,
+            version += 1
+        WHERE (team = NONE AND owner = $USER) OR team IN $TEAMS
+        RETURN AFTER;"
+    );
+
+    let updated: Option<ThreatModel> = db
+        .query(sql)
+        .bind(("ID", model_id))
+        .bind(("USER", user_id))
+        .bind(("ROLES", TeamRole::Editor.and_above()))
+        .bind(("TITLE", request.title))
+        .bind(("DESCRIPTION", request.description))
+        .bind(("FILES", files))
+        .bind(("THREATS", request.threats))
+        .bind(("MITIGATIONS", request.mitigations))
+        .observed()
+        .await?
+        .take(1)?;
+
+    updated.ok_or(ServerResponseError::NotFound)
+}
@@ -0,0 +1,144 @@
+use crate::dto::threat_model::ThreatModelRequest;
+use crate::error::ServerResponseError;
+use crate::models::EntryType;
+use crate::services::embeddings::get::get_entries_by_mitre_id;
+use crate::services::files::get::get_file_metadata_by_token;
+use std::collections::HashSet;
+use std::sync::Arc;
+use surrealdb::sql::Thing;
+use surrealdb::Surreal;
+
+/// Checks a threat model before it is written and returns the records of its attached files.
+///
+/// Every MITRE ID must exist in the `threat` or `mitigation` table, `mitigated_by` may only refer
+/// to mitigations of the same model and files must be readable by `user_id`.
+pub(crate) async fn validate_request<T>(
+    db: &Arc<Surreal<T>>,
+    request: &ThreatModelRequest,
+    user_id: &Thing,
+) -> Result<Vec<Thing>, ServerResponseError>
+where
+    T: surrealdb::Connection,
+{
+    if request.title.trim().is_empty() {
+        return Err(ServerResponseError::invalid_field(
+            "title",
+            "must not be empty",
+        ));
+    }
+
+    let threat_ids = unique_ids(request.threats.iter().map(|t| &t.mitre_id), "threats")?;
+    let mitigation_ids = unique_ids(
+        request.mitigations.iter().map(|m| &m.mitre_id),
+        "mitigations",
+    )?;
+
+    for threat in &request.threats {
+        if let Some(id) = threat
+            .mitigated_by
+            .iter()
+            .find(|id| !mitigation_ids.contains(id.as_str()))
+        {
+            return Err(ServerResponseError::invalid_field(
+                "threats",
+                format!(
+                    "{} is mitigated by {}, which is not a mitigation of this model",
+                    threat.mitre_id, id
+                ),
+            ));
+        }
+    }
+
+    check_entries_exist(db, EntryType::Threat, threat_ids).await?;
+    check_entries_exist(db, EntryType::Mitigation, mitigation_ids).await?;
+
+    accessible_files(db, &request.files, user_id).await
+}
+
+fn unique_ids<'a>(
+    ids: impl Iterator<Item = &'a String>,
+    field: &str,
+) -> Result<HashSet<&'a str>, ServerResponseError> {
+    let mut unique = HashSet::new();
+
+    for id in ids {
+        if !unique.insert(id.as_str()) {
+            return Err(ServerResponseError::invalid_field(
+                field,
+                format!("{id} is listed more than once"),
+            ));
+        }
+    }
+
+    Ok(unique)
+}
+
+async fn check_entries_exist<T>(
+    db: &Arc<Surreal<T>>,
+    entry_type: EntryType,
+    ids: HashSet<&str>,
+) -> Result<(), ServerResponseError>
+where
+    T: surrealdb::Connection,
+{
+    let requested: Vec<String> = ids.iter().map(|id| id.to_string()).collect();
+    let found = get_entries_by_mitre_id(db, entry_type, requested.clone()).await?;
+    let found: HashSet<String> = found.into_iter().map(|entry| entry.mitre_id).collect();
+
+    let mut missing: Vec<&String> = requested.iter().filter(|id| !found.contains(*id)).collect();
+
+    if missing.is_empty() {
+        return Ok(());
+    }
+
+    missing.sort();
+
+    let field = match entry_type {
+        EntryType::Threat => "threats",
+        EntryType::Mitigation => "mitigations",
+    };
+
+    Err(ServerResponseError::invalid_field(
+        field,
+        format!(
+            "unknown {} IDs: {}",
+            entry_type,
+            missing
+                .iter()
+                .map(|id| id.as_str())
+                .collect::<Vec<_>>()
+                .join(", ")
+        ),
+    ))
+}
+
+async fn accessible_files<T>(
+    db: &Arc<Surreal<T>>,
+    files: &[String],
+    user_id: &Thing,
+) -> Result<Vec<Thing>, ServerResponseError>
+where
+    T: surrealdb::Connection,
+{
+    if files.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    let accessible: Vec<String> = get_file_metadata_by_token(db, user_id.clone())
+        .await?
+        .into_iter()
+        .map(|file| file.id.id.to_raw())
+        .collect();
+
+    if let Some(file) = files.iter().find(|file| !accessible.contains(file)) {
+        return Err(ServerResponseError::invalid_field(
+            "files",
+            format!("file {file} does not exist"),
+        ));
+    }
+
+    Ok(files
+        .iter()
+        .map(|file| Thing::from(("file", file.as_str())))
+        .collect())
+}
@@ -0,0 +1,57 @@
+use super::get::get_threat_model;
+use crate::error::ServerResponseError;
+use crate::metrics::ObserveQuery;
+use crate::models::threat_model::ThreatModelVersion;
+use std::sync::Arc;
+use surrealdb::sql::Thing;
+use surrealdb::Surreal;
+
+/// Returns every version of the threat model with ID `model_id` readable by `user_id`, newest first
+pub async fn list_threat_model_versions<T>(
+    db: &Arc<Surreal<T>>,
+    model_id: String,
+    user_id: Thing,
+) -> Result<Vec<ThreatModelVersion>, ServerResponseError>
+where
+    T: surrealdb::Connection,
+{
+    let model = Thing::from(("threat_model", model_id.as_str()));
+    get_threat_model(db, model_id, user_id).await?;
+
+    const SQL: &str =
+        "SELECT * FROM threat_model_version WHERE model = $MODEL ORDER BY version DESC;";
+    let versions: Vec<ThreatModelVersion> = db
+        .query(SQL)
+        .bind(("MODEL", model))
+        .observed()
+        .await?
+        .take(0)?;
+
+    Ok(versions)
+}
+
+/// Returns a single version of the threat model with ID `model_id` readable by `user_id`
+pub async fn get_threat_model_version<T>(
+    db: &Arc<Surreal<T>>,
+    model_id: String,
+    version: u64,
+    user_id: Thing,
+) -> Result<ThreatModelVersion, ServerResponseError>
+where
+    T: surrealdb::Connection,
+{
+    let model = Thing::from(("threat_model", model_id.as_str()));
+    get_threat_model(db, model_id, user_id).await?;
+
+    const SQL: &str =
+        "SELECT * FROM threat_model_version WHERE model = $MODEL AND version = $VERSION;";
+    let found: Option<ThreatModelVersion> = db
+        .query(SQL)
+        .bind(("MODEL", model))
+        .bind(("VERSION", version))
+        .observed()
+        .await?
+        .take(0)?;
+
+    found.ok_or(ServerResponseError::NotFound)
+}
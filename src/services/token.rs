@@ -1,4 +1,5 @@
 use crate::error::ServerResponseError;
+use crate::metrics::ObserveQuery;
 use serde::{Deserialize, Serialize};
 use std::sync::Arc;
 use surrealdb::sql::Thing;
@@ -36,8 +37,12 @@ where
         password
     );
 
-    let query_result: Option<AuthenticatedUser> =
-        db.query(query).bind(("email", username)).await?.take(0)?;
+    let query_result: Option<AuthenticatedUser> = db
+        .query(query)
+        .bind(("email", username))
+        .observed()
+        .await?
+        .take(0)?;
 
     query_result.ok_or(ServerResponseError::UnauthorizedWithMessage(
         "Invalid username or password".to_string(),
@@ -1,3 +1,4 @@
+use crate::metrics::ObserveQuery;
 use crate::models::user_info::UserInfo;
 use crate::models::Record;
 use crate::{dto::UserRegistrationRequest, error::ServerResponseError};
@@ -20,6 +21,7 @@ where
         .bind(("email", user.email.clone()))
         .bind(("picture", user.picture.clone()))
         .bind(("role", user.role.clone()))
+        .observed()
         .await?;
 
     let records: Vec<Record> = res.take(0)?;
@@ -64,6 +66,7 @@ where
     db.query(REGISTER_USER_SQL)
         .bind(("user_content", user_registration.user))
         .bind(("password", password))
+        .observed()
         .await?
         .check()?;
     Ok(())
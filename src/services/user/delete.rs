@@ -1,19 +1,53 @@
 use crate::error::ServerResponseError;
+use crate::metrics::ObserveQuery;
 use crate::models::thing::Thing;
 use std::sync::Arc;
 use surrealdb::Surreal;
 
+/// Deletes a user together with its credentials, the teams in which it is the only member, the
+/// files it uploaded that do not belong to a team and its unfinished uploads. The content of the
+/// files is queued for removal from the file store.
+///
+/// Fails with [`ServerResponseError::Conflict`] if the user is the last owner of a team that has
+/// other members, ownership has to be handed over first.
 pub async fn delete_user<T>(db: &Arc<Surreal<T>>, user_id: Thing) -> Result<(), ServerResponseError>
 where
     T: surrealdb::Connection,
 {
+    const CHECK_SQL: &str = "
+            SELECT VALUE name FROM team
+            WHERE <-member_of[WHERE role = 'owner'].in CONTAINS $USER_ID
+                AND count(<-member_of[WHERE role = 'owner']) = 1
+                AND count(<-member_of) > 1;
+        ";
+    let teams: Vec<String> = db
+        .query(CHECK_SQL)
+        .bind(("USER_ID", user_id.clone()))
+        .observed()
+        .await?
+        .take(0)?;
+
+    if !teams.is_empty() {
+        return Err(ServerResponseError::Conflict(format!(
+            "transfer ownership of these teams first: {}",
+            teams.join(", ")
+        )));
+    }
+
     const SQL: &str = "
             BEGIN TRANSACTION;
+            DELETE team WHERE <-member_of.in CONTAINS $USER_ID AND count(<-member_of) = 1;
             DELETE user_auth WHERE ->auth_for->user.id CONTAINS $USER_ID;
+            DELETE file WHERE team = NONE AND ->files_for->user CONTAINS $USER_ID;
+            DELETE upload_session WHERE owner = $USER_ID;
             DELETE $USER_ID;
             COMMIT TRANSACTION;
         ";
-    db.query(SQL).bind(("USER_ID", user_id)).await?;
+    db.query(SQL)
+        .bind(("USER_ID", user_id))
+        .observed()
+        .await?
+        .check()?;
 
     Ok(())
 }
@@ -1,27 +1,43 @@
+use crate::dto::chat_request::LlmSelection;
 use crate::dto::user_update_request::UserUpdateRequest;
 use crate::error::ServerResponseError;
+use crate::metrics::ObserveQuery;
 use crate::models::thing::Thing;
+use crate::services::llm::LlmBackends;
 use std::sync::Arc;
 use surrealdb::Surreal;
 
 pub async fn update_user_data<T>(
     db: &Arc<Surreal<T>>,
+    llm: &LlmBackends,
     user_id: Thing,
     update_data: UserUpdateRequest,
 ) -> Result<(), ServerResponseError>
 where
     T: surrealdb::Connection,
 {
+    if update_data.llm_backend.is_some() || update_data.llm_model.is_some() {
+        llm.select(&LlmSelection {
+            backend: update_data.llm_backend.clone(),
+            model: update_data.llm_model.clone(),
+        })?;
+    }
+
     const SQL: &str = "UPDATE $user_id SET
 		username = $username,
 		url_safe_username = $url_safe_username,
 		first_name = $first_name,
-		last_name = $last_name;";
+		last_name = $last_name,
+		llm_backend = $llm_backend,
+		llm_model = $llm_model;";
     db.query(SQL)
         .bind(("user_id", user_id.clone()))
         .bind(("username", update_data.username))
         .bind(("first_name", update_data.first_name))
         .bind(("last_name", update_data.last_name))
+        .bind(("llm_backend", update_data.llm_backend))
+        .bind(("llm_model", update_data.llm_model))
+        .observed()
         .await?;
 
     if let Some(password) = update_data.password {
@@ -29,6 +45,7 @@ where
         db.query(SQL)
             .bind(("new_password", password))
             .bind(("user_id", user_id))
+            .observed()
             .await?;
     }
     Ok(())
@@ -1,7 +1,8 @@
 use crate::auth::oauth::Oauth;
-use crate::server::db::INTERNAL_DB;
+use crate::config::Config;
 use crate::server_error::ServerError;
 use crate::services::files::state::FilesServiceState;
+use crate::services::llm::LlmBackends;
 use actix_web::web;
 use std::sync::Arc;
 use surrealdb::engine::remote::ws::{Client, Ws};
@@ -9,48 +10,25 @@ use surrealdb::Surreal;
 
 pub struct AppState {
     pub db: Arc<Surreal<Client>>,
+    /// The `default/log` database holding the audit trail
+    pub log_db: Arc<Surreal<Client>>,
     pub oauth: Arc<Oauth>,
     pub files: FilesServiceState,
-}
-
-#[tracing::instrument]
-async fn setup_db() -> Result<(), ServerError> {
-    let db_url = tosic_utils::prelude::env!("SURREALDB_URL");
-    let db_user = tosic_utils::prelude::env!("SURREALDB_USER");
-    let db_pass = tosic_utils::prelude::env!("SURREALDB_PASS");
-
-    INTERNAL_DB.connect::<Ws>(db_url).await?;
-
-    INTERNAL_DB
-        .signin(surrealdb::opt::auth::Root {
-            username: db_user.as_str(),
-            password: db_pass.as_str(),
-        })
-        .await?;
-
-    let namespace = tosic_utils::prelude::env!("SURREALDB_NAMESPACE", "default");
-    let database = tosic_utils::prelude::env!("SURREALDB_DATABASE", "default");
-
-    INTERNAL_DB
-        .use_ns(namespace.as_str())
-        .use_db(database.as_str())
-        .await?;
-
-    Ok(())
+    pub llm: LlmBackends,
+    /// The configuration the server was started with
+    pub config: Arc<Config>,
 }
 
 #[tracing::instrument]
 pub async fn db(ns: &str, db: &str) -> Result<Surreal<Client>, ServerError> {
-    let db_url = tosic_utils::prelude::env!("SURREALDB_URL");
-    let db_user = tosic_utils::prelude::env!("SURREALDB_USER");
-    let db_pass = tosic_utils::prelude::env!("SURREALDB_PASS");
+    let config = &Config::global().database;
 
-    let database = Surreal::new::<Ws>(db_url).await?;
+    let database = Surreal::new::<Ws>(config.url.as_str()).await?;
 
     database
         .signin(surrealdb::opt::auth::Root {
-            username: &db_user,
-            password: &db_pass,
+            username: &config.user,
+            password: config.password.expose(),
         })
         .await?;
 
@@ -61,11 +39,25 @@ pub async fn db(ns: &str, db: &str) -> Result<Surreal<Client>, ServerError> {
 
 #[tracing::instrument]
 pub async fn app_state() -> Result<web::Data<AppState>, ServerError> {
+    let config = Config::global();
     let database = db("default", "default").await?;
 
+    let scanner = config
+        .scanner
+        .clone()
+        .build()
+        .await
+        .map_err(|err| ServerError::Error(err.to_string()))?;
+
     Ok(web::Data::new(AppState {
         db: Arc::new(database),
-        oauth: Arc::new(Oauth::new().await?),
-        files: FilesServiceState::new(),
+        log_db: Arc::new(db("default", "log").await?),
+        oauth: Arc::new(Oauth::new(&config.oauth, &config.base_url).await?),
+        files: FilesServiceState::new(&config, scanner)
+            .await
+            .map_err(|err| ServerError::Error(err.to_string()))?,
+        llm: LlmBackends::from_config(&config.llm)
+            .map_err(|err| ServerError::Error(err.to_string()))?,
+        config,
     }))
 }
@@ -1,10 +1,17 @@
-use crate::endpoints::__path_health;
-use crate::models::{datetime::Datetime, thing::Thing};
+use crate::dto::chat_chunk::{ChatChunk, TokenUsage};
+use crate::dto::chat_request::{ChatRequest, Keyword, LlmSelection};
+use crate::dto::health::{DependencyHealth, HealthReport, HealthStatus, HealthSummary, MigrationStatus};
+use crate::dto::llm_backend::{BreakerState, LlmBackendHealth, LlmBackendInfo};
+use crate::dto::problem::{FieldError, ProblemDetails, PROBLEM_JSON};
+use crate::dto::threat_analysis::{AnalysisEntry, ThreatAnalysis};
+use crate::endpoints::api::chat::{__path_chat, __path_list_llm_backends, __path_structured_chat};
+use crate::endpoints::{__path_health, __path_live, __path_metrics, __path_ready};
+use crate::models::{datetime::Datetime, thing::Thing, MITREEntry};
 use std::collections::BTreeMap;
 use utoipa::{Modify, OpenApi};
 
 use utoipa::openapi::security::{ApiKey, ApiKeyValue, HttpAuthScheme, HttpBuilder, SecurityScheme};
-use utoipa::openapi::OpenApi as OpenApiSpec;
+use utoipa::openapi::{ContentBuilder, OpenApi as OpenApiSpec, Ref, RefOr};
 
 /// Constructs a new struct that implements [`Modify`] trait for [`utoipa`] documentation.
 ///
@@ -54,6 +61,47 @@ impl Modify for NormalizePath {
     }
 }
 
+/// Documents the body of every error response without one as [`ProblemDetails`]
+struct ProblemResponses;
+
+impl Modify for ProblemResponses {
+    fn modify(&self, openapi: &mut OpenApiSpec) {
+        let content = ContentBuilder::new()
+            .schema(Some(Ref::from_schema_name("ProblemDetails")))
+            .build();
+
+        for item in openapi.paths.paths.values_mut() {
+            let operations = [
+                &mut item.get,
+                &mut item.put,
+                &mut item.post,
+                &mut item.delete,
+                &mut item.options,
+                &mut item.head,
+                &mut item.patch,
+                &mut item.trace,
+            ];
+
+            for operation in operations.into_iter().flatten() {
+                for (status, response) in operation.responses.responses.iter_mut() {
+                    if !status.starts_with(['4', '5']) {
+                        continue;
+                    }
+
+                    // responses with a documented body, like failed health checks, keep it
+                    if let RefOr::T(response) = response {
+                        if response.content.is_empty() {
+                            response
+                                .content
+                                .insert(PROBLEM_JSON.to_string(), content.clone());
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
+
 pub struct OpenApiSecurityConfig;
 
 impl Modify for OpenApiSecurityConfig {
@@ -82,18 +130,30 @@ impl Modify for OpenApiSecurityConfig {
 
 #[derive(OpenApi)]
 #[openapi(
+    paths(chat, structured_chat, list_llm_backends),
     nest(
         (path = "/user", api = crate::endpoints::api::user::UserApi),
         (path = "/oauth", api = crate::endpoints::api::oauth::OauthApi),
         (path = "/files", api = crate::endpoints::api::files::FilesApi),
+        (path = "/shared", api = crate::endpoints::api::shared::SharedApi),
         (path = "/embeddings", api = crate::endpoints::api::embeddings::EmbeddingsApi),
+        (path = "/threat-models", api = crate::endpoints::api::threat_model::ThreatModelApi),
+        (path = "/teams", api = crate::endpoints::api::team::TeamApi),
+        (path = "/admin", api = crate::endpoints::api::admin::AdminApi),
+    ),
+    components(
+        schemas(Datetime, Thing, ChatRequest, Keyword, LlmSelection, ChatChunk, TokenUsage, LlmBackendInfo, ThreatAnalysis, AnalysisEntry, MITREEntry),
+        responses(ThreatAnalysis)
     ),
-    components(schemas(Datetime, Thing), responses()),
     tags(
         (name = "user", description = "User management"),
+        (name = "llm", description = "Chat with the configured LLM backends"),
         (name = "oauth", description = "OAuth provider management"),
         (name = "files", description = "Files management"),
         (name = "embeddings", description = "Embeddings management"),
+        (name = "threat_model", description = "Saved threat models and their history"),
+        (name = "team", description = "Teams that share files and threat models"),
+        (name = "admin", description = "Administration, requires the `Admin` or `Owner` role"),
     ),
     modifiers(&AddV1Prefix)
 )]
@@ -101,14 +161,17 @@ pub struct DocsV1;
 
 #[derive(OpenApi)]
 #[openapi(
-    paths(health),
+    paths(health, live, ready, metrics),
     nest(
         (path = "/", api = DocsV1),
     ),
-    components(schemas(Datetime, Thing), responses()),
+    components(
+        schemas(Datetime, Thing, HealthReport, HealthStatus, DependencyHealth, MigrationStatus, HealthSummary, LlmBackendHealth, BreakerState, ProblemDetails, FieldError),
+        responses(HealthReport, HealthSummary, ProblemDetails)
+    ),
     tags(
-        (name = "health", description = "Health check")
+        (name = "health", description = "Health check and metrics")
     ),
-    modifiers(&NormalizePath, &OpenApiSecurityConfig)
+    modifiers(&NormalizePath, &OpenApiSecurityConfig, &ProblemResponses)
 )]
 pub struct ApiDocs;
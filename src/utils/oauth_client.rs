@@ -6,10 +6,6 @@ macro_rules! define_oauth_client {
         $scope_type:ty,
         $user_info_type:ty,
         {
-            client_id_env: $client_id_env:expr,
-            client_secret_env: $client_secret_env:expr,
-            base_url_env: $base_url_env:expr,
-            default_base_url: $default_base_url:expr,
             user_info_mapping: |$user_info_var:ident| $user_info_mapping:block,
         }
     } => {
@@ -28,8 +24,11 @@ macro_rules! define_oauth_client {
         struct GetUserInfoRequest;
 
         impl $oauth_struct {
-            #[tracing::instrument]
-            pub async fn new() -> Result<Self, crate::auth::oauth::error::OauthError> {
+            #[tracing::instrument(skip(credentials))]
+            pub async fn new(
+                credentials: &crate::config::settings::OauthCredentials,
+                base_url: &str,
+            ) -> Result<Self, crate::auth::oauth::error::OauthError> {
                 let mut details = $provider_struct::fetch().await?;
 
                 let config = details.get_config();
@@ -42,16 +41,12 @@ macro_rules! define_oauth_client {
                     return Err(crate::auth::oauth::error::OauthError::ConfigError);
                 }
 
-                let base_url = tosic_utils::prelude::env!($base_url_env, $default_base_url);
                 let endpoint = config.get_redirect_endpoint();
                 let redirect_url = format!("{}{}", base_url, endpoint);
 
-                let client_id = tosic_utils::prelude::env!($client_id_env);
-                let client_secret = tosic_utils::prelude::env!($client_secret_env);
-
                 let basic = crate::auth::oauth::basic::BasicOauth::new(
-                    client_id,
-                    client_secret,
+                    credentials.client_id.clone(),
+                    credentials.client_secret.expose().to_string(),
                     redirect_url,
                     config.get_auth_url(),
                     config.get_token_url(),
@@ -61,6 +56,17 @@ macro_rules! define_oauth_client {
                 Ok(Self { basic, details })
             }
 
+            /// Name of the provider if it can be used to log in, otherwise why not
+            pub(crate) fn check(&self) -> Result<String, String> {
+                let name: String = self.details.name.clone().into();
+
+                match &self.details.config {
+                    Some(config) if config.user_info_url.as_deref().is_some_and(|url| !url.is_empty()) => Ok(name),
+                    Some(_) => Err(format!("{name} has no user info URL")),
+                    None => Err(format!("{name} has no configuration")),
+                }
+            }
+
             pub fn get_auth_url(&self) -> (String, oauth2::CsrfToken) {
                 self.basic.get_authorization_url()
             }